@@ -1,13 +1,70 @@
-use super::raws::config::Config;
+use super::{gui::settings::audio::MAX_VOLUME, raws::config::Config};
 use rodio::Source;
+use specs::World;
 
 type AudioResult<T> = Result<T, Box<dyn ::std::error::Error>>;
 
-pub fn configure_music(configs: &Config, handle: &rodio::OutputStreamHandle) -> AudioResult<rodio::Sink> {
-    let master_volume: f32 = configs.audio.master_volume as f32 / 25.0;
-    let music_volume: f32 = configs.audio.music_volume as f32 / 25.0;
+const MUSIC_PATH: &str = "./resources/audio";
+const SFX_PATH: &str = "./resources/audio/sfx";
 
-    let file = std::fs::File::open("./resources/audio/dungeon_sewer.ogg")?;
+///Game events that make a sound
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum SfxEvent {
+    Hit,
+    Pickup,
+    Descend,
+    PlayerDeath,
+}
+
+impl SfxEvent {
+    ///Name of the sound file played for the event
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Hit => "hit",
+            Self::Pickup => "pickup",
+            Self::Descend => "descend",
+            Self::PlayerDeath => "death",
+        }
+    }
+
+    ///Loudness of the event relative to the other sound effects
+    const fn volume(self) -> f32 {
+        match self {
+            Self::Hit => 0.6,
+            Self::Pickup => 0.5,
+            Self::Descend => 0.8,
+            Self::PlayerDeath => 1.0,
+        }
+    }
+}
+
+///Sound effects raised by the systems this frame, which are played once the frame is done
+#[derive(Default)]
+pub struct SfxQueue {
+    events: Vec<SfxEvent>,
+}
+
+impl SfxQueue {
+    pub fn push(&mut self, event: SfxEvent) {
+        self.events.push(event);
+    }
+
+    #[cfg(test)]
+    pub fn events(&self) -> &[SfxEvent] {
+        &self.events
+    }
+}
+
+///Starts the named music track on a sink of its own, looping it until the sink is dropped
+pub fn configure_music(
+    configs: &Config,
+    handle: &rodio::OutputStreamHandle,
+    track: &str,
+) -> AudioResult<rodio::Sink> {
+    let master_volume: f32 = configs.audio.master_volume as f32 / MAX_VOLUME as f32;
+    let music_volume: f32 = configs.audio.music_volume as f32 / MAX_VOLUME as f32;
+
+    let file = std::fs::File::open(format!("{MUSIC_PATH}/{track}.ogg"))?;
     let source = rodio::Decoder::new(std::io::BufReader::new(file))?.repeat_infinite();
     let music_sink = rodio::Sink::try_new(handle)?;
 
@@ -18,11 +75,51 @@ pub fn configure_music(configs: &Config, handle: &rodio::OutputStreamHandle) ->
 }
 
 pub fn configure_sfx(configs: &Config, handle: &rodio::OutputStreamHandle) -> AudioResult<rodio::Sink> {
-    let master_volume: f32 = configs.audio.master_volume as f32 / 25.0;
-    let sfx_volume: f32 = configs.audio.sfx_volume as f32 / 25.0;
+    let master_volume: f32 = configs.audio.master_volume as f32 / MAX_VOLUME as f32;
+    let sfx_volume: f32 = configs.audio.sfx_volume as f32 / MAX_VOLUME as f32;
 
     let sfx_sink = rodio::Sink::try_new(handle)?;
     sfx_sink.set_volume(master_volume * sfx_volume);
 
     Ok(sfx_sink)
 }
+
+///Plays the named sound effect on the sfx sink, at a volume relative to the configured sfx volume
+pub fn play_sfx(sink: &rodio::Sink, name: &str, volume: f32) -> AudioResult<()> {
+    let file = std::fs::File::open(format!("{SFX_PATH}/{name}.ogg"))?;
+    let source = rodio::Decoder::new(std::io::BufReader::new(file))?.amplify(volume);
+    sink.append(source);
+    Ok(())
+}
+
+///Plays every queued sound effect. The queue is emptied even when sound effects are muted or there
+///is no audio device, so it never builds up
+pub fn play_queued_sfx(configs: &Config, world: &World, sfx_sink: Option<&rodio::Sink>) {
+    let events = std::mem::take(&mut world.fetch_mut::<SfxQueue>().events);
+    let Some(sink) = sfx_sink.filter(|_| configs.audio.sfx_volume > 0) else {
+        return;
+    };
+    for event in events {
+        //Sound effects whose file is missing are skipped
+        play_sfx(sink, event.name(), event.volume()).ok();
+    }
+}
+
+/// Applies the configured volumes to whichever sinks are available
+pub fn update_volumes(
+    configs: &Config,
+    music_sink: Option<&rodio::Sink>,
+    sfx_sink: Option<&rodio::Sink>,
+) {
+    let master_volume: f32 = configs.audio.master_volume as f32 / MAX_VOLUME as f32;
+    let music_volume: f32 = configs.audio.music_volume as f32 / MAX_VOLUME as f32;
+    let sfx_volume: f32 = configs.audio.sfx_volume as f32 / MAX_VOLUME as f32;
+
+    if let Some(sink) = music_sink {
+        sink.set_volume(master_volume * music_volume);
+    }
+
+    if let Some(sink) = sfx_sink {
+        sink.set_volume(master_volume * sfx_volume);
+    }
+}
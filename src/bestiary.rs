@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, io};
+
+const BESTIARY_PATH: &str = "./bestiary.ron";
+const TEMP_BESTIARY_PATH: &str = "./bestiary.ron.tmp";
+
+///What the player has learned about one kind of monster over all their runs
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct BestiaryEntry {
+    pub times_seen: u32,
+    pub times_killed: u32,
+    pub first_depth: i32,
+}
+
+///Every kind of monster the player has come across, kept apart from the save like the statistics
+#[derive(PartialEq, Eq, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Bestiary {
+    pub entries: BTreeMap<String, BestiaryEntry>,
+}
+
+impl Bestiary {
+    ///Counts a monster coming into sight, the depth is only kept the first time its kind is seen
+    pub fn record_sighting(&mut self, name: &str, depth: i32) {
+        self.entries
+            .entry(name.to_string())
+            .or_insert(BestiaryEntry {
+                times_seen: 0,
+                times_killed: 0,
+                first_depth: depth,
+            })
+            .times_seen += 1;
+    }
+
+    ///Counts a monster being slain, which only counts for kinds already seen
+    pub fn record_kill(&mut self, name: &str) {
+        if let Some(entry) = self.entries.get_mut(name) {
+            entry.times_killed += 1;
+        }
+    }
+}
+
+///Reads the bestiary of previous runs, starting empty if there is none or it is unreadable
+pub fn load_bestiary() -> Bestiary {
+    fs::read_to_string(BESTIARY_PATH)
+        .ok()
+        .and_then(|data| ron::de::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+///Writes to a temporary file first, so a crash while saving can not corrupt the previous bestiary
+pub fn save_bestiary(bestiary: &Bestiary) -> io::Result<()> {
+    let data = ron::ser::to_string_pretty(bestiary, ron::ser::PrettyConfig::new())
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    fs::write(TEMP_BESTIARY_PATH, data)?;
+    fs::rename(TEMP_BESTIARY_PATH, BESTIARY_PATH)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sightings_keep_the_depth_a_kind_was_first_seen_at() {
+        let mut bestiary = Bestiary::default();
+        bestiary.record_sighting("Goblin", 2);
+        bestiary.record_sighting("Goblin", 5);
+        bestiary.record_kill("Goblin");
+
+        assert_eq!(
+            bestiary.entries["Goblin"],
+            BestiaryEntry {
+                times_seen: 2,
+                times_killed: 1,
+                first_depth: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn kills_of_unseen_kinds_are_not_recorded() {
+        let mut bestiary = Bestiary::default();
+        bestiary.record_kill("Orc");
+        assert!(bestiary.entries.is_empty());
+    }
+}
@@ -1,51 +1,223 @@
 use crate::{
-    components::{Position, Render},
+    components::{Hidden, LightSource, Position, Render, Size},
     constants::{colors, consoles},
+    gui::UiState,
     map_builder::map::{Map, TileStatus, TileType},
+    raws::config::VisualConfigs,
 };
-use rltk::{ColorPair, Point, Rltk};
-use specs::{Join, World, WorldExt};
+use rltk::{Algorithm2D, ColorPair, DrawBatch, Point, Rltk, RGBA};
+use specs::{Entity, Join, World, WorldExt};
 
 const EDGE_BUFFER: usize = 2;
+const LIGHT_TINT: f32 = 0.25;
+//Size of the map view in tiles at the normal scale, determined by UI Image
+const VIEW_WIDTH: i32 = 57;
+const VIEW_HEIGHT: i32 = 43;
+//The map view is drawn with a single batch, so it goes first
+const MAP_VIEW_Z_ORDER: usize = 0;
 
-pub fn render(ecs: &World, ctx: &mut Rltk) {
-    let map = ecs.fetch::<Map>();
-    let (min_x, max_x, min_y, max_y) = get_screen_bounds(ecs);
+type Cell = (ColorPair, rltk::FontCharType);
 
-    ctx.set_active_console(consoles::MAP_CONSOLE);
+///What was last drawn on each cell of the map view. Only the cells that differ from it are drawn
+///again, rather than clearing and drawing the whole view every frame, so moving, a changed field of
+///view or a new level only redraw what they changed
+#[derive(Default)]
+pub struct MapRenderCache {
+    cells: Vec<Option<Cell>>,
+    ///Cells of the view drawn in the last frame
+    pub dirty_tiles: Vec<Point>,
+}
 
-    for (ty, y) in (min_y..max_y).zip(0..).skip(EDGE_BUFFER) {
-        for (tx, x) in (min_x..max_x).zip(0..).skip(EDGE_BUFFER) {
-            if tx > 0 && tx < map.width && ty > 0 && ty < map.height {
-                let idx = map.xy_idx(tx, ty);
-                if map.is_tile_status_set(idx, TileStatus::Revealed) {
-                    let (glyph, color_pair) = get_tile_glyph(idx, &*map);
-                    ctx.set(x, y, color_pair.fg, color_pair.bg, glyph);
+impl MapRenderCache {
+    ///Forgets what is on the map view, so it is cleared and drawn whole in the next frame
+    pub fn invalidate(&mut self) {
+        self.cells.clear();
+    }
+
+    ///Remembers what the view is drawn with from now on and finds the cells that changed. Returns
+    ///whether the view has to be cleared first, as nothing was known about what was on it
+    fn update(&mut self, cells: Vec<Option<Cell>>) -> bool {
+        let cleared = self.cells.len() != cells.len();
+        self.dirty_tiles = cells
+            .iter()
+            .enumerate()
+            .filter(|&(idx, cell)| {
+                if cleared {
+                    cell.is_some()
+                } else {
+                    self.cells[idx] != *cell
                 }
-            }
-        }
+            })
+            .map(|(idx, _)| Point::new(idx as i32 % VIEW_WIDTH, idx as i32 / VIEW_WIDTH))
+            .collect();
+        self.cells = cells;
+        cleared
+    }
+
+    fn cell(&self, point: Point) -> Option<Cell> {
+        self.cells[(point.y * VIEW_WIDTH + point.x) as usize]
+    }
+}
+
+///For when something other than the camera draws on or clears the map view
+pub fn invalidate_map_view(ecs: &World) {
+    ecs.fetch_mut::<MapRenderCache>().invalidate();
+}
+///Zoom of the map view when large tiles are enabled
+pub const LARGE_TILE_SCALE: i32 = 2;
+
+pub fn render(ecs: &World, ctx: &mut Rltk, visual: &VisualConfigs) {
+    let mut draw_batch = DrawBatch::new();
+    render_map_batched(ecs, &mut draw_batch, visual);
+    render_entities_batched(ecs, &mut draw_batch, visual);
+    submit(ctx, &mut draw_batch);
+}
+
+///Renders a snapshot taken during map generation, without any of the entities on the current map
+pub fn render_snapshot(ecs: &World, snapshot: &Map, ctx: &mut Rltk, visual: &VisualConfigs) {
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(consoles::CHAR_CONSOLE).cls();
+    draw_batch.target(consoles::MAP_CONSOLE).cls();
+    batch_map(
+        snapshot,
+        &mut draw_batch,
+        get_screen_bounds(ecs),
+        None,
+        visual,
+    );
+    submit(ctx, &mut draw_batch);
+    invalidate_map_view(ecs);
+}
+
+///Draws everything in the batch at once, in the order it was added. The character console is left
+///active, as it was when the map view was drawn cell by cell
+fn submit(ctx: &mut Rltk, draw_batch: &mut DrawBatch) {
+    draw_batch.target(consoles::CHAR_CONSOLE);
+    draw_batch
+        .submit(MAP_VIEW_Z_ORDER)
+        .expect("Unable to submit the map view");
+    rltk::render_draw_buffer(ctx).expect("Unable to draw the map view");
+}
+
+///Adds the tiles of the map view that changed since the last frame to the batch. On dark levels the
+///tiles the player can see are tinted by the color of their light
+pub fn render_map_batched(ecs: &World, draw_batch: &mut DrawBatch, visual: &VisualConfigs) {
+    let map = ecs.fetch::<Map>();
+    let light = ecs
+        .read_storage::<LightSource>()
+        .get(*ecs.fetch::<Entity>())
+        .filter(|_| map.is_dark)
+        .map(|light| RGBA::from(light.color));
+
+    let mut cells = vec![None; (VIEW_WIDTH * VIEW_HEIGHT) as usize];
+    for (point, color_pair, glyph) in map_cells(&map, get_screen_bounds(ecs), light, visual) {
+        cells[(point.y * VIEW_WIDTH + point.x) as usize] = Some((color_pair, glyph));
     }
 
+    let mut cache = ecs.fetch_mut::<MapRenderCache>();
+    draw_batch.target(consoles::MAP_CONSOLE);
+    if cache.update(cells) {
+        draw_batch.cls();
+    }
+    //Tiles no longer drawn are set back to what clearing the console leaves
+    let blank = (
+        ColorPair::new(
+            RGBA::from_u8(255, 255, 255, 255),
+            RGBA::from_u8(0, 0, 0, 255),
+        ),
+        rltk::to_cp437(' '),
+    );
+    for &point in &cache.dirty_tiles {
+        let (color_pair, glyph) = cache.cell(point).unwrap_or(blank);
+        draw_batch.set(point, color_pair, glyph);
+    }
+}
+
+fn batch_map(
+    map: &Map,
+    draw_batch: &mut DrawBatch,
+    bounds: (i32, i32, i32, i32),
+    light: Option<RGBA>,
+    visual: &VisualConfigs,
+) {
+    draw_batch.target(consoles::MAP_CONSOLE);
+    for (point, color_pair, glyph) in map_cells(map, bounds, light, visual) {
+        draw_batch.set(point, color_pair, glyph);
+    }
+}
+
+///Where in the view each revealed tile in it is drawn, with its colors and glyph
+fn map_cells<'a>(
+    map: &'a Map,
+    (min_x, max_x, min_y, max_y): (i32, i32, i32, i32),
+    light: Option<RGBA>,
+    visual: &'a VisualConfigs,
+) -> impl Iterator<Item = (Point, ColorPair, rltk::FontCharType)> + 'a {
+    (min_y..max_y)
+        .zip(0..)
+        .skip(EDGE_BUFFER)
+        .flat_map(move |(ty, y)| {
+            (min_x..max_x)
+                .zip(0..)
+                .skip(EDGE_BUFFER)
+                .map(move |(tx, x)| (tx, ty, x, y))
+        })
+        .filter(move |&(tx, ty, ..)| tx > 0 && tx < map.width && ty > 0 && ty < map.height)
+        .map(move |(tx, ty, x, y)| (map.xy_idx(tx, ty), x, y))
+        .filter(move |&(idx, ..)| map.is_tile_status_set(idx, TileStatus::Revealed))
+        .map(move |(idx, x, y)| {
+            let (glyph, color_pair) = get_tile_glyph(idx, map);
+            let fg = match light {
+                Some(light) if map.is_tile_status_set(idx, TileStatus::Visible) => {
+                    color_pair.fg.lerp(light, LIGHT_TINT)
+                }
+                _ => color_pair.fg,
+            };
+            let fg = visual.recolor(fg);
+            let bg = if visual.background_enabled && map.bloodstains.contains(&idx) {
+                colors::BLOODSTAIN.into()
+            } else {
+                color_pair.bg
+            };
+            (Point::new(x, y), ColorPair::new(fg, bg), glyph)
+        })
+}
+
+///Adds the entities standing on visible tiles in view to the batch
+fn render_entities_batched(ecs: &World, draw_batch: &mut DrawBatch, visual: &VisualConfigs) {
+    let (min_x, _, min_y, _) = get_screen_bounds(ecs);
     let positions = ecs.read_storage::<Position>();
     let renderables = ecs.read_storage::<Render>();
+    let hidden = ecs.read_storage::<Hidden>();
+    let sizes = ecs.read_storage::<Size>();
     let map = ecs.fetch::<Map>();
 
-    let mut data = (&positions, &renderables).join().collect::<Vec<_>>();
+    //Large creatures are drawn on every tile they take up
+    let mut data = (&positions, &renderables, sizes.maybe(), !&hidden)
+        .join()
+        .flat_map(|(pos, render, size, ())| {
+            let size = size.copied().unwrap_or_default();
+            size.tiles(Point::new(pos.x, pos.y))
+                .map(move |tile| (tile, render))
+        })
+        .collect::<Vec<_>>();
+    //Drawn from the highest order down, so an item never hides the monster standing on it
     data.sort_by(|&a, &b| b.1.render_order.cmp(&a.1.render_order));
 
-    ctx.set_active_console(consoles::CHAR_CONSOLE);
+    draw_batch.target(consoles::CHAR_CONSOLE);
 
     for (pos, render) in &data {
+        if !map.in_bounds(*pos) {
+            continue;
+        }
         let idx = map.xy_idx(pos.x, pos.y);
         if map.is_tile_status_set(idx, TileStatus::Visible) {
             let offset_x = pos.x - min_x;
             let offset_y = pos.y - min_y;
             if offset_x >= EDGE_BUFFER as i32 && offset_y >= EDGE_BUFFER as i32 {
-                ctx.set(
-                    offset_x,
-                    offset_y,
-                    render.colors.fg,
-                    render.colors.bg,
+                draw_batch.set(
+                    Point::new(offset_x, offset_y),
+                    ColorPair::new(visual.recolor(render.colors.fg), render.colors.bg),
                     render.glyph,
                 );
             }
@@ -53,7 +225,8 @@ pub fn render(ecs: &World, ctx: &mut Rltk) {
     }
 }
 
-fn get_tile_glyph(idx: usize, map: &Map) -> (rltk::FontCharType, ColorPair) {
+///Glyph and colors a tile is drawn with, walls are dimmed once out of sight
+pub fn get_tile_glyph(idx: usize, map: &Map) -> (rltk::FontCharType, ColorPair) {
     let bg = colors::BACKGROUND;
     #[allow(clippy::match_on_vec_items)]
     let (glyph, fg) = match map.tiles[idx] {
@@ -66,15 +239,58 @@ fn get_tile_glyph(idx: usize, map: &Map) -> (rltk::FontCharType, ColorPair) {
             },
         ),
         TileType::Floor => (46, colors::FLOOR),
+        TileType::DoorClosed => (43, colors::DOOR),
+        TileType::DoorOpen => (39, colors::DOOR),
         TileType::StairsDown => (174, colors::STAIRS),
+        TileType::StairsUp => (175, colors::STAIRS),
+        TileType::Lava => (247, colors::LAVA),
+        TileType::ShallowWater => (126, colors::SHALLOW_WATER),
+        TileType::HealingPool => (247, colors::HEALING_POOL),
     };
 
     (glyph, ColorPair::new(fg, bg))
 }
 
+///Number of map tiles that fit in the view when every tile is drawn `scale` times larger
+pub const fn viewport_tiles(scale: i32) -> (i32, i32) {
+    (VIEW_WIDTH / scale, VIEW_HEIGHT / scale)
+}
+
+///Zooms the map and creature consoles in around the center of the view, where the player is
+pub fn apply_scale(ctx: &mut Rltk, scale: i32) {
+    for console in [consoles::MAP_CONSOLE, consoles::CHAR_CONSOLE] {
+        ctx.set_active_console(console);
+        ctx.set_scale(scale as f32, VIEW_WIDTH / 2, VIEW_HEIGHT / 2);
+    }
+}
+
+pub fn tile_scale(ecs: &World) -> i32 {
+    if ecs.fetch::<UiState>().large_tiles {
+        LARGE_TILE_SCALE
+    } else {
+        1
+    }
+}
+
+///Converts a position on the unscaled hud into the position in the map view drawn beneath it
+pub const fn hud_to_view(scale: i32, (x, y): (i32, i32)) -> (i32, i32) {
+    (
+        VIEW_WIDTH / 2 + (x - VIEW_WIDTH / 2) / scale,
+        VIEW_HEIGHT / 2 + (y - VIEW_HEIGHT / 2) / scale,
+    )
+}
+
+///Converts a position in the map view into where it is drawn on the unscaled hud
+pub const fn view_to_hud(scale: i32, (x, y): (i32, i32)) -> (i32, i32) {
+    (
+        VIEW_WIDTH / 2 + (x - VIEW_WIDTH / 2) * scale,
+        VIEW_HEIGHT / 2 + (y - VIEW_HEIGHT / 2) * scale,
+    )
+}
+
 pub fn get_screen_bounds(ecs: &World) -> (i32, i32, i32, i32) {
     let player_pos = ecs.fetch::<Point>();
-    let (x_chars, y_chars) = (57, 43); //Determined by UI Image
+    let (x_chars, y_chars) = (VIEW_WIDTH, VIEW_HEIGHT);
 
     let center_x = (x_chars / 2) as i32;
     let center_y = (y_chars / 2) as i32;
@@ -86,3 +302,96 @@ pub fn get_screen_bounds(ecs: &World) -> (i32, i32, i32, i32) {
 
     (min_x, max_x, min_y, max_y)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ecs::pre_run_systems,
+        test_support::{room_map, test_world},
+    };
+
+    #[test]
+    fn large_tiles_show_half_as_many_tiles() {
+        assert_eq!(viewport_tiles(1), (VIEW_WIDTH, VIEW_HEIGHT));
+        assert_eq!(viewport_tiles(LARGE_TILE_SCALE), (28, 21));
+    }
+
+    #[test]
+    fn hud_and_view_positions_convert_around_the_center() {
+        let center = (VIEW_WIDTH / 2, VIEW_HEIGHT / 2);
+        assert_eq!(hud_to_view(LARGE_TILE_SCALE, center), center);
+        assert_eq!(view_to_hud(LARGE_TILE_SCALE, (30, 20)), (32, 19));
+        assert_eq!(hud_to_view(LARGE_TILE_SCALE, (32, 19)), (30, 20));
+        assert_eq!(hud_to_view(1, (5, 7)), (5, 7));
+    }
+
+    #[test]
+    fn map_cells_follow_what_the_player_knows() {
+        let mut map = room_map(10, 10, 1);
+        for idx in 0..map.tiles.len() {
+            map.set_tile_status(idx, TileStatus::Revealed);
+        }
+        let unknown = map.xy_idx(6, 6);
+        map.remove_tile_status(unknown, TileStatus::Revealed);
+        let lit = map.xy_idx(3, 3);
+        map.set_tile_status(lit, TileStatus::Visible);
+        let stained = map.xy_idx(4, 4);
+        map.stain(stained);
+        let visual = VisualConfigs::default();
+        let light = RGBA::named(rltk::YELLOW);
+
+        //The view starts a tile before the map, past the edge buffer
+        let cells = map_cells(&map, (-1, 9, -1, 9), Some(light), &visual).collect::<Vec<_>>();
+        assert_eq!(cells.len(), 8 * 8 - 1);
+        for (point, color_pair, glyph) in cells {
+            let idx = map.xy_idx(point.x - 1, point.y - 1);
+            assert_ne!(idx, unknown);
+            let (tile_glyph, tile_colors) = get_tile_glyph(idx, &map);
+            assert_eq!(glyph, tile_glyph);
+            let fg = if idx == lit {
+                tile_colors.fg.lerp(light, LIGHT_TINT)
+            } else {
+                tile_colors.fg
+            };
+            assert_eq!(color_pair.fg, visual.recolor(fg));
+            let bg = if idx == stained {
+                colors::BLOODSTAIN.into()
+            } else {
+                tile_colors.bg
+            };
+            assert_eq!(color_pair.bg, bg);
+        }
+    }
+
+    ///Draws the map view as a frame would, returning the cells that had to be drawn
+    fn frame(world: &World) -> usize {
+        let mut draw_batch = DrawBatch::new();
+        render_map_batched(world, &mut draw_batch, &VisualConfigs::default());
+        world.fetch::<MapRenderCache>().dirty_tiles.len()
+    }
+
+    #[test]
+    fn only_changed_cells_are_drawn_again() {
+        let mut world = test_world(12, 10, (5, 5));
+        pre_run_systems::execute(&mut world);
+        let in_view = {
+            let map = world.fetch::<Map>();
+            let visual = VisualConfigs::default();
+            map_cells(&map, get_screen_bounds(&world), None, &visual).count()
+        };
+        assert!(in_view > 0);
+        assert_eq!(frame(&world), in_view);
+        assert_eq!(frame(&world), 0);
+
+        {
+            let mut map = world.fetch_mut::<Map>();
+            let idx = map.xy_idx(3, 3);
+            map.stain(idx);
+        }
+        assert_eq!(frame(&world), 1);
+
+        invalidate_map_view(&world);
+        assert_eq!(frame(&world), in_view);
+    }
+}
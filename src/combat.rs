@@ -0,0 +1,165 @@
+use crate::ecs::CombatStats;
+
+const CRIT_MULTIPLIER: i32 = 2;
+
+///What equipment adds to a melee blow, the power and crit chance of the attacker and the defense
+///of the defender
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Default)]
+pub struct MeleeBonuses {
+    pub power: i32,
+    pub defense: i32,
+    pub crit_chance: i32,
+}
+
+///What a single blow did to its target
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct CombatOutcome {
+    pub damage: i32,
+    pub critical: bool,
+    pub killed: bool,
+}
+
+impl CombatOutcome {
+    ///Whether the defense of the target stopped all of the damage
+    pub const fn is_blocked(self) -> bool {
+        self.damage == 0
+    }
+}
+
+///Multiplies the damage of a hit when the d100 roll is within the crit chance, returning the
+///damage dealt and whether the hit was critical. Hits that deal no damage are never critical
+pub const fn apply_critical(damage: i32, crit_chance: i32, crit_roll: i32) -> (i32, bool) {
+    if damage > 0 && crit_roll <= crit_chance {
+        (damage * CRIT_MULTIPLIER, true)
+    } else {
+        (damage, false)
+    }
+}
+
+///Resolves one melee blow against the current health of the defender, the crit roll being a d100
+pub fn resolve_melee(
+    attacker: &CombatStats,
+    defender: &CombatStats,
+    bonuses: MeleeBonuses,
+    crit_roll: i32,
+) -> CombatOutcome {
+    let damage = i32::max(
+        0,
+        attacker.power + bonuses.power - defender.defense - bonuses.defense,
+    );
+    let (damage, critical) = apply_critical(
+        damage,
+        attacker.crit_chance + bonuses.crit_chance,
+        crit_roll,
+    );
+    CombatOutcome {
+        damage,
+        critical,
+        killed: damage >= defender.hp,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const fn stats(hp: i32, power: i32, defense: i32, crit_chance: i32) -> CombatStats {
+        CombatStats {
+            max_hp: hp,
+            hp,
+            defense,
+            power,
+            crit_chance,
+        }
+    }
+
+    #[test]
+    fn ordinary_hit_deals_power_minus_defense() {
+        let outcome = resolve_melee(
+            &stats(10, 5, 0, 5),
+            &stats(10, 0, 2, 0),
+            MeleeBonuses::default(),
+            100,
+        );
+        assert_eq!(
+            outcome,
+            CombatOutcome {
+                damage: 3,
+                critical: false,
+                killed: false,
+            }
+        );
+    }
+
+    #[test]
+    fn hit_for_the_remaining_health_kills() {
+        let outcome = resolve_melee(
+            &stats(10, 5, 0, 5),
+            &stats(3, 0, 2, 0),
+            MeleeBonuses::default(),
+            100,
+        );
+        assert_eq!(outcome.damage, 3);
+        assert!(outcome.killed);
+    }
+
+    #[test]
+    fn attack_is_blocked_when_defense_covers_the_power() {
+        let bonuses = MeleeBonuses {
+            power: 1,
+            defense: 2,
+            crit_chance: 0,
+        };
+        let outcome = resolve_melee(&stats(10, 5, 0, 100), &stats(10, 0, 4, 0), bonuses, 1);
+        assert!(outcome.is_blocked());
+        assert!(!outcome.critical);
+        assert!(!outcome.killed);
+    }
+
+    #[test]
+    fn equal_power_and_defense_deal_no_damage() {
+        let outcome = resolve_melee(
+            &stats(10, 4, 0, 0),
+            &stats(10, 0, 4, 0),
+            MeleeBonuses::default(),
+            100,
+        );
+        assert_eq!(outcome.damage, 0);
+    }
+
+    #[test]
+    fn defense_above_the_power_never_heals() {
+        let outcome = resolve_melee(
+            &stats(10, 3, 0, 0),
+            &stats(1, 0, 50, 0),
+            MeleeBonuses::default(),
+            100,
+        );
+        assert_eq!(outcome.damage, 0);
+        assert!(!outcome.killed);
+    }
+
+    #[test]
+    fn roll_within_the_crit_chance_doubles_the_damage() {
+        let bonuses = MeleeBonuses {
+            power: 0,
+            defense: 0,
+            crit_chance: 5,
+        };
+        let attacker = stats(10, 5, 0, 5);
+        let defender = stats(10, 0, 1, 0);
+        let outcome = resolve_melee(&attacker, &defender, bonuses, 10);
+        assert_eq!(outcome.damage, 8);
+        assert!(outcome.critical);
+        let outcome = resolve_melee(&attacker, &defender, bonuses, 11);
+        assert_eq!(outcome.damage, 4);
+        assert!(!outcome.critical);
+    }
+
+    #[test]
+    fn only_damaging_hits_are_critical() {
+        assert_eq!(apply_critical(4, 5, 5), (8, true));
+        assert_eq!(apply_critical(4, 5, 6), (4, false));
+        assert_eq!(apply_critical(0, 100, 1), (0, false));
+    }
+}
@@ -4,8 +4,14 @@
 
 pub mod colors {
     pub const BACKGROUND: (u8, u8, u8) = (17, 0, 22);
+    pub const BLOODSTAIN: (u8, u8, u8) = (77, 0, 0);
+    pub const ELITE: (u8, u8, u8) = (255, 128, 0);
+    pub const DOOR: (u8, u8, u8) = (153, 102, 51);
     pub const FLOOR: (u8, u8, u8) = (26, 26, 26);
     pub const FOREGROUND: (u8, u8, u8) = (243, 251, 241);
+    pub const HEALING_POOL: (u8, u8, u8) = (51, 204, 153);
+    pub const LAVA: (u8, u8, u8) = (230, 77, 0);
+    pub const SHALLOW_WATER: (u8, u8, u8) = (51, 102, 204);
     pub const STAIRS: (u8, u8, u8) = (0, 0, 255);
     pub const WALL_REVEALED: (u8, u8, u8) = (77, 77, 77);
     pub const WALL_VISIBLE: (u8, u8, u8) = (0, 179, 0);
@@ -0,0 +1,52 @@
+use enum_cycling::{EnumCycle, IntoEnumCycle};
+use serde::{Deserialize, Serialize};
+use strum::{AsRefStr, EnumIter};
+
+///Chosen when starting a new run, and kept for the whole of it
+#[derive(
+    PartialEq,
+    Eq,
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    Serialize,
+    Deserialize,
+    AsRefStr,
+    EnumIter,
+    EnumCycle,
+)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    ///Scale applied to monster stats on top of the scaling by depth
+    pub const fn monster_multiplier(self) -> f32 {
+        match self {
+            Self::Easy => 0.75,
+            Self::Normal => 1.0,
+            Self::Hard => 1.25,
+        }
+    }
+
+    ///Extra monsters and items rolled for every spawn region
+    pub const fn extra_spawns(self) -> i32 {
+        match self {
+            Self::Easy => -1,
+            Self::Normal => 0,
+            Self::Hard => 2,
+        }
+    }
+
+    pub const fn player_hp(self) -> i32 {
+        match self {
+            Self::Easy => 40,
+            Self::Normal => 30,
+            Self::Hard => 20,
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use crate::{
+    components::{OtherLevelPosition, ParticleLifetime, Position},
+    map_builder::map::Map,
+};
+use serde::{Deserialize, Serialize};
+use specs::prelude::*;
+use std::collections::HashMap;
+
+///Keeps the levels the player has left behind, so taking the stairs back leads to the same layout
+///and the same monsters and items
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct DungeonMaster {
+    maps: HashMap<i32, Map>,
+}
+
+impl DungeonMaster {
+    ///Takes the current level out of play, its map is cached and the entities on it are set aside
+    ///until the player returns
+    pub fn store_level(ecs: &World) {
+        let mut map = std::mem::take(&mut *ecs.fetch_mut::<Map>());
+        map.strip_derived();
+        let depth = map.depth;
+        ecs.fetch_mut::<Self>().maps.insert(depth, map);
+
+        let entities = ecs.entities();
+        let player_ent = *ecs.fetch::<Entity>();
+        let particles = ecs.read_storage::<ParticleLifetime>();
+        let mut positions = ecs.write_storage::<Position>();
+        let mut other_positions = ecs.write_storage::<OtherLevelPosition>();
+
+        let left_behind = (&entities, &positions, !&particles)
+            .join()
+            .filter(|(ent, _, ())| *ent != player_ent)
+            .map(|(ent, pos, ())| (ent, pos.x, pos.y))
+            .collect::<Vec<_>>();
+        for (ent, x, y) in left_behind {
+            positions.remove(ent);
+            other_positions
+                .insert(ent, OtherLevelPosition { x, y, depth })
+                .expect("Unable to insert position on another level");
+        }
+    }
+
+    ///Brings a visited level back into play, returning false if the depth has not been visited
+    pub fn get_level(ecs: &mut World, depth: i32) -> bool {
+        let Some(mut map) = ecs.fetch_mut::<Self>().maps.remove(&depth) else {
+            return false;
+        };
+        map.rebuild_derived();
+        ecs.insert(map);
+
+        let entities = ecs.entities();
+        let mut positions = ecs.write_storage::<Position>();
+        let mut other_positions = ecs.write_storage::<OtherLevelPosition>();
+        let returning = (&entities, &other_positions)
+            .join()
+            .filter(|(_, other)| other.depth == depth)
+            .map(|(ent, other)| (ent, other.x, other.y))
+            .collect::<Vec<_>>();
+        for (ent, x, y) in returning {
+            other_positions.remove(ent);
+            positions
+                .insert(ent, Position { x, y })
+                .expect("Unable to insert position");
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{room_map, spawn_monster, test_world};
+
+    #[test]
+    fn stored_level_keeps_its_monsters_until_the_player_returns() {
+        let mut world = test_world(10, 10, (2, 2));
+        let player_ent = *world.fetch::<Entity>();
+        let monster = spawn_monster(&mut world, (5, 6), 5, 1);
+
+        DungeonMaster::store_level(&world);
+        assert!(world.read_storage::<Position>().get(monster).is_none());
+        let left_at = world
+            .read_storage::<OtherLevelPosition>()
+            .get(monster)
+            .map(|pos| (pos.x, pos.y, pos.depth));
+        assert_eq!(left_at, Some((5, 6, 1)));
+        assert!(world.read_storage::<Position>().contains(player_ent));
+
+        world.insert(room_map(10, 10, 2));
+        assert!(!DungeonMaster::get_level(&mut world, 3));
+        assert!(DungeonMaster::get_level(&mut world, 1));
+        assert_eq!(world.fetch::<Map>().depth, 1);
+        let back_at = world
+            .read_storage::<Position>()
+            .get(monster)
+            .map(|pos| (pos.x, pos.y));
+        assert_eq!(back_at, Some((5, 6)));
+        assert!(!world.read_storage::<OtherLevelPosition>().contains(monster));
+    }
+}
@@ -1,5 +1,8 @@
-use crate::map_builder::map::Map;
-use rltk::ColorPair;
+use crate::{
+    difficulty::Difficulty, dungeon_master::DungeonMaster, game_seed::GameSeed,
+    identification::Identification, map_builder::map::Map, run_summary::RunSummary,
+};
+use rltk::{ColorPair, RGB, RGBA};
 use serde::{Deserialize, Serialize};
 use specs::{
     error::NoError,
@@ -7,6 +10,7 @@ use specs::{
     saveload::{ConvertSaveload, Marker},
 };
 use specs_derive::*;
+use strum::AsRefStr;
 
 //Components are organized by who they are **TYPICALLY** assigned to.
 //(N)PC
@@ -25,61 +29,279 @@ pub struct Position {
     pub y: i32,
 }
 
+///Takes the place of the position of entities left behind on another level
+#[derive(Component, ConvertSaveload, Clone)]
+pub struct OtherLevelPosition {
+    pub x: i32,
+    pub y: i32,
+    pub depth: i32,
+}
+
 #[derive(Component, ConvertSaveload, Clone)]
 pub struct Render {
     pub glyph: rltk::FontCharType,
     pub colors: ColorPair,
+    ///Of the entities sharing a tile, the lowest order is drawn on top: 0 for particles,
+    ///1 for the player and monsters, 2 for items and 3 for traps
     pub render_order: i32,
 }
 
+#[derive(Component, Serialize, Deserialize, Clone)]
+pub struct LightSource {
+    pub radius: i32,
+    pub color: RGB,
+}
+
 pub struct SerializeMe;
 
 #[derive(Component, Serialize, Deserialize, Clone)]
 pub struct SerializationHelper {
     pub map: Map,
+    pub identification: Identification,
+    pub difficulty: Difficulty,
+    pub seed: GameSeed,
+    pub dungeon: DungeonMaster,
+    pub summary: RunSummary,
 }
 
 //(N)PC Components
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct BlocksTile {}
 
-#[derive(Component, Serialize, Deserialize, Clone)]
-pub struct Player {}
+///Guardian of a boss level, whose death opens the way down
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Boss {}
+
+///Footprint of creatures taking up more than one tile, which spans right and down from their
+///position
+#[derive(Component, Debug, Serialize, Deserialize, PartialEq, Eq, Copy, Clone)]
+pub struct Size {
+    pub w: i32,
+    pub h: i32,
+}
+
+impl Default for Size {
+    fn default() -> Self {
+        Self { w: 1, h: 1 }
+    }
+}
+
+impl Size {
+    ///Every tile taken up when standing with the top left corner on the anchor
+    pub fn tiles(self, anchor: rltk::Point) -> impl Iterator<Item = rltk::Point> {
+        (anchor.y..anchor.y + self.h)
+            .flat_map(move |y| (anchor.x..anchor.x + self.w).map(move |x| rltk::Point::new(x, y)))
+    }
+}
+
+#[derive(Component, Serialize, Deserialize, Clone, Default)]
+pub struct Player {
+    #[serde(default)]
+    pub quiet_waits: i32,
+}
 
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct Monster {}
 
+///Side a creature fights on, which decides who it attacks and who attacks it
+#[derive(Component, Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Faction {
+    Player,
+    Monster,
+    ///Leaves everyone alone until hurt, after which it fights as a monster
+    Neutral,
+    Ally,
+}
+
+impl Faction {
+    ///Whether creatures of this faction attack creatures of the other on sight
+    pub const fn is_hostile_to(self, other: Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::Monster, Self::Player | Self::Ally) | (Self::Player | Self::Ally, Self::Monster)
+        )
+    }
+
+    ///Whether both factions fight on the same side, neutral creatures have no side
+    pub const fn is_friendly_to(self, other: Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::Player | Self::Ally, Self::Player | Self::Ally) | (Self::Monster, Self::Monster)
+        )
+    }
+}
+
+//Noise made by moving normally and while sneaking, which scales how close monsters notice from
+const NOISE_WALKING: i32 = 2;
+const NOISE_SNEAKING: i32 = 1;
+
+///How well a creature avoids the notice of monsters, and whether it is currently sneaking
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Stealth {
+    pub skill: i32,
+    pub sneaking: bool,
+}
+
+impl Stealth {
+    pub const fn noise_level(&self) -> i32 {
+        if self.sneaking {
+            NOISE_SNEAKING
+        } else {
+            NOISE_WALKING
+        }
+    }
+
+    ///Aggro range monsters notice this creature from, shrunk by noise and skill but never below one
+    pub fn aggro_range_against(&self, aggro_range: i32) -> i32 {
+        i32::max(
+            1,
+            aggro_range * self.noise_level() / NOISE_WALKING - self.skill,
+        )
+    }
+}
+
+///Creature called in by a summoning item, which vanishes once its turns run out
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Summoned {
+    pub turns_remaining: i32,
+}
+
+///Percent chance of leaving an item behind on death
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct DropsLoot {
+    pub chance: i32,
+}
+
+///A unique variant of a monster, carrying a generated name in place of the name of its kind
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Elite {
+    pub kind: String,
+}
+
+///Marks a monster the player has seen, so it is only added to the bestiary once
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Sighted {}
+
+///How close a foe has to be for a monster to take notice of it, and where the monster last saw the
+///foe it is after. Foes are looked for there for a few turns after they are lost from sight
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Aggro {
+    pub range: i32,
+    pub last_known_position: Option<rltk::Point>,
+    pub pursuit_turns: i32,
+}
+
+///The path a monster is following and the tile it leads to, so that the path is only searched for
+///again once the target moves or the way is blocked. Steps are kept last first
+#[derive(Component, Debug, Serialize, Deserialize, Clone, Default)]
+pub struct PathCache {
+    pub target: Option<rltk::Point>,
+    pub steps: Vec<usize>,
+}
+
+///Fraction of its max HP below which a monster runs from the player, monsters without it never flee
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct FleeThreshold {
+    pub fraction: f32,
+}
+
 #[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct CombatStats {
     pub max_hp: i32,
     pub hp: i32,
     pub defense: i32,
     pub power: i32,
+    ///Percent chance of a hit dealing double damage, before any weapon bonus
+    pub crit_chance: i32,
 }
 
-#[derive(Component, Debug, ConvertSaveload, Clone)]
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct EntityMoved {}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct EntryTrigger {}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Hidden {}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct SingleActivation {}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Initiative {
+    pub current: i32,
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Speed {
+    pub speed: i32,
+}
+
+///Damage waiting to be dealt, each amount with whoever dealt it if anyone did. It is never saved,
+///as the damage system deals and clears it within the turn it was suffered
+#[derive(Component, Debug, Clone)]
 pub struct SufferDamage {
-    pub amount: Vec<i32>,
+    pub amount: Vec<(i32, Option<Entity>)>,
 }
 
 impl SufferDamage {
-    pub fn new_damage(store: &mut WriteStorage<'_, Self>, victim: Entity, amount: i32) {
+    pub fn new_damage(
+        store: &mut WriteStorage<'_, Self>,
+        victim: Entity,
+        amount: i32,
+        source: Option<Entity>,
+    ) {
         if let Some(suffering) = store.get_mut(victim) {
-            suffering.amount.push(amount);
+            suffering.amount.push((amount, source));
         } else {
             let dmg = Self {
-                amount: vec![amount],
+                amount: vec![(amount, source)],
             };
             store.insert(victim, dmg).expect("Unable to insert damage");
         }
     }
 }
 
+#[derive(PartialEq, Eq, Copy, Clone, Serialize, Deserialize, AsRefStr)]
+pub enum HungerState {
+    #[strum(serialize = "Well Fed")]
+    WellFed,
+    Normal,
+    Hungry,
+    Starving,
+}
+
+#[derive(Component, Serialize, Deserialize, Clone)]
+pub struct Experience {
+    pub current: i32,
+    pub level: i32,
+}
+
+///Gold carried, filled by walking over gold piles
+#[derive(Component, Serialize, Deserialize, Clone, Default)]
+pub struct Wallet {
+    pub gold: i32,
+}
+
+///Arrows in a quiver, used up one per shot, or in a bundle lying around until it is picked up
+#[derive(Component, Serialize, Deserialize, Clone, Default)]
+pub struct Ammo {
+    pub count: i32,
+}
+
+#[derive(Component, Serialize, Deserialize, Clone)]
+pub struct HungerClock {
+    pub state: HungerState,
+    pub duration: i32,
+}
+
 #[derive(Component, ConvertSaveload, Clone)]
 pub struct FieldOfView {
     pub visible_tiles: Vec<rltk::Point>,
     pub range: i32,
     pub is_dirty: bool,
+    ///Lights the view was last computed with, on dark levels it is only recomputed when these change
+    pub lights: Vec<(rltk::Point, i32)>,
 }
 
 #[derive(Component, Debug, ConvertSaveload, Clone)]
@@ -103,6 +325,26 @@ pub struct WantsToRemoveItem {
     pub item: Entity,
 }
 
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct WantsToShoot {
+    pub target: rltk::Point,
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct WantsToTeleport {}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct WantsToSummon {
+    pub name: String,
+}
+
+///Throws a carried item at the target, whatever the item is
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct WantsToThrow {
+    pub item: Entity,
+    pub target: rltk::Point,
+}
+
 #[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct WantsToUseItem {
     pub item: Entity,
@@ -113,9 +355,20 @@ pub struct WantsToUseItem {
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct Item {}
 
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Unidentified {
+    pub appearance: String,
+}
+
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct Consumable {}
 
+///How heavy an item is, which makes it hit harder when thrown
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Weight {
+    pub pounds: i32,
+}
+
 #[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct AreaOfEffect {
     pub radius: i32,
@@ -126,11 +379,81 @@ pub struct Confusion {
     pub turns: i32,
 }
 
+///Rounds left before the flames go out, dealing damage every round
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct OnFire {
+    pub turns: i32,
+}
+
+///Sets whatever it hits on fire for that many rounds
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct InflictsBurning {
+    pub turns: i32,
+}
+
+///Damage taken every round until the poison wears off
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct Poison {
+    pub damage_per_turn: i32,
+    pub turns: i32,
+}
+
+///Poisons whoever it hits in melee, for a monster its own attacks and for a weapon its wielder's
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct InflictsPoison {
+    pub damage_per_turn: i32,
+    pub turns: i32,
+}
+
+///Cures its user of poison
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct ProvidesCure {}
+
+///Catches fire from burning neighbors
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Flammable {}
+
 #[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct InflictsDamage {
     pub damage: i32,
 }
 
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Potion {}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Scroll {}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct ProvidesNutrition {}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct ProvidesMapReveal {}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct ProvidesTeleport {}
+
+///Uses left in a reusable item, which stays in the backpack once they run out
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct Charges {
+    pub current: i32,
+    pub max: i32,
+}
+
+///Lifts the curse of everything its user has equipped
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct ProvidesRemoveCurse {}
+
+///Equipment that can not be taken off once equipped, until its curse is removed
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Cursed {}
+
+///Name of the creature the item calls to fight for its user
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct SummonsAlly {
+    pub name: String,
+}
+
 #[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct ProvidesHealing {
     pub heal_amount: i32,
@@ -141,21 +464,27 @@ pub struct Range {
     pub range: i32,
 }
 
+///The letter is kept for as long as the item stays in the backpack, so it is always listed under
+///the same one
 #[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct InBackpack {
     pub owner: Entity,
+    pub letter: char,
 }
 
-#[derive(PartialEq, Copy, Clone, Serialize, Deserialize)]
+#[derive(PartialEq, Eq, Copy, Clone, Serialize, Deserialize, AsRefStr)]
 pub enum EquipmentSlot {
     Head,
     Neck,
     Torso,
     Body,
+    #[strum(serialize = "Primary Hand")]
     PrimaryHand,
+    #[strum(serialize = "Off Hand")]
     OffHand,
     Feet,
     Finger,
+    Ranged,
 }
 
 #[derive(Component, Serialize, Deserialize, Clone)]
@@ -169,9 +498,12 @@ pub struct Equipped {
     pub slot: EquipmentSlot,
 }
 
+///Light weapons strike several times each turn, every hit dealing the full damage
 #[derive(Component, ConvertSaveload, Clone)]
 pub struct MeleeDamageBonus {
     pub bonus: i32,
+    pub attacks_per_turn: i32,
+    pub crit_bonus: i32,
 }
 
 #[derive(Component, ConvertSaveload, Clone)]
@@ -179,8 +511,81 @@ pub struct DefenseBonus {
     pub bonus: i32,
 }
 
+#[derive(Component, ConvertSaveload, Clone)]
+pub struct RangedWeapon {
+    pub range: i32,
+    pub damage: i32,
+    pub crit_bonus: i32,
+}
+
+///A pile of coins, taken into the wallet of whoever steps on it
+#[derive(Component, Serialize, Deserialize, Clone)]
+pub struct Gold {
+    pub amount: i32,
+}
+
 //Particles
 #[derive(Component, Serialize, Deserialize, Clone)]
 pub struct ParticleLifetime {
     pub lifetime_ms: f32,
+    ///Lifetime the particle started with, telling how far through its life it is
+    pub total_ms: f32,
+    ///Colors the glyph fades from and to over the particle's life, if it changes at all
+    pub fade: Option<(RGBA, RGBA)>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FACTIONS: [Faction; 4] = [
+        Faction::Player,
+        Faction::Monster,
+        Faction::Neutral,
+        Faction::Ally,
+    ];
+
+    #[test]
+    fn monsters_and_the_players_side_are_hostile_to_each_other() {
+        let hostile = FACTIONS
+            .iter()
+            .flat_map(|a| FACTIONS.iter().map(move |b| (*a, *b)))
+            .filter(|(a, b)| a.is_hostile_to(*b))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            hostile,
+            vec![
+                (Faction::Player, Faction::Monster),
+                (Faction::Monster, Faction::Player),
+                (Faction::Monster, Faction::Ally),
+                (Faction::Ally, Faction::Monster),
+            ]
+        );
+    }
+
+    #[test]
+    fn neutral_creatures_have_no_side() {
+        for other in FACTIONS {
+            assert!(!Faction::Neutral.is_hostile_to(other));
+            assert!(!Faction::Neutral.is_friendly_to(other));
+            assert!(!other.is_friendly_to(Faction::Neutral));
+        }
+        assert!(Faction::Ally.is_friendly_to(Faction::Player));
+        assert!(Faction::Monster.is_friendly_to(Faction::Monster));
+        assert!(!Faction::Monster.is_friendly_to(Faction::Ally));
+    }
+
+    #[test]
+    fn sneaking_shrinks_the_range_monsters_notice_from() {
+        let mut stealth = Stealth {
+            skill: 1,
+            sneaking: false,
+        };
+        assert_eq!(stealth.aggro_range_against(6), 5);
+        stealth.sneaking = true;
+        assert_eq!(stealth.aggro_range_against(6), 2);
+        //Even the stealthiest creature is noticed right next to a monster
+        stealth.skill = 10;
+        assert_eq!(stealth.aggro_range_against(6), 1);
+    }
 }
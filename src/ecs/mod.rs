@@ -1,52 +1,331 @@
 pub mod components;
 mod systems;
 pub use components::*;
+use specs::{
+    shred::{Accessor, ResourceId},
+    Dispatcher, DispatcherBuilder, System,
+};
+pub use systems::carried_weight;
+pub use systems::carry_capacity;
 pub use systems::cull_dead_characters;
 pub use systems::cull_dead_particles;
+pub use systems::effective_combat_stats;
+pub use systems::monsters_ready;
+pub use systems::DeadEntities;
 pub use systems::ParticleBuilder;
+pub use systems::ACTION_COST;
+pub use systems::BASE_CRIT_CHANCE;
+pub use systems::HUNGER_STATE_DURATION;
+pub use systems::THROW_RANGE;
+pub use systems::XP_PER_LEVEL;
 
 pub mod pre_run_systems {
+    use super::OrderedDispatcher;
     use crate::ecs::systems;
-    use specs::{RunNow, WorldExt};
+    use specs::{Dispatcher, WorldExt};
+    use std::cell::RefCell;
 
-    pub fn execute(world: &mut specs::World) {
-        let mut vis = systems::VisibilitySystem {};
-        let mut map_index = systems::MapIndexingSystem {};
+    thread_local! {
+        static DISPATCHER: RefCell<Dispatcher<'static, 'static>> = RefCell::new(
+            OrderedDispatcher::new()
+                .with(systems::VisibilitySystem {}, "visibility")
+                .with(systems::MapIndexingSystem {}, "map_index")
+                .build()
+        );
+    }
 
-        vis.run_now(world);
-        map_index.run_now(world);
+    pub fn execute(world: &mut specs::World) {
+        DISPATCHER.with(|dispatcher| dispatcher.borrow_mut().dispatch(world));
 
         world.maintain();
     }
 }
 
 pub mod all_systems {
+    use super::{OrderedDispatcher, SystemList};
     use crate::ecs::systems;
-    use specs::{RunNow, WorldExt};
+    use specs::{Dispatcher, WorldExt};
+    use std::cell::RefCell;
+
+    thread_local! {
+        static BEFORE_SUMMONING: RefCell<Dispatcher<'static, 'static>> =
+            RefCell::new(before_summoning(OrderedDispatcher::new()).build());
+        static AFTER_SUMMONING: RefCell<Dispatcher<'static, 'static>> =
+            RefCell::new(after_summoning(OrderedDispatcher::new()).build());
+    }
+
+    //Summoning needs the whole world, so the turn is split into the systems before and after it
+    pub(super) fn before_summoning<L: SystemList>(list: L) -> L {
+        //Indexed first, so every move this turn starts from where everything actually stands
+        list.with(systems::MapIndexingSystem {}, "map_index")
+            .with(systems::VisibilitySystem {}, "visibility")
+            .with(systems::BestiarySystem {}, "bestiary")
+            .with(systems::TrapDetectionSystem {}, "trap_detection")
+            .with(systems::InitiativeSystem {}, "initiative")
+            .with(systems::ConfusionSystem {}, "confusion")
+            .with(systems::SummonExpirySystem {}, "summon_expiry")
+            .with(systems::MonsterAI {}, "monster_ai")
+            .with(systems::AllyAI {}, "ally_ai")
+            .with(systems::MapIndexingSystem {}, "map_index_after_moves")
+            .with(systems::TerrainSystem {}, "terrain")
+            .with(systems::TriggerSystem {}, "triggers")
+            .with(systems::GoldCollectionSystem {}, "gold")
+            .with(systems::MeleeCombatSystem {}, "melee")
+            .with(systems::RangedCombatSystem {}, "ranged")
+            .with(systems::ThrowSystem {}, "throws")
+            .with(systems::HungerSystem {}, "hunger")
+            .with(systems::FireSystem {}, "fire")
+            .with(systems::PoisonSystem {}, "poison")
+            .with(systems::DamageSystem {}, "damage")
+            .with(systems::ItemCollectionSystem {}, "pickup_items")
+            .with(systems::ItemUseSystem {}, "use_items")
+    }
+
+    pub(super) fn after_summoning<L: SystemList>(list: L) -> L {
+        list.with(systems::TeleportSystem {}, "teleports")
+            .with(systems::ItemDropSystem {}, "drop_items")
+            .with(systems::ItemRemoveSystem {}, "remove_items")
+            .with(systems::ParticleSpawnSystem {}, "particles")
+    }
 
     pub fn execute(world: &mut specs::World) {
-        let mut vis = systems::VisibilitySystem {};
-        let mut map_index = systems::MapIndexingSystem {};
-        let mut mons = systems::MonsterAI {};
-        let mut melee = systems::MeleeCombatSystem {};
-        let mut damage = systems::DamageSystem {};
-        let mut pickup_items = systems::ItemCollectionSystem {};
-        let mut use_items = systems::ItemUseSystem {};
-        let mut drop_items = systems::ItemDropSystem {};
-        let mut rem_items = systems::ItemRemoveSystem {};
-        let mut particles = systems::ParticleSpawnSystem {};
-
-        vis.run_now(world);
-        mons.run_now(world);
-        map_index.run_now(world);
-        melee.run_now(world);
-        damage.run_now(world);
-        pickup_items.run_now(world);
-        use_items.run_now(world);
-        drop_items.run_now(world);
-        rem_items.run_now(world);
-        particles.run_now(world);
+        BEFORE_SUMMONING.with(|dispatcher| dispatcher.borrow_mut().dispatch(world));
+        systems::summon_allies(world);
+        AFTER_SUMMONING.with(|dispatcher| dispatcher.borrow_mut().dispatch(world));
 
         world.maintain();
     }
 }
+
+///Systems added one after another, in the order they have to run in
+trait SystemList {
+    fn with<S>(self, system: S, name: &'static str) -> Self
+    where
+        S: for<'a> System<'a> + Send + 'static;
+}
+
+///Builds a dispatcher that keeps the order systems are added in wherever it matters. Each system
+///depends on every system added before it that writes what it uses or uses what it writes, so only
+///systems that share nothing run in parallel
+struct OrderedDispatcher {
+    builder: DispatcherBuilder<'static, 'static>,
+    added: Vec<(&'static str, Vec<ResourceId>, Vec<ResourceId>)>,
+}
+
+impl OrderedDispatcher {
+    fn new() -> Self {
+        Self {
+            builder: DispatcherBuilder::new(),
+            added: Vec::new(),
+        }
+    }
+
+    fn with<S>(mut self, system: S, name: &'static str) -> Self
+    where
+        S: for<'a> System<'a> + Send + 'static,
+    {
+        let (reads, writes) = {
+            let accessor = system.accessor();
+            (accessor.reads(), accessor.writes())
+        };
+        let deps = self.dependencies(&reads, &writes);
+        self.builder.add(system, name, &deps);
+        self.added.push((name, reads, writes));
+        self
+    }
+
+    ///Names of the systems added so far that a system using the given data has to wait for
+    fn dependencies(&self, reads: &[ResourceId], writes: &[ResourceId]) -> Vec<&'static str> {
+        self.added
+            .iter()
+            .filter(|(_, earlier_reads, earlier_writes)| {
+                earlier_writes
+                    .iter()
+                    .any(|id| reads.contains(id) || writes.contains(id))
+                    || writes.iter().any(|id| earlier_reads.contains(id))
+            })
+            .map(|(earlier, ..)| *earlier)
+            .collect()
+    }
+
+    fn build(self) -> Dispatcher<'static, 'static> {
+        self.builder.build()
+    }
+}
+
+impl SystemList for OrderedDispatcher {
+    fn with<S>(self, system: S, name: &'static str) -> Self
+    where
+        S: for<'a> System<'a> + Send + 'static,
+    {
+        Self::with(self, system, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        game_log::GameLog,
+        state::{Gameplay, State},
+        test_support::{spawn_monster, test_world},
+    };
+    use specs::{prelude::*, RunNow};
+
+    #[derive(Default)]
+    struct Turns(u32);
+
+    #[derive(Default)]
+    struct Log(Vec<&'static str>);
+
+    struct CountTurn;
+    impl<'a> System<'a> for CountTurn {
+        type SystemData = Write<'a, Turns>;
+
+        fn run(&mut self, mut turns: Self::SystemData) {
+            turns.0 += 1;
+        }
+    }
+
+    struct ReadTurn;
+    impl<'a> System<'a> for ReadTurn {
+        type SystemData = (Read<'a, Turns>, Write<'a, Log>);
+
+        fn run(&mut self, (_, mut log): Self::SystemData) {
+            log.0.push("read turn");
+        }
+    }
+
+    struct WriteLog;
+    impl<'a> System<'a> for WriteLog {
+        type SystemData = Write<'a, Log>;
+
+        fn run(&mut self, mut log: Self::SystemData) {
+            log.0.push("write log");
+        }
+    }
+
+    fn data<S: for<'a> System<'a>>(system: &S) -> (Vec<ResourceId>, Vec<ResourceId>) {
+        let accessor = system.accessor();
+        (accessor.reads(), accessor.writes())
+    }
+
+    #[test]
+    fn systems_wait_only_for_earlier_ones_sharing_their_data() {
+        let dispatcher = OrderedDispatcher::new().with(CountTurn, "count");
+        let (reads, writes) = data(&WriteLog);
+        assert!(dispatcher.dependencies(&reads, &writes).is_empty());
+
+        let dispatcher = dispatcher.with(WriteLog, "write_log");
+        let (reads, writes) = data(&ReadTurn);
+        assert_eq!(
+            dispatcher.dependencies(&reads, &writes),
+            vec!["count", "write_log"]
+        );
+
+        //The order they were added in is kept where they share data
+        let mut world = World::new();
+        world.insert(Turns::default());
+        world.insert(Log::default());
+        dispatcher
+            .with(ReadTurn, "read_turn")
+            .build()
+            .dispatch(&world);
+        assert_eq!(world.fetch::<Turns>().0, 1);
+        assert_eq!(world.fetch::<Log>().0, vec!["write log", "read turn"]);
+    }
+
+    ///Runs every system of a list on its own, one after another
+    struct RunEach(Vec<Box<dyn for<'a> RunNow<'a>>>);
+
+    impl SystemList for RunEach {
+        fn with<S>(mut self, system: S, _: &'static str) -> Self
+        where
+            S: for<'a> System<'a> + Send + 'static,
+        {
+            self.0.push(Box::new(system));
+            self
+        }
+    }
+
+    ///A fight between the player and a few monsters, some of them confused or poisoned so the
+    ///systems sharing the random number generator all draw from it
+    fn fight() -> World {
+        crate::raws::spawn::load().unwrap();
+        let mut world = test_world(16, 16, (8, 8));
+        for (i, pos) in [(7, 7), (9, 9), (11, 8), (5, 10)].iter().enumerate() {
+            let monster = spawn_monster(&mut world, *pos, 12, 2);
+            if i % 2 == 0 {
+                world
+                    .write_storage()
+                    .insert(monster, Confusion { turns: 4 })
+                    .unwrap();
+            } else {
+                let poison = Poison {
+                    damage_per_turn: 1,
+                    turns: 3,
+                };
+                world.write_storage().insert(monster, poison).unwrap();
+            }
+        }
+        pre_run_systems::execute(&mut world);
+        world
+    }
+
+    ///Position, health and remaining effects of every entity, along with the next roll
+    fn outcome(world: &World) -> (Vec<String>, i32) {
+        let entities = world.entities();
+        let positions = world.read_storage::<Position>();
+        let stats = world.read_storage::<CombatStats>();
+        let confusions = world.read_storage::<Confusion>();
+        let poisons = world.read_storage::<Poison>();
+        let described = (&entities, &positions, stats.maybe())
+            .join()
+            .map(|(ent, pos, stats)| {
+                format!(
+                    "{:?} at {},{} hp {:?} confused {:?} poisoned {:?}",
+                    ent,
+                    pos.x,
+                    pos.y,
+                    stats.map(|stats| stats.hp),
+                    confusions.get(ent).map(|confusion| confusion.turns),
+                    poisons.get(ent).map(|poison| poison.turns),
+                )
+            })
+            .collect();
+        let roll = world
+            .write_resource::<rltk::RandomNumberGenerator>()
+            .roll_dice(1, 1000);
+        (described, roll)
+    }
+
+    #[test]
+    fn dispatched_turns_end_as_if_run_one_system_at_a_time() {
+        let rounds = [Gameplay::PlayerTurn, Gameplay::MonsterTurn].repeat(4);
+
+        let mut dispatched = fight();
+        for round in &rounds {
+            dispatched.insert(State::Game(*round));
+            all_systems::execute(&mut dispatched);
+        }
+
+        let mut sequential = fight();
+        let mut before = all_systems::before_summoning(RunEach(Vec::new()));
+        let mut after = all_systems::after_summoning(RunEach(Vec::new()));
+        for round in &rounds {
+            sequential.insert(State::Game(*round));
+            for system in &mut before.0 {
+                system.run_now(&sequential);
+            }
+            systems::summon_allies(&mut sequential);
+            for system in &mut after.0 {
+                system.run_now(&sequential);
+            }
+            sequential.maintain();
+        }
+
+        let log_len = sequential.fetch::<GameLog>().recent(100).count();
+        assert!(log_len > 0);
+        assert_eq!(outcome(&dispatched), outcome(&sequential));
+    }
+}
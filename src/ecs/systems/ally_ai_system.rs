@@ -0,0 +1,143 @@
+use super::{
+    monster_ai_system::{closest_visible, creatures_by_faction, hostile_to, step_towards},
+    ACTION_COST,
+};
+use crate::{
+    components::{EntityMoved, Faction, FieldOfView, Initiative, Position, Size, WantsToMelee},
+    map_builder::map::Map,
+    state::{Gameplay, State, State::Game},
+};
+use rltk::Point;
+use specs::prelude::*;
+
+//How far allies let the player get before following when there is nothing to fight
+const FOLLOW_DISTANCE: f32 = 2.0;
+
+pub struct AllyAI {}
+
+impl<'a> System<'a> for AllyAI {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Point>,
+        ReadExpect<'a, State>,
+        ReadStorage<'a, Faction>,
+        ReadStorage<'a, Size>,
+        WriteExpect<'a, Map>,
+        WriteStorage<'a, EntityMoved>,
+        WriteStorage<'a, FieldOfView>,
+        WriteStorage<'a, Initiative>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, WantsToMelee>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            player_pos,
+            state,
+            factions,
+            sizes,
+            mut map,
+            mut moved,
+            mut fields_of_view,
+            mut initiatives,
+            mut positions,
+            mut attacks,
+        ) = data;
+
+        if *state != Game(Gameplay::MonsterTurn) {
+            return;
+        }
+
+        let foes = hostile_to(
+            Faction::Ally,
+            &creatures_by_faction(&entities, &factions, &positions, &sizes),
+        );
+
+        for (ent, fov, pos, _) in (&entities, &mut fields_of_view, &mut positions, &factions)
+            .join()
+            .filter(|(.., faction)| **faction == Faction::Ally)
+        {
+            //Allies act on the same initiative as monsters
+            if let Some(initiative) = initiatives.get_mut(ent) {
+                if initiative.current > 0 {
+                    continue;
+                }
+                initiative.current += ACTION_COST;
+            }
+
+            let here = Point::new(pos.x, pos.y);
+            let size = sizes.get(ent).copied().unwrap_or_default();
+            let distance = |point| rltk::DistanceAlg::Pythagoras.distance2d(here, point);
+
+            //Fight the closest monster in sight, or else keep up with the player
+            let has_moved = match closest_visible(here, fov, &foes) {
+                Some((target, target_pos)) if distance(target_pos) < 2.0 => {
+                    attacks
+                        .insert(ent, WantsToMelee { target })
+                        .expect("Unable to insert attack");
+                    false
+                }
+                Some((_, target_pos)) => {
+                    step_towards(pos, fov, &mut map, target_pos, *player_pos, size)
+                }
+                None if distance(*player_pos) > FOLLOW_DISTANCE => {
+                    step_towards(pos, fov, &mut map, *player_pos, *player_pos, size)
+                }
+                None => false,
+            };
+            if has_moved {
+                moved
+                    .insert(ent, EntityMoved {})
+                    .expect("Unable to insert movement marker");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        components::Monster,
+        ecs::pre_run_systems,
+        test_support::{spawn_monster, test_world},
+    };
+
+    ///A monster that has been turned to the player's side
+    fn spawn_ally(world: &mut World, pos: (i32, i32)) -> Entity {
+        let ally = spawn_monster(world, pos, 10, 2);
+        world.write_storage::<Monster>().remove(ally);
+        world.write_storage().insert(ally, Faction::Ally).unwrap();
+        ally
+    }
+
+    #[test]
+    fn ally_attacks_the_monster_beside_it_and_not_the_player() {
+        let mut world = test_world(12, 12, (4, 5));
+        let ally = spawn_ally(&mut world, (5, 5));
+        let monster = spawn_monster(&mut world, (6, 5), 10, 2);
+        pre_run_systems::execute(&mut world);
+        world.insert(State::Game(Gameplay::MonsterTurn));
+
+        AllyAI {}.run_now(&world);
+        let target = world
+            .read_storage::<WantsToMelee>()
+            .get(ally)
+            .map(|attack| attack.target);
+        assert_eq!(target, Some(monster));
+    }
+
+    #[test]
+    fn ally_follows_the_player_with_nothing_to_fight() {
+        let mut world = test_world(12, 12, (2, 5));
+        let ally = spawn_ally(&mut world, (7, 5));
+        pre_run_systems::execute(&mut world);
+        world.insert(State::Game(Gameplay::MonsterTurn));
+
+        AllyAI {}.run_now(&world);
+        let x = world.read_storage::<Position>().get(ally).map(|pos| pos.x);
+        assert_eq!(x, Some(6));
+    }
+}
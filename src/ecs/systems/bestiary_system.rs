@@ -0,0 +1,67 @@
+use crate::{
+    bestiary::Bestiary,
+    components::{Elite, FieldOfView, Hidden, Monster, Name, Position, Sighted},
+    map_builder::map::Map,
+};
+use rltk::Point;
+use specs::prelude::*;
+
+///Adds each monster to the bestiary the first time the player sees it
+pub struct BestiarySystem {}
+
+impl<'a> System<'a> for BestiarySystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, Map>,
+        ReadStorage<'a, Elite>,
+        ReadStorage<'a, FieldOfView>,
+        ReadStorage<'a, Hidden>,
+        ReadStorage<'a, Monster>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Position>,
+        WriteExpect<'a, Bestiary>,
+        WriteStorage<'a, Sighted>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            player_ent,
+            map,
+            elites,
+            fields_of_view,
+            hidden,
+            monsters,
+            names,
+            positions,
+            mut bestiary,
+            mut sighted,
+        ) = data;
+
+        let Some(player_fov) = fields_of_view.get(*player_ent) else {
+            return;
+        };
+
+        let newly_seen = (
+            &entities, &monsters, &names, &positions, !&hidden, !&sighted,
+        )
+            .join()
+            .filter(|(_, _, _, pos, (), ())| {
+                player_fov.visible_tiles.contains(&Point::new(pos.x, pos.y))
+            })
+            //Elites are filed under their kind rather than their own name
+            .map(|(ent, _, name, ..)| {
+                let kind = elites.get(ent).map_or(&name.name, |elite| &elite.kind);
+                (ent, kind.clone())
+            })
+            .collect::<Vec<_>>();
+        for (ent, name) in newly_seen {
+            bestiary.record_sighting(&name, map.depth);
+            sighted
+                .insert(ent, Sighted {})
+                .expect("Unable to insert sighted marker");
+        }
+    }
+}
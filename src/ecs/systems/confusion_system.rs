@@ -0,0 +1,90 @@
+use crate::{
+    components::Confusion,
+    game_log::GameLog,
+    state::{Gameplay, State, State::Game},
+};
+use specs::prelude::*;
+
+///Counts down the player's confusion once per round, whatever they did with their turn. Monsters
+///count theirs down as they act
+pub struct ConfusionSystem {}
+
+impl<'a> System<'a> for ConfusionSystem {
+    type SystemData = (
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, State>,
+        WriteExpect<'a, GameLog>,
+        WriteStorage<'a, Confusion>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (player_ent, state, mut logs, mut confusions) = data;
+
+        //Rounds begin with the players turn
+        if *state != Game(Gameplay::PlayerTurn) {
+            return;
+        }
+
+        let Some(confusion) = confusions.get_mut(*player_ent) else {
+            return;
+        };
+        confusion.turns -= 1;
+        if confusion.turns < 1 {
+            confusions.remove(*player_ent);
+            logs.push(&"You are no longer confused.");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        components::{EntityMoved, EntryTrigger, Name, Position},
+        ecs::{pre_run_systems, systems::TriggerSystem},
+        test_support::test_world,
+    };
+
+    #[test]
+    fn confusion_trap_confuses_the_player_until_it_wears_off() {
+        let mut world = test_world(10, 10, (4, 4));
+        let player_ent = *world.fetch::<Entity>();
+        world
+            .create_entity()
+            .with(Position { x: 4, y: 4 })
+            .with(Name {
+                name: "Confusion Trap".to_string(),
+            })
+            .with(EntryTrigger {})
+            .with(Confusion { turns: 2 })
+            .build();
+        pre_run_systems::execute(&mut world);
+        world
+            .write_storage()
+            .insert(player_ent, EntityMoved {})
+            .unwrap();
+        TriggerSystem {}.run_now(&world);
+        world.maintain();
+        let turns = |world: &World| {
+            world
+                .read_storage::<Confusion>()
+                .get(player_ent)
+                .map(|confusion| confusion.turns)
+        };
+        assert_eq!(turns(&world), Some(2));
+
+        //Monster turns in between do not count, and the player need not move for it to wear off
+        let mut left = Vec::new();
+        for _ in 0..3 {
+            ConfusionSystem {}.run_now(&world);
+            world.insert(Game(Gameplay::MonsterTurn));
+            ConfusionSystem {}.run_now(&world);
+            world.insert(Game(Gameplay::PlayerTurn));
+            left.push(turns(&world));
+        }
+        assert_eq!(left, vec![Some(1), None, None]);
+        let logs = world.fetch::<GameLog>();
+        let (message, _) = logs.recent(1).next().unwrap();
+        assert_eq!(message, "You are no longer confused.");
+    }
+}
@@ -1,54 +1,355 @@
-use crate::state::{Gameplay, State};
-use crate::{CombatStats, GameLog, Name, Player, State::Game, SufferDamage};
+use super::ParticleBuilder;
+use crate::bestiary::Bestiary;
+use crate::combat;
+use crate::constants::colors;
+use crate::map_builder::map::{Map, TileType};
+use crate::meta_stats::MetaStats;
+use crate::run_summary::RunSummary;
+use crate::spawning;
+use crate::{Boss, CombatStats, Elite, Experience, Faction, GameLog, Name, Position, SufferDamage};
+use rltk::{ColorPair, Point, RandomNumberGenerator, RGB};
 use specs::prelude::*;
 
+pub const XP_PER_LEVEL: i32 = 100;
+pub const BASE_CRIT_CHANCE: i32 = 5;
+const HP_PER_LEVEL: i32 = 10;
+
+///Rolls whether a hit is critical, returning the damage it deals
+pub fn roll_critical(
+    rng: &mut RandomNumberGenerator,
+    crit_chance: i32,
+    damage: i32,
+) -> (i32, bool) {
+    if damage <= 0 {
+        return (damage, false);
+    }
+    combat::apply_critical(damage, crit_chance, rng.roll_dice(1, 100))
+}
+
+///Creatures that died this turn, in the order they died, each with whoever dealt the final blow
+///if anyone did, waiting to be culled
+#[derive(Default)]
+pub struct DeadEntities {
+    queue: Vec<(Entity, Option<Entity>)>,
+}
+
+impl DeadEntities {
+    ///Queues a death, a creature hit again before being culled is still only queued once and
+    ///keeps its first killer
+    pub fn push(&mut self, entity: Entity, killer: Option<Entity>) {
+        if !self.queue.iter().any(|(victim, _)| *victim == entity) {
+            self.queue.push((entity, killer));
+        }
+    }
+}
+
 pub struct DamageSystem {}
 
 impl<'a> System<'a> for DamageSystem {
     type SystemData = (
+        Entities<'a>,
+        WriteExpect<'a, DeadEntities>,
         WriteStorage<'a, CombatStats>,
+        WriteStorage<'a, Faction>,
         WriteStorage<'a, SufferDamage>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut all_stats, mut damages) = data;
+        let (entities, mut dead, mut all_stats, mut factions, mut damages) = data;
 
-        for (mut stats, damage) in (&mut all_stats, &damages).join() {
-            stats.hp -= damage.amount.iter().sum::<i32>();
+        for (entity, stats, damage, faction) in
+            (&entities, &mut all_stats, &damages, (&mut factions).maybe()).join()
+        {
+            //The blow that takes the last of the health is the one that kills
+            for &(amount, source) in &damage.amount {
+                let was_alive = stats.hp > 0;
+                stats.hp -= amount;
+                if was_alive && stats.hp < 1 {
+                    dead.push(entity, source);
+                }
+            }
+            if stats.hp < 1 {
+                dead.push(entity, None);
+            }
+            //Neutral creatures fight back once hurt
+            if let Some(faction) = faction.filter(|faction| **faction == Faction::Neutral) {
+                *faction = Faction::Monster;
+            }
         }
 
         damages.clear();
     }
 }
 
-pub fn cull_dead_characters(ecs: &mut World) {
-    let mut dead: Vec<Entity> = Vec::new();
-    //This needs to be enclosed, or entities is seen as being borrowed immutably and mutably
+type DeathHandler = fn(&mut World, Entity, Option<Entity>);
+
+//Everything a death sets off, run in this order for each dead creature before it is deleted
+const DEATH_HANDLERS: [DeathHandler; 6] = [
+    announce_death,
+    grant_experience,
+    drop_loot,
+    record_kill,
+    open_boss_exit,
+    stain_floor,
+];
+
+///Runs the death handlers for every creature that died this turn and removes the dead monsters,
+///returning whether the player has died
+pub fn cull_dead_characters(ecs: &mut World) -> bool {
+    let dead = std::mem::take(&mut ecs.fetch_mut::<DeadEntities>().queue);
+    let player_ent = *ecs.fetch::<Entity>();
+    let mut player_died = false;
+    for (victim, killer) in dead {
+        //Healed back up after the damage landed, such as by quaffing a potion the same turn
+        let still_dead = ecs
+            .read_storage::<CombatStats>()
+            .get(victim)
+            .is_some_and(|stats| stats.hp < 1);
+        if !ecs.is_alive(victim) || !still_dead {
+            continue;
+        }
+
+        for handler in DEATH_HANDLERS {
+            handler(ecs, victim, killer);
+        }
+        if victim == player_ent {
+            player_died = true;
+        } else {
+            ecs.delete_entity(victim).expect("Unable to delete victim");
+        }
+    }
+    player_died
+}
+
+///Whether the death counts as a victory for the player, which takes them dealing the final blow
+///to anything but an ally
+fn is_player_kill(ecs: &World, victim: Entity, killer: Option<Entity>) -> bool {
+    let player_ent = *ecs.fetch::<Entity>();
+    victim != player_ent
+        && killer == Some(player_ent)
+        && ecs.read_storage::<Faction>().get(victim) != Some(&Faction::Ally)
+}
+
+fn announce_death(ecs: &mut World, victim: Entity, _killer: Option<Entity>) {
+    if victim == *ecs.fetch::<Entity>() {
+        return;
+    }
+    if let Some(name) = ecs.read_storage::<Name>().get(victim) {
+        ecs.fetch_mut::<GameLog>()
+            .push(&format!("{} is dead", &name.name));
+    }
+}
+
+fn grant_experience(ecs: &mut World, victim: Entity, killer: Option<Entity>) {
+    if !is_player_kill(ecs, victim, killer) {
+        return;
+    }
+    let experience = ecs
+        .read_storage::<CombatStats>()
+        .get(victim)
+        .map_or(0, |stats| stats.max_hp + stats.power);
+    if experience > 0 {
+        gain_experience(ecs, experience);
+    }
+}
+
+fn drop_loot(ecs: &mut World, victim: Entity, _killer: Option<Entity>) {
+    spawning::roll_drops(ecs, victim);
+}
+
+///Counts the kill for the statistics and the bestiary, elites being filed under their kind
+fn record_kill(ecs: &mut World, victim: Entity, killer: Option<Entity>) {
+    if !is_player_kill(ecs, victim, killer) {
+        return;
+    }
+    ecs.fetch_mut::<MetaStats>().monsters_killed += 1;
     {
-        let mut all_stats = ecs.write_storage::<CombatStats>();
-        let mut log = ecs.write_resource::<GameLog>();
-        let players = ecs.read_storage::<Player>();
-        let names = ecs.read_storage::<Name>();
-        let entities = ecs.entities();
-        for (entity, stats) in (&entities, &mut all_stats).join() {
-            if stats.hp < 1 {
-                match players.get(entity) {
-                    None => {
-                        dead.push(entity);
-                        if let Some(name) = names.get(entity) {
-                            log.push(&format!("{} is dead", &name.name));
-                        }
-                    }
-                    Some(_) => {
-                        //Update State
-                        let mut state = ecs.fetch_mut::<State>();
-                        *state = Game(Gameplay::GameOver);
-                    }
-                }
-            }
+        let mut summary = ecs.fetch_mut::<RunSummary>();
+        summary.kills += 1;
+        if ecs.read_storage::<Boss>().contains(victim) {
+            summary.bosses_slain += 1;
         }
     }
-    for victim in dead {
-        ecs.delete_entity(victim).expect("Unable to delete victim");
+    let elites = ecs.read_storage::<Elite>();
+    let names = ecs.read_storage::<Name>();
+    let kind = elites
+        .get(victim)
+        .map(|elite| &elite.kind)
+        .or_else(|| names.get(victim).map(|name| &name.name));
+    if let Some(kind) = kind {
+        ecs.fetch_mut::<Bestiary>().record_kill(kind);
+    }
+}
+
+///Boss levels have no stairs down until their boss falls, they appear where it died
+fn open_boss_exit(ecs: &mut World, victim: Entity, _killer: Option<Entity>) {
+    if !ecs.read_storage::<Boss>().contains(victim) {
+        return;
+    }
+    if let Some(pos) = ecs.read_storage::<Position>().get(victim) {
+        let mut map = ecs.fetch_mut::<Map>();
+        let idx = map.xy_idx(pos.x, pos.y);
+        map.tiles[idx] = TileType::StairsDown;
+    }
+    ecs.fetch_mut::<GameLog>().push_colored(
+        &"The ground shakes as a way down opens up.",
+        RGB::named(rltk::GOLD),
+    );
+}
+
+fn stain_floor(ecs: &mut World, victim: Entity, _killer: Option<Entity>) {
+    if let Some(pos) = ecs.read_storage::<Position>().get(victim) {
+        let mut map = ecs.fetch_mut::<Map>();
+        let idx = map.xy_idx(pos.x, pos.y);
+        map.stain(idx);
+    }
+}
+
+///Grants the player experience, leveling them up each time they have enough
+fn gain_experience(ecs: &World, amount: i32) {
+    let player_ent = *ecs.fetch::<Entity>();
+    let mut experiences = ecs.write_storage::<Experience>();
+    let Some(experience) = experiences.get_mut(player_ent) else {
+        return;
+    };
+
+    experience.current += amount;
+    while experience.current >= experience.level * XP_PER_LEVEL {
+        experience.current -= experience.level * XP_PER_LEVEL;
+        experience.level += 1;
+
+        if let Some(stats) = ecs.write_storage::<CombatStats>().get_mut(player_ent) {
+            stats.max_hp += HP_PER_LEVEL;
+            stats.hp = stats.max_hp;
+        }
+        ecs.fetch_mut::<GameLog>().push_colored(
+            &format!("Welcome to level {}!", experience.level),
+            RGB::named(rltk::GOLD),
+        );
+        let player_pos = *ecs.fetch::<Point>();
+        ecs.fetch_mut::<ParticleBuilder>().create_particle(
+            player_pos.x,
+            player_pos.y,
+            ColorPair::new(RGB::named(rltk::GOLD), RGB::from(colors::BACKGROUND)),
+            rltk::to_cp437('*'),
+            400.0,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{spawn_monster, test_world};
+
+    ///Deals each blow to the victim in order, then culls the dead
+    fn strike(world: &mut World, victim: Entity, blows: &[(i32, Option<Entity>)]) {
+        for &(amount, source) in blows {
+            SufferDamage::new_damage(&mut world.write_storage(), victim, amount, source);
+        }
+        DamageSystem {}.run_now(world);
+        world.maintain();
+        cull_dead_characters(world);
+        world.maintain();
+    }
+
+    fn player_experience(world: &World) -> i32 {
+        let player_ent = *world.fetch::<Entity>();
+        world
+            .read_storage::<Experience>()
+            .get(player_ent)
+            .unwrap()
+            .current
+    }
+
+    #[test]
+    fn player_kill_is_credited_exactly_once() {
+        let mut world = test_world(10, 10, (2, 2));
+        let player_ent = *world.fetch::<Entity>();
+        let monster = spawn_monster(&mut world, (3, 2), 5, 2);
+        world
+            .fetch_mut::<Bestiary>()
+            .record_sighting("Test Monster", 1);
+
+        strike(
+            &mut world,
+            monster,
+            &[(4, Some(player_ent)), (4, Some(player_ent))],
+        );
+
+        assert!(!world.is_alive(monster));
+        assert_eq!(world.fetch::<RunSummary>().kills, 1);
+        assert_eq!(world.fetch::<MetaStats>().monsters_killed, 1);
+        assert_eq!(
+            world.fetch::<Bestiary>().entries["Test Monster"].times_killed,
+            1
+        );
+        assert_eq!(player_experience(&world), 7);
+    }
+
+    #[test]
+    fn kill_without_the_player_is_not_credited() {
+        let mut world = test_world(10, 10, (2, 2));
+        let monster = spawn_monster(&mut world, (3, 2), 5, 2);
+        let other = spawn_monster(&mut world, (4, 2), 5, 2);
+
+        strike(&mut world, monster, &[(3, None), (3, Some(other))]);
+
+        assert!(!world.is_alive(monster));
+        assert_eq!(world.fetch::<RunSummary>().kills, 0);
+        assert_eq!(world.fetch::<MetaStats>().monsters_killed, 0);
+        assert_eq!(player_experience(&world), 0);
+    }
+
+    #[test]
+    fn only_the_final_blow_counts() {
+        let mut world = test_world(10, 10, (2, 2));
+        let player_ent = *world.fetch::<Entity>();
+        let poisoned = spawn_monster(&mut world, (3, 2), 5, 2);
+        let wounded = spawn_monster(&mut world, (4, 2), 5, 2);
+
+        strike(&mut world, poisoned, &[(3, Some(player_ent)), (3, None)]);
+        assert_eq!(world.fetch::<RunSummary>().kills, 0);
+
+        strike(&mut world, wounded, &[(3, None), (3, Some(player_ent))]);
+        assert_eq!(world.fetch::<RunSummary>().kills, 1);
+    }
+
+    #[test]
+    fn dying_creatures_stain_the_floor_they_die_on() {
+        let mut world = test_world(10, 10, (2, 2));
+        let monster = spawn_monster(&mut world, (6, 4), 5, 2);
+        let survivor = spawn_monster(&mut world, (3, 7), 20, 2);
+        strike(&mut world, monster, &[(10, None)]);
+        strike(&mut world, survivor, &[(10, None)]);
+
+        let map = world.fetch::<Map>();
+        let mut stains = map.bloodstains.iter().copied().collect::<Vec<_>>();
+        stains.sort_unstable();
+        assert_eq!(stains, vec![map.xy_idx(6, 4)]);
+    }
+
+    #[test]
+    fn enough_experience_levels_the_player_up_and_heals_them() {
+        let world = test_world(10, 10, (2, 2));
+        let player_ent = *world.fetch::<Entity>();
+        let max_hp = {
+            let mut all_stats = world.write_storage::<CombatStats>();
+            let stats = all_stats.get_mut(player_ent).unwrap();
+            stats.hp = 1;
+            stats.max_hp
+        };
+
+        gain_experience(&world, XP_PER_LEVEL - 1);
+        assert_eq!(player_experience(&world), XP_PER_LEVEL - 1);
+        //Level 2 takes twice the experience of level 1, left over experience carries over
+        gain_experience(&world, 2 * XP_PER_LEVEL + 11);
+        let experiences = world.read_storage::<Experience>();
+        let experience = experiences.get(player_ent).unwrap();
+        assert_eq!((experience.level, experience.current), (3, 10));
+        let all_stats = world.read_storage::<CombatStats>();
+        let stats = all_stats.get(player_ent).unwrap();
+        assert_eq!(stats.max_hp, max_hp + 2 * HP_PER_LEVEL);
+        assert_eq!(stats.hp, stats.max_hp);
     }
 }
@@ -0,0 +1,190 @@
+use super::ParticleBuilder;
+use crate::{
+    components::{CombatStats, Flammable, OnFire, Position, SufferDamage},
+    constants::colors,
+    game_log::GameLog,
+    map_builder::map::{Map, TileType},
+    state::{Gameplay, State, State::Game},
+};
+use rltk::{Algorithm2D, ColorPair, Point, RGB, RGBA};
+use specs::prelude::*;
+
+//Damage a burning creature takes every round
+const BURN_DAMAGE: i32 = 2;
+const FLAME_LIFETIME: f32 = 250.0;
+
+///Burns everything that is on fire once per round, letting the flames spread to flammable
+///neighbors and putting them out in water
+pub struct FireSystem {}
+
+impl<'a> System<'a> for FireSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, Map>,
+        ReadExpect<'a, State>,
+        ReadStorage<'a, CombatStats>,
+        ReadStorage<'a, Flammable>,
+        ReadStorage<'a, Position>,
+        WriteExpect<'a, GameLog>,
+        WriteExpect<'a, ParticleBuilder>,
+        WriteStorage<'a, OnFire>,
+        WriteStorage<'a, SufferDamage>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            player_ent,
+            map,
+            state,
+            all_stats,
+            flammables,
+            positions,
+            mut logs,
+            mut particle_builder,
+            mut burning,
+            mut damages,
+        ) = data;
+
+        //Fire burns once per round, which begins with the players turn
+        if *state != Game(Gameplay::PlayerTurn) {
+            return;
+        }
+
+        let mut extinguished = Vec::new();
+        let mut ignited = Vec::new();
+        for (ent, fire, pos, stats) in (&entities, &mut burning, &positions, &all_stats).join() {
+            if stats.hp <= 0 {
+                continue;
+            }
+            let is_player = ent == *player_ent;
+            let idx = map.xy_idx(pos.x, pos.y);
+            if matches!(
+                map.tiles[idx],
+                TileType::ShallowWater | TileType::HealingPool
+            ) {
+                if is_player {
+                    logs.push(&"The water puts out the flames.");
+                }
+                extinguished.push(ent);
+                continue;
+            }
+
+            SufferDamage::new_damage(&mut damages, ent, BURN_DAMAGE, None);
+            particle_builder.create_fade(
+                pos.x,
+                pos.y,
+                ColorPair::new(RGB::named(rltk::ORANGE), RGB::from(colors::BACKGROUND)),
+                RGBA::from(RGB::named(rltk::RED)),
+                rltk::to_cp437('^'),
+                FLAME_LIFETIME,
+            );
+            if is_player {
+                logs.push_colored(
+                    &format!("You burn for {BURN_DAMAGE} damage."),
+                    RGB::named(rltk::ORANGE),
+                );
+            }
+
+            //The flames weaken as they spread, so a crowd can not keep setting itself alight
+            fire.turns -= 1;
+            if fire.turns < 1 {
+                extinguished.push(ent);
+                continue;
+            }
+            for (dx, dy) in (-1..=1).flat_map(|dy| (-1..=1).map(move |dx| (dx, dy))) {
+                let neighbor = Point::new(pos.x + dx, pos.y + dy);
+                if (dx, dy) == (0, 0) || !map.in_bounds(neighbor) {
+                    continue;
+                }
+                for other in &map.tile_content[map.xy_idx(neighbor.x, neighbor.y)] {
+                    if flammables.contains(*other) && !ignited.iter().any(|(e, _)| e == other) {
+                        ignited.push((*other, fire.turns));
+                    }
+                }
+            }
+        }
+
+        for ent in extinguished {
+            burning.remove(ent);
+        }
+        for (ent, turns) in ignited {
+            if burning.contains(ent) {
+                continue;
+            }
+            burning
+                .insert(ent, OnFire { turns })
+                .expect("Unable to set entity on fire");
+            if ent == *player_ent {
+                logs.push_colored(&"You catch fire!", RGB::named(rltk::ORANGE));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ecs::pre_run_systems,
+        test_support::{spawn_monster, test_world},
+    };
+
+    fn fire_turns(world: &World, ent: Entity) -> Option<i32> {
+        world
+            .read_storage::<OnFire>()
+            .get(ent)
+            .map(|fire| fire.turns)
+    }
+
+    #[test]
+    fn fire_spreads_to_flammable_neighbors_only() {
+        let mut world = test_world(10, 10, (2, 2));
+        let burning = spawn_monster(&mut world, (5, 5), 20, 1);
+        let flammable = spawn_monster(&mut world, (6, 6), 20, 1);
+        let fireproof = spawn_monster(&mut world, (4, 5), 20, 1);
+        let far_away = spawn_monster(&mut world, (8, 5), 20, 1);
+        world
+            .write_storage()
+            .insert(burning, OnFire { turns: 3 })
+            .unwrap();
+        for ent in [flammable, far_away] {
+            world.write_storage().insert(ent, Flammable {}).unwrap();
+        }
+        pre_run_systems::execute(&mut world);
+        FireSystem {}.run_now(&world);
+
+        let damage = world
+            .read_storage::<SufferDamage>()
+            .get(burning)
+            .map(|damage| damage.amount.clone());
+        assert_eq!(damage, Some(vec![(BURN_DAMAGE, None)]));
+        assert_eq!(fire_turns(&world, burning), Some(2));
+        //The flames spread with the turns the burning creature has left
+        assert_eq!(fire_turns(&world, flammable), Some(2));
+        assert_eq!(fire_turns(&world, fireproof), None);
+        assert_eq!(fire_turns(&world, far_away), None);
+    }
+
+    #[test]
+    fn water_puts_the_flames_out() {
+        let mut world = test_world(10, 10, (2, 2));
+        {
+            let mut map = world.fetch_mut::<Map>();
+            let idx = map.xy_idx(5, 5);
+            map.tiles[idx] = TileType::ShallowWater;
+        }
+        let burning = spawn_monster(&mut world, (5, 5), 20, 1);
+        world
+            .write_storage()
+            .insert(burning, OnFire { turns: 3 })
+            .unwrap();
+        pre_run_systems::execute(&mut world);
+        FireSystem {}.run_now(&world);
+
+        assert_eq!(fire_turns(&world, burning), None);
+        assert!(!world.read_storage::<SufferDamage>().contains(burning));
+    }
+}
@@ -0,0 +1,125 @@
+use crate::{
+    components::{HungerClock, HungerState, SufferDamage},
+    game_log::GameLog,
+    state::{Gameplay, State, State::Game},
+};
+use specs::prelude::*;
+
+pub const HUNGER_STATE_DURATION: i32 = 200;
+const STARVATION_DAMAGE: i32 = 1;
+
+pub struct HungerSystem {}
+
+impl<'a> System<'a> for HungerSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, State>,
+        WriteExpect<'a, GameLog>,
+        WriteStorage<'a, HungerClock>,
+        WriteStorage<'a, SufferDamage>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, player_ent, state, mut logs, mut hunger_clocks, mut suffering) = data;
+
+        //The clock only ticks once per round, which begins with the players turn
+        if *state != Game(Gameplay::PlayerTurn) {
+            return;
+        }
+
+        for (ent, clock) in (&entities, &mut hunger_clocks).join() {
+            clock.duration -= 1;
+            if clock.duration > 0 {
+                continue;
+            }
+
+            let is_player = ent == *player_ent;
+            match clock.state {
+                HungerState::WellFed => {
+                    clock.state = HungerState::Normal;
+                    clock.duration = HUNGER_STATE_DURATION;
+                    if is_player {
+                        logs.push(&"You are no longer well fed.");
+                    }
+                }
+                HungerState::Normal => {
+                    clock.state = HungerState::Hungry;
+                    clock.duration = HUNGER_STATE_DURATION;
+                    if is_player {
+                        logs.push(&"You are hungry.");
+                    }
+                }
+                HungerState::Hungry => {
+                    clock.state = HungerState::Starving;
+                    clock.duration = HUNGER_STATE_DURATION;
+                    if is_player {
+                        logs.push(&"You are starving!");
+                    }
+                }
+                HungerState::Starving => {
+                    SufferDamage::new_damage(&mut suffering, ent, STARVATION_DAMAGE, None);
+                    if is_player {
+                        logs.push(&format!(
+                            "Your hunger pangs are getting painful! You suffer {STARVATION_DAMAGE} hp damage."
+                        ));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::test_world;
+
+    #[test]
+    fn hunger_worsens_each_state_until_starving_hurts() {
+        let mut world = test_world(10, 10, (2, 2));
+        let player_ent = *world.fetch::<Entity>();
+        let clock = HungerClock {
+            state: HungerState::Normal,
+            duration: 1,
+        };
+        world.write_storage().insert(player_ent, clock).unwrap();
+
+        //Monster turns do not count as rounds
+        world.insert(Game(Gameplay::MonsterTurn));
+        HungerSystem {}.run_now(&world);
+        world.insert(Game(Gameplay::PlayerTurn));
+        HungerSystem {}.run_now(&world);
+        {
+            let clocks = world.read_storage::<HungerClock>();
+            let clock = clocks.get(player_ent).unwrap();
+            assert!(clock.state == HungerState::Hungry);
+            assert_eq!(clock.duration, HUNGER_STATE_DURATION);
+        }
+
+        for _ in 0..HUNGER_STATE_DURATION {
+            HungerSystem {}.run_now(&world);
+        }
+        {
+            let clocks = world.read_storage::<HungerClock>();
+            assert!(clocks.get(player_ent).unwrap().state == HungerState::Starving);
+            assert!(!world.read_storage::<SufferDamage>().contains(player_ent));
+        }
+
+        world
+            .write_storage::<HungerClock>()
+            .get_mut(player_ent)
+            .unwrap()
+            .duration = 1;
+        HungerSystem {}.run_now(&world);
+        let damage = world
+            .read_storage::<SufferDamage>()
+            .get(player_ent)
+            .map(|damage| damage.amount.clone());
+        assert_eq!(damage, Some(vec![(STARVATION_DAMAGE, None)]));
+        let logs = world.fetch::<GameLog>();
+        let (message, _) = logs.recent(1).next().unwrap();
+        assert!(message.starts_with("Your hunger pangs"));
+    }
+}
@@ -0,0 +1,131 @@
+use crate::{
+    components::{EntityMoved, Initiative, Position, Speed, Stealth},
+    map_builder::map::{Map, TileType},
+    state::{Gameplay, State, State::Game},
+};
+use specs::prelude::*;
+
+//Speed of the player, and of any monster without a speed of its own
+pub const NORMAL_SPEED: i32 = 4;
+//Initiative an entity has to build up again after acting
+pub const ACTION_COST: i32 = NORMAL_SPEED;
+//Rounds a move takes while sneaking or wading through water
+const SLOW_MOVE_ROUNDS: i32 = 2;
+
+pub struct InitiativeSystem {}
+
+impl<'a> System<'a> for InitiativeSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, Map>,
+        ReadExpect<'a, State>,
+        ReadStorage<'a, EntityMoved>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Speed>,
+        ReadStorage<'a, Stealth>,
+        WriteStorage<'a, Initiative>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (player_ent, map, state, moved, positions, speeds, stealths, mut initiatives) = data;
+
+        //Initiative is gained once per round, which begins with the players turn
+        if *state != Game(Gameplay::PlayerTurn) {
+            return;
+        }
+
+        //Moving while sneaking or through water is slow, giving everyone else a second round of
+        //initiative
+        let moved_player = moved.contains(*player_ent);
+        let sneaked = moved_player
+            && stealths
+                .get(*player_ent)
+                .is_some_and(|stealth| stealth.sneaking);
+        let waded = moved_player
+            && positions
+                .get(*player_ent)
+                .is_some_and(|pos| map.tiles[map.xy_idx(pos.x, pos.y)] == TileType::ShallowWater);
+        let rounds = if sneaked || waded {
+            SLOW_MOVE_ROUNDS
+        } else {
+            1
+        };
+
+        //Monsters and allies left behind on other levels wait for the player to return
+        for (initiative, speed, _) in (&mut initiatives, speeds.maybe(), &positions).join() {
+            initiative.current -= speed.map_or(NORMAL_SPEED, |speed| speed.speed) * rounds;
+        }
+    }
+}
+
+///Checks if any monster or ally still has an action left this round
+pub fn monsters_ready(world: &World) -> bool {
+    let initiatives = world.read_storage::<Initiative>();
+    let positions = world.read_storage::<Position>();
+    (&initiatives, &positions)
+        .join()
+        .any(|(initiative, _)| initiative.current <= 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ecs::{pre_run_systems, systems::MonsterAI},
+        test_support::{spawn_monster, test_world},
+    };
+
+    #[test]
+    fn fast_monsters_act_more_often_each_round() {
+        let mut world = test_world(20, 10, (1, 1));
+        let normal = spawn_monster(&mut world, (15, 8), 5, 1);
+        let fast = spawn_monster(&mut world, (17, 8), 5, 1);
+        //Both start as if they had just acted
+        for monster in [normal, fast] {
+            world
+                .write_storage()
+                .insert(
+                    monster,
+                    Initiative {
+                        current: ACTION_COST,
+                    },
+                )
+                .unwrap();
+        }
+        world
+            .write_storage()
+            .insert(
+                fast,
+                Speed {
+                    speed: NORMAL_SPEED * 2,
+                },
+            )
+            .unwrap();
+        pre_run_systems::execute(&mut world);
+
+        let initiative = |world: &World, monster| {
+            world
+                .read_storage::<Initiative>()
+                .get(monster)
+                .unwrap()
+                .current
+        };
+        let mut actions = [0, 0];
+        for _ in 0..4 {
+            world.insert(Game(Gameplay::PlayerTurn));
+            InitiativeSystem {}.run_now(&world);
+            world.insert(Game(Gameplay::MonsterTurn));
+            while monsters_ready(&world) {
+                let before = [normal, fast].map(|monster| initiative(&world, monster));
+                MonsterAI {}.run_now(&world);
+                for (i, monster) in [normal, fast].iter().enumerate() {
+                    if initiative(&world, *monster) > before[i] {
+                        actions[i] += 1;
+                    }
+                }
+            }
+        }
+        assert_eq!(actions, [4, 8]);
+    }
+}
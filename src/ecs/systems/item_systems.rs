@@ -1,32 +1,138 @@
+use super::{ParticleBuilder, HUNGER_STATE_DURATION};
 use crate::{
+    audio::{SfxEvent, SfxQueue},
     components::{
-        AreaOfEffect, CombatStats, Consumable, Equipment, Equipped, InBackpack, InflictsDamage,
-        Name, Position, ProvidesHealing, SufferDamage, WantsToDropItem, WantsToPickupItem,
-        WantsToRemoveItem, WantsToUseItem,
+        Ammo, AreaOfEffect, Charges, CombatStats, Confusion, Consumable, Cursed, Equipment,
+        Equipped, Gold, HungerClock, HungerState, InBackpack, InflictsBurning, InflictsDamage,
+        Name, OnFire, Poison, Position, ProvidesCure, ProvidesHealing, ProvidesMapReveal,
+        ProvidesNutrition, ProvidesRemoveCurse, ProvidesTeleport, Render, SufferDamage,
+        SummonsAlly, Unidentified, Wallet, WantsToDropItem, WantsToPickupItem, WantsToRemoveItem,
+        WantsToSummon, WantsToTeleport, WantsToUseItem, Weight,
     },
+    constants::colors,
     game_log::GameLog,
-    map_builder::map::Map,
+    identification::Identification,
+    map_builder::map::{aoe_tiles, Map, TileStatus},
+    run_summary::RunSummary,
 };
-use rltk::{Algorithm2D, Point};
-use specs::{Entities, Entity, Join, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage};
+use rltk::{ColorPair, Point, RGB, RGBA};
+use specs::{
+    storage::MaskedStorage, Entities, Entity, Join, ReadExpect, ReadStorage, Storage, System,
+    WriteExpect, WriteStorage,
+};
+use std::ops::Deref;
 
 const INVENTORY_LIMIT: usize = 9;
+//Pounds anyone can carry, and how many more each point of power lets them carry
+const BASE_CARRY_CAPACITY: i32 = 10;
+const CAPACITY_PER_POWER: i32 = 4;
+const EFFECT_LIFETIME: f32 = 400.0;
+
+///Puts an item in the owner's backpack, under the letter of the stack it joins or else the first
+///letter no other carried item uses
+fn backpack_entry(
+    entities: &Entities<'_>,
+    backpack: &WriteStorage<'_, InBackpack>,
+    names: &ReadStorage<'_, Name>,
+    consumables: &ReadStorage<'_, Consumable>,
+    owner: Entity,
+    item: Entity,
+) -> InBackpack {
+    let carried = (entities, backpack)
+        .join()
+        .filter(|(_, carried)| carried.owner == owner)
+        .collect::<Vec<_>>();
+    let stack_letter = carried
+        .iter()
+        .find(|(other, _)| {
+            consumables.contains(item)
+                && consumables.contains(*other)
+                && names.get(*other).map(|name| &name.name)
+                    == names.get(item).map(|name| &name.name)
+        })
+        .map(|(_, other)| other.letter);
+    let letter = stack_letter.unwrap_or_else(|| {
+        ('a'..='z')
+            .find(|letter| carried.iter().all(|(_, other)| other.letter != *letter))
+            .unwrap_or('z')
+    });
+    InBackpack { owner, letter }
+}
+
+///The most weight a creature can carry, which grows with its strength
+pub const fn carry_capacity(stats: &CombatStats) -> i32 {
+    BASE_CARRY_CAPACITY + stats.power * CAPACITY_PER_POWER
+}
+
+///The weight of everything the owner carries, both in the backpack and equipped
+pub fn carried_weight<D>(
+    owner: Entity,
+    backpack: &Storage<'_, InBackpack, D>,
+    equipped_items: &ReadStorage<'_, Equipped>,
+    weights: &ReadStorage<'_, Weight>,
+) -> i32
+where
+    D: Deref<Target = MaskedStorage<InBackpack>>,
+{
+    let in_backpack = (backpack, weights)
+        .join()
+        .filter(|(item, _)| item.owner == owner)
+        .map(|(_, weight)| weight.pounds);
+    let equipped = (equipped_items, weights)
+        .join()
+        .filter(|(item, _)| item.owner == owner)
+        .map(|(_, weight)| weight.pounds);
+    in_backpack.chain(equipped).sum()
+}
 
 pub struct ItemCollectionSystem {}
 
 impl<'a> System<'a> for ItemCollectionSystem {
     #[allow(clippy::type_complexity)]
     type SystemData = (
+        Entities<'a>,
         ReadExpect<'a, Entity>,
+        WriteStorage<'a, Ammo>,
+        ReadStorage<'a, CombatStats>,
+        ReadStorage<'a, Consumable>,
+        ReadStorage<'a, Equipped>,
         ReadStorage<'a, Name>,
+        ReadStorage<'a, Unidentified>,
+        ReadStorage<'a, Weight>,
         WriteExpect<'a, GameLog>,
         WriteStorage<'a, InBackpack>,
         WriteStorage<'a, Position>,
         WriteStorage<'a, WantsToPickupItem>,
+        WriteExpect<'a, RunSummary>,
+        WriteExpect<'a, SfxQueue>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (player_ent, names, mut logs, mut backpack, mut positions, mut attempts) = data;
+        let (
+            entities,
+            player_ent,
+            mut quivers,
+            all_stats,
+            consumables,
+            equipped_items,
+            names,
+            unidentified_items,
+            weights,
+            mut logs,
+            mut backpack,
+            mut positions,
+            mut attempts,
+            mut summary,
+            mut sfx,
+        ) = data;
+
+        //Unidentified items are only known by their appearance
+        let display_name = |item: Entity| {
+            unidentified_items.get(item).map_or_else(
+                || names.get(item).unwrap().name.clone(),
+                |unidentified| unidentified.appearance.clone(),
+            )
+        };
 
         let player_inventory_size = (&backpack)
             .join()
@@ -34,36 +140,106 @@ impl<'a> System<'a> for ItemCollectionSystem {
             .count();
 
         for pickup in attempts.join() {
+            //Arrows go into the quiver rather than the backpack
+            let bundle = quivers.get(pickup.item).map(|ammo| ammo.count);
+            if let (Some(count), Some(quiver)) = (bundle, quivers.get_mut(pickup.collected_by)) {
+                quiver.count += count;
+                entities.delete(pickup.item).expect("Unable to delete ammo");
+                if pickup.collected_by == *player_ent {
+                    sfx.push(SfxEvent::Pickup);
+                    logs.push_colored(
+                        &format!("You pick up {count} arrows."),
+                        RGB::named(rltk::CYAN),
+                    );
+                }
+                continue;
+            }
+
             if player_inventory_size >= INVENTORY_LIMIT {
                 logs.push(&format!(
                     "You are unable to pick up the {}.",
-                    names.get(pickup.item).unwrap().name
+                    display_name(pickup.item)
                 ));
                 logs.push(&"You are carrying too many items!");
                 attempts.clear();
                 return;
             }
+            if let Some(stats) = all_stats.get(pickup.collected_by) {
+                let carried =
+                    carried_weight(pickup.collected_by, &backpack, &equipped_items, &weights);
+                let item_weight = weights.get(pickup.item).map_or(0, |weight| weight.pounds);
+                if carried + item_weight > carry_capacity(stats) {
+                    logs.push(&format!(
+                        "You are unable to pick up the {}.",
+                        display_name(pickup.item)
+                    ));
+                    logs.push(&"You are carrying too much.");
+                    continue;
+                }
+            }
             positions.remove(pickup.item);
+            let entry = backpack_entry(
+                &entities,
+                &backpack,
+                &names,
+                &consumables,
+                pickup.collected_by,
+                pickup.item,
+            );
             backpack
-                .insert(
-                    pickup.item,
-                    InBackpack {
-                        owner: pickup.collected_by,
-                    },
-                )
+                .insert(pickup.item, entry)
                 .expect("Unable to insert backpack entry");
 
             if pickup.collected_by == *player_ent {
-                logs.push(&format!(
-                    "You pick up the {}.",
-                    names.get(pickup.item).unwrap().name
-                ));
+                summary.items_collected += 1;
+                sfx.push(SfxEvent::Pickup);
+                logs.push_colored(
+                    &format!("You pick up the {}.", display_name(pickup.item)),
+                    RGB::named(rltk::CYAN),
+                );
             }
         }
         attempts.clear();
     }
 }
 
+pub struct GoldCollectionSystem {}
+
+impl<'a> System<'a> for GoldCollectionSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, Map>,
+        ReadStorage<'a, Gold>,
+        ReadStorage<'a, Position>,
+        WriteStorage<'a, Wallet>,
+        WriteExpect<'a, GameLog>,
+        WriteExpect<'a, RunSummary>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, player_ent, map, gold, positions, mut wallets, mut logs, mut summary) = data;
+
+        for (owner, wallet, pos) in (&entities, &mut wallets, &positions).join() {
+            let idx = map.xy_idx(pos.x, pos.y);
+            for pile in &map.tile_content[idx] {
+                let Some(coins) = gold.get(*pile) else {
+                    continue;
+                };
+                wallet.gold += coins.amount;
+                entities.delete(*pile).expect("Unable to delete gold");
+                if owner == *player_ent {
+                    summary.gold_collected += coins.amount;
+                    logs.push_colored(
+                        &format!("You pick up {} gold.", coins.amount),
+                        RGB::named(rltk::GOLD),
+                    );
+                }
+            }
+        }
+    }
+}
+
 pub struct ItemDropSystem {}
 
 impl<'a> System<'a> for ItemDropSystem {
@@ -112,6 +288,8 @@ impl<'a> System<'a> for ItemRemoveSystem {
     type SystemData = (
         Entities<'a>,
         ReadExpect<'a, Entity>,
+        ReadStorage<'a, Consumable>,
+        ReadStorage<'a, Cursed>,
         ReadStorage<'a, Name>,
         WriteExpect<'a, GameLog>,
         WriteStorage<'a, WantsToRemoveItem>,
@@ -120,12 +298,41 @@ impl<'a> System<'a> for ItemRemoveSystem {
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, player_ent, names, mut logs, mut intents, mut equipped_items, mut backpacks) =
-            data;
+        let (
+            entities,
+            player_ent,
+            consumables,
+            cursed_items,
+            names,
+            mut logs,
+            mut intents,
+            mut equipped_items,
+            mut backpacks,
+        ) = data;
         for (entity, intent) in (&entities, &intents).join() {
+            if cursed_items.contains(intent.item) {
+                if entity == *player_ent {
+                    logs.push_colored(
+                        &format!(
+                            "The {} is stuck to you!",
+                            names.get(intent.item).unwrap().name
+                        ),
+                        RGB::named(rltk::MAGENTA),
+                    );
+                }
+                continue;
+            }
             equipped_items.remove(intent.item);
+            let entry = backpack_entry(
+                &entities,
+                &backpacks,
+                &names,
+                &consumables,
+                entity,
+                intent.item,
+            );
             backpacks
-                .insert(intent.item, InBackpack { owner: entity })
+                .insert(intent.item, entry)
                 .expect("Unable to insert item into backpack");
             if entity == *player_ent {
                 logs.push(&format!(
@@ -146,18 +353,45 @@ impl<'a> System<'a> for ItemUseSystem {
     type SystemData = (
         Entities<'a>,
         ReadExpect<'a, Entity>,
-        ReadExpect<'a, Map>,
+        WriteExpect<'a, Map>,
         ReadStorage<'a, AreaOfEffect>,
         ReadStorage<'a, Consumable>,
         ReadStorage<'a, InflictsDamage>,
         ReadStorage<'a, Name>,
         ReadStorage<'a, ProvidesHealing>,
+        ReadStorage<'a, ProvidesMapReveal>,
+        ReadStorage<'a, ProvidesNutrition>,
         ReadStorage<'a, Equipment>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Render>,
         WriteExpect<'a, GameLog>,
+        WriteExpect<'a, Identification>,
+        WriteExpect<'a, ParticleBuilder>,
         WriteStorage<'a, Equipped>,
         WriteStorage<'a, InBackpack>,
         WriteStorage<'a, CombatStats>,
+        WriteStorage<'a, Confusion>,
+        WriteStorage<'a, HungerClock>,
         WriteStorage<'a, SufferDamage>,
+        WriteStorage<'a, Unidentified>,
+        //Grouped, as systems can only fetch so many storages on their own
+        (
+            (
+                ReadStorage<'a, SummonsAlly>,
+                WriteStorage<'a, WantsToSummon>,
+            ),
+            (
+                ReadStorage<'a, ProvidesTeleport>,
+                WriteStorage<'a, WantsToTeleport>,
+            ),
+            (
+                ReadStorage<'a, ProvidesRemoveCurse>,
+                WriteStorage<'a, Cursed>,
+            ),
+            (ReadStorage<'a, InflictsBurning>, WriteStorage<'a, OnFire>),
+            (ReadStorage<'a, ProvidesCure>, WriteStorage<'a, Poison>),
+            WriteStorage<'a, Charges>,
+        ),
         WriteStorage<'a, WantsToUseItem>,
     );
 
@@ -166,24 +400,90 @@ impl<'a> System<'a> for ItemUseSystem {
         let (
             entities,
             player_ent,
-            map,
+            mut map,
             aoe,
             consumables,
             damaging_items,
             names,
             healing_items,
+            map_reveals,
+            nutritious_items,
             equipment,
+            positions,
+            renderables,
             mut logs,
+            mut identification,
+            mut particle_builder,
             mut equipped_items,
             mut backpack,
             mut all_stats,
+            mut confusions,
+            mut hunger_clocks,
             mut suffering,
+            mut unidentified_items,
+            (
+                (summoners, mut summons),
+                (teleporters, mut teleports),
+                (curse_removers, mut cursed_items),
+                (burning_items, mut burning),
+                (curing_items, mut poisoned),
+                mut charged_items,
+            ),
             mut intents,
         ) = data;
 
         for (user, intent) in (&entities, &intents).join() {
             let mut used_item = true;
 
+            //Items with charges do nothing once they run out, and lose one with every other use
+            if let Some(charges) = charged_items.get_mut(intent.item) {
+                if charges.current <= 0 {
+                    if user == *player_ent {
+                        logs.push(&format!(
+                            "The {} has no charges left.",
+                            names.get(intent.item).unwrap().name
+                        ));
+                    }
+                    continue;
+                }
+                charges.current -= 1;
+            }
+
+            //Using an item identifies every other item of its kind
+            if let Some(unidentified) = unidentified_items.get(intent.item) {
+                let item_name = names.get(intent.item).unwrap().name.clone();
+                if user == *player_ent {
+                    logs.push(&format!(
+                        "The {} was a {}.",
+                        unidentified.appearance, item_name
+                    ));
+                }
+                identification.identify(&item_name);
+                let same_kind = (&entities, &names, &unidentified_items)
+                    .join()
+                    .filter(|(_, name, _)| name.name == item_name)
+                    .map(|(ent, _, _)| ent)
+                    .collect::<Vec<_>>();
+                for ent in same_kind {
+                    unidentified_items.remove(ent);
+                }
+            }
+
+            //Show the item flying towards its target
+            if let (Some(target), Some(pos), Some(render)) = (
+                intent.target,
+                positions.get(user),
+                renderables.get(intent.item),
+            ) {
+                particle_builder.create_projectile(
+                    &map,
+                    Point::new(pos.x, pos.y),
+                    target,
+                    render.colors,
+                    render.glyph,
+                );
+            }
+
             //Get all targets!
             let mut targets: Vec<Entity> = Vec::new();
             match intent.target {
@@ -196,10 +496,15 @@ impl<'a> System<'a> for ItemUseSystem {
                         }
                     }
                     Some(area) => {
-                        let mut affected_tiles = rltk::field_of_view(target, area.radius, &*map);
-                        affected_tiles.retain(|t| (*map).in_bounds(Point::new(t.x, t.y)));
-                        for tile in &affected_tiles {
-                            let idx = map.xy_idx(tile.x, tile.y);
+                        particle_builder.create_ring(
+                            target,
+                            area.radius,
+                            ColorPair::new(RGB::named(rltk::ORANGE), RGB::from(colors::BACKGROUND)),
+                            RGBA::from(RGB::named(rltk::DARK_RED)),
+                            rltk::to_cp437('░'),
+                            EFFECT_LIFETIME,
+                        );
+                        for idx in aoe_tiles(target, area.radius, &map) {
                             for mob in &map.tile_content[idx] {
                                 targets.push(*mob);
                             }
@@ -213,6 +518,19 @@ impl<'a> System<'a> for ItemUseSystem {
                 for target in &targets {
                     if let Some(stats) = all_stats.get_mut(*target) {
                         stats.hp = i32::min(stats.max_hp, stats.hp + heal.heal_amount);
+                        if let Some(pos) = positions.get(*target) {
+                            particle_builder.create_fade(
+                                pos.x,
+                                pos.y,
+                                ColorPair::new(
+                                    RGB::named(rltk::GREEN),
+                                    RGB::from(colors::BACKGROUND),
+                                ),
+                                RGBA::from(RGB::from(colors::BACKGROUND)),
+                                rltk::to_cp437('♥'),
+                                EFFECT_LIFETIME,
+                            );
+                        }
                         if user == *player_ent {
                             logs.push(&format!(
                                 "You use the {}, healing {} hp.",
@@ -225,10 +543,80 @@ impl<'a> System<'a> for ItemUseSystem {
                 }
             }
 
+            //if the item can be eaten...
+            if nutritious_items.get(intent.item).is_some() {
+                for target in &targets {
+                    if let Some(clock) = hunger_clocks.get_mut(*target) {
+                        clock.state = HungerState::WellFed;
+                        clock.duration = HUNGER_STATE_DURATION;
+                        if user == *player_ent {
+                            logs.push(&format!(
+                                "You eat the {}.",
+                                names.get(intent.item).unwrap().name
+                            ));
+                        }
+                    }
+                }
+            }
+
+            //if the item reveals the level...
+            if map_reveals.get(intent.item).is_some() {
+                //Only the terrain is revealed, monsters still need to be seen
+                for idx in 0..map.tiles.len() {
+                    map.set_tile_status(idx, TileStatus::Revealed);
+                }
+                if user == *player_ent {
+                    logs.push(&"The dungeon layout is revealed to you!");
+                }
+            }
+
+            //if the item teleports...
+            if teleporters.get(intent.item).is_some() {
+                for target in &targets {
+                    teleports
+                        .insert(*target, WantsToTeleport {})
+                        .expect("Unable to insert teleport");
+                }
+                if user == *player_ent {
+                    logs.push(&"The world spins around you.");
+                }
+            }
+
+            //if the item summons an ally...
+            if let Some(summoner) = summoners.get(intent.item) {
+                summons
+                    .insert(
+                        user,
+                        WantsToSummon {
+                            name: summoner.name.clone(),
+                        },
+                    )
+                    .expect("Unable to insert summon");
+            }
+
+            //if the item lifts curses...
+            if curse_removers.contains(intent.item) {
+                let lifted = (&entities, &equipped_items, &cursed_items)
+                    .join()
+                    .filter(|(_, equipped, _)| targets.contains(&equipped.owner))
+                    .map(|(item, ..)| item)
+                    .collect::<Vec<_>>();
+                for item in &lifted {
+                    cursed_items.remove(*item);
+                }
+                if user == *player_ent {
+                    logs.push(&if lifted.is_empty() {
+                        "You feel a brief warmth, but nothing happens."
+                    } else {
+                        "You feel as if someone is watching over you."
+                    });
+                }
+            }
+
             //if the item deals damage on use...
             if let Some(damage) = damaging_items.get(intent.item) {
                 for mob in &targets {
-                    SufferDamage::new_damage(&mut suffering, *mob, damage.damage);
+                    SufferDamage::new_damage(&mut suffering, *mob, damage.damage, Some(user));
                     if user == *player_ent && all_stats.get(*mob).is_some() {
                         let mob_name = &names.get(*mob).unwrap().name;
                         let item_name = &names.get(intent.item).unwrap().name;
@@ -241,8 +629,76 @@ impl<'a> System<'a> for ItemUseSystem {
                 }
             }
 
+            //if the item confuses...
+            let confusion_turns = confusions.get(intent.item).map(|confusion| confusion.turns);
+            if let Some(turns) = confusion_turns {
+                for mob in &targets {
+                    if all_stats.get(*mob).is_some() {
+                        confusions
+                            .insert(*mob, Confusion { turns })
+                            .expect("Unable to confuse target");
+                        if user == *player_ent {
+                            logs.push_colored(
+                                &format!("{} is confused.", names.get(*mob).unwrap().name),
+                                RGB::named(rltk::MAGENTA),
+                            );
+                        }
+                    }
+                }
+            }
+
+            //if the item cures poison...
+            if curing_items.contains(intent.item) {
+                let cured = targets
+                    .iter()
+                    .filter(|target| poisoned.remove(**target).is_some())
+                    .count();
+                if user == *player_ent {
+                    logs.push(&if cured > 0 {
+                        "The poison leaves your body."
+                    } else {
+                        "You feel a little queasy, but nothing happens."
+                    });
+                }
+            }
+
+            //if the item sets things on fire...
+            if let Some(fire) = burning_items.get(intent.item) {
+                for mob in &targets {
+                    if all_stats.get(*mob).is_some() {
+                        burning
+                            .insert(*mob, OnFire { turns: fire.turns })
+                            .expect("Unable to set target on fire");
+                        if user == *player_ent {
+                            logs.push_colored(
+                                &format!("{} catches fire.", names.get(*mob).unwrap().name),
+                                RGB::named(rltk::ORANGE),
+                            );
+                        }
+                    }
+                }
+            }
+
+            //Cursed items keep anything else from being equipped in their slot
+            let stuck_item = equipment.get(intent.item).and_then(|equipment| {
+                (&entities, &equipped_items, &cursed_items)
+                    .join()
+                    .find(|(_, equipped, _)| {
+                        equipped.owner == targets[0] && equipped.slot == equipment.slot
+                    })
+                    .map(|(item, ..)| item)
+            });
+            if let Some(item) = stuck_item {
+                if targets[0] == *player_ent {
+                    logs.push_colored(
+                        &format!("The {} is stuck to you!", names.get(item).unwrap().name),
+                        RGB::named(rltk::MAGENTA),
+                    );
+                }
+            }
+
             //If the item can be equipped...
-            if let Some(equipment) = equipment.get(intent.item) {
+            if let (Some(equipment), None) = (equipment.get(intent.item), stuck_item) {
                 //De-equip all items that share a slot
                 let mut to_unequip = Vec::new();
                 for (item, already_equipped, name) in (&entities, &equipped_items, &names).join() {
@@ -256,10 +712,20 @@ impl<'a> System<'a> for ItemUseSystem {
                     }
                 }
 
+                //The item being equipped leaves the backpack first, freeing its letter
+                backpack.remove(intent.item);
                 for item in &to_unequip {
                     equipped_items.remove(*item);
+                    let entry = backpack_entry(
+                        &entities,
+                        &backpack,
+                        &names,
+                        &consumables,
+                        targets[0],
+                        *item,
+                    );
                     backpack
-                        .insert(*item, InBackpack { owner: targets[0] })
+                        .insert(*item, entry)
                         .expect("Unable to put unequipped item into backpack");
                 }
 
@@ -273,7 +739,6 @@ impl<'a> System<'a> for ItemUseSystem {
                         },
                     )
                     .expect("Unable to equip desired item");
-                backpack.remove(intent.item);
 
                 //Inform if player is equipping
                 if targets[0] == *player_ent {
@@ -295,3 +760,318 @@ impl<'a> System<'a> for ItemUseSystem {
         intents.clear();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        components::{EquipmentSlot, WantsToMelee},
+        ecs::{pre_run_systems, systems::MonsterAI},
+        raws, spawning,
+        state::{Gameplay, State},
+        test_support::{spawn_monster, test_world},
+    };
+    use specs::prelude::*;
+
+    ///Letter the item would be put in the player's backpack under
+    fn letter_for(world: &World, item: Entity) -> char {
+        backpack_entry(
+            &world.entities(),
+            &world.write_storage(),
+            &world.read_storage(),
+            &world.read_storage(),
+            *world.fetch::<Entity>(),
+            item,
+        )
+        .letter
+    }
+
+    fn item(world: &mut World, name: &str, consumable: bool) -> Entity {
+        let builder = world.create_entity().with(Name {
+            name: name.to_string(),
+        });
+        if consumable {
+            builder.with(Consumable {}).build()
+        } else {
+            builder.build()
+        }
+    }
+
+    #[test]
+    fn stepping_on_gold_moves_it_into_the_wallet() {
+        let mut world = test_world(10, 10, (3, 3));
+        let player_ent = *world.fetch::<Entity>();
+        let pile = world
+            .create_entity()
+            .with(Position { x: 3, y: 3 })
+            .with(Gold { amount: 15 })
+            .build();
+        pre_run_systems::execute(&mut world);
+
+        GoldCollectionSystem {}.run_now(&world);
+        world.maintain();
+
+        assert!(!world.is_alive(pile));
+        assert_eq!(
+            world.read_storage::<Wallet>().get(player_ent).unwrap().gold,
+            15
+        );
+        assert_eq!(world.fetch::<RunSummary>().gold_collected, 15);
+    }
+
+    #[test]
+    fn items_take_the_first_free_letter_or_that_of_their_stack() {
+        let mut world = test_world(10, 10, (3, 3));
+        let player_ent = *world.fetch::<Entity>();
+        let mut carried = Vec::new();
+        for name in ["Dagger", "Health Potion", "Shield"] {
+            let item = item(&mut world, name, name == "Health Potion");
+            let entry = InBackpack {
+                owner: player_ent,
+                letter: letter_for(&world, item),
+            };
+            world.write_storage().insert(item, entry).unwrap();
+            carried.push(item);
+        }
+        let letters = carried
+            .iter()
+            .map(|item| {
+                world
+                    .read_storage::<InBackpack>()
+                    .get(*item)
+                    .unwrap()
+                    .letter
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(letters, vec!['a', 'b', 'c']);
+
+        //Dropping the dagger frees its letter without moving the others
+        world.write_storage::<InBackpack>().remove(carried[0]);
+        let potion = item(&mut world, "Health Potion", true);
+        assert_eq!(letter_for(&world, potion), 'b');
+        let sword = item(&mut world, "Longsword", false);
+        assert_eq!(letter_for(&world, sword), 'a');
+    }
+
+    fn is_equipped(world: &World, item: Entity) -> bool {
+        world.read_storage::<Equipped>().contains(item)
+    }
+
+    fn try_to_remove(world: &mut World, item: Entity) {
+        let player_ent = *world.fetch::<Entity>();
+        world
+            .write_storage()
+            .insert(player_ent, WantsToRemoveItem { item })
+            .unwrap();
+        ItemRemoveSystem {}.run_now(world);
+        world.write_storage::<WantsToRemoveItem>().clear();
+        world.maintain();
+    }
+
+    fn use_item(world: &mut World, item: Entity, target: Option<Point>) {
+        let player_ent = *world.fetch::<Entity>();
+        world
+            .write_storage()
+            .insert(player_ent, WantsToUseItem { item, target })
+            .unwrap();
+        ItemUseSystem {}.run_now(world);
+        world.maintain();
+    }
+
+    #[test]
+    fn cursed_equipment_stays_on_until_the_curse_is_lifted() {
+        raws::spawn::load().unwrap();
+        let mut world = test_world(10, 10, (3, 3));
+        let player_ent = *world.fetch::<Entity>();
+        let shield = spawning::spawn_equipped(
+            &mut world,
+            "Shield of Frailty",
+            player_ent,
+            EquipmentSlot::OffHand,
+        )
+        .unwrap();
+        assert!(world.read_storage::<Cursed>().contains(shield));
+
+        try_to_remove(&mut world, shield);
+        assert!(is_equipped(&world, shield));
+        assert!(!world.read_storage::<InBackpack>().contains(shield));
+
+        let scroll = item(&mut world, "Remove Curse Scroll", true);
+        world
+            .write_storage()
+            .insert(scroll, ProvidesRemoveCurse {})
+            .unwrap();
+        world
+            .write_storage()
+            .insert(
+                scroll,
+                InBackpack {
+                    owner: player_ent,
+                    letter: 'a',
+                },
+            )
+            .unwrap();
+        use_item(&mut world, scroll, None);
+        assert!(!world.read_storage::<Cursed>().contains(shield));
+
+        try_to_remove(&mut world, shield);
+        assert!(!is_equipped(&world, shield));
+        assert!(world.read_storage::<InBackpack>().contains(shield));
+    }
+
+    #[test]
+    fn wand_loses_a_charge_per_use_and_refuses_once_empty() {
+        let mut world = test_world(10, 10, (3, 3));
+        let player_ent = *world.fetch::<Entity>();
+        let monster = spawn_monster(&mut world, (5, 3), 20, 1);
+        let wand = item(&mut world, "Wand of Magic Missile", false);
+        world
+            .write_storage()
+            .insert(wand, Charges { current: 2, max: 2 })
+            .unwrap();
+        world
+            .write_storage()
+            .insert(wand, InflictsDamage { damage: 6 })
+            .unwrap();
+        world
+            .write_storage()
+            .insert(
+                wand,
+                InBackpack {
+                    owner: player_ent,
+                    letter: 'a',
+                },
+            )
+            .unwrap();
+        pre_run_systems::execute(&mut world);
+
+        let blows = |world: &World| {
+            world
+                .read_storage::<SufferDamage>()
+                .get(monster)
+                .map_or(0, |damage| damage.amount.len())
+        };
+        for used in 1..=3 {
+            use_item(&mut world, wand, Some(Point::new(5, 3)));
+            assert_eq!(blows(&world), usize::min(used, 2));
+        }
+        assert_eq!(
+            world.read_storage::<Charges>().get(wand).unwrap().current,
+            0
+        );
+        assert!(world.is_alive(wand));
+        assert!(world.read_storage::<InBackpack>().contains(wand));
+    }
+
+    ///Has the player try to pick up an item of the given weight, returning whether it was taken
+    fn pick_up(world: &mut World, pounds: i32) -> bool {
+        let player_ent = *world.fetch::<Entity>();
+        let item = world
+            .create_entity()
+            .with(Name {
+                name: "Anvil".to_string(),
+            })
+            .with(Position { x: 3, y: 3 })
+            .with(Weight { pounds })
+            .build();
+        world
+            .write_storage()
+            .insert(
+                player_ent,
+                WantsToPickupItem {
+                    collected_by: player_ent,
+                    item,
+                },
+            )
+            .unwrap();
+        ItemCollectionSystem {}.run_now(world);
+        world.write_storage::<WantsToPickupItem>().clear();
+        world.maintain();
+        world.read_storage::<InBackpack>().contains(item)
+    }
+
+    #[test]
+    fn pickup_that_would_exceed_the_capacity_is_refused() {
+        let mut world = test_world(10, 10, (3, 3));
+        let player_ent = *world.fetch::<Entity>();
+        let room_left = {
+            let stats = world.read_storage::<CombatStats>();
+            carry_capacity(stats.get(player_ent).unwrap())
+                - carried_weight(
+                    player_ent,
+                    &world.read_storage(),
+                    &world.read_storage(),
+                    &world.read_storage(),
+                )
+        };
+
+        assert!(!pick_up(&mut world, room_left + 1));
+        let logs = world.fetch::<GameLog>();
+        let (message, _) = logs.recent(1).next().unwrap();
+        assert_eq!(message, "You are carrying too much.");
+        drop(logs);
+
+        assert!(pick_up(&mut world, room_left));
+        assert!(!pick_up(&mut world, 1));
+    }
+
+    #[test]
+    fn confusion_scroll_makes_its_target_stumble_instead_of_attacking() {
+        let mut world = test_world(10, 10, (3, 3));
+        let player_ent = *world.fetch::<Entity>();
+        let monster = spawn_monster(&mut world, (4, 3), 20, 1);
+        let scroll = item(&mut world, "Confusion Scroll", true);
+        world
+            .write_storage()
+            .insert(scroll, Confusion { turns: 4 })
+            .unwrap();
+        world
+            .write_storage()
+            .insert(
+                scroll,
+                InBackpack {
+                    owner: player_ent,
+                    letter: 'a',
+                },
+            )
+            .unwrap();
+        pre_run_systems::execute(&mut world);
+
+        use_item(&mut world, scroll, Some(Point::new(4, 3)));
+        assert!(!world.is_alive(scroll));
+        let turns = |world: &World| {
+            world
+                .read_storage::<Confusion>()
+                .get(monster)
+                .map(|confusion| confusion.turns)
+        };
+        assert_eq!(turns(&world), Some(4));
+        assert!(!world.read_storage::<Confusion>().contains(player_ent));
+
+        world.insert(State::Game(Gameplay::MonsterTurn));
+        MonsterAI {}.run_now(&world);
+        assert!(!world.read_storage::<WantsToMelee>().contains(monster));
+        assert_eq!(turns(&world), Some(3));
+    }
+
+    #[test]
+    fn magic_mapping_reveals_the_layout_but_not_what_is_on_it() {
+        let mut world = test_world(20, 12, (2, 2));
+        spawn_monster(&mut world, (15, 8), 10, 1);
+        let scroll = item(&mut world, "Magic Mapping Scroll", true);
+        world
+            .write_storage()
+            .insert(scroll, ProvidesMapReveal {})
+            .unwrap();
+        use_item(&mut world, scroll, None);
+
+        let map = world.fetch::<Map>();
+        assert!((0..map.tiles.len()).all(|idx| map.is_tile_status_set(idx, TileStatus::Revealed)));
+        let monster_idx = map.xy_idx(15, 8);
+        assert!(!map.is_tile_status_set(monster_idx, TileStatus::Visible));
+        assert!(!world.entities().is_alive(scroll));
+        let logs = world.fetch::<GameLog>();
+        let (message, _) = logs.recent(1).next().unwrap();
+        assert_eq!(message, "The dungeon layout is revealed to you!");
+    }
+}
@@ -1,7 +1,8 @@
 use crate::{
-    components::{BlocksTile, Position},
+    components::{BlocksTile, Position, Size},
     map_builder::map::{Map, TileStatus},
 };
+use rltk::{Algorithm2D, Point};
 use specs::prelude::*;
 
 pub struct MapIndexingSystem {}
@@ -11,20 +12,28 @@ impl<'a> System<'a> for MapIndexingSystem {
         Entities<'a>,
         ReadStorage<'a, Position>,
         ReadStorage<'a, BlocksTile>,
+        ReadStorage<'a, Size>,
         WriteExpect<'a, Map>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, position, blockers, mut map) = data;
+        let (entities, position, blockers, sizes, mut map) = data;
 
         map.populate_blocked();
         map.clear_content_index();
-        for (position, entity) in (&position, &entities).join() {
-            let idx = map.xy_idx(position.x, position.y);
-            if blockers.get(entity).is_some() {
-                map.set_tile_status(idx, TileStatus::Blocked);
+        for (position, entity, size) in (&position, &entities, sizes.maybe()).join() {
+            //Large creatures are found on, and block, every tile they take up
+            let size = size.copied().unwrap_or_default();
+            for tile in size.tiles(Point::new(position.x, position.y)) {
+                if !map.in_bounds(tile) {
+                    continue;
+                }
+                let idx = map.xy_idx(tile.x, tile.y);
+                if blockers.get(entity).is_some() {
+                    map.set_tile_status(idx, TileStatus::Blocked);
+                }
+                map.tile_content[idx].push(entity);
             }
-            map.tile_content[idx].push(entity);
         }
     }
 }
@@ -1,11 +1,68 @@
 use super::ParticleBuilder;
 use crate::{
-    constants::colors, CombatStats, DefenseBonus, Equipped, GameLog, MeleeDamageBonus, Name,
+    audio::{SfxEvent, SfxQueue},
+    combat::{self, MeleeBonuses},
+    constants::colors,
+    CombatStats, DefenseBonus, Equipped, GameLog, InflictsPoison, MeleeDamageBonus, Name, Poison,
     Position, SufferDamage, WantsToMelee,
 };
-use rltk::{ColorPair, RGB};
+use rltk::{ColorPair, Point, RandomNumberGenerator, RGB};
 use specs::prelude::*;
 
+///Melee power and defense of an entity, including the bonuses of everything it has equipped
+pub fn effective_combat_stats(
+    entity: Entity,
+    stats: &CombatStats,
+    equipped_items: &ReadStorage<'_, Equipped>,
+    damage_bonuses: &ReadStorage<'_, MeleeDamageBonus>,
+    defense_bonuses: &ReadStorage<'_, DefenseBonus>,
+) -> (i32, i32) {
+    let bonuses = equipment_bonuses(entity, equipped_items, damage_bonuses, defense_bonuses);
+    (stats.power + bonuses.power, stats.defense + bonuses.defense)
+}
+
+///Everything an entity gets from what it has equipped
+struct EquipmentBonuses {
+    power: i32,
+    defense: i32,
+    crit_chance: i32,
+    attacks_per_turn: i32,
+}
+
+///Adds up the bonuses of everything the entity has equipped, in a single pass over the equipment
+fn equipment_bonuses(
+    entity: Entity,
+    equipped_items: &ReadStorage<'_, Equipped>,
+    damage_bonuses: &ReadStorage<'_, MeleeDamageBonus>,
+    defense_bonuses: &ReadStorage<'_, DefenseBonus>,
+) -> EquipmentBonuses {
+    let mut bonuses = EquipmentBonuses {
+        power: 0,
+        defense: 0,
+        crit_chance: 0,
+        attacks_per_turn: 1,
+    };
+    for (equipped_item, damage_bonus, defense_bonus) in (
+        equipped_items,
+        damage_bonuses.maybe(),
+        defense_bonuses.maybe(),
+    )
+        .join()
+    {
+        if equipped_item.owner != entity {
+            continue;
+        }
+        if let Some(damage_bonus) = damage_bonus {
+            bonuses.power += damage_bonus.bonus;
+            bonuses.crit_chance += damage_bonus.crit_bonus;
+            bonuses.attacks_per_turn =
+                i32::max(bonuses.attacks_per_turn, damage_bonus.attacks_per_turn);
+        }
+        bonuses.defense += defense_bonus.map_or(0, |bonus| bonus.bonus);
+    }
+    bonuses
+}
+
 pub struct MeleeCombatSystem {}
 
 impl<'a> System<'a> for MeleeCombatSystem {
@@ -15,78 +72,130 @@ impl<'a> System<'a> for MeleeCombatSystem {
         ReadStorage<'a, CombatStats>,
         ReadStorage<'a, DefenseBonus>,
         ReadStorage<'a, Equipped>,
+        ReadStorage<'a, InflictsPoison>,
         ReadStorage<'a, MeleeDamageBonus>,
         ReadStorage<'a, Name>,
         ReadStorage<'a, Position>,
         WriteExpect<'a, GameLog>,
         WriteExpect<'a, ParticleBuilder>,
+        WriteExpect<'a, RandomNumberGenerator>,
+        WriteExpect<'a, SfxQueue>,
+        WriteStorage<'a, Poison>,
         WriteStorage<'a, SufferDamage>,
         WriteStorage<'a, WantsToMelee>,
     );
 
+    #[allow(clippy::too_many_lines)]
     fn run(&mut self, data: Self::SystemData) {
         let (
             entities,
             all_stats,
             defense_bonuses,
             equipped_items,
+            poisoners,
             damage_bonuses,
             names,
             positions,
             mut game_log,
             mut particle_builder,
+            mut rng,
+            mut sfx,
+            mut poisoned,
             mut damages,
             mut attacks,
         ) = data;
 
         for (attacker, attack, name, stats) in (&entities, &attacks, &names, &all_stats).join() {
             if stats.hp > 0 {
-                let mut attack_bonus_sum = 0;
-                for (_, damage_bonus, equipped_item) in
-                    (&entities, &damage_bonuses, &equipped_items).join()
-                {
-                    if equipped_item.owner == attacker {
-                        attack_bonus_sum += damage_bonus.bonus;
-                    }
-                }
-
                 //If the target is alive
                 let target_stats = all_stats.get(attack.target).unwrap();
                 if target_stats.hp > 0 {
-                    let mut defense_bonus_sum = 0;
-                    for (_, defense_bonus, equipped_item) in
-                        (&entities, &defense_bonuses, &equipped_items).join()
-                    {
-                        if equipped_item.owner == attack.target {
-                            defense_bonus_sum += defense_bonus.bonus;
+                    let attacker_bonuses = equipment_bonuses(
+                        attacker,
+                        &equipped_items,
+                        &damage_bonuses,
+                        &defense_bonuses,
+                    );
+                    let defender_bonuses = equipment_bonuses(
+                        attack.target,
+                        &equipped_items,
+                        &damage_bonuses,
+                        &defense_bonuses,
+                    );
+                    let bonuses = MeleeBonuses {
+                        power: attacker_bonuses.power,
+                        defense: defender_bonuses.defense,
+                        crit_chance: attacker_bonuses.crit_chance,
+                    };
+                    let target_name = &(names.get(attack.target).unwrap().name);
+
+                    //Every hit is resolved on its own, stopping once the target is dead
+                    let mut defender = target_stats.clone();
+                    let mut any_critical = false;
+                    let mut any_hit = false;
+                    for _ in 0..attacker_bonuses.attacks_per_turn {
+                        let outcome =
+                            combat::resolve_melee(stats, &defender, bonuses, rng.roll_dice(1, 100));
+                        any_critical |= outcome.critical;
+                        //Inform player
+                        if outcome.is_blocked() {
+                            game_log.push(&format!(
+                                "{} blocked the attack of {}.",
+                                target_name, &name.name
+                            ));
+                        } else {
+                            let message = format!(
+                                "{} hits {} for {} damage.",
+                                &name.name, target_name, outcome.damage
+                            );
+                            if outcome.critical {
+                                game_log.push_colored(
+                                    &format!("Critical hit! {message}"),
+                                    RGB::named(rltk::YELLOW),
+                                );
+                            } else {
+                                game_log.push_colored(&message, RGB::named(rltk::RED));
+                            }
+                            SufferDamage::new_damage(
+                                &mut damages,
+                                attack.target,
+                                outcome.damage,
+                                Some(attacker),
+                            );
+                            sfx.push(SfxEvent::Hit);
+                            defender.hp -= outcome.damage;
+                            any_hit = true;
+                        }
+                        if outcome.killed {
+                            break;
                         }
                     }
 
-                    //Calculate damage
-                    let bonus_diff = attack_bonus_sum - defense_bonus_sum;
-                    let damage = i32::max(0, stats.power - target_stats.defense + bonus_diff);
-                    let target_name = &(names.get(attack.target).unwrap().name);
-
-                    //Inform player
-                    let message;
-                    if damage == 0 {
-                        message = format!("{} is unable to hurt {}.", &name.name, target_name);
-                    } else {
-                        message =
-                            format!("{} hits {} for {} damage.", &name.name, target_name, damage);
-                        SufferDamage::new_damage(&mut damages, attack.target, damage);
+                    //Venom comes from the attacker itself or from the weapon it wields
+                    let venom = poisoners.get(attacker).or_else(|| {
+                        (&poisoners, &equipped_items)
+                            .join()
+                            .find(|(_, equipped)| equipped.owner == attacker)
+                            .map(|(poison, _)| poison)
+                    });
+                    if let Some(venom) = venom.filter(|_| any_hit && defender.hp > 0) {
+                        poisoned
+                            .insert(
+                                attack.target,
+                                Poison {
+                                    damage_per_turn: venom.damage_per_turn,
+                                    turns: venom.turns,
+                                },
+                            )
+                            .expect("Unable to poison target");
+                        game_log.push_colored(
+                            &format!("{target_name} is poisoned."),
+                            RGB::named(rltk::GREEN),
+                        );
                     }
-                    game_log.push(&message);
 
-                    //Create damage effect
                     if let Some(pos) = positions.get(attack.target) {
-                        particle_builder.create_particle(
-                            pos.x,
-                            pos.y,
-                            ColorPair::new(RGB::named(rltk::ORANGE), RGB::from(colors::BACKGROUND)),
-                            19, //‼
-                            200.0,
-                        );
+                        create_hit_effect(&mut particle_builder, pos, any_critical);
                     }
                 }
             }
@@ -94,3 +203,192 @@ impl<'a> System<'a> for MeleeCombatSystem {
         attacks.clear();
     }
 }
+
+///Creates the damage effect on the target, critical hits burst around it
+fn create_hit_effect(particle_builder: &mut ParticleBuilder, pos: &Position, critical: bool) {
+    if critical {
+        particle_builder.create_burst(
+            Point::new(pos.x, pos.y),
+            ColorPair::new(RGB::named(rltk::YELLOW), RGB::from(colors::BACKGROUND)),
+            rltk::to_cp437('*'),
+            200.0,
+        );
+    } else {
+        particle_builder.create_particle(
+            pos.x,
+            pos.y,
+            ColorPair::new(RGB::named(rltk::ORANGE), RGB::from(colors::BACKGROUND)),
+            19, //‼
+            200.0,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        components::EquipmentSlot,
+        test_support::{spawn_monster, test_world},
+    };
+
+    ///Has the attacker strike the target once, returning the damage each hit dealt
+    fn strike(world: &mut World, attacker: Entity, target: Entity) -> Vec<i32> {
+        world
+            .write_storage()
+            .insert(attacker, WantsToMelee { target })
+            .unwrap();
+        MeleeCombatSystem {}.run_now(world);
+        world.maintain();
+        world
+            .read_storage::<SufferDamage>()
+            .get(target)
+            .map_or_else(Vec::new, |damage| {
+                damage.amount.iter().map(|(amount, _)| *amount).collect()
+            })
+    }
+
+    fn equip(world: &mut World, owner: Entity, bonus: MeleeDamageBonus) {
+        world
+            .create_entity()
+            .with(Equipped {
+                owner,
+                slot: EquipmentSlot::PrimaryHand,
+            })
+            .with(bonus)
+            .build();
+    }
+
+    #[test]
+    fn light_weapon_strikes_twice_a_turn() {
+        let mut world = test_world(10, 10, (1, 1));
+        let attacker = spawn_monster(&mut world, (4, 4), 10, 3);
+        let target = spawn_monster(&mut world, (5, 4), 50, 1);
+        equip(
+            &mut world,
+            attacker,
+            MeleeDamageBonus {
+                bonus: 1,
+                attacks_per_turn: 2,
+                crit_bonus: 0,
+            },
+        );
+
+        assert_eq!(strike(&mut world, attacker, target), vec![4, 4]);
+    }
+
+    #[test]
+    fn certain_critical_hit_doubles_the_damage() {
+        let mut world = test_world(10, 10, (1, 1));
+        let attacker = spawn_monster(&mut world, (4, 4), 10, 3);
+        let target = spawn_monster(&mut world, (5, 4), 50, 1);
+        world
+            .write_storage::<CombatStats>()
+            .get_mut(attacker)
+            .unwrap()
+            .crit_chance = 100;
+
+        assert_eq!(strike(&mut world, attacker, target), vec![6]);
+        let logs = world.fetch::<GameLog>();
+        let (message, _) = logs.recent(1).next().unwrap();
+        assert_eq!(
+            message,
+            "Critical hit! Test Monster hits Test Monster for 6 damage."
+        );
+    }
+
+    #[test]
+    fn bonuses_of_everything_equipped_add_up() {
+        let mut world = test_world(10, 10, (1, 1));
+        let wearer = spawn_monster(&mut world, (4, 4), 10, 3);
+        let other = spawn_monster(&mut world, (5, 4), 10, 3);
+        equip(
+            &mut world,
+            wearer,
+            MeleeDamageBonus {
+                bonus: 2,
+                attacks_per_turn: 1,
+                crit_bonus: 5,
+            },
+        );
+        equip(
+            &mut world,
+            wearer,
+            MeleeDamageBonus {
+                bonus: 1,
+                attacks_per_turn: 2,
+                crit_bonus: 0,
+            },
+        );
+        world
+            .create_entity()
+            .with(Equipped {
+                owner: wearer,
+                slot: EquipmentSlot::OffHand,
+            })
+            .with(DefenseBonus { bonus: 3 })
+            .build();
+        //Worn by someone else, so it counts for nothing
+        equip(
+            &mut world,
+            other,
+            MeleeDamageBonus {
+                bonus: 10,
+                attacks_per_turn: 3,
+                crit_bonus: 10,
+            },
+        );
+
+        let bonuses = equipment_bonuses(
+            wearer,
+            &world.read_storage(),
+            &world.read_storage(),
+            &world.read_storage(),
+        );
+        assert_eq!(bonuses.power, 3);
+        assert_eq!(bonuses.defense, 3);
+        assert_eq!(bonuses.crit_chance, 5);
+        assert_eq!(bonuses.attacks_per_turn, 2);
+
+        let stats = world
+            .read_storage::<CombatStats>()
+            .get(wearer)
+            .cloned()
+            .unwrap();
+        let effective = effective_combat_stats(
+            wearer,
+            &stats,
+            &world.read_storage(),
+            &world.read_storage(),
+            &world.read_storage(),
+        );
+        assert_eq!(effective, (6, 3));
+    }
+
+    #[test]
+    fn only_blows_that_land_make_a_sound() {
+        let mut world = test_world(10, 10, (1, 1));
+        let attacker = spawn_monster(&mut world, (4, 4), 10, 3);
+        let target = spawn_monster(&mut world, (5, 4), 50, 1);
+        equip(
+            &mut world,
+            attacker,
+            MeleeDamageBonus {
+                bonus: 0,
+                attacks_per_turn: 2,
+                crit_bonus: 0,
+            },
+        );
+        strike(&mut world, attacker, target);
+        assert_eq!(
+            world.fetch::<SfxQueue>().events(),
+            [SfxEvent::Hit, SfxEvent::Hit]
+        );
+
+        //The blows of a creature too weak to get through the armor are blocked in silence
+        let weakling = spawn_monster(&mut world, (4, 5), 10, 0);
+        world.write_storage::<SufferDamage>().clear();
+        strike(&mut world, weakling, target);
+        assert_eq!(world.fetch::<SfxQueue>().events().len(), 2);
+    }
+}
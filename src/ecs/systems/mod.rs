@@ -1,15 +1,41 @@
+mod ally_ai_system;
+mod bestiary_system;
+mod confusion_system;
 mod damage_system;
+mod fire_system;
+mod hunger_system;
+mod initiative_system;
 mod item_systems;
 mod map_indexing_system;
 mod melee_combat_system;
 mod monster_ai_system;
 mod particle_system;
+mod poison_system;
+mod ranged_combat_system;
+mod summon_systems;
+mod teleport_system;
+mod terrain_system;
+mod throw_system;
+mod trap_systems;
 mod visibility_system;
 
+pub use ally_ai_system::*;
+pub use bestiary_system::*;
+pub use confusion_system::*;
 pub use damage_system::*;
+pub use fire_system::*;
+pub use hunger_system::*;
+pub use initiative_system::*;
 pub use item_systems::*;
 pub use map_indexing_system::*;
 pub use melee_combat_system::*;
 pub use monster_ai_system::*;
 pub use particle_system::*;
+pub use poison_system::*;
+pub use ranged_combat_system::*;
+pub use summon_systems::*;
+pub use teleport_system::*;
+pub use terrain_system::*;
+pub use throw_system::*;
+pub use trap_systems::*;
 pub use visibility_system::*;
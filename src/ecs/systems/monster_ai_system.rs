@@ -1,11 +1,23 @@
+use super::{roll_critical, ParticleBuilder, ACTION_COST};
 use crate::{
-    components::{FieldOfView, Monster, Position, WantsToMelee},
-    map_builder::map::Map,
+    components::{
+        Aggro, CombatStats, Confusion, EntityMoved, Faction, FieldOfView, FleeThreshold,
+        InflictsDamage, Initiative, Monster, Name, PathCache, Position, Range, Size, Stealth,
+        SufferDamage, WantsToMelee,
+    },
+    constants::colors,
+    game_log::GameLog,
+    map_builder::map::{has_line_of_sight, Map, TileStatus},
     state::{Gameplay, State, State::Game},
 };
-use rltk::Point;
+use rltk::{Algorithm2D, ColorPair, DijkstraMap, Point, RandomNumberGenerator, RGB};
 use specs::prelude::*;
 
+//How far from the player fleeing monsters look for a way out
+const FLEE_DEPTH: f32 = 20.0;
+//How many turns monsters keep looking for a foe they lost sight of
+const PURSUIT_TURNS: i32 = 5;
+
 pub struct MonsterAI {}
 impl<'a> System<'a> for MonsterAI {
     #[allow(clippy::type_complexity)]
@@ -14,23 +26,58 @@ impl<'a> System<'a> for MonsterAI {
         ReadExpect<'a, Point>,
         ReadExpect<'a, Entity>,
         ReadExpect<'a, State>,
+        ReadStorage<'a, Faction>,
         ReadStorage<'a, Monster>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Range>,
+        ReadStorage<'a, InflictsDamage>,
+        ReadStorage<'a, CombatStats>,
+        ReadStorage<'a, FleeThreshold>,
+        ReadStorage<'a, Size>,
+        ReadStorage<'a, Stealth>,
         WriteExpect<'a, Map>,
+        WriteExpect<'a, GameLog>,
+        WriteExpect<'a, ParticleBuilder>,
+        WriteExpect<'a, RandomNumberGenerator>,
+        WriteStorage<'a, Aggro>,
+        WriteStorage<'a, Confusion>,
+        WriteStorage<'a, EntityMoved>,
+        WriteStorage<'a, Initiative>,
+        WriteStorage<'a, PathCache>,
         WriteStorage<'a, Position>,
         WriteStorage<'a, FieldOfView>,
+        WriteStorage<'a, SufferDamage>,
         WriteStorage<'a, WantsToMelee>,
     );
 
+    #[allow(clippy::too_many_lines)]
     fn run(&mut self, data: Self::SystemData) {
         let (
             entities,
             player_pos,
             player_ent,
             state,
+            factions,
             monsters,
-            map,
+            names,
+            ranges,
+            damages,
+            all_stats,
+            flee_thresholds,
+            sizes,
+            stealths,
+            mut map,
+            mut logs,
+            mut particle_builder,
+            mut rng,
+            mut aggros,
+            mut confusions,
+            mut moved,
+            mut initiatives,
+            mut path_caches,
             mut positions,
             mut fields_of_view,
+            mut suffering,
             mut attacks,
         ) = data;
 
@@ -38,37 +85,632 @@ impl<'a> System<'a> for MonsterAI {
             return;
         }
 
+        //Only built once a monster needs to flee, as most turns nobody does
+        let mut flee_map: Option<DijkstraMap> = None;
+        let creatures = creatures_by_faction(&entities, &factions, &positions, &sizes);
+
         for (mut fov, mut pos, ent, _) in
             (&mut fields_of_view, &mut positions, &entities, &monsters).join()
         {
-            //If monster can see player attack if within range or approach
-            if fov.visible_tiles.contains(&*player_pos) {
-                let distance =
-                    rltk::DistanceAlg::Pythagoras.distance2d(Point::new(pos.x, pos.y), *player_pos);
-                if distance < 2.0 {
-                    attacks
-                        .insert(
-                            ent,
-                            WantsToMelee {
-                                target: *player_ent,
-                            },
-                        )
-                        .expect("Unable to insert attack");
-                } else {
-                    let path = rltk::a_star_search(
-                        map.xy_idx(pos.x, pos.y) as i32,
-                        map.xy_idx(player_pos.x, player_pos.y) as i32,
-                        &*map,
-                    );
+            //Only monsters whose initiative has run out get to act
+            if let Some(initiative) = initiatives.get_mut(ent) {
+                if initiative.current > 0 {
+                    continue;
+                }
+                initiative.current += ACTION_COST;
+            }
+            let size = sizes.get(ent).copied().unwrap_or_default();
+            let path_cache = path_caches
+                .entry(ent)
+                .expect("Unable to access path cache")
+                .or_insert_with(PathCache::default);
+
+            //Confused monsters stumble around instead of acting
+            if let Some(confusion) = confusions.get_mut(ent) {
+                confusion.turns -= 1;
+                if confusion.turns < 1 {
+                    confusions.remove(ent);
+                }
+
+                if stumble(pos, fov, &mut map, *player_pos, size, &mut rng) {
+                    moved
+                        .insert(ent, EntityMoved {})
+                        .expect("Unable to insert movement marker");
+                }
+
+                particle_builder.create_particle(
+                    pos.x,
+                    pos.y,
+                    ColorPair::new(RGB::named(rltk::MAGENTA), RGB::from(colors::BACKGROUND)),
+                    rltk::to_cp437('?'),
+                    200.0,
+                );
+                continue;
+            }
+
+            //Badly hurt monsters run from the player, and only fight back once cornered
+            let wants_to_flee = match (all_stats.get(ent), flee_thresholds.get(ent)) {
+                (Some(combat_stats), Some(threshold)) => {
+                    (combat_stats.hp as f32) < combat_stats.max_hp as f32 * threshold.fraction
+                }
+                _ => false,
+            };
+            if wants_to_flee && fov.visible_tiles.contains(&*player_pos) {
+                let flee_map = flee_map.get_or_insert_with(|| {
+                    let player_idx = map.xy_idx(player_pos.x, player_pos.y);
+                    DijkstraMap::new(map.width, map.height, &[player_idx], &*map, FLEE_DEPTH)
+                });
+                if flee(pos, fov, &mut map, flee_map, *player_pos, size) {
+                    moved
+                        .insert(ent, EntityMoved {})
+                        .expect("Unable to insert movement marker");
+                    continue;
+                }
+            }
+
+            //Foes in sight are only noticed within the aggro range, shrunk for stealthy foes, unless
+            //already being pursued
+            let here = Point::new(pos.x, pos.y);
+            let faction = factions.get(ent).copied().unwrap_or(Faction::Monster);
+            let foes = hostile_to(faction, &creatures);
+            let mut aggro = aggros.get_mut(ent);
+            let noticed = closest_visible(here, fov, &foes).filter(|(target, target_pos)| {
+                aggro.as_ref().is_none_or(|aggro| {
+                    let range = stealths.get(*target).map_or(aggro.range, |stealth| {
+                        stealth.aggro_range_against(aggro.range)
+                    });
+                    aggro.last_known_position.is_some()
+                        || rltk::DistanceAlg::Chebyshev.distance2d(here, *target_pos)
+                            <= range as f32
+                })
+            });
+
+            //Foes that slipped out of sight are looked for where they were last seen
+            let Some((target, target_pos)) = noticed else {
+                let Some(aggro) = aggro else {
+                    continue;
+                };
+                let Some(last_known_position) = aggro.last_known_position else {
+                    continue;
+                };
+                aggro.pursuit_turns -= 1;
+                if here == last_known_position || aggro.pursuit_turns <= 0 {
+                    aggro.last_known_position = None;
+                } else if follow_path(
+                    pos,
+                    fov,
+                    &mut map,
+                    path_cache,
+                    last_known_position,
+                    *player_pos,
+                    size,
+                ) {
+                    moved
+                        .insert(ent, EntityMoved {})
+                        .expect("Unable to insert movement marker");
+                }
+                continue;
+            };
+            if let Some(aggro) = aggro.as_mut() {
+                aggro.last_known_position = Some(target_pos);
+                aggro.pursuit_turns = PURSUIT_TURNS;
+            }
+
+            //Attack the foe if within range or else approach it, large monsters reaching from
+            //whichever of their tiles is closest
+            let distance = size
+                .tiles(here)
+                .map(|tile| rltk::DistanceAlg::Pythagoras.distance2d(tile, target_pos))
+                .fold(f32::MAX, f32::min);
+            //Shots need a clear line, which the field of view does not always guarantee
+            let ranged_attack = match (ranges.get(ent), damages.get(ent)) {
+                (Some(range), Some(damage))
+                    if distance <= range.range as f32
+                        && has_line_of_sight(&map, Point::new(pos.x, pos.y), target_pos) =>
+                {
+                    Some(damage)
+                }
+                _ => None,
+            };
 
-                    if path.success && path.steps.len() > 1 {
-                        //Do note, that this does NOT check if the player is there
-                        pos.x = path.steps[1] as i32 % map.width;
-                        pos.y = path.steps[1] as i32 / map.width;
-                        fov.is_dirty = true;
+            if let Some(damage) = ranged_attack {
+                let crit_chance = all_stats.get(ent).map_or(0, |stats| stats.crit_chance);
+                let (amount, critical) = roll_critical(&mut rng, crit_chance, damage.damage);
+                SufferDamage::new_damage(&mut suffering, target, amount, Some(ent));
+                particle_builder.create_projectile(
+                    &map,
+                    Point::new(pos.x, pos.y),
+                    target_pos,
+                    ColorPair::new(RGB::named(rltk::ORANGE), RGB::from(colors::BACKGROUND)),
+                    rltk::to_cp437('*'),
+                );
+                if critical {
+                    particle_builder.create_burst(
+                        target_pos,
+                        ColorPair::new(RGB::named(rltk::YELLOW), RGB::from(colors::BACKGROUND)),
+                        rltk::to_cp437('*'),
+                        200.0,
+                    );
+                }
+                if let Some(name) = names.get(ent) {
+                    let target_name = if target == *player_ent {
+                        "you"
+                    } else {
+                        names
+                            .get(target)
+                            .map_or("something", |name| name.name.as_str())
+                    };
+                    let message = format!(
+                        "{} shoots {} for {} damage.",
+                        name.name, target_name, amount
+                    );
+                    if critical {
+                        logs.push_colored(
+                            &format!("Critical hit! {message}"),
+                            RGB::named(rltk::YELLOW),
+                        );
+                    } else {
+                        logs.push_colored(&message, RGB::named(rltk::RED));
                     }
                 }
+            } else if distance < 2.0 {
+                attacks
+                    .insert(ent, WantsToMelee { target })
+                    .expect("Unable to insert attack");
+            } else if follow_path(
+                pos,
+                fov,
+                &mut map,
+                path_cache,
+                target_pos,
+                *player_pos,
+                size,
+            ) {
+                moved
+                    .insert(ent, EntityMoved {})
+                    .expect("Unable to insert movement marker");
+            }
+        }
+    }
+}
+
+///Every creature with a faction and its position, gathered before anyone moves. Large creatures
+///are listed once for every tile they take up, so the closest of them is the one fought or chased
+pub(super) fn creatures_by_faction(
+    entities: &Entities<'_>,
+    factions: &ReadStorage<'_, Faction>,
+    positions: &WriteStorage<'_, Position>,
+    sizes: &ReadStorage<'_, Size>,
+) -> Vec<(Entity, Faction, Point)> {
+    (entities, factions, positions, sizes.maybe())
+        .join()
+        .flat_map(|(ent, faction, pos, size)| {
+            let size = size.copied().unwrap_or_default();
+            size.tiles(Point::new(pos.x, pos.y))
+                .map(move |tile| (ent, *faction, tile))
+        })
+        .collect()
+}
+
+///The creatures the given faction attacks on sight
+pub(super) fn hostile_to(
+    faction: Faction,
+    creatures: &[(Entity, Faction, Point)],
+) -> Vec<(Entity, Point)> {
+    creatures
+        .iter()
+        .filter(|(_, other, _)| faction.is_hostile_to(*other))
+        .map(|(ent, _, point)| (*ent, *point))
+        .collect()
+}
+
+///The closest of the given creatures that can be seen from the field of view, if any
+pub(super) fn closest_visible(
+    from: Point,
+    fov: &FieldOfView,
+    creatures: &[(Entity, Point)],
+) -> Option<(Entity, Point)> {
+    creatures
+        .iter()
+        .filter(|(_, point)| fov.visible_tiles.contains(point))
+        .min_by(|(_, a), (_, b)| {
+            let distance = |point| rltk::DistanceAlg::Pythagoras.distance2d(from, point);
+            distance(*a).total_cmp(&distance(*b))
+        })
+        .copied()
+}
+
+///Takes the next step of the path kept in the cache, only searching for a new path when there is
+///none to the target, or when its next step can not be taken from here or is blocked
+fn follow_path(
+    pos: &mut Position,
+    fov: &mut FieldOfView,
+    map: &mut Map,
+    cache: &mut PathCache,
+    target: Point,
+    player_pos: Point,
+    size: Size,
+) -> bool {
+    let here = Point::new(pos.x, pos.y);
+    let cached_step = cache.steps.last().copied().filter(|&idx| {
+        cache.target == Some(target)
+            && rltk::DistanceAlg::Chebyshev.distance2d(here, map.index_to_point2d(idx)) <= 1.0
+    });
+    if let Some(next) = cached_step {
+        if step_to(pos, fov, map, next, player_pos, size) {
+            cache.steps.pop();
+            return true;
+        }
+    }
+
+    cache.target = Some(target);
+    cache.steps = find_path(pos, map, target, size);
+    cache.steps.reverse();
+    let moved = cache
+        .steps
+        .pop()
+        .is_some_and(|next| step_to(pos, fov, map, next, player_pos, size));
+    if !moved {
+        cache.steps.clear();
+    }
+    moved
+}
+
+///Takes the first step of the shortest path to the target. The target's own tile counts as free
+///while searching, as whoever stands on it would otherwise block every path to them, and so do the
+///tiles a large creature takes up itself
+pub(super) fn step_towards(
+    pos: &mut Position,
+    fov: &mut FieldOfView,
+    map: &mut Map,
+    target: Point,
+    player_pos: Point,
+    size: Size,
+) -> bool {
+    find_path(pos, map, target, size)
+        .first()
+        .is_some_and(|&next| step_to(pos, fov, map, next, player_pos, size))
+}
+
+///The steps of the shortest path to the target, leaving out the tile the search starts from. See
+///`step_towards` for the tiles that count as free
+fn find_path(pos: &Position, map: &mut Map, target: Point, size: Size) -> Vec<usize> {
+    let target_idx = map.xy_idx(target.x, target.y);
+    let freed = size
+        .tiles(Point::new(pos.x, pos.y))
+        .chain(std::iter::once(target))
+        .filter(|tile| map.in_bounds(*tile))
+        .map(|tile| map.xy_idx(tile.x, tile.y))
+        .filter(|idx| map.is_tile_status_set(*idx, TileStatus::Blocked))
+        .collect::<Vec<_>>();
+    for idx in &freed {
+        map.remove_tile_status(*idx, TileStatus::Blocked);
+    }
+    let path = rltk::a_star_search(map.xy_idx(pos.x, pos.y), target_idx, &*map);
+    for idx in freed {
+        map.set_tile_status(idx, TileStatus::Blocked);
+    }
+
+    if path.success {
+        path.steps.into_iter().skip(1).collect()
+    } else {
+        Vec::new()
+    }
+}
+
+///Steps to the neighboring tile furthest from the player, returning false if none of them is
+///further away than where the monster stands
+fn flee(
+    pos: &mut Position,
+    fov: &mut FieldOfView,
+    map: &mut Map,
+    flee_map: &DijkstraMap,
+    player_pos: Point,
+    size: Size,
+) -> bool {
+    let old_idx = map.xy_idx(pos.x, pos.y);
+    let Some(new_idx) = DijkstraMap::find_highest_exit(flee_map, old_idx, &*map) else {
+        return false;
+    };
+    let new_pos = map.index_to_point2d(new_idx);
+    let distance = |point| rltk::DistanceAlg::Pythagoras.distance2d(point, player_pos);
+    if distance(new_pos) <= distance(Point::new(pos.x, pos.y)) {
+        return false;
+    }
+
+    step_to(pos, fov, map, new_idx, player_pos, size)
+}
+
+///Moves to a random neighboring tile if it is free, returning whether a move was made
+fn stumble(
+    pos: &mut Position,
+    fov: &mut FieldOfView,
+    map: &mut Map,
+    player_pos: Point,
+    size: Size,
+    rng: &mut RandomNumberGenerator,
+) -> bool {
+    let new_x = pos.x + rng.range(-1, 2);
+    let new_y = pos.y + rng.range(-1, 2);
+    map.in_bounds(Point::new(new_x, new_y))
+        && step_to(pos, fov, map, map.xy_idx(new_x, new_y), player_pos, size)
+}
+
+///Moves onto the tile if nothing blocks it, keeping the blocked tiles up to date so that no other
+///monster can move onto the same tile this turn. Large creatures need every tile they would take up
+///to be free, save for those they already stand on. Returns whether a move was made
+pub(super) fn step_to(
+    pos: &mut Position,
+    fov: &mut FieldOfView,
+    map: &mut Map,
+    new_idx: usize,
+    player_pos: Point,
+    size: Size,
+) -> bool {
+    let new_pos = map.index_to_point2d(new_idx);
+    let old_tiles = size.tiles(Point::new(pos.x, pos.y)).collect::<Vec<_>>();
+    let new_tiles = size.tiles(new_pos).collect::<Vec<_>>();
+    let is_free = |tile: &Point| {
+        map.in_bounds(*tile)
+            && *tile != player_pos
+            && (old_tiles.contains(tile)
+                || !map.is_tile_status_set(map.xy_idx(tile.x, tile.y), TileStatus::Blocked))
+    };
+    if !new_tiles.iter().all(is_free) {
+        return false;
+    }
+
+    for tile in old_tiles {
+        map.remove_tile_status(map.xy_idx(tile.x, tile.y), TileStatus::Blocked);
+    }
+    for tile in new_tiles {
+        map.set_tile_status(map.xy_idx(tile.x, tile.y), TileStatus::Blocked);
+    }
+    pos.x = new_pos.x;
+    pos.y = new_pos.y;
+    fov.is_dirty = true;
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ecs::pre_run_systems,
+        map_builder::map::TileType,
+        test_support::{room_map, spawn_monster, test_world, TEST_SEED},
+    };
+
+    ///A monster next to the player with 2 of its 10 health left, which flees below the threshold
+    fn wounded_monster(threshold: Option<f32>) -> (World, Entity) {
+        let mut world = test_world(12, 12, (4, 5));
+        let monster = spawn_monster(&mut world, (5, 5), 10, 1);
+        world
+            .write_storage::<CombatStats>()
+            .get_mut(monster)
+            .unwrap()
+            .hp = 2;
+        if let Some(fraction) = threshold {
+            world
+                .write_storage()
+                .insert(monster, FleeThreshold { fraction })
+                .unwrap();
+        }
+        pre_run_systems::execute(&mut world);
+        world.insert(State::Game(Gameplay::MonsterTurn));
+        MonsterAI {}.run_now(&world);
+        world.maintain();
+        (world, monster)
+    }
+
+    #[test]
+    fn badly_hurt_monster_steps_away_instead_of_attacking() {
+        let (world, monster) = wounded_monster(Some(0.5));
+        let pos = world
+            .read_storage::<Position>()
+            .get(monster)
+            .map(|pos| pos.x);
+        assert_eq!(pos, Some(6));
+        assert!(!world.read_storage::<WantsToMelee>().contains(monster));
+    }
+
+    #[test]
+    fn monster_without_a_threshold_fights_to_the_death() {
+        let (world, monster) = wounded_monster(None);
+        assert!(world.read_storage::<WantsToMelee>().contains(monster));
+    }
+
+    #[test]
+    fn two_monsters_never_step_onto_the_same_tile() {
+        //The only way to the player from the east is a single gap in a wall, which both monsters
+        //head for as they pursue the player out of sight
+        let mut world = test_world(12, 5, (2, 2));
+        {
+            let mut map = world.fetch_mut::<Map>();
+            for y in [1, 3] {
+                let idx = map.xy_idx(3, y);
+                map.tiles[idx] = TileType::Wall;
             }
+            map.populate_blocked();
+        }
+        let first = spawn_monster(&mut world, (4, 1), 5, 1);
+        let second = spawn_monster(&mut world, (4, 3), 5, 1);
+        for monster in [first, second] {
+            let aggro = Aggro {
+                range: 8,
+                last_known_position: Some(Point::new(2, 2)),
+                pursuit_turns: PURSUIT_TURNS,
+            };
+            world.write_storage().insert(monster, aggro).unwrap();
         }
+        pre_run_systems::execute(&mut world);
+        world.insert(State::Game(Gameplay::MonsterTurn));
+        MonsterAI {}.run_now(&world);
+
+        let positions = world.read_storage::<Position>();
+        let tiles = [first, second]
+            .map(|monster| positions.get(monster).map(|pos| (pos.x, pos.y)).unwrap());
+        assert_ne!(tiles[0], tiles[1]);
+        assert_eq!(tiles.iter().filter(|tile| **tile == (3, 2)).count(), 1);
+    }
+
+    ///Where a monster with an aggro range of 5 ends its turn, standing the given distance east of
+    ///the player with nothing in between
+    fn monster_x_after_turn(distance: i32, sneaking: bool) -> i32 {
+        let mut world = test_world(14, 5, (1, 2));
+        let player_ent = *world.fetch::<Entity>();
+        world
+            .write_storage::<Stealth>()
+            .get_mut(player_ent)
+            .unwrap()
+            .sneaking = sneaking;
+        let monster = spawn_monster(&mut world, (1 + distance, 2), 5, 1);
+        let aggro = Aggro {
+            range: 5,
+            last_known_position: None,
+            pursuit_turns: 0,
+        };
+        world.write_storage().insert(monster, aggro).unwrap();
+        pre_run_systems::execute(&mut world);
+        world.insert(State::Game(Gameplay::MonsterTurn));
+        MonsterAI {}.run_now(&world);
+        let x = world.read_storage::<Position>().get(monster).unwrap().x;
+        x
+    }
+
+    #[test]
+    fn monster_only_notices_foes_within_its_aggro_range() {
+        //Both distances are within the monster's field of view
+        assert_eq!(monster_x_after_turn(7, false), 8);
+        assert_eq!(monster_x_after_turn(4, false), 4);
+    }
+
+    #[test]
+    fn sneaking_player_goes_unnoticed_closer_by() {
+        assert_eq!(monster_x_after_turn(4, true), 5);
+    }
+
+    #[test]
+    fn cached_path_is_followed_until_blocked_or_retargeted() {
+        //A wall splits the room, with a single gap at its bottom
+        let mut map = room_map(14, 9, 1);
+        for y in 1..=6 {
+            let idx = map.xy_idx(5, y);
+            map.tiles[idx] = TileType::Wall;
+        }
+        map.populate_blocked();
+        let mut pos = Position { x: 2, y: 2 };
+        let mut fov = FieldOfView {
+            visible_tiles: Vec::new(),
+            range: 8,
+            is_dirty: false,
+            lights: Vec::new(),
+        };
+        let mut cache = PathCache::default();
+        let player_pos = Point::new(12, 1);
+        let mut follow = |pos: &mut Position, map: &mut Map, cache: &mut PathCache, target| {
+            follow_path(
+                pos,
+                &mut fov,
+                map,
+                cache,
+                target,
+                player_pos,
+                Size::default(),
+            )
+        };
+
+        let target = Point::new(9, 2);
+        assert!(follow(&mut pos, &mut map, &mut cache, target));
+        assert_eq!(cache.target, Some(target));
+        //Taking a cached step pops it without searching again
+        let mut expected = cache.steps.clone();
+        let next = expected.pop().unwrap();
+        assert!(follow(&mut pos, &mut map, &mut cache, target));
+        assert_eq!(map.xy_idx(pos.x, pos.y), next);
+        assert_eq!(cache.steps, expected);
+
+        //Something standing in the next step has the monster find a way around it
+        let blocked = *cache.steps.last().unwrap();
+        map.set_tile_status(blocked, TileStatus::Blocked);
+        assert!(follow(&mut pos, &mut map, &mut cache, target));
+        assert_ne!(map.xy_idx(pos.x, pos.y), blocked);
+        assert!(!cache.steps.contains(&blocked));
+        map.remove_tile_status(blocked, TileStatus::Blocked);
+
+        //A new target means a new path, which is then followed all the way
+        let target = Point::new(11, 7);
+        assert!(follow(&mut pos, &mut map, &mut cache, target));
+        assert_eq!(cache.target, Some(target));
+        while !cache.steps.is_empty() {
+            assert!(follow(&mut pos, &mut map, &mut cache, target));
+        }
+        assert_eq!((pos.x, pos.y), (11, 7));
+    }
+
+    ///Where a confused monster in the middle of a room stumbles to over a few turns
+    fn stumbles(seed: u64) -> Vec<(i32, i32)> {
+        let mut world = test_world(20, 20, (1, 1));
+        world.insert(RandomNumberGenerator::seeded(seed));
+        let monster = spawn_monster(&mut world, (10, 10), 5, 1);
+        world
+            .write_storage()
+            .insert(monster, Confusion { turns: 8 })
+            .unwrap();
+        world.insert(State::Game(Gameplay::MonsterTurn));
+        (0..6)
+            .map(|_| {
+                pre_run_systems::execute(&mut world);
+                MonsterAI {}.run_now(&world);
+                world.maintain();
+                let positions = world.read_storage::<Position>();
+                let pos = positions.get(monster).unwrap();
+                (pos.x, pos.y)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_makes_monsters_stumble_the_same_way() {
+        assert_eq!(stumbles(TEST_SEED), stumbles(TEST_SEED));
+        assert_ne!(stumbles(TEST_SEED), stumbles(TEST_SEED + 1));
+    }
+
+    ///Every hit the player takes, with whether the archer dealt it, and where an archer with a
+    ///range of 4 ends its turn, standing the given distance east of the player
+    fn archer_turn(distance: i32) -> (Vec<(i32, bool)>, i32) {
+        let mut world = test_world(14, 5, (1, 2));
+        let archer = spawn_monster(&mut world, (1 + distance, 2), 5, 1);
+        world
+            .write_storage()
+            .insert(archer, Range { range: 4 })
+            .unwrap();
+        world
+            .write_storage()
+            .insert(archer, InflictsDamage { damage: 3 })
+            .unwrap();
+        pre_run_systems::execute(&mut world);
+        world.insert(State::Game(Gameplay::MonsterTurn));
+        MonsterAI {}.run_now(&world);
+
+        let player_ent = *world.fetch::<Entity>();
+        let damage = world
+            .read_storage::<SufferDamage>()
+            .get(player_ent)
+            .map_or_else(Vec::new, |damage| {
+                damage
+                    .amount
+                    .iter()
+                    .map(|(amount, dealer)| (*amount, *dealer == Some(archer)))
+                    .collect()
+            });
+        let x = world.read_storage::<Position>().get(archer).unwrap().x;
+        (damage, x)
+    }
+
+    #[test]
+    fn ranged_monster_shoots_from_within_its_range_and_approaches_otherwise() {
+        assert_eq!(archer_turn(3), (vec![(3, true)], 4));
+        assert_eq!(archer_turn(6), (Vec::new(), 6));
     }
 }
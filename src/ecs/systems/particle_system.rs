@@ -1,11 +1,14 @@
-use crate::{ParticleLifetime, Position, Render};
-use rltk::{ColorPair, FontCharType};
+use crate::{map_builder::map::Map, ParticleLifetime, Position, Render};
+use rltk::{Algorithm2D, BaseMap, BresenhamCircle, ColorPair, FontCharType, Point, RGBA};
 use specs::prelude::*;
 
+const PROJECTILE_LIFETIME: f32 = 100.0;
+
 struct ParticleRequest {
     x: i32,
     y: i32,
     colors: ColorPair,
+    fade_to: Option<RGBA>,
     glyph: FontCharType,
     lifetime: f32,
 }
@@ -33,10 +36,106 @@ impl ParticleBuilder {
             x,
             y,
             colors,
+            fade_to: None,
             glyph,
             lifetime,
         })
     }
+
+    ///Creates a particle whose glyph fades from its foreground color to `fade_to` over its life
+    pub fn create_fade(
+        &mut self,
+        x: i32,
+        y: i32,
+        colors: ColorPair,
+        fade_to: RGBA,
+        glyph: FontCharType,
+        lifetime: f32,
+    ) {
+        self.requests.push(ParticleRequest {
+            x,
+            y,
+            colors,
+            fade_to: Some(fade_to),
+            glyph,
+            lifetime,
+        });
+    }
+
+    ///Creates fading particles on the circle of the given radius around the center
+    pub fn create_ring(
+        &mut self,
+        center: Point,
+        radius: i32,
+        colors: ColorPair,
+        fade_to: RGBA,
+        glyph: FontCharType,
+        lifetime: f32,
+    ) {
+        for point in BresenhamCircle::new(center, radius) {
+            self.create_fade(point.x, point.y, colors, fade_to, glyph, lifetime);
+        }
+    }
+
+    ///Creates a particle on the center and on every tile around it
+    pub fn create_burst(
+        &mut self,
+        center: Point,
+        colors: ColorPair,
+        glyph: FontCharType,
+        lifetime: f32,
+    ) {
+        for delta_x in -1..=1 {
+            for delta_y in -1..=1 {
+                self.create_particle(
+                    center.x + delta_x,
+                    center.y + delta_y,
+                    colors,
+                    glyph,
+                    lifetime,
+                );
+            }
+        }
+    }
+
+    ///Creates a projectile flying from one point towards another, which stops before the first
+    ///opaque tile in its path
+    pub fn create_projectile(
+        &mut self,
+        map: &Map,
+        from: Point,
+        to: Point,
+        colors: ColorPair,
+        glyph: FontCharType,
+    ) {
+        spawn_projectile_path(self, from, projectile_end(map, from, to), glyph, colors);
+    }
+}
+
+///Where a projectile flying from one point towards another comes down, on the target unless a wall
+///or closed door is in the way, in which case it stops in front of it
+pub fn projectile_end(map: &Map, from: Point, to: Point) -> Point {
+    let mut end = from;
+    for step in rltk::line2d_bresenham(from, to).iter().skip(1) {
+        if !map.in_bounds(*step) || map.is_opaque(map.xy_idx(step.x, step.y)) {
+            break;
+        }
+        end = *step;
+    }
+    end
+}
+
+///Creates a particle on every step of the line between the two points, excluding the origin
+pub fn spawn_projectile_path(
+    builder: &mut ParticleBuilder,
+    from: Point,
+    to: Point,
+    glyph: FontCharType,
+    colors: ColorPair,
+) {
+    for step in rltk::line2d_bresenham(from, to).iter().skip(1) {
+        builder.create_particle(step.x, step.y, colors, glyph, PROJECTILE_LIFETIME);
+    }
 }
 
 pub struct ParticleSpawnSystem {}
@@ -79,6 +178,10 @@ impl<'a> System<'a> for ParticleSpawnSystem {
                     p,
                     ParticleLifetime {
                         lifetime_ms: new_particle.lifetime,
+                        total_ms: new_particle.lifetime,
+                        fade: new_particle
+                            .fade_to
+                            .map(|fade_to| (new_particle.colors.fg, fade_to)),
                     },
                 )
                 .expect("Unable to give particle lifetime.");
@@ -87,8 +190,19 @@ impl<'a> System<'a> for ParticleSpawnSystem {
     }
 }
 
+///Color of a fading particle with the given lifetime left, moving evenly from `from` to `to`
+pub fn fade_color(from: RGBA, to: RGBA, remaining_ms: f32, total_ms: f32) -> RGBA {
+    let progress = if total_ms > 0. {
+        (1. - remaining_ms / total_ms).clamp(0., 1.)
+    } else {
+        1.
+    };
+    from.lerp(to, progress)
+}
+
 pub fn cull_dead_particles(ecs: &mut World, frame_time: f32) {
     let mut particles = ecs.write_storage::<ParticleLifetime>();
+    let mut renderables = ecs.write_storage::<Render>();
     let entities = ecs.entities();
 
     let mut dead_particles = Vec::new();
@@ -97,10 +211,13 @@ pub fn cull_dead_particles(ecs: &mut World, frame_time: f32) {
         particle.lifetime_ms -= frame_time;
         if particle.lifetime_ms <= 0. {
             dead_particles.push(ent);
+        } else if let (Some((from, to)), Some(render)) = (particle.fade, renderables.get_mut(ent)) {
+            render.colors.fg = fade_color(from, to, particle.lifetime_ms, particle.total_ms);
         }
     }
 
     std::mem::drop(particles);
+    std::mem::drop(renderables);
     std::mem::drop(entities);
 
     for victim in &dead_particles {
@@ -110,3 +227,80 @@ pub fn cull_dead_particles(ecs: &mut World, frame_time: f32) {
 
     ecs.maintain();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{map_builder::map::TileType, test_support::room_map};
+    use rltk::RGB;
+
+    fn colors() -> ColorPair {
+        ColorPair::new(RGB::named(rltk::ORANGE), RGB::named(rltk::BLACK))
+    }
+
+    fn particle_points(builder: &ParticleBuilder) -> Vec<(i32, i32)> {
+        builder
+            .requests
+            .iter()
+            .map(|request| (request.x, request.y))
+            .collect()
+    }
+
+    #[test]
+    fn path_has_a_particle_on_every_step_but_the_origin() {
+        let mut builder = ParticleBuilder::new();
+        spawn_projectile_path(
+            &mut builder,
+            Point::new(1, 1),
+            Point::new(5, 3),
+            rltk::to_cp437('*'),
+            colors(),
+        );
+        let steps = rltk::line2d_bresenham(Point::new(1, 1), Point::new(5, 3));
+        assert_eq!(builder.requests.len(), steps.len() - 1);
+        assert_eq!(
+            particle_points(&builder),
+            steps[1..]
+                .iter()
+                .map(|step| (step.x, step.y))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn projectile_stops_in_front_of_a_wall() {
+        let mut map = room_map(10, 10, 1);
+        let wall = map.xy_idx(5, 2);
+        map.tiles[wall] = TileType::Wall;
+        assert_eq!(
+            projectile_end(&map, Point::new(1, 2), Point::new(8, 2)),
+            Point::new(4, 2)
+        );
+
+        let mut builder = ParticleBuilder::new();
+        builder.create_projectile(
+            &map,
+            Point::new(1, 2),
+            Point::new(8, 2),
+            colors(),
+            rltk::to_cp437('*'),
+        );
+        assert_eq!(particle_points(&builder), vec![(2, 2), (3, 2), (4, 2)]);
+    }
+
+    #[test]
+    fn fade_is_halfway_at_half_the_lifetime() {
+        let (from, to) = (
+            RGBA::from_f32(1., 0., 0., 1.),
+            RGBA::from_f32(0., 0., 1., 0.),
+        );
+        assert_eq!(fade_color(from, to, 100., 100.), from);
+        assert_eq!(
+            fade_color(from, to, 50., 100.),
+            RGBA::from_f32(0.5, 0., 0.5, 0.5)
+        );
+        assert_eq!(fade_color(from, to, 0., 100.), to);
+        //Particles without a lifetime are shown faded all the way
+        assert_eq!(fade_color(from, to, 0., 0.), to);
+    }
+}
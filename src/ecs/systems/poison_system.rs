@@ -0,0 +1,129 @@
+use super::ParticleBuilder;
+use crate::{
+    components::{CombatStats, Poison, Position, SufferDamage},
+    constants::colors,
+    game_log::GameLog,
+    state::{Gameplay, State, State::Game},
+};
+use rltk::{ColorPair, RGB, RGBA};
+use specs::prelude::*;
+
+const POISON_LIFETIME: f32 = 250.0;
+
+///Deals the damage of every poison once per round, until it wears off
+pub struct PoisonSystem {}
+
+impl<'a> System<'a> for PoisonSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, State>,
+        ReadStorage<'a, CombatStats>,
+        ReadStorage<'a, Position>,
+        WriteExpect<'a, GameLog>,
+        WriteExpect<'a, ParticleBuilder>,
+        WriteStorage<'a, Poison>,
+        WriteStorage<'a, SufferDamage>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            player_ent,
+            state,
+            all_stats,
+            positions,
+            mut logs,
+            mut particle_builder,
+            mut poisoned,
+            mut damages,
+        ) = data;
+
+        //Poison works once per round, which begins with the players turn
+        if *state != Game(Gameplay::PlayerTurn) {
+            return;
+        }
+
+        let mut worn_off = Vec::new();
+        for (ent, poison, stats) in (&entities, &mut poisoned, &all_stats).join() {
+            if stats.hp <= 0 {
+                continue;
+            }
+            SufferDamage::new_damage(&mut damages, ent, poison.damage_per_turn, None);
+            if let Some(pos) = positions.get(ent) {
+                particle_builder.create_fade(
+                    pos.x,
+                    pos.y,
+                    ColorPair::new(RGB::named(rltk::GREEN), RGB::from(colors::BACKGROUND)),
+                    RGBA::from(RGB::from(colors::BACKGROUND)),
+                    rltk::to_cp437('*'),
+                    POISON_LIFETIME,
+                );
+            }
+            if ent == *player_ent {
+                logs.push_colored(
+                    &format!("You suffer {} poison damage.", poison.damage_per_turn),
+                    RGB::named(rltk::GREEN),
+                );
+            }
+
+            poison.turns -= 1;
+            if poison.turns < 1 {
+                worn_off.push(ent);
+                if ent == *player_ent {
+                    logs.push(&"The poison wears off.");
+                }
+            }
+        }
+
+        for ent in worn_off {
+            poisoned.remove(ent);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{spawn_monster, test_world};
+
+    #[test]
+    fn poison_deals_damage_once_per_round_until_it_wears_off() {
+        let mut world = test_world(10, 10, (2, 2));
+        let player_ent = *world.fetch::<Entity>();
+        let monster = spawn_monster(&mut world, (5, 5), 20, 1);
+        for ent in [player_ent, monster] {
+            world
+                .write_storage()
+                .insert(
+                    ent,
+                    Poison {
+                        damage_per_turn: 2,
+                        turns: 3,
+                    },
+                )
+                .unwrap();
+        }
+
+        for _ in 0..5 {
+            PoisonSystem {}.run_now(&world);
+            //Monster turns in between do not count as rounds
+            world.insert(Game(Gameplay::MonsterTurn));
+            PoisonSystem {}.run_now(&world);
+            world.insert(Game(Gameplay::PlayerTurn));
+        }
+
+        for ent in [player_ent, monster] {
+            let ticks = world
+                .read_storage::<SufferDamage>()
+                .get(ent)
+                .map(|damage| damage.amount.clone());
+            assert_eq!(ticks, Some(vec![(2, None); 3]));
+            assert!(!world.read_storage::<Poison>().contains(ent));
+        }
+        let logs = world.fetch::<GameLog>();
+        let (message, _) = logs.recent(1).next().unwrap();
+        assert_eq!(message, "The poison wears off.");
+    }
+}
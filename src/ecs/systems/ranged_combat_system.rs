@@ -0,0 +1,257 @@
+use super::{effective_combat_stats, roll_critical, ParticleBuilder};
+use crate::{
+    constants::colors, map_builder::map::Map, Ammo, CombatStats, DefenseBonus, Equipped, GameLog,
+    MeleeDamageBonus, Name, Position, RangedWeapon, SufferDamage, WantsToShoot,
+};
+use rltk::{ColorPair, Point, RandomNumberGenerator, RGB};
+use specs::prelude::*;
+
+pub struct RangedCombatSystem {}
+
+impl<'a> System<'a> for RangedCombatSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Map>,
+        ReadStorage<'a, CombatStats>,
+        ReadStorage<'a, DefenseBonus>,
+        ReadStorage<'a, Equipped>,
+        ReadStorage<'a, MeleeDamageBonus>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, RangedWeapon>,
+        WriteExpect<'a, GameLog>,
+        WriteExpect<'a, ParticleBuilder>,
+        WriteExpect<'a, RandomNumberGenerator>,
+        WriteStorage<'a, Ammo>,
+        WriteStorage<'a, SufferDamage>,
+        WriteStorage<'a, WantsToShoot>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            map,
+            all_stats,
+            defense_bonuses,
+            equipped_items,
+            damage_bonuses,
+            names,
+            positions,
+            ranged_weapons,
+            mut game_log,
+            mut particle_builder,
+            mut rng,
+            mut quivers,
+            mut damages,
+            mut shots,
+        ) = data;
+
+        for (shooter, shot, name, pos) in (&entities, &shots, &names, &positions).join() {
+            //Shooting needs an equipped ranged weapon, which is not used up
+            let Some(weapon) = (&ranged_weapons, &equipped_items)
+                .join()
+                .find(|(_, equipped)| equipped.owner == shooter)
+                .map(|(weapon, _)| weapon)
+            else {
+                continue;
+            };
+            //Shooters with a quiver use up an arrow with every shot
+            if let Some(quiver) = quivers.get_mut(shooter) {
+                if quiver.count <= 0 {
+                    game_log.push(&"You are out of arrows.");
+                    continue;
+                }
+                quiver.count -= 1;
+            }
+
+            particle_builder.create_projectile(
+                &map,
+                Point::new(pos.x, pos.y),
+                shot.target,
+                ColorPair::new(RGB::named(rltk::ORANGE), RGB::from(colors::BACKGROUND)),
+                rltk::to_cp437('*'),
+            );
+
+            let idx = map.xy_idx(shot.target.x, shot.target.y);
+            for target in &map.tile_content[idx] {
+                let Some(target_stats) = all_stats.get(*target) else {
+                    continue;
+                };
+                if target_stats.hp <= 0 {
+                    continue;
+                }
+
+                let (_, defense_total) = effective_combat_stats(
+                    *target,
+                    target_stats,
+                    &equipped_items,
+                    &damage_bonuses,
+                    &defense_bonuses,
+                );
+                let damage = i32::max(0, weapon.damage - defense_total);
+                let crit_chance =
+                    all_stats.get(shooter).map_or(0, |stats| stats.crit_chance) + weapon.crit_bonus;
+                let (damage, critical) = roll_critical(&mut rng, crit_chance, damage);
+                let target_name = &names.get(*target).unwrap().name;
+
+                if damage == 0 {
+                    game_log.push(&format!(
+                        "{} blocked the shot of {}.",
+                        target_name, &name.name
+                    ));
+                } else if critical {
+                    game_log.push_colored(
+                        &format!(
+                            "Critical hit! {} shoots {} for {} damage.",
+                            &name.name, target_name, damage
+                        ),
+                        RGB::named(rltk::YELLOW),
+                    );
+                    particle_builder.create_burst(
+                        shot.target,
+                        ColorPair::new(RGB::named(rltk::YELLOW), RGB::from(colors::BACKGROUND)),
+                        rltk::to_cp437('*'),
+                        200.0,
+                    );
+                    SufferDamage::new_damage(&mut damages, *target, damage, Some(shooter));
+                } else {
+                    game_log.push_colored(
+                        &format!(
+                            "{} shoots {} for {} damage.",
+                            &name.name, target_name, damage
+                        ),
+                        RGB::named(rltk::RED),
+                    );
+                    SufferDamage::new_damage(&mut damages, *target, damage, Some(shooter));
+                }
+            }
+        }
+        shots.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        components::EquipmentSlot,
+        ecs::pre_run_systems,
+        test_support::{spawn_monster, test_world},
+    };
+
+    #[test]
+    fn every_shot_uses_up_an_arrow_until_the_quiver_is_empty() {
+        let mut world = test_world(10, 10, (2, 2));
+        let player_ent = *world.fetch::<Entity>();
+        world
+            .create_entity()
+            .with(RangedWeapon {
+                range: 6,
+                damage: 3,
+                crit_bonus: 0,
+            })
+            .with(Equipped {
+                owner: player_ent,
+                slot: EquipmentSlot::Ranged,
+            })
+            .build();
+        world
+            .write_storage()
+            .insert(player_ent, Ammo { count: 2 })
+            .unwrap();
+        let monster = spawn_monster(&mut world, (6, 2), 50, 1);
+        pre_run_systems::execute(&mut world);
+
+        for _ in 0..3 {
+            world
+                .write_storage()
+                .insert(
+                    player_ent,
+                    WantsToShoot {
+                        target: Point::new(6, 2),
+                    },
+                )
+                .unwrap();
+            RangedCombatSystem {}.run_now(&world);
+        }
+
+        let quiver = world.read_storage::<Ammo>().get(player_ent).unwrap().count;
+        assert_eq!(quiver, 0);
+        let hits = world
+            .read_storage::<SufferDamage>()
+            .get(monster)
+            .map_or(0, |damage| damage.amount.len());
+        assert_eq!(hits, 2);
+        let logs = world.fetch::<GameLog>();
+        let (message, _) = logs.recent(1).next().unwrap();
+        assert_eq!(message, "You are out of arrows.");
+    }
+
+    #[test]
+    fn only_an_equipped_ranged_weapon_shoots_and_defense_softens_it() {
+        let mut world = test_world(10, 10, (2, 2));
+        let player_ent = *world.fetch::<Entity>();
+        world
+            .write_storage::<CombatStats>()
+            .get_mut(player_ent)
+            .unwrap()
+            .crit_chance = 0;
+        world
+            .write_storage()
+            .insert(player_ent, Ammo { count: 5 })
+            .unwrap();
+        let bow = world
+            .create_entity()
+            .with(RangedWeapon {
+                range: 6,
+                damage: 5,
+                crit_bonus: 0,
+            })
+            .build();
+        let monster = spawn_monster(&mut world, (6, 2), 50, 1);
+        world
+            .write_storage::<CombatStats>()
+            .get_mut(monster)
+            .unwrap()
+            .defense = 2;
+        pre_run_systems::execute(&mut world);
+
+        let shoot = |world: &World| {
+            world
+                .write_storage()
+                .insert(
+                    player_ent,
+                    WantsToShoot {
+                        target: Point::new(6, 2),
+                    },
+                )
+                .unwrap();
+            RangedCombatSystem {}.run_now(world);
+            world
+                .read_storage::<SufferDamage>()
+                .get(monster)
+                .map(|damage| damage.amount.clone())
+        };
+        //A bow that is only carried can not be fired
+        assert_eq!(shoot(&world), None);
+
+        world
+            .write_storage()
+            .insert(
+                bow,
+                Equipped {
+                    owner: player_ent,
+                    slot: EquipmentSlot::Ranged,
+                },
+            )
+            .unwrap();
+        assert_eq!(shoot(&world), Some(vec![(3, Some(player_ent))]));
+        //The carried bow did not use up an arrow
+        assert_eq!(
+            world.read_storage::<Ammo>().get(player_ent).unwrap().count,
+            4
+        );
+        assert!(!world.read_storage::<WantsToShoot>().contains(player_ent));
+    }
+}
@@ -0,0 +1,115 @@
+use super::ParticleBuilder;
+use crate::{
+    components::{Name, Position, Summoned, WantsToSummon},
+    constants::colors,
+    game_log::GameLog,
+    map_builder::map::{Map, TileStatus},
+    spawning,
+    state::{Gameplay, State, State::Game},
+};
+use rltk::{Algorithm2D, ColorPair, Point, RGB, RGBA};
+use specs::prelude::*;
+
+//How many rounds a summoned creature stays before vanishing
+const SUMMON_TURNS: i32 = 30;
+const SUMMON_EFFECT_LIFETIME: f32 = 400.0;
+
+///Brings the creatures called by summoning items into play, next to whoever called them
+pub fn summon_allies(ecs: &mut World) {
+    let requests = {
+        let entities = ecs.entities();
+        let positions = ecs.read_storage::<Position>();
+        let mut summons = ecs.write_storage::<WantsToSummon>();
+        let requests = (&entities, &summons, &positions)
+            .join()
+            .map(|(_, summon, pos)| (summon.name.clone(), Point::new(pos.x, pos.y)))
+            .collect::<Vec<_>>();
+        summons.clear();
+        requests
+    };
+
+    for (name, origin) in requests {
+        let (spot, depth) = {
+            let map = ecs.fetch::<Map>();
+            let spot = (-1..=1)
+                .flat_map(|delta_x| (-1..=1).map(move |delta_y| (delta_x, delta_y)))
+                .map(|(delta_x, delta_y)| Point::new(origin.x + delta_x, origin.y + delta_y))
+                .filter(|point| *point != origin && map.in_bounds(*point))
+                .find(|point| {
+                    !map.is_tile_status_set(map.xy_idx(point.x, point.y), TileStatus::Blocked)
+                });
+            (spot, map.depth)
+        };
+
+        let Some(spot) = spot else {
+            ecs.fetch_mut::<GameLog>()
+                .push(&"There is no room for anything to answer your call.");
+            continue;
+        };
+        if spawning::spawn_ally(ecs, &name, (spot.x, spot.y), depth, SUMMON_TURNS).is_none() {
+            continue;
+        }
+
+        let idx = ecs.fetch::<Map>().xy_idx(spot.x, spot.y);
+        ecs.fetch_mut::<Map>()
+            .set_tile_status(idx, TileStatus::Blocked);
+        ecs.fetch_mut::<GameLog>().push_colored(
+            &format!("A {name} answers your call."),
+            RGB::named(rltk::LIGHT_BLUE),
+        );
+        ecs.fetch_mut::<ParticleBuilder>().create_fade(
+            spot.x,
+            spot.y,
+            ColorPair::new(RGB::named(rltk::WHITE), RGB::from(colors::BACKGROUND)),
+            RGBA::from(RGB::named(rltk::LIGHT_BLUE)),
+            rltk::to_cp437('*'),
+            SUMMON_EFFECT_LIFETIME,
+        );
+    }
+}
+
+///Counts down the time summoned creatures have left, removing those whose time is up
+pub struct SummonExpirySystem {}
+
+impl<'a> System<'a> for SummonExpirySystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, State>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Position>,
+        WriteExpect<'a, GameLog>,
+        WriteExpect<'a, ParticleBuilder>,
+        WriteStorage<'a, Summoned>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, state, names, positions, mut logs, mut particle_builder, mut summons) = data;
+
+        //Counted once per round, like initiative
+        if *state != Game(Gameplay::PlayerTurn) {
+            return;
+        }
+
+        //Summons left behind on other levels wait for the player to return
+        for (ent, summoned, pos) in (&entities, &mut summons, &positions).join() {
+            summoned.turns_remaining -= 1;
+            if summoned.turns_remaining > 0 {
+                continue;
+            }
+
+            entities.delete(ent).expect("Unable to remove summon");
+            particle_builder.create_fade(
+                pos.x,
+                pos.y,
+                ColorPair::new(RGB::named(rltk::LIGHT_BLUE), RGB::from(colors::BACKGROUND)),
+                RGBA::from(RGB::from(colors::BACKGROUND)),
+                rltk::to_cp437('*'),
+                SUMMON_EFFECT_LIFETIME,
+            );
+            if let Some(name) = names.get(ent) {
+                logs.push(&format!("The {} fades away.", name.name));
+            }
+        }
+    }
+}
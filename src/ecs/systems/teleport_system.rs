@@ -0,0 +1,127 @@
+use super::ParticleBuilder;
+use crate::{
+    components::{EntityMoved, FieldOfView, Position, WantsToTeleport},
+    constants::colors,
+    map_builder::map::{Map, TileStatus, TileType},
+};
+use rltk::{ColorPair, Point, RandomNumberGenerator, RGB};
+use specs::prelude::*;
+
+pub struct TeleportSystem {}
+
+impl<'a> System<'a> for TeleportSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Entity>,
+        WriteExpect<'a, Map>,
+        WriteExpect<'a, Point>,
+        WriteExpect<'a, ParticleBuilder>,
+        WriteExpect<'a, RandomNumberGenerator>,
+        WriteStorage<'a, EntityMoved>,
+        WriteStorage<'a, FieldOfView>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, WantsToTeleport>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            player_ent,
+            mut map,
+            mut player_pos,
+            mut particle_builder,
+            mut rng,
+            mut moved,
+            mut fields_of_view,
+            mut positions,
+            mut teleports,
+        ) = data;
+
+        for (ent, _, pos) in (&entities, &teleports, &mut positions).join() {
+            let Some(destination) = random_destination(&map, &mut rng) else {
+                continue;
+            };
+
+            for (x, y) in [(pos.x, pos.y), (destination.x, destination.y)] {
+                particle_builder.create_particle(
+                    x,
+                    y,
+                    ColorPair::new(RGB::named(rltk::MAGENTA), RGB::from(colors::BACKGROUND)),
+                    rltk::to_cp437('*'),
+                    200.0,
+                );
+            }
+
+            let old_idx = map.xy_idx(pos.x, pos.y);
+            let new_idx = map.xy_idx(destination.x, destination.y);
+            map.remove_tile_status(old_idx, TileStatus::Blocked);
+            map.set_tile_status(new_idx, TileStatus::Blocked);
+            pos.x = destination.x;
+            pos.y = destination.y;
+
+            if ent == *player_ent {
+                *player_pos = destination;
+            }
+            if let Some(fov) = fields_of_view.get_mut(ent) {
+                fov.is_dirty = true;
+            }
+            moved
+                .insert(ent, EntityMoved {})
+                .expect("Unable to insert movement marker");
+        }
+
+        teleports.clear();
+    }
+}
+
+///Picks a random floor tile that nothing is standing on
+fn random_destination(map: &Map, rng: &mut RandomNumberGenerator) -> Option<Point> {
+    let candidates = (0..map.tiles.len())
+        .filter(|&idx| {
+            map.tiles[idx] == TileType::Floor && !map.is_tile_status_set(idx, TileStatus::Blocked)
+        })
+        .collect::<Vec<_>>();
+    rng.random_slice_entry(&candidates)
+        .map(|&idx| Point::new(idx as i32 % map.width, idx as i32 / map.width))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ecs::pre_run_systems,
+        test_support::{spawn_monster, test_world},
+    };
+
+    #[test]
+    fn teleports_land_the_player_on_a_free_floor_tile() {
+        let mut world = test_world(8, 8, (2, 2));
+        let player_ent = *world.fetch::<Entity>();
+        spawn_monster(&mut world, (5, 5), 10, 1);
+        for _ in 0..20 {
+            pre_run_systems::execute(&mut world);
+            let old_idx = {
+                let pos = world.fetch::<Point>();
+                world.fetch::<Map>().xy_idx(pos.x, pos.y)
+            };
+            world
+                .write_storage()
+                .insert(player_ent, WantsToTeleport {})
+                .unwrap();
+            TeleportSystem {}.run_now(&world);
+
+            let destination = *world.fetch::<Point>();
+            let positions = world.read_storage::<Position>();
+            let pos = positions.get(player_ent).unwrap();
+            assert_eq!((pos.x, pos.y), (destination.x, destination.y));
+            assert!((destination.x, destination.y) != (5, 5));
+            let map = world.fetch::<Map>();
+            let new_idx = map.xy_idx(destination.x, destination.y);
+            assert!(map.tiles[new_idx] == TileType::Floor);
+            assert!(map.is_tile_status_set(new_idx, TileStatus::Blocked));
+            assert!(old_idx == new_idx || !map.is_tile_status_set(old_idx, TileStatus::Blocked));
+            assert!(world.read_storage::<EntityMoved>().contains(player_ent));
+        }
+    }
+}
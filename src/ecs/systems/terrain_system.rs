@@ -0,0 +1,171 @@
+use super::ACTION_COST;
+use crate::{
+    components::{CombatStats, EntityMoved, Flammable, Initiative, OnFire, Position, SufferDamage},
+    game_log::GameLog,
+    map_builder::map::{Map, TileType},
+};
+use rltk::RGB;
+use specs::prelude::*;
+
+//Damage taken every time a creature steps into lava
+const LAVA_DAMAGE: i32 = 6;
+//Rounds a flammable creature keeps burning after stepping into lava
+const LAVA_BURN_TURNS: i32 = 3;
+//Health restored every time a creature steps into a healing pool
+const POOL_HEALING: i32 = 3;
+
+///Applies the effects of the terrain creatures step onto, burning them in lava, healing them in
+///healing pools and slowing monsters down as they wade through water. Water putting out fires is
+///left to the fire system, which checks every round
+pub struct TerrainSystem {}
+
+impl<'a> System<'a> for TerrainSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, Map>,
+        ReadStorage<'a, EntityMoved>,
+        ReadStorage<'a, Flammable>,
+        ReadStorage<'a, Position>,
+        WriteExpect<'a, GameLog>,
+        WriteStorage<'a, CombatStats>,
+        WriteStorage<'a, Initiative>,
+        WriteStorage<'a, OnFire>,
+        WriteStorage<'a, SufferDamage>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            player_ent,
+            map,
+            moved,
+            flammables,
+            positions,
+            mut logs,
+            mut all_stats,
+            mut initiatives,
+            mut burning,
+            mut damages,
+        ) = data;
+
+        for (ent, _, pos, stats) in (&entities, &moved, &positions, &mut all_stats).join() {
+            if stats.hp <= 0 {
+                continue;
+            }
+            let is_player = ent == *player_ent;
+            match map.tiles[map.xy_idx(pos.x, pos.y)] {
+                TileType::Lava => {
+                    if is_player {
+                        logs.push_colored(&"The lava burns you!", RGB::named(rltk::RED));
+                    }
+                    SufferDamage::new_damage(&mut damages, ent, LAVA_DAMAGE, None);
+                    if flammables.contains(ent) && !burning.contains(ent) {
+                        burning
+                            .insert(
+                                ent,
+                                OnFire {
+                                    turns: LAVA_BURN_TURNS,
+                                },
+                            )
+                            .expect("Unable to set entity on fire");
+                    }
+                }
+                TileType::HealingPool => {
+                    if is_player && stats.hp < stats.max_hp {
+                        logs.push_colored(
+                            &"The waters of the pool soothe your wounds.",
+                            RGB::named(rltk::GREEN),
+                        );
+                    }
+                    stats.hp = i32::min(stats.max_hp, stats.hp + POOL_HEALING);
+                }
+                //The player's wading is paid for in the initiative system, like sneaking
+                TileType::ShallowWater if !is_player => {
+                    if let Some(initiative) = initiatives.get_mut(ent) {
+                        initiative.current += ACTION_COST;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{spawn_monster, test_world};
+
+    ///A world whose tile at (5, 5) is of the given type, with a monster that just stepped onto it
+    fn stepped_onto(tile: TileType) -> (World, Entity) {
+        let mut world = test_world(10, 10, (2, 2));
+        {
+            let mut map = world.fetch_mut::<Map>();
+            let idx = map.xy_idx(5, 5);
+            map.tiles[idx] = tile;
+        }
+        let monster = spawn_monster(&mut world, (5, 5), 20, 1);
+        world
+            .write_storage()
+            .insert(monster, EntityMoved {})
+            .unwrap();
+        (world, monster)
+    }
+
+    #[test]
+    fn lava_burns_and_sets_flammable_creatures_alight() {
+        let (world, monster) = stepped_onto(TileType::Lava);
+        world.write_storage().insert(monster, Flammable {}).unwrap();
+        TerrainSystem {}.run_now(&world);
+
+        let damage = world
+            .read_storage::<SufferDamage>()
+            .get(monster)
+            .map(|damage| damage.amount.clone());
+        assert_eq!(damage, Some(vec![(LAVA_DAMAGE, None)]));
+        let burning = world
+            .read_storage::<OnFire>()
+            .get(monster)
+            .map(|fire| fire.turns);
+        assert_eq!(burning, Some(LAVA_BURN_TURNS));
+    }
+
+    #[test]
+    fn healing_pool_restores_health_up_to_the_maximum() {
+        let (world, monster) = stepped_onto(TileType::HealingPool);
+        world
+            .write_storage::<CombatStats>()
+            .get_mut(monster)
+            .unwrap()
+            .hp = 10;
+        let hp = |world: &World| world.read_storage::<CombatStats>().get(monster).unwrap().hp;
+
+        TerrainSystem {}.run_now(&world);
+        assert_eq!(hp(&world), 10 + POOL_HEALING);
+        world
+            .write_storage::<CombatStats>()
+            .get_mut(monster)
+            .unwrap()
+            .hp = 19;
+        TerrainSystem {}.run_now(&world);
+        assert_eq!(hp(&world), 20);
+        assert!(!world.read_storage::<SufferDamage>().contains(monster));
+    }
+
+    #[test]
+    fn wading_monsters_lose_a_turn() {
+        let (world, monster) = stepped_onto(TileType::ShallowWater);
+        world
+            .write_storage()
+            .insert(monster, Initiative { current: 0 })
+            .unwrap();
+        TerrainSystem {}.run_now(&world);
+        let initiative = world
+            .read_storage::<Initiative>()
+            .get(monster)
+            .map(|i| i.current);
+        assert_eq!(initiative, Some(ACTION_COST));
+    }
+}
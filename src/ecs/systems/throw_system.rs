@@ -0,0 +1,200 @@
+use super::{effective_combat_stats, projectile_end, spawn_projectile_path, ParticleBuilder};
+use crate::{
+    components::{
+        CombatStats, DefenseBonus, Equipped, InBackpack, MeleeDamageBonus, Name, Position,
+        SufferDamage, Unidentified, WantsToThrow, Weight,
+    },
+    constants::colors,
+    game_log::GameLog,
+    map_builder::map::Map,
+};
+use rltk::{ColorPair, Point, RGB};
+use specs::prelude::*;
+
+///How far any item can be thrown
+pub const THROW_RANGE: i32 = 5;
+//Every this many pounds of weight add a point of damage to the throw
+const POUNDS_PER_DAMAGE: i32 = 2;
+
+pub struct ThrowSystem {}
+
+impl<'a> System<'a> for ThrowSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, Map>,
+        ReadStorage<'a, CombatStats>,
+        ReadStorage<'a, DefenseBonus>,
+        ReadStorage<'a, Equipped>,
+        ReadStorage<'a, MeleeDamageBonus>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Unidentified>,
+        ReadStorage<'a, Weight>,
+        WriteExpect<'a, GameLog>,
+        WriteExpect<'a, ParticleBuilder>,
+        WriteStorage<'a, InBackpack>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, SufferDamage>,
+        WriteStorage<'a, WantsToThrow>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            player_ent,
+            map,
+            all_stats,
+            defense_bonuses,
+            equipped_items,
+            damage_bonuses,
+            names,
+            unidentified_items,
+            weights,
+            mut logs,
+            mut particle_builder,
+            mut backpack,
+            mut positions,
+            mut damages,
+            mut throws,
+        ) = data;
+
+        for (thrower, throw) in (&entities, &throws).join() {
+            //Only items carried in the thrower's own backpack can be thrown
+            if backpack.get(throw.item).map(|item| item.owner) != Some(thrower) {
+                continue;
+            }
+            let Some(from) = positions.get(thrower).map(|pos| Point::new(pos.x, pos.y)) else {
+                continue;
+            };
+
+            let item_name = unidentified_items.get(throw.item).map_or_else(
+                || names.get(throw.item).map_or("item", |name| &name.name),
+                |unidentified| &unidentified.appearance,
+            );
+            let landing = projectile_end(&map, from, throw.target);
+            spawn_projectile_path(
+                &mut particle_builder,
+                from,
+                landing,
+                rltk::to_cp437('*'),
+                ColorPair::new(RGB::named(rltk::WHITE), RGB::from(colors::BACKGROUND)),
+            );
+            if thrower == *player_ent {
+                logs.push(&format!("You throw the {item_name}."));
+            }
+
+            let damage = improvised_damage(weights.get(throw.item));
+            for target in &map.tile_content[map.xy_idx(landing.x, landing.y)] {
+                let Some(target_stats) = all_stats.get(*target) else {
+                    continue;
+                };
+                if target_stats.hp <= 0 || *target == thrower {
+                    continue;
+                }
+                let (_, defense_total) = effective_combat_stats(
+                    *target,
+                    target_stats,
+                    &equipped_items,
+                    &damage_bonuses,
+                    &defense_bonuses,
+                );
+                //Even a blocked throw stings a little
+                let damage = i32::max(1, damage - defense_total);
+                let target_name = names.get(*target).map_or("it", |name| &name.name);
+                logs.push_colored(
+                    &format!("The {item_name} hits {target_name} for {damage} damage."),
+                    RGB::named(rltk::RED),
+                );
+                SufferDamage::new_damage(&mut damages, *target, damage, Some(thrower));
+            }
+
+            //The item leaves the backpack for good, lying where it landed until picked up again
+            backpack.remove(throw.item);
+            positions
+                .insert(
+                    throw.item,
+                    Position {
+                        x: landing.x,
+                        y: landing.y,
+                    },
+                )
+                .expect("Unable to land thrown item");
+        }
+        throws.clear();
+    }
+}
+
+///The damage an item deals when thrown, one plus a point for every few pounds it weighs
+fn improvised_damage(weight: Option<&Weight>) -> i32 {
+    1 + weight.map_or(0, |weight| weight.pounds / POUNDS_PER_DAMAGE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ecs::pre_run_systems,
+        map_builder::map::TileType,
+        test_support::{spawn_monster, test_world},
+    };
+
+    ///Throws a carried item of the given weight from the player at (2, 3) towards the target,
+    ///returning where it landed
+    fn throw(world: &mut World, pounds: i32, target: Point) -> (i32, i32) {
+        let player_ent = *world.fetch::<Entity>();
+        let item = world
+            .create_entity()
+            .with(Name {
+                name: "Rock".to_string(),
+            })
+            .with(Weight { pounds })
+            .with(InBackpack {
+                owner: player_ent,
+                letter: 'a',
+            })
+            .build();
+        pre_run_systems::execute(world);
+        world
+            .write_storage()
+            .insert(player_ent, WantsToThrow { item, target })
+            .unwrap();
+        ThrowSystem {}.run_now(world);
+        world.maintain();
+
+        assert!(!world.read_storage::<InBackpack>().contains(item));
+        let landing = world
+            .read_storage::<Position>()
+            .get(item)
+            .map(|pos| (pos.x, pos.y));
+        landing.unwrap()
+    }
+
+    #[test]
+    fn heavier_items_hit_harder() {
+        let mut world = test_world(10, 10, (2, 3));
+        let monster = spawn_monster(&mut world, (5, 3), 20, 1);
+
+        assert_eq!(throw(&mut world, 7, Point::new(5, 3)), (5, 3));
+        let damage = world
+            .read_storage::<SufferDamage>()
+            .get(monster)
+            .map(|damage| damage.amount.clone());
+        assert_eq!(damage, Some(vec![(4, Some(*world.fetch::<Entity>()))]));
+    }
+
+    #[test]
+    fn walls_stop_a_throw_short() {
+        let mut world = test_world(10, 10, (2, 3));
+        {
+            let mut map = world.fetch_mut::<Map>();
+            let idx = map.xy_idx(5, 3);
+            map.tiles[idx] = TileType::Wall;
+            map.populate_blocked();
+        }
+        let monster = spawn_monster(&mut world, (7, 3), 20, 1);
+
+        assert_eq!(throw(&mut world, 0, Point::new(7, 3)), (4, 3));
+        assert!(!world.read_storage::<SufferDamage>().contains(monster));
+    }
+}
@@ -0,0 +1,162 @@
+use crate::{
+    components::{
+        CombatStats, Confusion, EntityMoved, EntryTrigger, FieldOfView, Hidden, InflictsDamage,
+        Name, Position, SingleActivation, SufferDamage,
+    },
+    game_log::GameLog,
+    map_builder::map::Map,
+    state::{Gameplay, State, State::Game},
+};
+use rltk::{Point, RandomNumberGenerator, RGB};
+use specs::prelude::*;
+
+//Chance to spot a hidden trap right next to the player, dropping with every tile of distance
+const ADJACENT_DETECTION_CHANCE: i32 = 40;
+const DETECTION_FALLOFF: i32 = 10;
+
+pub struct TriggerSystem {}
+
+impl<'a> System<'a> for TriggerSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, Map>,
+        ReadStorage<'a, CombatStats>,
+        ReadStorage<'a, EntryTrigger>,
+        ReadStorage<'a, InflictsDamage>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, SingleActivation>,
+        WriteExpect<'a, GameLog>,
+        WriteStorage<'a, Confusion>,
+        WriteStorage<'a, EntityMoved>,
+        WriteStorage<'a, Hidden>,
+        WriteStorage<'a, SufferDamage>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            player_ent,
+            map,
+            all_stats,
+            triggers,
+            damages,
+            names,
+            positions,
+            single_activations,
+            mut logs,
+            mut confusions,
+            mut moved,
+            mut hidden,
+            mut suffering,
+        ) = data;
+
+        let mut spent_traps = Vec::new();
+        for (ent, _, pos) in (&entities, &moved, &positions).join() {
+            //Only creatures set off traps
+            if all_stats.get(ent).is_none() {
+                continue;
+            }
+
+            let idx = map.xy_idx(pos.x, pos.y);
+            for &trap in &map.tile_content[idx] {
+                if trap == ent || triggers.get(trap).is_none() {
+                    continue;
+                }
+
+                hidden.remove(trap);
+                if ent == *player_ent {
+                    logs.push_colored(
+                        &format!("You trigger a {}!", names.get(trap).unwrap().name),
+                        RGB::named(rltk::RED),
+                    );
+                }
+
+                if let Some(damage) = damages.get(trap) {
+                    SufferDamage::new_damage(&mut suffering, ent, damage.damage, None);
+                }
+                if let Some(confusion) = confusions.get(trap).cloned() {
+                    confusions
+                        .insert(ent, confusion)
+                        .expect("Unable to confuse trap victim");
+                }
+                if single_activations.get(trap).is_some() {
+                    spent_traps.push(trap);
+                }
+            }
+        }
+
+        for trap in spent_traps {
+            entities.delete(trap).expect("Unable to delete spent trap");
+        }
+        moved.clear();
+    }
+}
+
+pub struct TrapDetectionSystem {}
+
+impl<'a> System<'a> for TrapDetectionSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, Point>,
+        ReadExpect<'a, State>,
+        ReadStorage<'a, FieldOfView>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Position>,
+        WriteExpect<'a, GameLog>,
+        WriteExpect<'a, RandomNumberGenerator>,
+        WriteStorage<'a, Hidden>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            player_ent,
+            player_pos,
+            state,
+            fields_of_view,
+            names,
+            positions,
+            mut logs,
+            mut rng,
+            mut hidden,
+        ) = data;
+
+        //The player searches their surroundings once per round
+        if *state != Game(Gameplay::PlayerTurn) {
+            return;
+        }
+
+        let Some(player_fov) = fields_of_view.get(*player_ent) else {
+            return;
+        };
+
+        let mut spotted = Vec::new();
+        for (ent, _, pos) in (&entities, &hidden, &positions).join() {
+            let trap_pos = Point::new(pos.x, pos.y);
+            if !player_fov.visible_tiles.contains(&trap_pos) {
+                continue;
+            }
+
+            let distance = rltk::DistanceAlg::Pythagoras.distance2d(trap_pos, *player_pos);
+            let chance = ADJACENT_DETECTION_CHANCE - DETECTION_FALLOFF * (distance as i32 - 1);
+            if rng.roll_dice(1, 100) <= chance {
+                spotted.push(ent);
+            }
+        }
+
+        for ent in spotted {
+            hidden.remove(ent);
+            if let Some(name) = names.get(ent) {
+                logs.push_colored(
+                    &format!("You spot a {}.", name.name),
+                    RGB::named(rltk::ORANGE),
+                );
+            }
+        }
+    }
+}
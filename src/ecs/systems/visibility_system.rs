@@ -1,5 +1,5 @@
 use crate::{
-    components::{FieldOfView, Position},
+    components::{Equipped, FieldOfView, LightSource, Position},
     map_builder::map::{Map, TileStatus},
 };
 use rltk::{field_of_view, Point};
@@ -11,21 +11,58 @@ impl<'a> System<'a> for VisibilitySystem {
     type SystemData = (
         Entities<'a>,
         ReadExpect<'a, Entity>,
+        ReadStorage<'a, Equipped>,
+        ReadStorage<'a, LightSource>,
         ReadStorage<'a, Position>,
         WriteExpect<'a, Map>,
         WriteStorage<'a, FieldOfView>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, player_entity, positions, mut map, mut fields_of_view) = data;
+        let (
+            entities,
+            player_entity,
+            equipped_items,
+            light_sources,
+            positions,
+            mut map,
+            mut fields_of_view,
+        ) = data;
+
+        //Equipped lights shine from the position of whoever has them equipped
+        let lights = (&entities, &light_sources)
+            .join()
+            .filter_map(|(light_ent, light)| {
+                let holder = equipped_items
+                    .get(light_ent)
+                    .map_or(light_ent, |eq| eq.owner);
+                positions
+                    .get(holder)
+                    .map(|pos| (Point::new(pos.x, pos.y), light.radius))
+            })
+            .collect::<Vec<_>>();
 
         for (ent, fov, pos) in (&entities, &mut fields_of_view, &positions).join() {
+            //Lights can move without the player moving, which changes what the player sees in the dark
+            let is_player_in_dark = ent == *player_entity && map.is_dark;
+            if is_player_in_dark && fov.lights != lights {
+                fov.is_dirty = true;
+                fov.lights.clone_from(&lights);
+            }
             if fov.is_dirty {
                 fov.is_dirty = false;
                 fov.visible_tiles.clear();
                 fov.visible_tiles = field_of_view(Point::new(pos.x, pos.y), fov.range, &*map);
                 fov.visible_tiles
                     .retain(|t| t.x >= 0 && t.x < map.width && t.y >= 0 && t.y < map.height);
+                if is_player_in_dark {
+                    fov.visible_tiles.retain(|tile| {
+                        lights.iter().any(|(light_pos, radius)| {
+                            rltk::DistanceAlg::Pythagoras.distance2d(*light_pos, *tile)
+                                <= *radius as f32
+                        })
+                    });
+                }
                 if ent == *player_entity {
                     for idx in 0..map.tile_status.len() {
                         map.remove_tile_status(idx, TileStatus::Visible);
@@ -40,3 +77,126 @@ impl<'a> System<'a> for VisibilitySystem {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{map_builder::map::DARKNESS_DEPTH, test_support::test_world};
+    use rltk::RGB;
+
+    ///Empties the player's view without marking it dirty, so it stays empty unless recomputed
+    fn forget_view(world: &World) {
+        let player_ent = *world.fetch::<Entity>();
+        let mut fields_of_view = world.write_storage::<FieldOfView>();
+        fields_of_view
+            .get_mut(player_ent)
+            .unwrap()
+            .visible_tiles
+            .clear();
+    }
+
+    fn is_view_computed(world: &World) -> bool {
+        let player_ent = *world.fetch::<Entity>();
+        let fields_of_view = world.read_storage::<FieldOfView>();
+        !fields_of_view
+            .get(player_ent)
+            .unwrap()
+            .visible_tiles
+            .is_empty()
+    }
+
+    #[test]
+    fn view_is_kept_while_nothing_changes() {
+        for is_dark in [false, true] {
+            let world = test_world(12, 12, (5, 5));
+            world.fetch_mut::<Map>().is_dark = is_dark;
+            VisibilitySystem {}.run_now(&world);
+            assert!(is_view_computed(&world));
+
+            forget_view(&world);
+            VisibilitySystem {}.run_now(&world);
+            assert!(!is_view_computed(&world));
+        }
+    }
+
+    #[test]
+    fn moving_light_recomputes_the_view_in_the_dark() {
+        let mut world = test_world(12, 12, (5, 5));
+        world.fetch_mut::<Map>().is_dark = true;
+        let torch = world
+            .create_entity()
+            .with(Position { x: 7, y: 5 })
+            .with(LightSource {
+                radius: 2,
+                color: RGB::named(rltk::ORANGE),
+            })
+            .build();
+        VisibilitySystem {}.run_now(&world);
+        forget_view(&world);
+
+        world
+            .write_storage::<Position>()
+            .insert(torch, Position { x: 8, y: 6 })
+            .unwrap();
+        VisibilitySystem {}.run_now(&world);
+        assert!(is_view_computed(&world));
+    }
+
+    fn visible_tiles(world: &World) -> Vec<(i32, i32)> {
+        let player_ent = *world.fetch::<Entity>();
+        let fields_of_view = world.read_storage::<FieldOfView>();
+        let mut tiles = fields_of_view
+            .get(player_ent)
+            .unwrap()
+            .visible_tiles
+            .iter()
+            .map(|tile| (tile.x, tile.y))
+            .collect::<Vec<_>>();
+        tiles.sort_unstable();
+        tiles
+    }
+
+    #[test]
+    fn only_lit_tiles_are_seen_on_dark_levels() {
+        assert!(!Map::new(10, 10, DARKNESS_DEPTH - 1).is_dark);
+        assert!(Map::new(10, 10, DARKNESS_DEPTH).is_dark);
+
+        let mut world = test_world(12, 12, (5, 5));
+        world
+            .create_entity()
+            .with(Position { x: 8, y: 5 })
+            .with(LightSource {
+                radius: 1,
+                color: RGB::named(rltk::ORANGE),
+            })
+            .build();
+        VisibilitySystem {}.run_now(&world);
+        let lit_view = visible_tiles(&world);
+
+        //The player carries a light of their own
+        let player_ent = *world.fetch::<Entity>();
+        let player_light = world
+            .read_storage::<LightSource>()
+            .get(player_ent)
+            .unwrap()
+            .radius;
+        world.fetch_mut::<Map>().is_dark = true;
+        VisibilitySystem {}.run_now(&world);
+        let dark_view = visible_tiles(&world);
+        let is_lit = |(x, y): (i32, i32)| {
+            let distance =
+                |light: Point| rltk::DistanceAlg::Pythagoras.distance2d(light, Point::new(x, y));
+            distance(Point::new(5, 5)) <= player_light as f32 || distance(Point::new(8, 5)) <= 1.0
+        };
+        let expected = lit_view
+            .iter()
+            .copied()
+            .filter(|tile| is_lit(*tile))
+            .collect::<Vec<_>>();
+        assert_eq!(dark_view, expected);
+        assert!(dark_view.contains(&(9, 5)));
+        assert!(lit_view.contains(&(10, 5)) && !dark_view.contains(&(10, 5)));
+        let map = world.fetch::<Map>();
+        assert!(!map.is_tile_status_set(map.xy_idx(10, 5), TileStatus::Visible));
+    }
+}
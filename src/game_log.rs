@@ -1,5 +1,8 @@
+use crate::constants::colors;
+use rltk::RGB;
+
 pub struct GameLog {
-    entries: Vec<String>,
+    entries: Vec<(String, RGB)>,
 }
 
 impl GameLog {
@@ -13,7 +16,19 @@ impl GameLog {
     where
         S: ToString,
     {
-        self.entries.push(log.to_string());
+        self.push_colored(log, RGB::from(colors::FOREGROUND));
+    }
+
+    pub fn push_colored<S>(&mut self, log: &S, color: RGB)
+    where
+        S: ToString,
+    {
+        self.entries.push((log.to_string(), color));
+    }
+
+    ///Returns up to `count` of the most recent entries, from newest to oldest
+    pub fn recent(&self, count: usize) -> impl DoubleEndedIterator<Item = &(String, RGB)> {
+        self.entries.iter().rev().take(count)
     }
 
     pub fn clear(&mut self) {
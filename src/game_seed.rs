@@ -0,0 +1,30 @@
+use rltk::RandomNumberGenerator;
+use serde::{Deserialize, Serialize};
+
+///Largest seed that can be entered, kept short enough to fit the seed entry screen and the HUD
+pub const MAX_SEED: u64 = 999_999_999;
+
+///Seed every level of a run is generated from, so the same seed always produces the same dungeon
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct GameSeed {
+    pub seed: u64,
+}
+
+impl GameSeed {
+    pub const fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    pub fn random() -> Self {
+        let mut rng = RandomNumberGenerator::new();
+        Self::new(rng.range(0, MAX_SEED + 1))
+    }
+
+    ///Each depth gets its own generator, so a level does not depend on how the previous ones were played
+    pub fn level_rng(self, depth: i32) -> RandomNumberGenerator {
+        RandomNumberGenerator::seeded(
+            self.seed
+                .wrapping_add((depth as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)),
+        )
+    }
+}
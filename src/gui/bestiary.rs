@@ -0,0 +1,72 @@
+use crate::{
+    bestiary::Bestiary,
+    constants::{colors, consoles},
+    raws::{config::Config, spawn::SPAWN_RAWS},
+};
+use rltk::{Rltk, RGB};
+
+const NAME_X: i32 = 12;
+const SEEN_X: i32 = 36;
+const SLAIN_X: i32 = 46;
+const DEPTH_X: i32 = 56;
+
+///Lists every monster of the dungeon, hiding those the player has never seen. Returns whether the
+///player has closed the bestiary
+pub fn show(configs: &Config, ctx: &mut Rltk, bestiary: &Bestiary) -> bool {
+    for i in 0..consoles::NUM_OF_CONSOLES {
+        ctx.set_active_console(i);
+        ctx.cls();
+    }
+    ctx.set_active_console(consoles::HUD_CONSOLE);
+
+    let fg = RGB::from(colors::FOREGROUND);
+    let bg = RGB::from(colors::BACKGROUND);
+    let yellow = RGB::named(rltk::YELLOW);
+
+    let y_base = 8;
+    let step = 2;
+    ctx.print_color_centered(y_base, yellow, bg, "Bestiary");
+    for (x, header) in [
+        (NAME_X, "Monster"),
+        (SEEN_X, "Seen"),
+        (SLAIN_X, "Slain"),
+        (DEPTH_X, "First met"),
+    ] {
+        ctx.print_color(x, y_base + step, yellow, bg, header);
+    }
+
+    let names = SPAWN_RAWS.lock().unwrap().monster_names();
+    let mut y = y_base + 2 * step;
+    for name in &names {
+        if let Some(entry) = bestiary.entries.get(name) {
+            ctx.print_color(NAME_X, y, fg, bg, name);
+            ctx.print_color(SEEN_X, y, fg, bg, entry.times_seen.to_string());
+            ctx.print_color(SLAIN_X, y, fg, bg, entry.times_killed.to_string());
+            ctx.print_color(DEPTH_X, y, fg, bg, format!("Level {}", entry.first_depth));
+        } else {
+            ctx.print_color(NAME_X, y, RGB::named(rltk::GRAY), bg, "???");
+        }
+        y += step;
+    }
+
+    let discovered = names
+        .iter()
+        .filter(|name| bestiary.entries.contains_key(*name))
+        .count();
+    ctx.print_color_centered(
+        y + step,
+        fg,
+        bg,
+        format!("Discovered {} of {} monsters", discovered, names.len()),
+    );
+    ctx.print_color_centered(
+        y + 2 * step,
+        yellow,
+        bg,
+        "Press Escape to return to the menu.",
+    );
+
+    let keys = &configs.keys;
+    ctx.key
+        .is_some_and(|key| key == keys.go_back || key == keys.select)
+}
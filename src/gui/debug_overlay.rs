@@ -0,0 +1,55 @@
+use crate::{
+    constants::{colors, consoles},
+    game_seed::GameSeed,
+    map_builder::map::Map,
+};
+use rltk::{Rltk, RGB};
+use specs::{Join, World, WorldExt};
+
+const OVERLAY_X: i32 = 2;
+const OVERLAY_Y: i32 = 2;
+
+///Number of entities alive in the world, including items, particles and the player
+pub fn live_entity_count(ecs: &World) -> usize {
+    (&ecs.entities()).join().count()
+}
+
+///Draws performance figures into the top left corner of the map view, for tuning the game
+pub fn draw_debug_overlay(ecs: &World, ctx: &mut Rltk) {
+    let lines = [
+        format!("FPS: {:.0}", ctx.fps),
+        format!("Frame: {:.1}ms", ctx.frame_time_ms),
+        format!("Entities: {}", live_entity_count(ecs)),
+        format!("Depth: {}", ecs.fetch::<Map>().depth),
+        format!("Seed: {}", ecs.fetch::<GameSeed>().seed),
+    ];
+
+    ctx.set_active_console(consoles::HUD_CONSOLE);
+    for (offset, line) in lines.iter().enumerate() {
+        ctx.print_color(
+            OVERLAY_X,
+            OVERLAY_Y + offset as i32,
+            RGB::named(rltk::YELLOW),
+            RGB::named(colors::BACKGROUND),
+            line,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{spawn_monster, test_world};
+
+    #[test]
+    fn count_follows_created_and_deleted_entities() {
+        let mut world = test_world(10, 10, (1, 1));
+        assert_eq!(live_entity_count(&world), 1);
+        let monster = spawn_monster(&mut world, (2, 2), 5, 1);
+        spawn_monster(&mut world, (3, 3), 5, 1);
+        assert_eq!(live_entity_count(&world), 3);
+        world.delete_entity(monster).unwrap();
+        world.maintain();
+        assert_eq!(live_entity_count(&world), 2);
+    }
+}
@@ -0,0 +1,58 @@
+use crate::{
+    constants::{colors, consoles},
+    difficulty::Difficulty,
+    raws::config::Config,
+    rex_assets::RexAssets,
+};
+use enum_cycling::IntoEnumCycle;
+use rltk::{Rltk, RGB};
+use strum::IntoEnumIterator;
+
+#[derive(PartialEq, Eq, Copy, Clone)]
+pub enum DifficultyResult {
+    Cancel,
+    NoResponse(Difficulty),
+    Selected(Difficulty),
+}
+
+pub fn show(
+    configs: &Config,
+    ctx: &mut Rltk,
+    current: Difficulty,
+    assets: &RexAssets,
+) -> DifficultyResult {
+    ctx.set_active_console(consoles::HUD_CONSOLE);
+    ctx.render_xp_sprite(&assets.title_screen, 0, 0);
+
+    let base_y = 45;
+    let step = 2;
+
+    ctx.print_color_centered(
+        base_y - step,
+        RGB::named(colors::FOREGROUND),
+        RGB::from(colors::BACKGROUND),
+        "Choose your difficulty",
+    );
+
+    for (index, option) in Difficulty::iter().enumerate() {
+        ctx.print_color_centered(
+            base_y + step * (index + 1),
+            if current == option {
+                RGB::named(rltk::YELLOW)
+            } else {
+                RGB::from(colors::FOREGROUND)
+            },
+            RGB::from(colors::BACKGROUND),
+            option.as_ref(),
+        );
+    }
+
+    let keys = &configs.keys;
+    match ctx.key {
+        Some(key) if key == keys.select => DifficultyResult::Selected(current),
+        Some(key) if key == keys.go_back => DifficultyResult::Cancel,
+        Some(key) if key == keys.move_up => DifficultyResult::NoResponse(current.up()),
+        Some(key) if key == keys.move_down => DifficultyResult::NoResponse(current.down()),
+        _ => DifficultyResult::NoResponse(current),
+    }
+}
@@ -0,0 +1,136 @@
+use super::{targeting::cursor_direction, tooltips};
+use crate::{
+    camera,
+    constants::{colors, consoles},
+    map_builder::map::{Map, TileStatus},
+    raws::config::Config,
+};
+use rltk::{Algorithm2D, Point, Rltk, RGB};
+use specs::World;
+
+///Lets the player move a cursor around the map to inspect it, returns false once they are done
+pub fn show(configs: &Config, world: &World, ctx: &mut Rltk) -> bool {
+    let (min_x, max_x, min_y, max_y) = camera::get_screen_bounds(world);
+    let scale = camera::tile_scale(world);
+    let mut ui_state = world.fetch_mut::<super::UiState>();
+    let ui_state = &mut *ui_state;
+    //The mouse only takes over the cursor once it moves
+    if ui_state.cursor.is_none() {
+        ui_state.last_mouse_pos = ctx.mouse_pos();
+    }
+    let cursor = ui_state
+        .cursor
+        .get_or_insert_with(|| *world.fetch::<Point>());
+
+    //The cursor follows the mouse, and can be moved with the same keys as the player
+    let true_mouse_pos = ctx.mouse_pos();
+    if true_mouse_pos != ui_state.last_mouse_pos {
+        ui_state.last_mouse_pos = true_mouse_pos;
+        *cursor = Point::new(true_mouse_pos.0 + min_x, true_mouse_pos.1 + min_y);
+    }
+    match ctx.key {
+        Some(key) if key == configs.keys.go_back => {
+            ui_state.cursor = None;
+            return false;
+        }
+        Some(key) => {
+            if let Some((delta_x, delta_y)) = cursor_direction(configs, key) {
+                cursor.x += delta_x;
+                cursor.y += delta_y;
+            }
+        }
+        None => {}
+    }
+
+    //Keep the cursor within the part of the map view that is shown
+    let (view_width, view_height) = camera::viewport_tiles(scale);
+    //The view is centered on the player
+    let (center_x, center_y) = {
+        let player_pos = world.fetch::<Point>();
+        (player_pos.x, player_pos.y)
+    };
+    cursor.x = cursor.x.clamp(
+        i32::max(min_x + 2, center_x - view_width / 2),
+        i32::min(max_x - 2, center_x + view_width / 2),
+    );
+    cursor.y = cursor.y.clamp(
+        i32::max(min_y + 2, center_y - view_height / 2),
+        i32::min(max_y - 2, center_y + view_height / 2),
+    );
+    let (screen_x, screen_y) = (cursor.x - min_x, cursor.y - min_y);
+
+    ctx.set_active_console(consoles::MAP_CONSOLE);
+    camera::invalidate_map_view(world);
+    ctx.print_color(
+        5,
+        0,
+        RGB::named(rltk::YELLOW),
+        RGB::from(colors::BACKGROUND),
+        "Examine: ",
+    );
+    ctx.set_bg(screen_x, screen_y, RGB::named(rltk::CYAN));
+
+    let lines = describe_tile(world, *cursor);
+    tooltips::draw(
+        ctx,
+        &lines,
+        camera::view_to_hud(scale, (screen_x, screen_y)),
+        (max_x - min_x, max_y - min_y),
+    );
+
+    true
+}
+
+///Describes what the player knows about a tile, the entities on it first and then the tile itself
+pub fn describe_tile(world: &World, point: Point) -> Vec<String> {
+    let map = world.fetch::<Map>();
+    if !map.in_bounds(point) {
+        return Vec::new();
+    }
+    let idx = map.xy_idx(point.x, point.y);
+    if !map.is_tile_status_set(idx, TileStatus::Revealed) {
+        return vec!["Unexplored".to_string()];
+    }
+
+    let mut lines = tooltips::describe_entities(world, &map, point);
+    lines.push(map.tiles[idx].as_ref().to_string());
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ecs::CombatStats,
+        test_support::{spawn_monster, test_world},
+    };
+    use specs::WorldExt;
+
+    #[test]
+    fn examined_tiles_only_show_what_the_player_knows() {
+        let mut world = test_world(10, 10, (2, 2));
+        let monster = spawn_monster(&mut world, (5, 5), 7, 1);
+        world
+            .write_storage::<CombatStats>()
+            .get_mut(monster)
+            .unwrap()
+            .hp = 4;
+        let point = Point::new(5, 5);
+        let idx = world.fetch::<Map>().xy_idx(5, 5);
+        assert_eq!(describe_tile(&world, point), vec!["Unexplored"]);
+
+        //Remembered tiles only show the tile itself
+        world
+            .fetch_mut::<Map>()
+            .set_tile_status(idx, TileStatus::Revealed);
+        assert_eq!(describe_tile(&world, point), vec!["Floor"]);
+
+        world
+            .fetch_mut::<Map>()
+            .set_tile_status(idx, TileStatus::Visible);
+        assert_eq!(
+            describe_tile(&world, point),
+            vec!["Test Monster (4/7)", "Floor"]
+        );
+    }
+}
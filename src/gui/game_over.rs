@@ -1,7 +1,17 @@
 use crate::constants::{colors, consoles};
-use rltk::{Rltk, RGB};
+use crate::meta_stats::MetaStats;
+use crate::run_summary::RunSummary;
+use rltk::{Rltk, VirtualKeyCode, RGB};
 
-pub fn show(ctx: &mut Rltk) -> bool {
+pub enum GameOverResult {
+    NoResponse,
+    ToMenu,
+    NewGamePlus,
+}
+
+///A run in which a boss fell counts as a victory, offering a new game plus with its equipment
+pub fn show(ctx: &mut Rltk, summary: &RunSummary, stats: &MetaStats) -> GameOverResult {
+    let victorious = summary.bosses_slain > 0;
     for i in 0..consoles::NUM_OF_CONSOLES {
         ctx.set_active_console(i);
         ctx.cls();
@@ -9,23 +19,65 @@ pub fn show(ctx: &mut Rltk) -> bool {
 
     ctx.set_active_console(consoles::HUD_CONSOLE);
 
-    let lines = [
-        "Your journey has ended!",
-        "One day, we'll tell you all about how you did.",
-        "That day, sadly, is not in this chapter..",
-        "Press any key to return to the menu.",
+    let y_base = 10;
+    let step = 2;
+    ctx.print_color_centered(
+        y_base,
+        RGB::named(rltk::YELLOW),
+        RGB::from(colors::BACKGROUND),
+        if victorious {
+            "Your journey has ended in victory!"
+        } else {
+            "Your journey has ended!"
+        },
+    );
+
+    let run_lines = [
+        format!("You reached level {}", summary.deepest_level),
+        format!("You survived for {} turns", summary.turns),
+        format!("You slew {} monsters", summary.kills),
+        format!("You defeated {} bosses", summary.bosses_slain),
+        format!("You collected {} items", summary.items_collected),
+        format!("You gathered {} gold", summary.gold_collected),
+    ];
+    let total_lines = [
+        format!("Journeys undertaken: {}", stats.runs),
+        format!("Deepest level reached: {}", stats.deepest_level),
+        format!("Monsters slain: {}", stats.monsters_killed),
+        format!("Deaths: {}", stats.deaths),
     ];
 
-    let y_base = 15;
-    let step = 2;
-    for (index, line) in lines.iter().enumerate() {
+    //The totals of every run are set apart from this run's summary by a blank line
+    let mut y = y_base + step;
+    for line in run_lines.iter().chain(&[String::new()]).chain(&total_lines) {
         ctx.print_color_centered(
-            y_base + step * index,
+            y,
             RGB::from(colors::FOREGROUND),
             RGB::from(colors::BACKGROUND),
             line,
         );
+        y += step;
     }
 
-    ctx.key.is_none()
+    if victorious {
+        y += step;
+        ctx.print_color_centered(
+            y,
+            RGB::named(rltk::GOLD),
+            RGB::from(colors::BACKGROUND),
+            "Press N to begin a new game plus with your equipment.",
+        );
+    }
+    ctx.print_color_centered(
+        y + step,
+        RGB::named(rltk::YELLOW),
+        RGB::from(colors::BACKGROUND),
+        "Press any key to return to the menu.",
+    );
+
+    match ctx.key {
+        None => GameOverResult::NoResponse,
+        Some(VirtualKeyCode::N) if victorious => GameOverResult::NewGamePlus,
+        Some(_) => GameOverResult::ToMenu,
+    }
 }
@@ -0,0 +1,94 @@
+use super::settings::keybindings::{bound_key, vlc_to_str};
+use crate::{
+    constants::{colors, consoles},
+    raws::config::Config,
+    state::KeyBindingOption,
+};
+use rltk::{Rltk, RGB};
+use strum::IntoEnumIterator;
+
+const BOX_X: i32 = 18;
+const BOX_Y: i32 = 4;
+const BOX_WIDTH: i32 = 42;
+const KEY_X: i32 = BOX_X + 26;
+
+//Keys that can not be rebound
+const FIXED_KEYS: [(&str, &str); 1] = [("Move", "Numpad")];
+
+///Every action the player can take and the key it is bound to, rebound keys included
+pub fn help_lines(configs: &Config) -> Vec<(String, String)> {
+    KeyBindingOption::iter()
+        .filter(|option| *option != KeyBindingOption::Back)
+        .map(|option| {
+            (
+                option.as_ref().to_string(),
+                vlc_to_str(bound_key(configs, option)).to_string(),
+            )
+        })
+        .chain(
+            FIXED_KEYS
+                .iter()
+                .map(|(action, key)| ((*action).to_string(), (*key).to_string())),
+        )
+        .collect()
+}
+
+///Draws the controls over the map, returns whether the player has dismissed them by pressing any key
+pub fn show(configs: &Config, ctx: &mut Rltk) -> bool {
+    let lines = help_lines(configs);
+    let fg = RGB::from(colors::FOREGROUND);
+    let bg = RGB::from(colors::BACKGROUND);
+    let yellow = RGB::named(rltk::YELLOW);
+
+    ctx.set_active_console(consoles::HUD_CONSOLE);
+    ctx.draw_box(
+        BOX_X,
+        BOX_Y,
+        BOX_WIDTH,
+        lines.len() as i32 + 3,
+        RGB::named(rltk::GRAY),
+        bg,
+    );
+    ctx.print_color(BOX_X + 2, BOX_Y, yellow, bg, "Controls");
+    for (y, (action, key)) in (BOX_Y + 2..).zip(&lines) {
+        ctx.print_color(BOX_X + 2, y, fg, bg, action);
+        ctx.print_color(KEY_X, y, yellow, bg, key);
+    }
+    ctx.print_color(
+        BOX_X + 2,
+        BOX_Y + lines.len() as i32 + 3,
+        yellow,
+        bg,
+        "Press any key to continue",
+    );
+
+    ctx.key.is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rltk::VirtualKeyCode;
+
+    #[test]
+    fn lines_follow_rebound_keys() {
+        let mut configs = Config::default();
+        configs.keys.quaff = VirtualKeyCode::Key7;
+        configs.keys.help = VirtualKeyCode::F2;
+        let lines = help_lines(&configs);
+
+        let key_of = |action: &str| {
+            lines
+                .iter()
+                .find(|(name, _)| name == action)
+                .map(|(_, key)| key.as_str())
+        };
+        assert_eq!(key_of("Quaff Potion"), Some("7"));
+        assert_eq!(key_of("Help"), Some("F2"));
+        assert_eq!(key_of("Back"), Some("Esc"));
+        assert_eq!(
+            lines.len(),
+            KeyBindingOption::iter().count() - 1 + FIXED_KEYS.len()
+        );
+    }
+}
@@ -1,12 +1,20 @@
 use crate::{
     constants::{colors, consoles},
-    ecs::CombatStats,
+    ecs::{
+        carried_weight, carry_capacity, effective_combat_stats, Ammo, CombatStats, Confusion,
+        Equipped, Experience, HungerClock, HungerState, Name, OnFire, Poison, RangedWeapon, Wallet,
+        XP_PER_LEVEL,
+    },
+    game_log::GameLog,
+    game_seed::GameSeed,
+    raws::config::VisualConfigs,
     rex_assets,
+    state::{Gameplay, State, State::Game},
 };
-use rltk::{Rltk, RGB};
-use specs::{Entity, World, WorldExt};
+use rltk::{Rltk, RGB, RGBA};
+use specs::{Entity, Join, World, WorldExt};
 
-pub fn show(world: &World, ctx: &mut Rltk) {
+pub fn show(world: &World, ctx: &mut Rltk, visual: &VisualConfigs) {
     let assets = world.fetch::<rex_assets::RexAssets>();
     ctx.set_active_console(consoles::HUD_CONSOLE);
     ctx.render_xp_sprite(&assets.ui, 0, 0);
@@ -31,11 +39,11 @@ pub fn show(world: &World, ctx: &mut Rltk) {
         //Show health bars
         let ratio = 10.0 * (players_stats.hp as f32 / players_stats.max_hp as f32);
         for i in 0..10 {
-            let foreground = if i < (ratio as i32) {
+            let foreground = visual.recolor(RGBA::from(if i < (ratio as i32) {
                 RGB::named(rltk::GREEN)
             } else {
                 RGB::named(rltk::RED)
-            };
+            }));
             ctx.set(
                 base_x + i,
                 base_y,
@@ -44,5 +52,241 @@ pub fn show(world: &World, ctx: &mut Rltk) {
                 61,
             );
         }
+
+        show_combat_stats(world, ctx, *player_entity, players_stats);
+        show_load(world, ctx, *player_entity, players_stats);
+    }
+
+    //Show level and progress towards the next one
+    let experiences = world.read_component::<Experience>();
+    if let Some(experience) = experiences.get(*player_entity) {
+        ctx.print_color(
+            63,
+            6,
+            RGB::named(colors::FOREGROUND),
+            RGB::named(colors::BACKGROUND),
+            format!(
+                "{} ({}/{})",
+                experience.level,
+                experience.current,
+                experience.level * XP_PER_LEVEL
+            ),
+        );
+    }
+
+    //Show hunger
+    let hunger_clocks = world.read_component::<HungerClock>();
+    if let Some(clock) = hunger_clocks.get(*player_entity) {
+        let color = match clock.state {
+            HungerState::WellFed => RGB::named(rltk::GREEN),
+            HungerState::Normal => RGB::named(colors::FOREGROUND),
+            HungerState::Hungry => RGB::named(rltk::ORANGE),
+            HungerState::Starving => RGB::named(rltk::RED),
+        };
+        ctx.print_color(
+            59,
+            7,
+            visual.recolor(RGBA::from(color)),
+            RGB::named(colors::BACKGROUND),
+            clock.state.as_ref(),
+        );
+    }
+
+    show_statuses(world, ctx);
+    show_ammo(world, ctx, *player_entity);
+
+    //Show gold carried
+    if let Some(wallet) = world.read_component::<Wallet>().get(*player_entity) {
+        ctx.print_color(
+            69,
+            7,
+            RGB::named(rltk::GOLD),
+            RGB::named(colors::BACKGROUND),
+            format!("Gold: {}", wallet.gold),
+        );
+    }
+
+    //Show the seed of the run, so it can be shared and replayed
+    ctx.print_color(
+        58,
+        58,
+        RGB::named(colors::FOREGROUND),
+        RGB::named(colors::BACKGROUND),
+        format!("Seed: {}", world.fetch::<GameSeed>().seed),
+    );
+
+    show_logs(world, ctx);
+
+    if world.fetch::<super::UiState>().show_minimap {
+        super::minimap::show(world, ctx, visual);
+    }
+
+    if world.fetch::<super::UiState>().show_debug {
+        super::debug_overlay::draw_debug_overlay(world, ctx);
+    }
+
+    if *world.fetch::<State>() == Game(Gameplay::AwaitingInput) {
+        super::tooltips::show(world, ctx);
+    }
+}
+
+///Prints the player's melee power and defense with the bonuses of equipped items, as used in combat
+fn show_combat_stats(world: &World, ctx: &mut Rltk, player_entity: Entity, stats: &CombatStats) {
+    let (power, defense) = effective_combat_stats(
+        player_entity,
+        stats,
+        &world.read_storage(),
+        &world.read_storage(),
+        &world.read_storage(),
+    );
+    ctx.print_color(
+        62,
+        2,
+        RGB::named(colors::FOREGROUND),
+        RGB::named(colors::BACKGROUND),
+        defense.to_string(),
+    );
+    ctx.print_color(
+        63,
+        5,
+        RGB::named(colors::FOREGROUND),
+        RGB::named(colors::BACKGROUND),
+        power.to_string(),
+    );
+}
+
+///Prints how much the player carries out of what they are able to, in red once it is all they can
+fn show_load(world: &World, ctx: &mut Rltk, player_entity: Entity, stats: &CombatStats) {
+    let carried = carried_weight(
+        player_entity,
+        &world.read_storage(),
+        &world.read_storage(),
+        &world.read_storage(),
+    );
+    let capacity = carry_capacity(stats);
+    let color = if carried >= capacity {
+        RGB::named(rltk::RED)
+    } else {
+        RGB::named(colors::FOREGROUND)
+    };
+    ctx.print_color(
+        59,
+        9,
+        color,
+        RGB::named(colors::BACKGROUND),
+        format!("Load: {carried}/{capacity} lb"),
+    );
+}
+
+///Abbreviated names of the effects the player is under, with the turns each has left
+pub fn gather_player_statuses(ecs: &World) -> Vec<(String, i32)> {
+    let player_entity = *ecs.fetch::<Entity>();
+    let mut statuses = Vec::new();
+    if let Some(confusion) = ecs.read_storage::<Confusion>().get(player_entity) {
+        statuses.push(("Conf".to_string(), confusion.turns));
+    }
+    if let Some(poison) = ecs.read_storage::<Poison>().get(player_entity) {
+        statuses.push(("Pois".to_string(), poison.turns));
+    }
+    if let Some(fire) = ecs.read_storage::<OnFire>().get(player_entity) {
+        statuses.push(("Burn".to_string(), fire.turns));
+    }
+    statuses
+}
+
+///Prints the active status effects in a row under the player's load
+fn show_statuses(world: &World, ctx: &mut Rltk) {
+    let row = gather_player_statuses(world)
+        .iter()
+        .map(|(name, turns)| format!("{name} {turns}"))
+        .collect::<Vec<_>>()
+        .join(" ");
+    ctx.print_color(
+        59,
+        10,
+        RGB::named(rltk::ORANGE),
+        RGB::named(colors::BACKGROUND),
+        row,
+    );
+}
+
+///Prints the equipped ranged weapon with the arrows left in the quiver, in red once they run out
+fn show_ammo(world: &World, ctx: &mut Rltk, player_entity: Entity) {
+    let names = world.read_storage::<Name>();
+    let ranged_weapons = world.read_storage::<RangedWeapon>();
+    let equipped_items = world.read_storage::<Equipped>();
+    let Some((name, ..)) = (&names, &ranged_weapons, &equipped_items)
+        .join()
+        .find(|(.., equipped)| equipped.owner == player_entity)
+    else {
+        return;
+    };
+    let count = world
+        .read_storage::<Ammo>()
+        .get(player_entity)
+        .map_or(0, |quiver| quiver.count);
+    let color = if count > 0 {
+        RGB::named(colors::FOREGROUND)
+    } else {
+        RGB::named(rltk::RED)
+    };
+    ctx.print_color(
+        59,
+        11,
+        color,
+        RGB::named(colors::BACKGROUND),
+        format!("{}: {count} arrows", name.name),
+    );
+}
+
+///Prints the most recent logs into the log panel, with the newest entry at the bottom
+fn show_logs(world: &World, ctx: &mut Rltk) {
+    const LOG_X: i32 = 1;
+    const LOG_BOTTOM_Y: i32 = 58;
+    const LOG_WIDTH: usize = 55;
+    const LOG_LINES: usize = 15;
+
+    let logs = world.fetch::<GameLog>();
+    for ((entry, color), y) in logs.recent(LOG_LINES).zip((0..=LOG_BOTTOM_Y).rev()) {
+        let text = entry.chars().take(LOG_WIDTH).collect::<String>();
+        ctx.print_color(LOG_X, y, *color, RGB::named(colors::BACKGROUND), text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{spawn_monster, test_world};
+
+    #[test]
+    fn only_the_players_own_statuses_are_shown() {
+        let mut world = test_world(10, 10, (2, 2));
+        let player_ent = *world.fetch::<Entity>();
+        assert!(gather_player_statuses(&world).is_empty());
+
+        world
+            .write_storage()
+            .insert(player_ent, OnFire { turns: 2 })
+            .unwrap();
+        world
+            .write_storage()
+            .insert(
+                player_ent,
+                Poison {
+                    damage_per_turn: 1,
+                    turns: 4,
+                },
+            )
+            .unwrap();
+        let monster = spawn_monster(&mut world, (5, 5), 10, 1);
+        world
+            .write_storage()
+            .insert(monster, Confusion { turns: 3 })
+            .unwrap();
+
+        assert_eq!(
+            gather_player_statuses(&world),
+            vec![("Pois".to_string(), 4), ("Burn".to_string(), 2)]
+        );
     }
 }
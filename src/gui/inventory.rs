@@ -1,12 +1,15 @@
 use crate::{
     constants::{colors, consoles},
-    ecs::{Equipped, InBackpack, Name},
+    ecs::{
+        Charges, Consumable, DefenseBonus, Equipped, InBackpack, MeleeDamageBonus, Name, Potion,
+        Scroll, Unidentified,
+    },
     raws::config::Config,
     rex_assets,
-    state::{Gameplay, State, State::Game},
 };
 use rltk::{Rltk, RGB};
 use specs::{Entity, Join, World, WorldExt};
+use std::fmt::Write;
 
 #[derive(PartialEq, Copy, Clone)]
 pub enum InvResult {
@@ -20,34 +23,106 @@ pub enum InvMode {
     Use,
     Drop,
     Remove,
+    Quaff,
+    Read,
+    Throw,
 }
 
-pub fn show(configs: &Config, world: &mut World, ctx: &mut Rltk) -> InvResult {
-    let player_ent = world.fetch::<Entity>();
-    let current_state = world.fetch::<State>();
-    let names = world.read_storage::<Name>();
-    let entities = world.entities();
+///Whether an item is listed when the inventory is opened in the given mode, quaffing and reading
+///only list potions and scrolls
+pub fn shown_in_mode(ecs: &World, mode: InvMode, item: Entity) -> bool {
+    match mode {
+        InvMode::Quaff => ecs.read_storage::<Potion>().contains(item),
+        InvMode::Read => ecs.read_storage::<Scroll>().contains(item),
+        InvMode::Use | InvMode::Drop | InvMode::Remove | InvMode::Throw => true,
+    }
+}
+
+///Lists the items carried by the owner under the name they are known by, in the order of their
+///letters. Identical consumables share a single entry with their count and the entity to use first
+pub fn group_inventory(ecs: &World, owner: Entity) -> Vec<(String, usize, Entity)> {
+    let entities = ecs.entities();
+    let names = ecs.read_storage::<Name>();
+    let backpack_items = ecs.read_storage::<InBackpack>();
+    let unidentified_items = ecs.read_storage::<Unidentified>();
+    let consumables = ecs.read_storage::<Consumable>();
 
-    //Get all relevant items
-    //Unable to simplify to avoid the duplication of the lines .join() .. .collect() because the
-    //if arms are of different types.
-    #[allow(clippy::filter_map)]
-    let relevant_entities = {
-        if *current_state == Game(Gameplay::Inventory(InvMode::Remove)) {
-            let equipped_items = world.read_storage::<Equipped>();
-            (&equipped_items, &names, &entities)
-                .join()
-                .filter(|item| item.0.owner == *player_ent)
-                .map(|item| (item.1, item.2))
-                .collect::<Vec<_>>()
+    let mut groups: Vec<(String, usize, Entity)> = Vec::new();
+    for (item, name, ent) in (&backpack_items, &names, &entities).join() {
+        if item.owner != owner {
+            continue;
+        }
+        let display_name = unidentified_items.get(ent).map_or_else(
+            || name.name.clone(),
+            |unidentified| unidentified.appearance.clone(),
+        );
+        let stack = if consumables.contains(ent) {
+            groups.iter_mut().find(|(other_name, _, other)| {
+                *other_name == display_name && consumables.contains(*other)
+            })
         } else {
-            let backpack_items = world.read_storage::<InBackpack>();
-            (&backpack_items, &names, &entities)
-                .join()
-                .filter(|item| item.0.owner == *player_ent)
-                .map(|item| (item.1, item.2))
-                .collect::<Vec<_>>()
+            None
+        };
+        match stack {
+            Some((_, count, _)) => *count += 1,
+            None => groups.push((display_name, 1, ent)),
         }
+    }
+    groups.sort_by_key(|(_, _, ent)| backpack_items.get(*ent).map(|item| item.letter));
+    groups
+}
+
+///The letter, label and entity of everything the owner has equipped
+fn equipped_entries(world: &World, owner: Entity) -> Vec<(char, String, Entity)> {
+    let names = world.read_storage::<Name>();
+    let entities = world.entities();
+    let equipped_items = world.read_storage::<Equipped>();
+    let damage_bonuses = world.read_storage::<MeleeDamageBonus>();
+    let defense_bonuses = world.read_storage::<DefenseBonus>();
+    (&equipped_items, &names, &entities)
+        .join()
+        .filter(|(item, ..)| item.owner == owner)
+        .zip('a'..='z')
+        .map(|((item, name, ent), letter)| {
+            let mut label = format!("{} ({})", name.name, item.slot.as_ref());
+            if let Some(bonus) = damage_bonuses.get(ent) {
+                let _ = write!(label, " {:+} power", bonus.bonus);
+            }
+            if let Some(bonus) = defense_bonuses.get(ent) {
+                let _ = write!(label, " {:+} defense", bonus.bonus);
+            }
+            (letter, label, ent)
+        })
+        .collect()
+}
+
+pub fn show(configs: &Config, world: &mut World, ctx: &mut Rltk, mode: InvMode) -> InvResult {
+    let player_ent = *world.fetch::<Entity>();
+
+    //Get all relevant items, equipped items are labeled with the slot they occupy and what they add
+    //to the player's stats, and lettered in order, while carried items keep the letter they were
+    //given when put in the backpack
+    let relevant_entities = if mode == InvMode::Remove {
+        equipped_entries(world, player_ent)
+    } else {
+        let backpack_items = world.read_storage::<InBackpack>();
+        let charged_items = world.read_storage::<Charges>();
+        group_inventory(world, player_ent)
+            .into_iter()
+            .filter(|(_, _, ent)| shown_in_mode(world, mode, *ent))
+            .map(|(name, count, ent)| {
+                let letter = backpack_items.get(ent).map_or('?', |item| item.letter);
+                match (count, charged_items.get(ent)) {
+                    (_, Some(charges)) => (
+                        letter,
+                        format!("{name} ({}/{})", charges.current, charges.max),
+                        ent,
+                    ),
+                    (1, None) => (letter, name, ent),
+                    _ => (letter, format!("{name} (x{count})"), ent),
+                }
+            })
+            .collect::<Vec<_>>()
     };
 
     ctx.set_active_console(consoles::HUD_CONSOLE);
@@ -59,14 +134,14 @@ pub fn show(configs: &Config, world: &mut World, ctx: &mut Rltk) -> InvResult {
     let base_y = 4;
 
     //Print out relevant items
-    for (offset, (name, _)) in relevant_entities.iter().enumerate() {
+    for (offset, (letter, name, _)) in relevant_entities.iter().enumerate() {
         let y = base_y + offset as i32;
         ctx.set(
             base_x + 1,
             y,
             RGB::named(rltk::YELLOW),
             RGB::from(colors::BACKGROUND),
-            97 + offset as rltk::FontCharType,
+            rltk::to_cp437(*letter),
         );
         ctx.set(
             base_x + 2,
@@ -75,7 +150,7 @@ pub fn show(configs: &Config, world: &mut World, ctx: &mut Rltk) -> InvResult {
             RGB::from(colors::BACKGROUND),
             rltk::to_cp437(')'),
         );
-        ctx.print(base_x + 4, y, &name.name.to_string());
+        ctx.print(base_x + 4, y, name);
     }
 
     //Respond to players response
@@ -85,11 +160,109 @@ pub fn show(configs: &Config, world: &mut World, ctx: &mut Rltk) -> InvResult {
             InvResult::Cancel
         } else {
             let selection = rltk::letter_to_option(key);
-            if selection > -1 && selection < relevant_entities.len() as i32 {
-                return InvResult::Selected(relevant_entities[selection as usize].1);
-            }
-            InvResult::NoResponse
+            relevant_entities
+                .iter()
+                .find(|(letter, ..)| (*letter as i32 - 'a' as i32) == selection)
+                .map_or(InvResult::NoResponse, |(.., ent)| InvResult::Selected(*ent))
         };
     }
     InvResult::NoResponse
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{components::EquipmentSlot, test_support::test_world};
+    use specs::Builder;
+
+    fn carry(world: &mut World, owner: Entity, name: &str, letter: char, consumable: bool) {
+        let builder = world
+            .create_entity()
+            .with(Name {
+                name: name.to_string(),
+            })
+            .with(InBackpack { owner, letter });
+        if consumable {
+            builder.with(Consumable {}).build();
+        } else {
+            builder.build();
+        }
+    }
+
+    #[test]
+    fn identical_consumables_share_an_entry() {
+        let mut world = test_world(10, 10, (2, 2));
+        let player_ent = *world.fetch::<Entity>();
+        carry(&mut world, player_ent, "Dagger", 'c', false);
+        carry(&mut world, player_ent, "Dagger", 'd', false);
+        carry(&mut world, player_ent, "Health Potion", 'a', true);
+        carry(&mut world, player_ent, "Health Potion", 'a', true);
+        carry(&mut world, player_ent, "Scroll of Fireball", 'b', true);
+
+        let entries = group_inventory(&world, player_ent)
+            .into_iter()
+            .map(|(name, count, _)| (name, count))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            entries,
+            vec![
+                ("Health Potion".to_string(), 2),
+                ("Scroll of Fireball".to_string(), 1),
+                ("Dagger".to_string(), 1),
+                ("Dagger".to_string(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn quaffing_and_reading_only_list_their_kind() {
+        let mut world = test_world(10, 10, (2, 2));
+        let potion = world.create_entity().with(Potion {}).build();
+        let scroll = world.create_entity().with(Scroll {}).build();
+        let dagger = world.create_entity().build();
+
+        for item in [potion, scroll, dagger] {
+            assert!(shown_in_mode(&world, InvMode::Use, item));
+            assert!(shown_in_mode(&world, InvMode::Drop, item));
+        }
+        assert!(shown_in_mode(&world, InvMode::Quaff, potion));
+        assert!(!shown_in_mode(&world, InvMode::Quaff, scroll));
+        assert!(!shown_in_mode(&world, InvMode::Quaff, dagger));
+        assert!(shown_in_mode(&world, InvMode::Read, scroll));
+        assert!(!shown_in_mode(&world, InvMode::Read, potion));
+        assert!(!shown_in_mode(&world, InvMode::Read, dagger));
+    }
+
+    #[test]
+    fn equipped_items_are_labeled_with_their_slot() {
+        let mut world = test_world(10, 10, (2, 2));
+        let player_ent = *world.fetch::<Entity>();
+        for (name, slot) in [
+            ("Dagger", EquipmentSlot::PrimaryHand),
+            ("Shield", EquipmentSlot::OffHand),
+        ] {
+            world
+                .create_entity()
+                .with(Name {
+                    name: name.to_string(),
+                })
+                .with(Equipped {
+                    owner: player_ent,
+                    slot,
+                })
+                .build();
+        }
+
+        let labels = equipped_entries(&world, player_ent)
+            .into_iter()
+            .map(|(letter, label, _)| (letter, label))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            labels,
+            vec![
+                ('a', "Dagger (Primary Hand)".to_string()),
+                ('b', "Shield (Off Hand)".to_string()),
+            ]
+        );
+    }
+}
@@ -13,6 +13,7 @@ pub fn show(
     ctx: &mut Rltk,
     current_state: MainOption,
     assets: &RexAssets,
+    notice: Option<&str>,
 ) -> (MainOption, bool) {
     ctx.set_active_console(consoles::HUD_CONSOLE);
     ctx.render_xp_sprite(&assets.title_screen, 0, 0);
@@ -22,6 +23,16 @@ pub fn show(
     let base_y = 45;
     let step = 2;
 
+    //Problems such as an unreadable save are reported above the options
+    if let Some(notice) = notice {
+        ctx.print_color_centered(
+            base_y - step,
+            RGB::named(rltk::RED),
+            RGB::from(colors::BACKGROUND),
+            notice,
+        );
+    }
+
     for (index, option) in MainOption::iter().enumerate() {
         ctx.print_color_centered(
             base_y + step * index,
@@ -0,0 +1,87 @@
+use crate::{
+    constants::{colors, consoles},
+    map_builder::map::{Map, TileStatus, TileType},
+    raws::config::VisualConfigs,
+};
+use rltk::{Point, Rltk, RGB, RGBA};
+use specs::World;
+
+const MINIMAP_X: i32 = 39;
+const MINIMAP_Y: i32 = 2;
+//Each character of the minimap covers a square of at least this many tiles per side
+const MIN_MINIMAP_SCALE: i32 = 4;
+//Larger maps are scaled down further so the minimap never grows wider than this
+const MAX_MINIMAP_COLUMNS: i32 = 16;
+
+///Converts a map position into the position of the minimap character covering it
+const fn to_minimap(x: i32, y: i32, scale: i32) -> (i32, i32) {
+    (MINIMAP_X + x / scale, MINIMAP_Y + y / scale)
+}
+
+///Draws the revealed parts of the map, scaled down, into the top right corner of the map view
+pub fn show(world: &World, ctx: &mut Rltk, visual: &VisualConfigs) {
+    let map = world.fetch::<Map>();
+    let player_pos = world.fetch::<Point>();
+    let bg = RGB::from(colors::BACKGROUND);
+
+    let scale = i32::max(
+        MIN_MINIMAP_SCALE,
+        (map.width + MAX_MINIMAP_COLUMNS - 1) / MAX_MINIMAP_COLUMNS,
+    );
+    let columns = (map.width + scale - 1) / scale;
+    let rows = (map.height + scale - 1) / scale;
+
+    //Stairs are drawn over floors, which are drawn over walls, so nothing important gets hidden
+    let priority = |tile: TileType| match tile {
+        TileType::StairsDown | TileType::StairsUp => 3,
+        TileType::Floor
+        | TileType::DoorOpen
+        | TileType::DoorClosed
+        | TileType::HealingPool
+        | TileType::Lava
+        | TileType::ShallowWater => 2,
+        TileType::Wall => 1,
+    };
+
+    let mut cells: Vec<Option<TileType>> = vec![None; (columns * rows) as usize];
+    for (idx, &tile) in map.tiles.iter().enumerate() {
+        if map.is_tile_status_set(idx, TileStatus::Revealed) {
+            let (x, y) = to_minimap(idx as i32 % map.width, idx as i32 / map.width, scale);
+            let cell = &mut cells[((y - MINIMAP_Y) * columns + x - MINIMAP_X) as usize];
+            if cell.is_none_or(|current| priority(tile) > priority(current)) {
+                *cell = Some(tile);
+            }
+        }
+    }
+
+    ctx.set_active_console(consoles::HUD_CONSOLE);
+    for (idx, cell) in cells.iter().enumerate() {
+        let (glyph, fg) = match cell {
+            None => (rltk::to_cp437(' '), colors::BACKGROUND),
+            Some(TileType::Wall) => (rltk::to_cp437('#'), colors::WALL_REVEALED),
+            Some(TileType::StairsDown) => (174, colors::STAIRS),
+            Some(TileType::StairsUp) => (175, colors::STAIRS),
+            Some(TileType::Lava) => (rltk::to_cp437('.'), colors::LAVA),
+            Some(_) => (rltk::to_cp437('.'), colors::FOREGROUND),
+        };
+        let x = MINIMAP_X + idx as i32 % columns;
+        let y = MINIMAP_Y + idx as i32 / columns;
+        ctx.set(x, y, visual.recolor(RGBA::from(RGB::from(fg))), bg, glyph);
+    }
+
+    let (x, y) = to_minimap(player_pos.x, player_pos.y, scale);
+    ctx.set(x, y, RGB::named(rltk::YELLOW), bg, rltk::to_cp437('@'));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiles_sharing_a_square_share_a_character() {
+        assert_eq!(to_minimap(0, 0, 4), (MINIMAP_X, MINIMAP_Y));
+        assert_eq!(to_minimap(3, 3, 4), (MINIMAP_X, MINIMAP_Y));
+        assert_eq!(to_minimap(4, 7, 4), (MINIMAP_X + 1, MINIMAP_Y + 1));
+        assert_eq!(to_minimap(63, 63, 4), (MINIMAP_X + 15, MINIMAP_Y + 15));
+    }
+}
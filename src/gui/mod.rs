@@ -1,9 +1,31 @@
+pub mod bestiary;
+pub mod debug_overlay;
+pub mod difficulty_menu;
+pub mod examine;
 pub mod game_over;
+pub mod help;
 pub mod hud;
 pub mod inventory;
 pub mod main_menu;
+pub mod minimap;
+pub mod pause_menu;
+pub mod seed_entry;
 pub mod settings;
 pub mod targeting;
+pub mod tooltips;
+
+///Interface options the player can toggle while playing
+#[derive(Default)]
+pub struct UiState {
+    pub show_minimap: bool,
+    ///Shows frame timings and entity counts over the map view
+    pub show_debug: bool,
+    ///Cursor of the targeting and examine modes
+    pub cursor: Option<rltk::Point>,
+    pub last_mouse_pos: (i32, i32),
+    ///Mirrors the visual setting, so the map view can be zoomed without access to the configs
+    pub large_tiles: bool,
+}
 
 //todo: Split the logic from each section from the art.
 // For the single responsiblity principle
\ No newline at end of file
@@ -0,0 +1,64 @@
+use crate::{
+    constants::{colors, consoles},
+    raws::config::Config,
+    state::PauseOption,
+};
+use enum_cycling::IntoEnumCycle;
+use rltk::{Rltk, RGB};
+use strum::IntoEnumIterator;
+
+///Draws the pause menu over the map, returns the highlighted option and whether it was chosen
+pub fn show(configs: &Config, ctx: &mut Rltk, current_state: PauseOption) -> (PauseOption, bool) {
+    const BOX_X: i32 = 18;
+    const BOX_Y: i32 = 16;
+    const BOX_WIDTH: i32 = 22;
+    let step = 2;
+    let height = step * PauseOption::iter().count() as i32 + 2;
+
+    ctx.set_active_console(consoles::HUD_CONSOLE);
+    ctx.draw_box(
+        BOX_X,
+        BOX_Y,
+        BOX_WIDTH,
+        height,
+        RGB::named(rltk::GRAY),
+        RGB::from(colors::BACKGROUND),
+    );
+    ctx.print_color(
+        BOX_X + 2,
+        BOX_Y,
+        RGB::named(rltk::YELLOW),
+        RGB::from(colors::BACKGROUND),
+        "Paused",
+    );
+
+    for (index, option) in PauseOption::iter().enumerate() {
+        ctx.print_color(
+            BOX_X + 2,
+            BOX_Y + 2 + step * index as i32,
+            if current_state == option {
+                RGB::named(rltk::YELLOW)
+            } else {
+                RGB::from(colors::FOREGROUND)
+            },
+            RGB::from(colors::BACKGROUND),
+            option.as_ref(),
+        );
+    }
+
+    let keys = &configs.keys;
+
+    if let Some(key) = ctx.key {
+        if key == keys.select {
+            return (current_state, true);
+        } else if key == keys.go_back {
+            return (PauseOption::Resume, true);
+        } else if key == keys.move_up {
+            return (current_state.up(), false);
+        } else if key == keys.move_down {
+            return (current_state.down(), false);
+        }
+    }
+
+    (current_state, false)
+}
@@ -0,0 +1,66 @@
+use crate::{
+    constants::{colors, consoles},
+    game_seed::MAX_SEED,
+    raws::config::Config,
+    rex_assets::RexAssets,
+};
+use rltk::{Rltk, VirtualKeyCode, RGB};
+
+#[derive(PartialEq, Eq, Copy, Clone)]
+pub enum SeedResult {
+    Cancel,
+    NoResponse(u64),
+    Selected(u64),
+}
+
+///Lets the player type in the seed of a new run, digit by digit
+pub fn show(configs: &Config, ctx: &mut Rltk, current: u64, assets: &RexAssets) -> SeedResult {
+    ctx.set_active_console(consoles::HUD_CONSOLE);
+    ctx.render_xp_sprite(&assets.title_screen, 0, 0);
+
+    let base_y = 45;
+    let step = 2;
+
+    ctx.print_color_centered(
+        base_y - step,
+        RGB::named(colors::FOREGROUND),
+        RGB::from(colors::BACKGROUND),
+        "Enter a seed",
+    );
+    ctx.print_color_centered(
+        base_y + step,
+        RGB::named(rltk::YELLOW),
+        RGB::from(colors::BACKGROUND),
+        current.to_string(),
+    );
+
+    let keys = &configs.keys;
+    match ctx.key {
+        Some(key) if key == keys.select => SeedResult::Selected(current),
+        Some(key) if key == keys.go_back => SeedResult::Cancel,
+        Some(VirtualKeyCode::Back) => SeedResult::NoResponse(current / 10),
+        Some(key) => match digit(key) {
+            Some(digit) if current * 10 + digit <= MAX_SEED => {
+                SeedResult::NoResponse(current * 10 + digit)
+            }
+            _ => SeedResult::NoResponse(current),
+        },
+        None => SeedResult::NoResponse(current),
+    }
+}
+
+const fn digit(key: VirtualKeyCode) -> Option<u64> {
+    match key {
+        VirtualKeyCode::Key0 | VirtualKeyCode::Numpad0 => Some(0),
+        VirtualKeyCode::Key1 | VirtualKeyCode::Numpad1 => Some(1),
+        VirtualKeyCode::Key2 | VirtualKeyCode::Numpad2 => Some(2),
+        VirtualKeyCode::Key3 | VirtualKeyCode::Numpad3 => Some(3),
+        VirtualKeyCode::Key4 | VirtualKeyCode::Numpad4 => Some(4),
+        VirtualKeyCode::Key5 | VirtualKeyCode::Numpad5 => Some(5),
+        VirtualKeyCode::Key6 | VirtualKeyCode::Numpad6 => Some(6),
+        VirtualKeyCode::Key7 | VirtualKeyCode::Numpad7 => Some(7),
+        VirtualKeyCode::Key8 | VirtualKeyCode::Numpad8 => Some(8),
+        VirtualKeyCode::Key9 | VirtualKeyCode::Numpad9 => Some(9),
+        _ => None,
+    }
+}
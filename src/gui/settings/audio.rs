@@ -7,10 +7,14 @@ use crate::{
 use enum_cycling::IntoEnumCycle;
 use rltk::{Rltk, RGB};
 
+///Highest step of every volume setting, the audio divides by it to get the playback volume
+#[allow(clippy::redundant_pub_crate)]
+pub(crate) const MAX_VOLUME: usize = 25;
+
 pub fn show(
     configs: &mut Config,
-    music_sink: &rodio::Sink,
-    sfx_sink: &rodio::Sink,
+    music_sink: Option<&rodio::Sink>,
+    sfx_sink: Option<&rodio::Sink>,
     ctx: &mut Rltk,
     current_option: AudioOption,
     assets: &RexAssets,
@@ -51,67 +55,24 @@ pub fn show(
             return current_option.up();
         } else if key == keys.move_down {
             return current_option.down();
-        } else if key == keys.move_left {
-            match current_option {
-                AudioOption::MasterVolume => {
-                    if audio.master_volume > 0 {
-                        audio.master_volume -= 1;
-                        let master_ratio = audio.master_volume as f32 / 25.0;
-                        let music_ratio = audio.music_volume as f32 / 25.0 * master_ratio;
-                        music_sink.set_volume(music_ratio);
-                        let sfx_ratio = audio.sfx_volume as f32 / 25.0 * master_ratio;
-                        sfx_sink.set_volume(sfx_ratio);
-                    }
-                }
-                AudioOption::MusicVolume => {
-                    if audio.music_volume > 0 {
-                        audio.music_volume -= 1;
-                        let master_ratio = audio.master_volume as f32 / 25.0;
-                        let music_ratio = audio.music_volume as f32 / 25.0 * master_ratio;
-                        music_sink.set_volume(music_ratio);
-                    }
-                }
-                AudioOption::SfxVolume => {
-                    if audio.sfx_volume > 0 {
-                        audio.sfx_volume -= 1;
-                        let master_ratio = audio.master_volume as f32 / 25.0;
-                        let sfx_ratio = audio.sfx_volume as f32 / 25.0 * master_ratio;
-                        sfx_sink.set_volume(sfx_ratio);
-                    }
-                }
-                AudioOption::Back => (),
-            }
-        } else if key == keys.move_right {
-            match current_option {
-                AudioOption::MasterVolume => {
-                    if audio.master_volume < 25 {
-                        audio.master_volume += 1;
-                        let master_ratio = audio.master_volume as f32 / 25.0;
-                        let music_ratio = audio.music_volume as f32 / 25.0 * master_ratio;
-                        music_sink.set_volume(music_ratio);
-                        let sfx_ratio = audio.sfx_volume as f32 / 25.0 * master_ratio;
-                        sfx_sink.set_volume(sfx_ratio);
-                    }
-                }
-                AudioOption::MusicVolume => {
-                    if audio.music_volume < 25 {
-                        audio.music_volume += 1;
-                        let master_ratio = audio.master_volume as f32 / 25.0;
-                        let music_ratio = audio.music_volume as f32 / 25.0 * master_ratio;
-                        music_sink.set_volume(music_ratio);
-                    }
-                }
-                AudioOption::SfxVolume => {
-                    if audio.sfx_volume < 25 {
-                        audio.sfx_volume += 1;
-                        let master_ratio = audio.master_volume as f32 / 25.0;
-                        let sfx_ratio = audio.sfx_volume as f32 / 25.0 * master_ratio;
-                        sfx_sink.set_volume(sfx_ratio);
-                    }
-                }
-                AudioOption::Back => (),
-            }
         }
+
+        let volume = match current_option {
+            AudioOption::MasterVolume => &mut audio.master_volume,
+            AudioOption::MusicVolume => &mut audio.music_volume,
+            AudioOption::SfxVolume => &mut audio.sfx_volume,
+            AudioOption::Back => return current_option,
+        };
+
+        if key == keys.move_left && *volume > 0 {
+            *volume -= 1;
+        } else if key == keys.move_right && *volume < MAX_VOLUME {
+            *volume += 1;
+        } else {
+            return current_option;
+        }
+
+        crate::audio::update_volumes(configs, music_sink, sfx_sink);
     }
 
     current_option
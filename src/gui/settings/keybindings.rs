@@ -6,7 +6,7 @@ use crate::{
 };
 use enum_cycling::IntoEnumCycle;
 use rltk::{Rltk, VirtualKeyCode, RGB};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
 use strum::IntoEnumIterator;
 
 pub fn show(
@@ -43,7 +43,7 @@ pub fn key_selected(
     assets: &RexAssets,
 ) -> bool {
     lazy_static::lazy_static! {
-        static ref BAD_KEY_CHOSEN: AtomicBool = AtomicBool::new(false);
+        static ref CONFLICTING_OPTION: Mutex<Option<KeyBindingOption>> = Mutex::new(None);
     }
 
     ctx.render_xp_sprite(&assets.keybindings, 0, 0);
@@ -74,12 +74,13 @@ pub fn key_selected(
         "Press A Key",
     );
 
-    if BAD_KEY_CHOSEN.load(Ordering::Relaxed) {
+    let mut conflict = CONFLICTING_OPTION.lock().unwrap();
+    if let Some(conflicting_option) = *conflict {
         ctx.print_color_centered(
             half_height - 4,
             RGB::named(rltk::RED),
             RGB::named(colors::BACKGROUND),
-            "Key already assigned.",
+            format!("Key assigned to {}.", conflicting_option.as_ref()),
         );
         ctx.print_color_centered(
             half_height - 3,
@@ -90,57 +91,92 @@ pub fn key_selected(
     }
 
     if let Some(key) = ctx.key {
-        if KeyBindingOption::iter().all(|option| *option_to_config(configs, option) != key) {
+        //Re-assigning the key an option already has is not a conflict
+        *conflict = if *option_to_config(configs, current_option) == key {
+            None
+        } else {
+            KeyBindingOption::iter().find(|option| *option_to_config(configs, *option) == key)
+        };
+
+        if conflict.is_none() {
             *option_to_config(configs, current_option) = key;
-            BAD_KEY_CHOSEN.store(false, Ordering::Relaxed);
             return true;
         }
-        BAD_KEY_CHOSEN.store(true, Ordering::Relaxed);
     }
     false
 }
 
 fn draw_all_keys(configs: &mut Config, ctx: &mut Rltk, current_option: KeyBindingOption) {
     let yellow = RGB::named(rltk::YELLOW);
+    let fg = RGB::named(colors::FOREGROUND);
     let bg = colors::BACKGROUND;
 
     let base_y = 5;
     let x = 26;
 
+    //One option a line, as there are too many to leave a line between them
     for (i, option) in KeyBindingOption::iter().enumerate() {
         if option != KeyBindingOption::Back {
-            let y = base_y + 2 * i;
-            if current_option == option {
-                ctx.print_color(x, y, yellow, bg, current_option.as_ref());
-            }
+            let y = base_y + i;
+            let color = if current_option == option { yellow } else { fg };
+            ctx.print_color(x, y, color, bg, option.as_ref());
             let key = vlc_to_str(*option_to_config(configs, option));
-            ctx.print_color(42, y, RGB::named(colors::FOREGROUND), bg, key);
+            ctx.print_color(42, y, fg, bg, key);
         }
     }
 }
 
+///Expands to the field of the key bindings an option is bound to, preceded by the given tokens so
+///that the field can be both read and borrowed mutably
+macro_rules! bound_key_field {
+    ($option:expr, $($keys:tt)+) => {
+        match $option {
+            KeyBindingOption::Right => $($keys)+.move_right,
+            KeyBindingOption::Left => $($keys)+.move_left,
+            KeyBindingOption::Up => $($keys)+.move_up,
+            KeyBindingOption::Down => $($keys)+.move_down,
+            KeyBindingOption::UpRight => $($keys)+.move_up_right,
+            KeyBindingOption::UpLeft => $($keys)+.move_up_left,
+            KeyBindingOption::DownRight => $($keys)+.move_down_right,
+            KeyBindingOption::DownLeft => $($keys)+.move_down_left,
+            KeyBindingOption::Descend => $($keys)+.descend,
+            KeyBindingOption::Ascend => $($keys)+.ascend,
+            KeyBindingOption::Inventory => $($keys)+.open_inventory,
+            KeyBindingOption::GrabItem => $($keys)+.grab_item,
+            KeyBindingOption::DropItem => $($keys)+.drop_item,
+            KeyBindingOption::RemoveItem => $($keys)+.remove_item,
+            KeyBindingOption::WaitTurn => $($keys)+.wait_turn,
+            KeyBindingOption::AutoExplore => $($keys)+.auto_explore,
+            KeyBindingOption::Fire => $($keys)+.fire,
+            KeyBindingOption::Examine => $($keys)+.examine,
+            KeyBindingOption::Select => $($keys)+.select,
+            KeyBindingOption::ExportLog => $($keys)+.export_log,
+            KeyBindingOption::ExportMap => $($keys)+.export_map,
+            KeyBindingOption::Quaff => $($keys)+.quaff,
+            KeyBindingOption::Read => $($keys)+.read,
+            KeyBindingOption::Rest => $($keys)+.rest,
+            KeyBindingOption::Sneak => $($keys)+.sneak,
+            KeyBindingOption::Throw => $($keys)+.throw,
+            KeyBindingOption::ToggleMinimap => $($keys)+.toggle_minimap,
+            KeyBindingOption::Help => $($keys)+.help,
+            KeyBindingOption::ToggleDebug => $($keys)+.toggle_debug,
+            KeyBindingOption::WaitTurnAlt => $($keys)+.wait_turn_alt,
+            KeyBindingOption::CycleTarget => $($keys)+.cycle_target,
+            KeyBindingOption::Back | KeyBindingOption::GoBack => $($keys)+.go_back,
+        }
+    };
+}
+
+///The key currently bound to the option
+pub const fn bound_key(configs: &Config, option: KeyBindingOption) -> VirtualKeyCode {
+    bound_key_field!(option, configs.keys)
+}
+
 fn option_to_config(configs: &mut Config, current_option: KeyBindingOption) -> &mut VirtualKeyCode {
-    match current_option {
-        KeyBindingOption::Right => &mut configs.keys.move_right,
-        KeyBindingOption::Left => &mut configs.keys.move_left,
-        KeyBindingOption::Up => &mut configs.keys.move_up,
-        KeyBindingOption::Down => &mut configs.keys.move_down,
-        KeyBindingOption::UpRight => &mut configs.keys.move_up_right,
-        KeyBindingOption::UpLeft => &mut configs.keys.move_up_left,
-        KeyBindingOption::DownRight => &mut configs.keys.move_down_right,
-        KeyBindingOption::DownLeft => &mut configs.keys.move_down_left,
-        KeyBindingOption::Descend => &mut configs.keys.descend,
-        KeyBindingOption::Inventory => &mut configs.keys.open_inventory,
-        KeyBindingOption::GrabItem => &mut configs.keys.grab_item,
-        KeyBindingOption::DropItem => &mut configs.keys.drop_item,
-        KeyBindingOption::RemoveItem => &mut configs.keys.remove_item,
-        KeyBindingOption::WaitTurn => &mut configs.keys.wait_turn,
-        KeyBindingOption::Select => &mut configs.keys.select,
-        KeyBindingOption::Back | KeyBindingOption::GoBack => &mut configs.keys.go_back,
-    }
+    bound_key_field!(current_option, &mut configs.keys)
 }
 
-const fn vlc_to_str(vlc: VirtualKeyCode) -> &'static str {
+pub const fn vlc_to_str(vlc: VirtualKeyCode) -> &'static str {
     match vlc {
         VirtualKeyCode::Key1 => "1",
         VirtualKeyCode::Key2 => "2",
@@ -223,6 +259,7 @@ const fn vlc_to_str(vlc: VirtualKeyCode) -> &'static str {
         VirtualKeyCode::Period => ".",
         VirtualKeyCode::Plus => "+",
         VirtualKeyCode::Tab => "Tab",
+        VirtualKeyCode::Slash => "/",
         _ => "Haven't gotten there yet",
     }
 }
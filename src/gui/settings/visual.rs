@@ -1,11 +1,11 @@
 use crate::{
     constants::{colors, consoles},
-    raws::config::Config,
+    raws::config::{Config, VisualConfigs},
     rex_assets::RexAssets,
     state::VisualOption,
 };
 use enum_cycling::{EnumCycle, IntoEnumCycle};
-use rltk::{Rltk, RGB};
+use rltk::{Rltk, RGB, RGBA};
 use serde::{Deserialize, Serialize};
 use strum::AsRefStr;
 
@@ -21,6 +21,84 @@ impl Default for Font {
     }
 }
 
+///Palette the map and hud are drawn with
+#[derive(
+    PartialEq, Eq, Copy, Clone, Debug, Default, Serialize, Deserialize, EnumCycle, AsRefStr,
+)]
+pub enum ColorTheme {
+    #[default]
+    Classic,
+    #[strum(serialize = "High Contrast")]
+    HighContrast,
+    Grayscale,
+}
+
+impl ColorTheme {
+    ///Adjusts a foreground color to the theme, the alpha is left as it is
+    pub fn recolor(self, color: RGBA) -> RGBA {
+        match self {
+            Self::Classic => color,
+            //Dark colors are lifted the most, so dim tiles stand out from the background
+            Self::HighContrast => {
+                RGBA::from_f32(color.r.sqrt(), color.g.sqrt(), color.b.sqrt(), color.a)
+            }
+            Self::Grayscale => {
+                let luminance =
+                    0.299f32.mul_add(color.r, 0.587f32.mul_add(color.g, 0.114 * color.b));
+                RGBA::from_f32(luminance, luminance, luminance, color.a)
+            }
+        }
+    }
+}
+
+///Color vision deficiency the colors are adjusted for
+#[derive(
+    PartialEq, Eq, Copy, Clone, Debug, Default, Serialize, Deserialize, EnumCycle, AsRefStr,
+)]
+pub enum ColorblindMode {
+    #[default]
+    None,
+    Protanopia,
+    Deuteranopia,
+    Tritanopia,
+}
+
+const RGB_TO_LMS: [[f32; 3]; 3] = [
+    [17.882_4, 43.516_1, 4.119_35],
+    [3.455_65, 27.155_4, 3.867_14],
+    [0.029_956_6, 0.184_309, 1.467_09],
+];
+const LMS_TO_RGB: [[f32; 3]; 3] = [
+    [0.080_944_45, -0.130_504_41, 0.116_721_07],
+    [-0.010_248_534, 0.054_019_33, -0.113_614_71],
+    [-0.000_365_296_94, -0.004_121_614_7, 0.693_511_4],
+];
+
+fn multiply(matrix: &[[f32; 3]; 3], [x, y, z]: [f32; 3]) -> [f32; 3] {
+    matrix.map(|[a, b, c]| a.mul_add(x, b.mul_add(y, c * z)))
+}
+
+///Shifts the colors a deficiency makes hard to tell apart towards ones that can still be seen,
+///by simulating how the color is perceived and spreading what is lost over the other channels
+pub fn remap_color(color: RGBA, mode: ColorblindMode) -> RGBA {
+    let [l, m, s] = multiply(&RGB_TO_LMS, [color.r, color.g, color.b]);
+    let perceived = match mode {
+        ColorblindMode::None => return color,
+        ColorblindMode::Protanopia => [2.023_44f32.mul_add(m, -2.525_81 * s), m, s],
+        ColorblindMode::Deuteranopia => [l, 0.494_207f32.mul_add(l, 1.248_27 * s), s],
+        ColorblindMode::Tritanopia => [l, m, (-0.395_913f32).mul_add(l, 0.801_109 * m)],
+    };
+    let [sim_r, sim_g, sim_b] = multiply(&LMS_TO_RGB, perceived);
+    let (err_r, err_g, err_b) = (color.r - sim_r, color.g - sim_g, color.b - sim_b);
+
+    RGBA::from_f32(
+        color.r,
+        (color.g + 0.7f32.mul_add(err_r, err_g)).clamp(0.0, 1.0),
+        (color.b + 0.7f32.mul_add(err_r, err_b)).clamp(0.0, 1.0),
+        color.a,
+    )
+}
+
 pub fn show(
     configs: &mut Config,
     ctx: &mut Rltk,
@@ -41,9 +119,15 @@ pub fn show(
         VisualOption::ScreenShake => ctx.print_color(26, 9, yellow, bg, opt),
         VisualOption::ActiveFont => ctx.print_color(26, 11, yellow, bg, opt),
         VisualOption::ColorMapping => ctx.print_color(26, 13, yellow, bg, opt),
-        VisualOption::Back => (),
+        VisualOption::ColorTheme
+        | VisualOption::Backgrounds
+        | VisualOption::Colorblind
+        | VisualOption::LargeTiles
+        | VisualOption::Back => (),
     }
 
+    show_extra_options(&configs.visual, ctx, current_option);
+
     //Snag configs
     let active_font = configs.visual.active_font.as_ref();
 
@@ -104,7 +188,61 @@ pub fn show(
         right = key == keys.move_right;
     }
 
-    match current_option {
+    change_option(visual, current_option, left, right);
+
+    current_option
+}
+
+///The newer options are not part of the menu's art, so they are printed in a column of their own
+fn show_extra_options(visual: &VisualConfigs, ctx: &mut Rltk, current_option: VisualOption) {
+    let bg = RGB::from(colors::BACKGROUND);
+    let on_off = |on: bool| {
+        if on {
+            ("On".to_string(), RGB::named((108, 217, 0)))
+        } else {
+            ("Off".to_string(), RGB::named((217, 0, 54)))
+        }
+    };
+    let cycled = |name: &str| (format!("{name} >"), RGB::named(colors::FOREGROUND));
+
+    let options = [
+        (
+            VisualOption::ColorTheme,
+            "Theme",
+            cycled(visual.color_theme.as_ref()),
+        ),
+        (
+            VisualOption::Backgrounds,
+            "Backgrounds",
+            on_off(visual.background_enabled),
+        ),
+        (
+            VisualOption::Colorblind,
+            "Colorblind",
+            cycled(visual.colorblind_mode.as_ref()),
+        ),
+        (
+            VisualOption::LargeTiles,
+            "Large Tiles",
+            on_off(visual.large_tiles),
+        ),
+    ];
+    for ((option, label, (value, value_color)), y) in
+        IntoIterator::into_iter(options).zip((5..).step_by(2))
+    {
+        let label_color = if current_option == option {
+            RGB::named(rltk::YELLOW)
+        } else {
+            RGB::named(colors::FOREGROUND)
+        };
+        ctx.print_color(52, y, label_color, bg, label);
+        ctx.print_color(64, y, value_color, bg, value);
+    }
+}
+
+///Changes the setting of the highlighted option to the left or right
+fn change_option(visual: &mut VisualConfigs, option: VisualOption, left: bool, right: bool) {
+    match option {
         VisualOption::FullScreen => {
             if left || right {
                 visual.full_screen = !visual.full_screen;
@@ -127,10 +265,32 @@ pub fn show(
                 visual.active_font = visual.active_font.down();
             }
         }
+        VisualOption::ColorTheme => {
+            if left {
+                visual.color_theme = visual.color_theme.up();
+            } else if right {
+                visual.color_theme = visual.color_theme.down();
+            }
+        }
+        VisualOption::Backgrounds => {
+            if left || right {
+                visual.background_enabled = !visual.background_enabled;
+            }
+        }
+        VisualOption::LargeTiles => {
+            if left || right {
+                visual.large_tiles = !visual.large_tiles;
+            }
+        }
+        VisualOption::Colorblind => {
+            if left {
+                visual.colorblind_mode = visual.colorblind_mode.up();
+            } else if right {
+                visual.colorblind_mode = visual.colorblind_mode.down();
+            }
+        }
         VisualOption::ColorMapping | VisualOption::Back => {}
     }
-
-    current_option
 }
 
 fn draw_scene(configs: &Config, ctx: &mut Rltk, assets: &RexAssets) {
@@ -173,3 +333,69 @@ fn draw_scene(configs: &Config, ctx: &mut Rltk, assets: &RexAssets) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx(a: f32, b: f32) -> bool {
+        (a - b).abs() < 0.001
+    }
+
+    #[test]
+    fn classic_theme_keeps_colors() {
+        let color = RGBA::from_f32(0.2, 0.4, 0.6, 0.5);
+        assert_eq!(ColorTheme::Classic.recolor(color), color);
+    }
+
+    #[test]
+    fn grayscale_theme_uses_luminance_and_keeps_alpha() {
+        let gray = ColorTheme::Grayscale.recolor(RGBA::from_f32(1.0, 0.0, 0.0, 0.5));
+        assert!(approx(gray.r, 0.299) && approx(gray.g, 0.299) && approx(gray.b, 0.299));
+        assert!(approx(gray.a, 0.5));
+    }
+
+    #[test]
+    fn high_contrast_theme_lifts_dark_colors() {
+        let lifted = ColorTheme::HighContrast.recolor(RGBA::from_f32(0.25, 0.0, 1.0, 1.0));
+        assert!(approx(lifted.r, 0.5));
+        assert!(approx(lifted.g, 0.0));
+        assert!(approx(lifted.b, 1.0));
+    }
+
+    #[test]
+    fn no_colorblind_mode_keeps_colors() {
+        let color = RGBA::from_f32(0.9, 0.1, 0.3, 1.0);
+        assert_eq!(remap_color(color, ColorblindMode::None), color);
+    }
+
+    #[test]
+    fn grays_look_the_same_to_every_mode() {
+        let gray = RGBA::from_f32(0.5, 0.5, 0.5, 1.0);
+        for mode in [
+            ColorblindMode::Protanopia,
+            ColorblindMode::Deuteranopia,
+            ColorblindMode::Tritanopia,
+        ] {
+            let remapped = remap_color(gray, mode);
+            assert!(approx(remapped.r, 0.5) && approx(remapped.g, 0.5) && approx(remapped.b, 0.5));
+        }
+    }
+
+    #[test]
+    fn red_and_green_are_pulled_apart_for_deuteranopia() {
+        let red = remap_color(
+            RGBA::from_f32(1.0, 0.0, 0.0, 1.0),
+            ColorblindMode::Deuteranopia,
+        );
+        let green = remap_color(
+            RGBA::from_f32(0.0, 1.0, 0.0, 1.0),
+            ColorblindMode::Deuteranopia,
+        );
+        //The lost red is moved into blue, which a deuteranope still sees
+        assert!(red.b > green.b);
+        for channel in [red.r, red.g, red.b, green.r, green.g, green.b] {
+            assert!((0.0..=1.0).contains(&channel));
+        }
+    }
+}
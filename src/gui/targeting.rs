@@ -1,11 +1,21 @@
 use crate::{
     camera,
     constants::{colors, consoles},
-    ecs::FieldOfView,
+    ecs::{AreaOfEffect, FieldOfView, Monster, Position},
+    map_builder::map::{aoe_tiles, has_line_of_sight, Map},
+    player,
     raws::config::Config,
 };
-use rltk::{Point, Rltk, RGB};
-use specs::{Entity, World, WorldExt};
+use rltk::{Point, Rltk, VirtualKeyCode, RGB};
+use specs::{Entity, Join, World, WorldExt};
+
+///What a target is picked for, which decides what the item does once it is picked
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Aim {
+    Use,
+    Fire,
+    Throw,
+}
 
 #[derive(PartialEq, Copy, Clone)]
 pub enum TargetResult {
@@ -14,13 +24,37 @@ pub enum TargetResult {
     Selected(Point),
 }
 
-pub fn show(configs: &Config, world: &World, ctx: &mut Rltk, range: i32) -> TargetResult {
+pub fn show(
+    configs: &Config,
+    world: &World,
+    ctx: &mut Rltk,
+    range: i32,
+    item: Entity,
+    aim: Aim,
+) -> TargetResult {
+    let result = select_target(configs, world, ctx, range, item, aim);
+    if result != TargetResult::NoResponse {
+        world.fetch_mut::<super::UiState>().cursor = None;
+    }
+    result
+}
+
+fn select_target(
+    configs: &Config,
+    world: &World,
+    ctx: &mut Rltk,
+    range: i32,
+    item: Entity,
+    aim: Aim,
+) -> TargetResult {
     let player_ent = world.fetch::<Entity>();
     let player_pos = world.fetch::<Point>();
     let views = world.read_storage::<FieldOfView>();
+    let map = world.fetch::<Map>();
     let (min_x, max_x, min_y, max_y) = camera::get_screen_bounds(world);
 
     ctx.set_active_console(consoles::MAP_CONSOLE);
+    camera::invalidate_map_view(world);
 
     ctx.print_color(
         5,
@@ -34,7 +68,7 @@ pub fn show(configs: &Config, world: &World, ctx: &mut Rltk, range: i32) -> Targ
     if let Some(visible) = views.get(*player_ent) {
         for idx in &visible.visible_tiles {
             let distance = rltk::DistanceAlg::Pythagoras.distance2d(*player_pos, *idx);
-            if distance < range as f32 {
+            if distance < range as f32 && has_line_of_sight(&map, *player_pos, *idx) {
                 let screen_x = idx.x - min_x;
                 let screen_y = idx.y - min_y;
                 if screen_x > 1
@@ -43,7 +77,7 @@ pub fn show(configs: &Config, world: &World, ctx: &mut Rltk, range: i32) -> Targ
                     && screen_y < max_y - min_y - 1
                 {
                     ctx.set_bg(screen_x, screen_y, RGB::named(rltk::BLUE));
-                    available_cells.push(idx);
+                    available_cells.push(*idx);
                 }
             }
         }
@@ -51,9 +85,38 @@ pub fn show(configs: &Config, world: &World, ctx: &mut Rltk, range: i32) -> Targ
         return TargetResult::Cancel;
     }
 
-    //Draw Cursor
+    //Monsters in range, closest first, which the cursor can cycle through
+    let mut targets = (
+        &world.read_storage::<Position>(),
+        &world.read_storage::<Monster>(),
+    )
+        .join()
+        .map(|(pos, _)| Point::new(pos.x, pos.y))
+        .filter(|pos| available_cells.contains(pos))
+        .collect::<Vec<_>>();
+    targets.sort_by_key(|pos| (pos.x - player_pos.x).pow(2) + (pos.y - player_pos.y).pow(2));
+
+    //The keyboard cursor starts on the nearest target, or on the player if there is none
+    let mut ui_state = world.fetch_mut::<super::UiState>();
+    let ui_state = &mut *ui_state;
+    //The mouse only takes over the cursor once it moves
+    if ui_state.cursor.is_none() {
+        ui_state.last_mouse_pos = ctx.mouse_pos();
+    }
+    let cursor = ui_state
+        .cursor
+        .get_or_insert_with(|| targets.first().copied().unwrap_or(*player_pos));
+
+    //Mouse selection, moving the mouse over a tile in range also moves the cursor there
     let true_mouse_pos = ctx.mouse_pos();
     let mouse_pos = { (true_mouse_pos.0 + min_x, true_mouse_pos.1 + min_y) };
+    if true_mouse_pos != ui_state.last_mouse_pos {
+        ui_state.last_mouse_pos = true_mouse_pos;
+        let hovered = Point::new(mouse_pos.0, mouse_pos.1);
+        if available_cells.contains(&hovered) {
+            *cursor = hovered;
+        }
+    }
     if ctx.left_click {
         return if available_cells
             .iter()
@@ -67,9 +130,138 @@ pub fn show(configs: &Config, world: &World, ctx: &mut Rltk, range: i32) -> Targ
         };
     }
 
-    if Some(configs.keys.go_back) == ctx.key {
-        return TargetResult::Cancel;
+    //Keyboard selection
+    let keys = &configs.keys;
+    match ctx.key {
+        Some(key) if key == keys.go_back => return TargetResult::Cancel,
+        Some(key) if key == keys.select && available_cells.contains(cursor) => {
+            return TargetResult::Selected(*cursor);
+        }
+        Some(key) if key == keys.cycle_target => {
+            let next = targets
+                .iter()
+                .position(|target| target == cursor)
+                .map_or(0, |current| current + 1);
+            if let Some(target) = targets.get(next).or_else(|| targets.first()) {
+                *cursor = *target;
+            }
+        }
+        Some(key) => {
+            if let Some(delta) = cursor_direction(configs, key) {
+                *cursor = move_cursor(*cursor, delta, &available_cells);
+            }
+        }
+        None => {}
     }
 
+    //A thrown item lands without going off, so only used items show their blast
+    if aim == Aim::Use {
+        show_blast(world, ctx, item, *cursor, (min_x, max_x, min_y, max_y));
+    }
+
+    //Draw Cursor
+    ctx.set_bg(cursor.x - min_x, cursor.y - min_y, RGB::named(rltk::CYAN));
+
     TargetResult::NoResponse
 }
+
+///Previews the tiles an area of effect item would hit, warning when the player would be caught in it
+fn show_blast(
+    world: &World,
+    ctx: &mut Rltk,
+    item: Entity,
+    cursor: Point,
+    (min_x, max_x, min_y, max_y): (i32, i32, i32, i32),
+) {
+    let player_pos = world.fetch::<Point>();
+    if let Some(area) = world.read_storage::<AreaOfEffect>().get(item) {
+        let map = world.fetch::<Map>();
+        let blast = aoe_tiles(cursor, area.radius, &map);
+        let color = if blast.contains(&map.xy_idx(player_pos.x, player_pos.y)) {
+            ctx.print_color(
+                20,
+                0,
+                RGB::named(rltk::RED),
+                RGB::from(colors::BACKGROUND),
+                "You are in the blast!",
+            );
+            RGB::named(rltk::RED)
+        } else {
+            RGB::named(rltk::ORANGE)
+        };
+        for idx in blast {
+            let screen_x = idx as i32 % map.width - min_x;
+            let screen_y = idx as i32 / map.width - min_y;
+            if screen_x > 1
+                && screen_x < max_x - min_x - 1
+                && screen_y > 1
+                && screen_y < max_y - min_y - 1
+            {
+                ctx.set_bg(screen_x, screen_y, color);
+            }
+        }
+    }
+}
+
+///Movement keys, arrow keys and the numpad all move the cursor
+pub fn cursor_direction(configs: &Config, key: VirtualKeyCode) -> Option<(i32, i32)> {
+    let keys = &configs.keys;
+    if key == keys.move_up || key == VirtualKeyCode::Up {
+        Some((0, -1))
+    } else if key == keys.move_down || key == VirtualKeyCode::Down {
+        Some((0, 1))
+    } else if key == keys.move_left || key == VirtualKeyCode::Left {
+        Some((-1, 0))
+    } else if key == keys.move_right || key == VirtualKeyCode::Right {
+        Some((1, 0))
+    } else if key == keys.move_up_left {
+        Some((-1, -1))
+    } else if key == keys.move_up_right {
+        Some((1, -1))
+    } else if key == keys.move_down_left {
+        Some((-1, 1))
+    } else if key == keys.move_down_right {
+        Some((1, 1))
+    } else {
+        player::numpad_direction(key)
+    }
+}
+
+///Moves the cursor unless that would take it off of the tiles within range
+fn move_cursor(cursor: Point, (delta_x, delta_y): (i32, i32), available: &[Point]) -> Point {
+    let moved = Point::new(cursor.x + delta_x, cursor.y + delta_y);
+    if available.contains(&moved) {
+        moved
+    } else {
+        cursor
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_stays_within_the_available_tiles() {
+        let available = [Point::new(4, 4), Point::new(5, 4), Point::new(5, 5)];
+        let cursor = Point::new(5, 4);
+        assert_eq!(move_cursor(cursor, (-1, 0), &available), Point::new(4, 4));
+        assert_eq!(move_cursor(cursor, (0, 1), &available), Point::new(5, 5));
+        assert_eq!(move_cursor(cursor, (1, 0), &available), cursor);
+        assert_eq!(move_cursor(cursor, (0, -1), &available), cursor);
+    }
+
+    #[test]
+    fn arrow_keys_move_the_cursor_alongside_the_bindings() {
+        let configs = Config::default();
+        assert_eq!(
+            cursor_direction(&configs, VirtualKeyCode::Up),
+            Some((0, -1))
+        );
+        assert_eq!(
+            cursor_direction(&configs, configs.keys.move_down_left),
+            Some((-1, 1))
+        );
+        assert_eq!(cursor_direction(&configs, configs.keys.cycle_target), None);
+    }
+}
@@ -0,0 +1,143 @@
+use crate::{
+    camera,
+    constants::{colors, consoles},
+    ecs::{CombatStats, Hidden, Name, Position, Unidentified},
+    map_builder::map::{Map, TileStatus},
+};
+use rltk::{Algorithm2D, Point, Rltk, RGB};
+use specs::{Join, World, WorldExt};
+
+///Shows what is on the visible tile under the mouse
+pub fn show(world: &World, ctx: &mut Rltk) {
+    let (min_x, max_x, min_y, max_y) = camera::get_screen_bounds(world);
+    let (mouse_x, mouse_y) = ctx.mouse_pos();
+    if mouse_x >= max_x - min_x || mouse_y >= max_y - min_y {
+        return;
+    }
+    //The map view may be zoomed in, unlike the hud the mouse position is read from
+    let (view_x, view_y) = camera::hud_to_view(camera::tile_scale(world), (mouse_x, mouse_y));
+    let point = Point::new(view_x + min_x, view_y + min_y);
+
+    let lines = describe_entities(world, &world.fetch::<Map>(), point);
+    if !lines.is_empty() {
+        draw(
+            ctx,
+            &lines,
+            (mouse_x, mouse_y),
+            (max_x - min_x, max_y - min_y),
+        );
+    }
+}
+
+///Names the entities the player can see on a tile, along with the health of those that have it.
+///Unidentified items are only named by their appearance
+pub fn describe_entities(world: &World, map: &Map, point: Point) -> Vec<String> {
+    if !map.in_bounds(point)
+        || !map.is_tile_status_set(map.xy_idx(point.x, point.y), TileStatus::Visible)
+    {
+        return Vec::new();
+    }
+
+    let all_stats = world.read_storage::<CombatStats>();
+    let hidden = world.read_storage::<Hidden>();
+    let unidentified_items = world.read_storage::<Unidentified>();
+    (
+        &world.entities(),
+        &world.read_storage::<Name>(),
+        &world.read_storage::<Position>(),
+        !&hidden,
+    )
+        .join()
+        .filter(|(_, _, pos, ())| pos.x == point.x && pos.y == point.y)
+        .map(|(ent, name, _, ())| {
+            let name = unidentified_items
+                .get(ent)
+                .map_or(&name.name, |unidentified| &unidentified.appearance);
+            all_stats.get(ent).map_or_else(
+                || name.clone(),
+                |stats| format!("{} ({}/{})", name, stats.hp, stats.max_hp),
+            )
+        })
+        .collect()
+}
+
+///Draws a boxed tooltip beside the screen position, on whichever side of it has more room
+pub fn draw(
+    ctx: &mut Rltk,
+    lines: &[String],
+    (screen_x, screen_y): (i32, i32),
+    (view_width, view_height): (i32, i32),
+) {
+    let width = lines.iter().map(String::len).max().unwrap_or(0) as i32 + 1;
+    let height = lines.len() as i32 + 1;
+    let box_x = if screen_x < view_width / 2 {
+        screen_x + 1
+    } else {
+        screen_x - 1 - width
+    };
+    //Boxes that would run past the bottom of the view are raised to end at it
+    let box_y = i32::min(screen_y, view_height - 1 - height);
+
+    ctx.set_active_console(consoles::HUD_CONSOLE);
+    ctx.draw_box(
+        box_x,
+        box_y,
+        width,
+        height,
+        RGB::named(rltk::GRAY),
+        RGB::from(colors::BACKGROUND),
+    );
+    for (line, y) in lines.iter().zip(box_y + 1..) {
+        ctx.print_color(
+            box_x + 1,
+            y,
+            RGB::named(colors::FOREGROUND),
+            RGB::from(colors::BACKGROUND),
+            line,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{spawn_monster, test_world};
+    use specs::Builder;
+
+    #[test]
+    fn tooltips_name_what_is_seen_on_visible_tiles() {
+        let mut world = test_world(10, 10, (2, 2));
+        spawn_monster(&mut world, (5, 5), 7, 1);
+        world
+            .create_entity()
+            .with(Position { x: 5, y: 5 })
+            .with(Name {
+                name: "Scroll of Teleportation".to_string(),
+            })
+            .with(Unidentified {
+                appearance: "Scroll of XYZZY".to_string(),
+            })
+            .build();
+        world
+            .create_entity()
+            .with(Position { x: 5, y: 5 })
+            .with(Name {
+                name: "Trap".to_string(),
+            })
+            .with(Hidden {})
+            .build();
+        let point = Point::new(5, 5);
+        let idx = world.fetch::<Map>().xy_idx(5, 5);
+        world
+            .fetch_mut::<Map>()
+            .set_tile_status(idx, TileStatus::Revealed);
+        assert!(describe_entities(&world, &world.fetch::<Map>(), point).is_empty());
+
+        world
+            .fetch_mut::<Map>()
+            .set_tile_status(idx, TileStatus::Visible);
+        let mut lines = describe_entities(&world, &world.fetch::<Map>(), point);
+        lines.sort();
+        assert_eq!(lines, vec!["Scroll of XYZZY", "Test Monster (7/7)"]);
+    }
+}
@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+const POTION_APPEARANCES: [&str; 8] = [
+    "Murky", "Bubbling", "Swirling", "Smoky", "Glowing", "Fizzing", "Viscous", "Cloudy",
+];
+const SCROLL_APPEARANCES: [&str; 8] = [
+    "ZELGO MER",
+    "XIXAXA",
+    "FOOBIE BLETCH",
+    "VELOX NEB",
+    "ELBIB YLOH",
+    "DAIYEN FOOELS",
+    "KIRJE",
+    "THARR",
+];
+
+const WEAPON_APPEARANCES: [&str; 4] = [
+    "Ornate Sword",
+    "Gleaming Sword",
+    "Rune-etched Sword",
+    "Jagged Sword",
+];
+const SHIELD_APPEARANCES: [&str; 4] = [
+    "Ornate Shield",
+    "Gleaming Shield",
+    "Studded Shield",
+    "Tarnished Shield",
+];
+
+///Keeps track of how unidentified items appear during a run, and which items have been identified
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Identification {
+    appearances: HashMap<String, String>,
+    identified: HashSet<String>,
+}
+
+impl Identification {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_identified(&self, name: &str) -> bool {
+        self.identified.contains(name)
+    }
+
+    pub fn identify(&mut self, name: &str) {
+        self.identified.insert(name.to_string());
+    }
+
+    ///Returns the appearance of the named item, picking an unused one of its kind the first time
+    pub fn appearance_of(
+        &mut self,
+        name: &str,
+        kind: &str,
+        rng: &mut rltk::RandomNumberGenerator,
+    ) -> String {
+        if let Some(appearance) = self.appearances.get(name) {
+            return appearance.clone();
+        }
+
+        let candidates = match kind {
+            "potion" => POTION_APPEARANCES
+                .iter()
+                .map(|adjective| format!("{adjective} Potion"))
+                .collect::<Vec<_>>(),
+            "scroll" => SCROLL_APPEARANCES
+                .iter()
+                .map(|label| format!("Scroll labeled {label}"))
+                .collect::<Vec<_>>(),
+            "weapon" => WEAPON_APPEARANCES.map(String::from).to_vec(),
+            "shield" => SHIELD_APPEARANCES.map(String::from).to_vec(),
+            _ => Vec::new(),
+        };
+        let unused = candidates
+            .into_iter()
+            .filter(|candidate| !self.appearances.values().any(|taken| taken == candidate))
+            .collect::<Vec<_>>();
+
+        let appearance = rng
+            .random_slice_entry(&unused)
+            .cloned()
+            .unwrap_or_else(|| format!("Strange {kind}"));
+        self.appearances
+            .insert(name.to_string(), appearance.clone());
+        appearance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TEST_SEED;
+
+    #[test]
+    fn each_item_keeps_an_appearance_no_other_item_shares() {
+        let mut rng = rltk::RandomNumberGenerator::seeded(TEST_SEED);
+        let mut identification = Identification::new();
+        let names = POTION_APPEARANCES.map(|adjective| format!("Potion of {adjective}"));
+        let appearances = names
+            .iter()
+            .map(|name| identification.appearance_of(name, "potion", &mut rng))
+            .collect::<HashSet<_>>();
+        assert_eq!(appearances.len(), POTION_APPEARANCES.len());
+        assert!(appearances
+            .iter()
+            .all(|appearance| appearance.ends_with(" Potion")));
+
+        let first = identification.appearance_of(&names[0], "potion", &mut rng);
+        assert_eq!(
+            identification.appearance_of(&names[0], "potion", &mut rng),
+            first
+        );
+        //Once every appearance of a kind is taken, further items look strange
+        assert_eq!(
+            identification.appearance_of("Potion of Plenty", "potion", &mut rng),
+            "Strange potion"
+        );
+
+        assert!(!identification.is_identified(&names[0]));
+        identification.identify(&names[0]);
+        assert!(identification.is_identified(&names[0]));
+        assert!(!identification.is_identified(&names[1]));
+    }
+}
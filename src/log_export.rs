@@ -0,0 +1,122 @@
+use crate::{
+    components::{Hidden, Monster, Name, Position},
+    game_log::GameLog,
+    gui::examine::describe_tile,
+    map_builder::map::{Map, TileStatus},
+};
+use rltk::Point;
+use specs::prelude::*;
+use std::fmt::Write;
+
+const LOG_PATH: &str = "./log.txt";
+const EXPORTED_ENTRIES: usize = 20;
+
+const DIRECTIONS: [(&str, (i32, i32)); 8] = [
+    ("North", (0, -1)),
+    ("North East", (1, -1)),
+    ("East", (1, 0)),
+    ("South East", (1, 1)),
+    ("South", (0, 1)),
+    ("South West", (-1, 1)),
+    ("West", (-1, 0)),
+    ("North West", (-1, -1)),
+];
+
+///Writes the most recent events and a description of the player's surroundings to a plain text
+///file, for players who have trouble reading the screen
+pub fn export(ecs: &World) -> std::io::Result<()> {
+    let mut text = String::from("Recent events, oldest first:\n");
+    {
+        let logs = ecs.fetch::<GameLog>();
+        for (entry, _) in logs.recent(EXPORTED_ENTRIES).rev() {
+            text.push_str(entry);
+            text.push('\n');
+        }
+    }
+    text.push('\n');
+    text.push_str(&describe_surroundings(ecs));
+    std::fs::write(LOG_PATH, text)
+}
+
+///Lists what is on each tile next to the player, followed by every monster in sight and where it is
+pub fn describe_surroundings(ecs: &World) -> String {
+    let player_pos = *ecs.fetch::<Point>();
+    let mut text = String::from("Surroundings:\n");
+    for (direction, (delta_x, delta_y)) in DIRECTIONS {
+        let point = Point::new(player_pos.x + delta_x, player_pos.y + delta_y);
+        let _ = writeln!(
+            text,
+            "{direction}: {}",
+            describe_tile(ecs, point).join(", ")
+        );
+    }
+
+    text.push_str("\nMonsters in sight:\n");
+    let map = ecs.fetch::<Map>();
+    let positions = ecs.read_storage::<Position>();
+    let names = ecs.read_storage::<Name>();
+    let monsters = ecs.read_storage::<Monster>();
+    let hidden = ecs.read_storage::<Hidden>();
+    let mut in_sight = (&positions, &names, &monsters, !&hidden)
+        .join()
+        .filter(|(pos, ..)| map.is_tile_status_set(map.xy_idx(pos.x, pos.y), TileStatus::Visible))
+        .map(|(pos, name, ..)| (pos.x - player_pos.x, pos.y - player_pos.y, &name.name))
+        .collect::<Vec<_>>();
+    in_sight.sort_by_key(|(delta_x, delta_y, _)| delta_x.abs().max(delta_y.abs()));
+
+    if in_sight.is_empty() {
+        text.push_str("None\n");
+    }
+    for (delta_x, delta_y, name) in in_sight {
+        let _ = writeln!(text, "{name}: {}", describe_offset(delta_x, delta_y));
+    }
+    text
+}
+
+///Describes an offset from the player in tiles, such as "3 north, 2 east"
+fn describe_offset(delta_x: i32, delta_y: i32) -> String {
+    let vertical = match delta_y {
+        0 => None,
+        y if y < 0 => Some(format!("{} north", -y)),
+        y => Some(format!("{y} south")),
+    };
+    let horizontal = match delta_x {
+        0 => None,
+        x if x < 0 => Some(format!("{} west", -x)),
+        x => Some(format!("{x} east")),
+    };
+    vec![vertical, horizontal]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ecs::pre_run_systems,
+        test_support::{spawn_monster, test_world},
+    };
+
+    #[test]
+    fn offsets_read_vertical_then_horizontal() {
+        assert_eq!(describe_offset(2, -3), "3 north, 2 east");
+        assert_eq!(describe_offset(-1, 0), "1 west");
+        assert_eq!(describe_offset(0, 4), "4 south");
+    }
+
+    #[test]
+    fn monsters_in_sight_are_listed_with_their_offset() {
+        let mut world = test_world(12, 12, (5, 5));
+        pre_run_systems::execute(&mut world);
+        assert!(describe_surroundings(&world).ends_with("Monsters in sight:\nNone\n"));
+
+        spawn_monster(&mut world, (7, 4), 5, 1);
+        pre_run_systems::execute(&mut world);
+        let text = describe_surroundings(&world);
+        assert!(text.starts_with("Surroundings:\nNorth: "));
+        assert!(text.ends_with("Monsters in sight:\nTest Monster: 1 north, 2 east\n"));
+    }
+}
@@ -19,52 +19,87 @@ use specs::prelude::*;
 
 //Internal mods and includes
 mod audio;
+mod bestiary;
 mod camera;
+mod combat;
 mod constants;
+mod difficulty;
+mod dungeon_master;
 mod ecs;
 mod game_log;
+mod game_seed;
 mod gui;
+mod identification;
+mod log_export;
 mod map_builder;
+mod map_export;
+mod meta_stats;
+mod music;
+mod new_game_plus;
 mod player;
 mod raws;
 mod rex_assets;
+mod run_summary;
 mod save_load_util;
 mod spawning;
 mod specs_helpers;
 mod state;
+#[cfg(test)]
+mod test_support;
 
 use constants::consoles;
+use dungeon_master::DungeonMaster;
 use ecs::*;
 use game_log::GameLog;
 use gui::{
+    difficulty_menu::DifficultyResult,
+    game_over::GameOverResult,
     inventory::{InvMode, InvResult},
-    targeting::TargetResult,
+    seed_entry::SeedResult,
+    targeting::{Aim, TargetResult},
 };
-use map_builder::map::Map;
+use map_builder::map::{Map, TileType};
+use meta_stats::MetaStats;
 use player::respond_to_input;
+use run_summary::RunSummary;
 use state::{
-    AudioOption, Gameplay,
-    Gameplay::{AwaitingInput, PreRun},
-    KeyBindingOption, MainOption, Menu, SettingsOption, State, VisualOption,
+    AudioOption, Gameplay, Gameplay::AwaitingInput, KeyBindingOption, MainOption, Menu,
+    PauseOption, SettingsOption, State, VisualOption,
 };
 
 //Macros
 
+//Time in ms each map generation snapshot is shown for
+const MAPGEN_FRAME_TIME: f32 = 100.0;
+
 //Main construct
 pub struct BashingBytes {
     pub world: World,
     pub configs: raws::config::Config,
     pub music_sink: Option<rodio::Sink>,
+    ///Needed to start a new music sink whenever the track changes
+    pub music_handle: Option<rodio::OutputStreamHandle>,
     pub sfx_sink: Option<rodio::Sink>,
+    pub mapgen_history: Vec<Map>,
+    pub mapgen_index: usize,
+    pub mapgen_timer: f32,
+    pub menu_notice: Option<String>,
+    ///Where leaving the settings goes, set when they are opened from the pause menu
+    pub settings_return: Option<State>,
+    pub auto_exploring: bool,
+    pub resting: bool,
+    ///Set when a new game plus is chosen, so the next run starts with the carried equipment
+    pub new_game_plus: bool,
 }
 
 impl BashingBytes {
-    /// Gathers all entities that are not related to the player
+    /// Gathers all entities that are neither related to the player nor left behind on another level
     fn entities_to_remove_on_level_change(&mut self) -> Vec<Entity> {
         let entities = self.world.entities();
         let player_ent = self.world.fetch::<Entity>();
         let backpack = self.world.read_storage::<InBackpack>();
         let equipped_items = self.world.read_storage::<Equipped>();
+        let other_levels = self.world.read_storage::<OtherLevelPosition>();
 
         let mut to_delete = entities.join().collect::<Vec<_>>();
         to_delete.retain(|ent| {
@@ -75,7 +110,10 @@ impl BashingBytes {
             let is_equipped_by_player = equipped_items
                 .get(*ent)
                 .map_or(false, |eq| eq.owner == *player_ent);
-            !is_player && !is_in_player_bag && !is_equipped_by_player
+            !is_player
+                && !is_in_player_bag
+                && !is_equipped_by_player
+                && !other_levels.contains(*ent)
         });
 
         to_delete
@@ -83,22 +121,24 @@ impl BashingBytes {
 
     /// Generates next level for the player to explore
     fn goto_next_level(&mut self) {
-        let to_delete = self.entities_to_remove_on_level_change();
-        for target in to_delete {
-            self.world
-                .delete_entity(target)
-                .expect("Unable to delete entity during level transition");
-        }
-        self.world.maintain();
-
         //Build new map and place player
         let current_depth = self.world.fetch::<Map>().depth;
-        self.generate_world_map(current_depth + 1);
+        self.change_level(current_depth + 1);
+        {
+            let mut summary = self.world.fetch_mut::<RunSummary>();
+            summary.deepest_level = summary.deepest_level.max(current_depth + 1);
+            let mut stats = self.world.fetch_mut::<MetaStats>();
+            stats.deepest_level = stats.deepest_level.max(current_depth + 1);
+        }
+        self.save_records();
 
         //Notify player and heal player
         self.world
             .fetch_mut::<GameLog>()
-            .push(&"You descend to the next level.");
+            .push_colored(&"You descend to the next level.", RGB::named(rltk::YELLOW));
+        self.world
+            .fetch_mut::<audio::SfxQueue>()
+            .push(audio::SfxEvent::Descend);
         let player_ent = self.world.fetch::<Entity>();
         let mut all_stats = self.world.write_storage::<CombatStats>();
         if let Some(player_stats) = all_stats.get_mut(*player_ent) {
@@ -106,10 +146,112 @@ impl BashingBytes {
         }
     }
 
+    ///Climbs back up to the level above, which is restored from the cache
+    fn goto_previous_level(&mut self) {
+        let current_depth = self.world.fetch::<Map>().depth;
+        self.change_level(current_depth - 1);
+        self.world.fetch_mut::<GameLog>().push_colored(
+            &"You climb up to the previous level.",
+            RGB::named(rltk::YELLOW),
+        );
+    }
+
+    ///Leaves the current level for the given depth, restoring it if it has been visited before
+    fn change_level(&mut self, new_depth: i32) {
+        let going_down = new_depth > self.world.fetch::<Map>().depth;
+        DungeonMaster::store_level(&self.world);
+
+        let to_delete = self.entities_to_remove_on_level_change();
+        for target in to_delete {
+            self.world
+                .delete_entity(target)
+                .expect("Unable to delete entity during level transition");
+        }
+        self.world.maintain();
+
+        if !DungeonMaster::get_level(&mut self.world, new_depth) {
+            self.generate_world_map(new_depth);
+            return;
+        }
+
+        //The player arrives on the stairs leading back to where they came from
+        let arrival = if going_down {
+            TileType::StairsUp
+        } else {
+            TileType::StairsDown
+        };
+        let (player_x, player_y) = {
+            let map = self.world.fetch::<Map>();
+            let idx = map.find_tile(arrival).unwrap_or_default() as i32;
+            (idx % map.width, idx / map.width)
+        };
+        self.mapgen_history.clear();
+        self.place_player(player_x, player_y);
+    }
+
+    ///Writes the statistics and the bestiary, which are kept across runs
+    fn save_records(&self) {
+        let stats = *self.world.fetch::<MetaStats>();
+        if let Err(err) = meta_stats::save_meta_stats(&stats) {
+            self.world.fetch_mut::<GameLog>().push_colored(
+                &format!("Unable to save the statistics: {err}"),
+                RGB::named(rltk::RED),
+            );
+        }
+        let result = bestiary::save_bestiary(&self.world.fetch::<bestiary::Bestiary>());
+        if let Err(err) = result {
+            self.world.fetch_mut::<GameLog>().push_colored(
+                &format!("Unable to save the bestiary: {err}"),
+                RGB::named(rltk::RED),
+            );
+        }
+    }
+
+    ///Saves the run in the background every few turns, as set in the configs
+    fn autosave_if_due(&mut self) {
+        let turns = self.world.fetch::<RunSummary>().turns;
+        if !save_load_util::is_autosave_due(turns, self.configs.save.autosave_turns) {
+            return;
+        }
+        if let Err(err) = save_load_util::autosave(&mut self.world, self.configs.save.format) {
+            self.world
+                .fetch_mut::<GameLog>()
+                .push_colored(&format!("Unable to autosave: {err}"), RGB::named(rltk::RED));
+        }
+    }
+
+    ///Counts the player's death once, as they stay dead until the game over screen is left
+    fn record_death(&self) {
+        //An autosave of a finished run must not be continued
+        if let Err(err) = save_load_util::delete_save() {
+            self.world
+                .fetch_mut::<GameLog>()
+                .push(&format!("Unable to remove the autosave: {err}"));
+        }
+        //The equipment is kept as it was at the moment of death, for a new game plus to pick up
+        if self.world.fetch::<RunSummary>().bosses_slain > 0 {
+            let carryover = new_game_plus::gather(&self.world);
+            if let Err(err) = new_game_plus::save_carryover(&carryover) {
+                self.world
+                    .fetch_mut::<GameLog>()
+                    .push(&format!("Unable to keep your equipment: {err}"));
+            }
+        }
+        let depth = self.world.fetch::<Map>().depth;
+        {
+            let mut stats = self.world.fetch_mut::<MetaStats>();
+            stats.deaths += 1;
+            stats.deepest_level = stats.deepest_level.max(depth);
+        }
+        self.save_records();
+    }
+
     /// Deletes all entities, and sets up for next game
     fn game_over_cleanup(&mut self) {
         self.world.delete_all();
         self.world.maintain();
+        self.auto_exploring = false;
+        self.resting = false;
 
         {
             let mut logs = self.world.write_resource::<GameLog>();
@@ -117,31 +259,67 @@ impl BashingBytes {
             logs.push(&"Welcome to my Roguelike!");
         }
 
+        //Every run shuffles the appearances of unidentified items anew
+        self.world.insert(identification::Identification::new());
+        self.world.insert(DungeonMaster::default());
+        self.world.insert(RunSummary::default());
+
         // Create new player resource
         let player_ent = spawning::spawn_player(&mut self.world, 0, 0);
         self.world.insert(player_ent);
         self.world.insert(Point::new(0, 0));
 
+        //A new game plus starts out wearing what the victorious run ended with
+        if std::mem::take(&mut self.new_game_plus) {
+            if let Some(carryover) = new_game_plus::take_carryover() {
+                new_game_plus::grant(&mut self.world, &carryover);
+                self.world.fetch_mut::<GameLog>().push_colored(
+                    &"Your equipment has followed you, though its power has faded.",
+                    RGB::named(rltk::GOLD),
+                );
+            }
+        }
+
         // Build a new map and place player
         self.generate_world_map(1);
     }
 
     ///Generates a new level using `random_builder` with the specified depth
     fn generate_world_map(&mut self, new_depth: i32) {
-        const MAP_HEIGHT: i32 = 64;
-        const MAP_WIDTH: i32 = 64;
-
-        let mut builder = map_builder::random_builder(MAP_WIDTH, MAP_HEIGHT, new_depth);
-        builder.build_map();
-        self.world.insert(builder.get_map());
-        builder.spawn_entities(&mut self.world);
-
-        // Updates the players position based on the new map generated
-        // Also must update the player component, and the player pos resource
+        let (map_width, map_height) = map_builder::map_size(new_depth);
+
+        //Levels are built and populated from the run's seed
+        let mut rng = self
+            .world
+            .fetch::<game_seed::GameSeed>()
+            .level_rng(new_depth);
+        let mut builder = map_builder::random_builder(map_width, map_height, new_depth, &mut rng);
+        builder.build_map(&mut rng);
+        self.world.insert(rng);
+        self.mapgen_history = builder.get_snapshot_history();
+        self.mapgen_index = 0;
+        self.mapgen_timer = 0.0;
         let Position {
             x: player_x,
             y: player_y,
         } = builder.get_starting_position();
+        let mut map = builder.get_map();
+        //Every level but the first has a way back up where the player arrives
+        if new_depth > 1 {
+            let start_idx = map.xy_idx(player_x, player_y);
+            map.tiles[start_idx] = TileType::StairsUp;
+        }
+        self.world.insert(map);
+        builder.spawn_entities(&mut self.world);
+
+        spawning::spawn_traps(&mut self.world, (player_x, player_y), new_depth);
+        spawning::spawn_gold(&mut self.world, (player_x, player_y), new_depth);
+        self.place_player(player_x, player_y);
+    }
+
+    // Updates the players position based on the new map
+    // Also must update the player component, and the player pos resource
+    fn place_player(&mut self, player_x: i32, player_y: i32) {
         self.world.insert(Point::new(player_x, player_y));
 
         let mut position_components = self.world.write_storage::<Position>();
@@ -157,67 +335,190 @@ impl BashingBytes {
         }
     }
 
+    ///The state to enter once a new level has been generated
+    const fn new_level_state() -> Gameplay {
+        if map_builder::SHOW_MAPGEN {
+            Gameplay::MapGeneration
+        } else {
+            Gameplay::PreRun
+        }
+    }
+
+    ///Plays back the snapshots of the current level's generation until a key is pressed
+    fn show_map_generation(&mut self, ctx: &mut Rltk) -> Gameplay {
+        if let Some(snapshot) = self.mapgen_history.get(self.mapgen_index) {
+            camera::render_snapshot(&self.world, snapshot, ctx, &self.configs.visual);
+        }
+
+        ctx.set_active_console(consoles::MAP_CONSOLE);
+        ctx.print_color(
+            5,
+            0,
+            RGB::named(rltk::YELLOW),
+            RGB::from(constants::colors::BACKGROUND),
+            "Press any key to begin",
+        );
+
+        self.mapgen_timer += ctx.frame_time_ms;
+        if self.mapgen_timer > MAPGEN_FRAME_TIME
+            && self.mapgen_index + 1 < self.mapgen_history.len()
+        {
+            self.mapgen_timer = 0.0;
+            self.mapgen_index += 1;
+        }
+
+        if ctx.key.is_some() {
+            Gameplay::PreRun
+        } else {
+            Gameplay::MapGeneration
+        }
+    }
+
+    ///Continues the saved game, staying in the main menu with a notice if it can not be loaded
+    fn load_saved_game(&mut self) -> State {
+        if !save_load_util::does_save_exist() {
+            return State::Menu(Menu::Main(MainOption::LoadGame));
+        }
+
+        match save_load_util::load_game(&mut self.world) {
+            Ok(()) => {
+                self.menu_notice = None;
+                if let Err(err) = save_load_util::delete_save() {
+                    self.world
+                        .fetch_mut::<GameLog>()
+                        .push(&format!("Unable to remove the old save: {err}"));
+                }
+                //Rebuilds the index of the map and the view of the player, which are not saved
+                State::Game(Gameplay::PreRun)
+            }
+            Err(err) => {
+                self.menu_notice = Some(err.to_string());
+                State::Menu(Menu::Main(MainOption::LoadGame))
+            }
+        }
+    }
+
+    ///Starts a new run once a difficulty has been chosen
+    fn choose_difficulty(&mut self, ctx: &mut Rltk, current: difficulty::Difficulty) -> State {
+        let result = {
+            let assets = self.world.fetch::<rex_assets::RexAssets>();
+            gui::difficulty_menu::show(&self.configs, ctx, current, &assets)
+        };
+        match result {
+            DifficultyResult::Cancel => {
+                self.new_game_plus = false;
+                State::Menu(Menu::Main(MainOption::NewGame))
+            }
+            DifficultyResult::NoResponse(option) => State::Menu(Menu::Difficulty(option)),
+            DifficultyResult::Selected(difficulty) => {
+                self.world.insert(difficulty);
+                self.game_over_cleanup();
+                {
+                    let mut stats = self.world.fetch_mut::<MetaStats>();
+                    stats.runs += 1;
+                    stats.deepest_level = stats.deepest_level.max(1);
+                }
+                self.save_records();
+                State::Game(Self::new_level_state())
+            }
+        }
+    }
+
+    ///Reads the seed of a new run, moving on to the difficulty once it is entered
+    fn enter_seed(&mut self, ctx: &mut Rltk, current: u64) -> State {
+        let result = {
+            let assets = self.world.fetch::<rex_assets::RexAssets>();
+            gui::seed_entry::show(&self.configs, ctx, current, &assets)
+        };
+        match result {
+            SeedResult::Cancel => State::Menu(Menu::Main(MainOption::SeededGame)),
+            SeedResult::NoResponse(seed) => State::Menu(Menu::SeedEntry(seed)),
+            SeedResult::Selected(seed) => {
+                self.world.insert(game_seed::GameSeed::new(seed));
+                let current = *self.world.fetch::<difficulty::Difficulty>();
+                State::Menu(Menu::Difficulty(current))
+            }
+        }
+    }
+
+    ///Leaving the settings returns to wherever they were opened from
+    fn settings_menu(&mut self, ctx: &mut Rltk, option: SettingsOption) -> State {
+        let assets = &*self.world.fetch::<rex_assets::RexAssets>();
+        match gui::settings::show_settings_menu(&self.configs, ctx, option, assets) {
+            (new_option, false) => State::Menu(Menu::Settings(new_option)),
+            (new_option, true) => match new_option {
+                SettingsOption::Audio => State::Menu(Menu::Audio(AudioOption::MasterVolume)),
+                SettingsOption::Visual => State::Menu(Menu::Visual(VisualOption::FullScreen)),
+                SettingsOption::Keybindings => {
+                    State::Menu(Menu::Keybinding(KeyBindingOption::Right))
+                }
+                SettingsOption::Back => {
+                    if raws::config::save(&self.configs).is_err() {
+                        //todo: Inform player of error in saving configs
+                    }
+                    self.settings_return
+                        .take()
+                        .unwrap_or(State::Menu(Menu::Main(MainOption::Settings)))
+                }
+            },
+        }
+    }
+
     fn calc_menu_state(&mut self, ctx: &mut Rltk, current_state: Menu) -> State {
         match current_state {
             Menu::Main(option) => {
                 let main_menu_res = {
                     //Assets are fetched here to please the borrow checker!
                     let assets = self.world.fetch::<rex_assets::RexAssets>();
-                    gui::main_menu::show(&self.configs, ctx, option, &*assets)
+                    gui::main_menu::show(
+                        &self.configs,
+                        ctx,
+                        option,
+                        &*assets,
+                        self.menu_notice.as_deref(),
+                    )
                 };
 
                 match main_menu_res {
                     (option, false) => State::Menu(Menu::Main(option)),
                     (option, true) => match option {
                         MainOption::NewGame => {
-                            self.game_over_cleanup();
-                            State::Game(PreRun)
+                            self.menu_notice = None;
+                            self.world.insert(game_seed::GameSeed::random());
+                            let current = *self.world.fetch::<difficulty::Difficulty>();
+                            State::Menu(Menu::Difficulty(current))
                         }
-                        MainOption::LoadGame => {
-                            if save_load_util::does_save_exist() {
-                                save_load_util::load_game(&mut self.world);
-                                save_load_util::delete_save();
-                                State::Game(AwaitingInput)
-                            } else {
-                                State::Menu(Menu::Main(MainOption::LoadGame))
-                            }
+                        MainOption::SeededGame => {
+                            self.menu_notice = None;
+                            State::Menu(Menu::SeedEntry(0))
                         }
+                        MainOption::LoadGame => self.load_saved_game(),
                         MainOption::Settings => State::Menu(Menu::Settings(SettingsOption::Audio)),
+                        MainOption::Bestiary => State::Menu(Menu::Bestiary),
                         MainOption::Quit => std::process::exit(0),
                     },
                 }
             }
-            Menu::Settings(option) => {
-                let assets = &*self.world.fetch::<rex_assets::RexAssets>();
-                match gui::settings::show_settings_menu(&self.configs, ctx, option, assets) {
-                    (new_option, false) => State::Menu(Menu::Settings(new_option)),
-                    (new_option, true) => match new_option {
-                        SettingsOption::Audio => {
-                            State::Menu(Menu::Audio(AudioOption::MasterVolume))
-                        }
-                        SettingsOption::Visual => {
-                            State::Menu(Menu::Visual(VisualOption::FullScreen))
-                        }
-                        SettingsOption::Keybindings => {
-                            State::Menu(Menu::Keybinding(KeyBindingOption::Right))
-                        }
-                        SettingsOption::Back => {
-                            if raws::config::save(&self.configs).is_err() {
-                                //todo: Inform player of error in saving configs
-                            }
-                            State::Menu(Menu::Main(MainOption::Settings))
-                        }
-                    },
+            Menu::Difficulty(current) => self.choose_difficulty(ctx, current),
+            Menu::SeedEntry(current) => self.enter_seed(ctx, current),
+            Menu::Settings(option) => self.settings_menu(ctx, option),
+            Menu::Bestiary => {
+                if gui::bestiary::show(
+                    &self.configs,
+                    ctx,
+                    &self.world.fetch::<bestiary::Bestiary>(),
+                ) {
+                    State::Menu(Menu::Main(MainOption::Bestiary))
+                } else {
+                    State::Menu(Menu::Bestiary)
                 }
             }
             Menu::Audio(option) => {
                 let assets = &*self.world.fetch::<rex_assets::RexAssets>();
-                //todo: Either audio::show needs to account for no audio,
-                // or it needs to be dealt with here
                 let new_opt = gui::settings::audio::show(
                     &mut self.configs,
-                    self.music_sink.as_ref().unwrap(),
-                    self.sfx_sink.as_ref().unwrap(),
+                    self.music_sink.as_ref(),
+                    self.sfx_sink.as_ref(),
                     ctx,
                     option,
                     assets,
@@ -259,6 +560,50 @@ impl BashingBytes {
         }
     }
 
+    ///Turns the item picked in the inventory into the intent of the inventory mode
+    fn use_selected_item(&self, item: Entity, mode: InvMode) -> State {
+        match mode {
+            InvMode::Use | InvMode::Quaff | InvMode::Read => {
+                self.world.read_storage::<Range>().get(item).map_or_else(
+                    || {
+                        let mut intent = self.world.write_storage::<WantsToUseItem>();
+                        intent
+                            .insert(
+                                *self.world.fetch::<Entity>(),
+                                WantsToUseItem { item, target: None },
+                            )
+                            .expect("Unable to insert intent");
+                        State::Game(Gameplay::PlayerTurn)
+                    },
+                    |range| State::Game(Gameplay::ShowTargeting(range.range, item, Aim::Use)),
+                )
+            }
+            InvMode::Drop => {
+                let mut intent = self.world.write_storage::<WantsToDropItem>();
+                intent
+                    .insert(*self.world.fetch::<Entity>(), WantsToDropItem { item })
+                    .expect("Unable to insert intent to drop item");
+                State::Game(Gameplay::PlayerTurn)
+            }
+            InvMode::Remove => {
+                let mut intent = self.world.write_storage::<WantsToRemoveItem>();
+                intent
+                    .insert(*self.world.fetch::<Entity>(), WantsToRemoveItem { item })
+                    .expect("Unable to insert intent to remove item");
+                State::Game(Gameplay::PlayerTurn)
+            }
+            InvMode::Throw => State::Game(Gameplay::ShowTargeting(THROW_RANGE, item, Aim::Throw)),
+        }
+    }
+
+    ///Runs the systems for the turn the player has just taken, counting it in the run summary
+    fn player_turn(&mut self) -> State {
+        self.world.fetch_mut::<RunSummary>().turns += 1;
+        ecs::all_systems::execute(&mut self.world);
+        music::note_turn(&self.world);
+        State::Game(Gameplay::MonsterTurn)
+    }
+
     fn calc_game_state(&mut self, ctx: &mut Rltk, current_state: Gameplay) -> State {
         match current_state {
             Gameplay::PreRun => {
@@ -266,110 +611,196 @@ impl BashingBytes {
                 State::Game(Gameplay::AwaitingInput)
             }
             Gameplay::AwaitingInput => State::Game(respond_to_input(self, ctx)),
-            Gameplay::PlayerTurn => {
-                ecs::all_systems::execute(&mut self.world);
-                State::Game(Gameplay::MonsterTurn)
-            }
+            Gameplay::PlayerTurn => self.player_turn(),
             Gameplay::MonsterTurn => {
                 ecs::all_systems::execute(&mut self.world);
-                State::Game(Gameplay::AwaitingInput)
+                //Fast monsters may act several times before the player gets to act again
+                if ecs::monsters_ready(&self.world) {
+                    State::Game(Gameplay::MonsterTurn)
+                } else {
+                    self.autosave_if_due();
+                    State::Game(Gameplay::AwaitingInput)
+                }
             }
             Gameplay::Inventory(mode) => {
-                match gui::inventory::show(&self.configs, &mut self.world, ctx) {
+                match gui::inventory::show(&self.configs, &mut self.world, ctx, mode) {
                     InvResult::Cancel => State::Game(Gameplay::AwaitingInput),
                     InvResult::NoResponse => State::Game(current_state),
-                    InvResult::Selected(item) => match mode {
-                        InvMode::Use => self.world.read_storage::<Range>().get(item).map_or_else(
-                            || {
-                                let mut intent = self.world.write_storage::<WantsToUseItem>();
-                                intent
-                                    .insert(
-                                        *self.world.fetch::<Entity>(),
-                                        WantsToUseItem { item, target: None },
-                                    )
-                                    .expect("Unable to insert intent");
-                                State::Game(Gameplay::PlayerTurn)
-                            },
-                            |range| State::Game(Gameplay::ShowTargeting(range.range, item)),
-                        ),
-                        InvMode::Drop => {
-                            let mut intent = self.world.write_storage::<WantsToDropItem>();
-                            intent
-                                .insert(*self.world.fetch::<Entity>(), WantsToDropItem { item })
-                                .expect("Unable to insert intent to drop item");
-                            State::Game(Gameplay::PlayerTurn)
-                        }
-                        InvMode::Remove => {
-                            let mut intent = self.world.write_storage::<WantsToRemoveItem>();
-                            intent
-                                .insert(*self.world.fetch::<Entity>(), WantsToRemoveItem { item })
-                                .expect("Unable to insert intent to remove item");
-                            State::Game(Gameplay::PlayerTurn)
-                        }
-                    },
+                    InvResult::Selected(item) => self.use_selected_item(item, mode),
                 }
             }
             Gameplay::NextLevel => {
                 self.goto_next_level();
-                State::Game(Gameplay::PreRun)
+                State::Game(Self::new_level_state())
             }
+            Gameplay::PreviousLevel => {
+                self.goto_previous_level();
+                State::Game(Self::new_level_state())
+            }
+            Gameplay::MapGeneration => State::Game(self.show_map_generation(ctx)),
             Gameplay::SaveGame => {
-                save_load_util::save_game(&mut self.world);
-                State::Menu(Menu::Main(MainOption::LoadGame))
+                match save_load_util::save_game(&mut self.world, self.configs.save.format) {
+                    Ok(()) => {
+                        self.save_records();
+                        State::Menu(Menu::Main(MainOption::LoadGame))
+                    }
+                    //Keep playing rather than leaving to a menu that can not continue this run
+                    Err(err) => {
+                        self.world.fetch_mut::<GameLog>().push_colored(
+                            &format!("Unable to save the game: {err}"),
+                            RGB::named(rltk::RED),
+                        );
+                        State::Game(AwaitingInput)
+                    }
+                }
+            }
+            Gameplay::GameOver => self.game_over(ctx),
+            Gameplay::ShowTargeting(range, item, aim) => {
+                match gui::targeting::show(&self.configs, &self.world, ctx, range, item, aim) {
+                    TargetResult::NoResponse => State::Game(current_state),
+                    TargetResult::Cancel => State::Game(Gameplay::AwaitingInput),
+                    TargetResult::Selected(target) => self.use_on_target(item, target, aim),
+                }
             }
-            Gameplay::GameOver => {
-                if gui::game_over::show(ctx) {
+            Gameplay::Examine => {
+                if gui::examine::show(&self.configs, &self.world, ctx) {
                     State::Game(current_state)
                 } else {
-                    self.game_over_cleanup();
-                    State::Menu(Menu::Main(MainOption::NewGame))
+                    State::Game(Gameplay::AwaitingInput)
                 }
             }
-            Gameplay::ShowTargeting(range, item) => {
-                match gui::targeting::show(&self.configs, &self.world, ctx, range) {
-                    TargetResult::NoResponse => State::Game(current_state),
-                    TargetResult::Cancel => State::Game(Gameplay::AwaitingInput),
-                    TargetResult::Selected(target) => {
-                        let mut intent = self.world.write_storage::<WantsToUseItem>();
-                        intent
-                            .insert(
-                                *self.world.fetch::<Entity>(),
-                                WantsToUseItem {
-                                    item,
-                                    target: Some(target),
-                                },
-                            )
-                            .expect("Unable to insert intent");
-                        State::Game(Gameplay::PlayerTurn)
-                    }
+            Gameplay::Help => {
+                if gui::help::show(&self.configs, ctx) {
+                    State::Game(Gameplay::AwaitingInput)
+                } else {
+                    State::Game(current_state)
                 }
             }
+            Gameplay::Paused(option) => match gui::pause_menu::show(&self.configs, ctx, option) {
+                (option, false) => State::Game(Gameplay::Paused(option)),
+                (option, true) => self.pause_option_selected(option),
+            },
         }
     }
+
+    ///Shows how the run went and the stats of every run so far until a key is pressed, then sets up for the next run
+    fn game_over(&mut self, ctx: &mut Rltk) -> State {
+        let summary = *self.world.fetch::<RunSummary>();
+        let stats = *self.world.fetch::<MetaStats>();
+        camera::invalidate_map_view(&self.world);
+        match gui::game_over::show(ctx, &summary, &stats) {
+            GameOverResult::NoResponse => State::Game(Gameplay::GameOver),
+            GameOverResult::ToMenu => {
+                self.game_over_cleanup();
+                State::Menu(Menu::Main(MainOption::NewGame))
+            }
+            //The new run is set up once its difficulty is chosen, which hands out the equipment
+            GameOverResult::NewGamePlus => {
+                self.game_over_cleanup();
+                self.new_game_plus = true;
+                self.world.insert(game_seed::GameSeed::random());
+                let current = *self.world.fetch::<difficulty::Difficulty>();
+                State::Menu(Menu::Difficulty(current))
+            }
+        }
+    }
+
+    ///Resuming goes straight back to the player's turn, without any time passing
+    const fn pause_option_selected(&mut self, option: PauseOption) -> State {
+        match option {
+            PauseOption::Resume => State::Game(AwaitingInput),
+            PauseOption::SaveAndQuit => State::Game(Gameplay::SaveGame),
+            PauseOption::Settings => {
+                self.settings_return = Some(State::Game(Gameplay::Paused(option)));
+                State::Menu(Menu::Settings(SettingsOption::Audio))
+            }
+            PauseOption::QuitToMenu => State::Menu(Menu::Main(MainOption::NewGame)),
+        }
+    }
+
+    fn use_on_target(&self, item: Entity, target: Point, aim: Aim) -> State {
+        let player_ent = *self.world.fetch::<Entity>();
+        match aim {
+            //Ranged weapons are fired rather than used up
+            Aim::Fire => {
+                self.world
+                    .write_storage::<WantsToShoot>()
+                    .insert(player_ent, WantsToShoot { target })
+                    .expect("Unable to insert intent");
+            }
+            Aim::Throw => {
+                self.world
+                    .write_storage::<WantsToThrow>()
+                    .insert(player_ent, WantsToThrow { item, target })
+                    .expect("Unable to insert intent");
+            }
+            Aim::Use => {
+                self.world
+                    .write_storage::<WantsToUseItem>()
+                    .insert(
+                        player_ent,
+                        WantsToUseItem {
+                            item,
+                            target: Some(target),
+                        },
+                    )
+                    .expect("Unable to insert intent");
+            }
+        }
+        State::Game(Gameplay::PlayerTurn)
+    }
 }
 
 impl GameState for BashingBytes {
     fn tick(&mut self, ctx: &mut Rltk) {
+        let current_state = *self.world.fetch::<State>();
+
+        //In game, the camera only clears the map view when it has to
         for i in 0..consoles::NUM_OF_CONSOLES {
+            if i == consoles::MAP_CONSOLE && matches!(current_state, State::Game(_)) {
+                continue;
+            }
             ctx.set_active_console(i);
             ctx.cls();
         }
+        if !matches!(current_state, State::Game(_)) {
+            camera::invalidate_map_view(&self.world);
+        }
 
-        let current_state = *self.world.fetch::<State>();
+        music::update_music(
+            &self.configs,
+            &self.world,
+            self.music_handle.as_ref(),
+            &mut self.music_sink,
+            ctx.frame_time_ms,
+        );
 
         let next_state: State = match current_state {
             State::Menu(menu) => self.calc_menu_state(ctx, menu),
             State::Game(game) => {
-                gui::hud::show(&self.world, ctx);
-                camera::render(&self.world, ctx);
+                self.world.fetch_mut::<gui::UiState>().large_tiles =
+                    self.configs.visual.large_tiles;
+                camera::apply_scale(ctx, camera::tile_scale(&self.world));
+                gui::hud::show(&self.world, ctx, &self.configs.visual);
+                camera::render(&self.world, ctx, &self.configs.visual);
 
                 ecs::cull_dead_particles(&mut self.world, ctx.frame_time_ms);
 
                 let state = self.calc_game_state(ctx, game);
 
-                ecs::cull_dead_characters(&mut self.world);
-
-                state
+                let next_state = if !ecs::cull_dead_characters(&mut self.world) {
+                    state
+                } else if game == Gameplay::GameOver {
+                    State::Game(game)
+                } else {
+                    self.record_death();
+                    self.world
+                        .fetch_mut::<audio::SfxQueue>()
+                        .push(audio::SfxEvent::PlayerDeath);
+                    State::Game(Gameplay::GameOver)
+                };
+                audio::play_queued_sfx(&self.configs, &self.world, self.sfx_sink.as_ref());
+                next_state
             }
         };
 
@@ -390,7 +821,7 @@ fn main() -> BError {
 
     // todo: This should not be keeping a global state, but passing the raw spawns
     //  to be used as either a resource, or a part of BashingBytes struct
-    raws::spawn::load();
+    let menu_notice = raws::spawn::load().err();
 
     // This CANNOT be moved to an external function, because these functions spawn a thread in main,
     // which is required because if the thread dies, so does the audio stream
@@ -398,9 +829,10 @@ fn main() -> BError {
     let music_audio = rodio::OutputStream::try_default().ok();
     let sfx_audio = rodio::OutputStream::try_default().ok();
 
-    let music_sink = music_audio
-        .as_ref()
-        .and_then(|(_stream, handle)| audio::configure_music(&configs, handle).ok());
+    let music_sink = music_audio.as_ref().and_then(|(_stream, handle)| {
+        audio::configure_music(&configs, handle, music::MusicContext::Menu.track()).ok()
+    });
+    let music_handle = music_audio.as_ref().map(|(_stream, handle)| handle.clone());
     let sfx_sink = sfx_audio
         .as_ref()
         .and_then(|(_stream, handle)| audio::configure_sfx(&configs, handle).ok());
@@ -418,7 +850,16 @@ fn main() -> BError {
             world,
             configs,
             music_sink,
+            music_handle,
             sfx_sink,
+            mapgen_history: Vec::new(),
+            mapgen_index: 0,
+            mapgen_timer: 0.0,
+            menu_notice,
+            settings_return: None,
+            auto_exploring: false,
+            resting: false,
+            new_game_plus: false,
         };
         temp.generate_world_map(1);
         temp
@@ -436,3 +877,94 @@ fn main() -> BError {
 
     main_loop(context, bashing_bytes)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{test_game, test_world};
+
+    fn player_tile(game: &BashingBytes) -> TileType {
+        let map = game.world.fetch::<Map>();
+        let pos = *game.world.fetch::<Point>();
+        map.tiles[map.xy_idx(pos.x, pos.y)]
+    }
+
+    #[test]
+    fn pause_options_lead_where_they_say() {
+        let mut game = test_game(test_world(10, 10, (2, 2)));
+        assert_eq!(
+            game.pause_option_selected(PauseOption::Resume),
+            State::Game(AwaitingInput)
+        );
+        assert_eq!(
+            game.pause_option_selected(PauseOption::SaveAndQuit),
+            State::Game(Gameplay::SaveGame)
+        );
+        assert_eq!(
+            game.pause_option_selected(PauseOption::QuitToMenu),
+            State::Menu(Menu::Main(MainOption::NewGame))
+        );
+        assert_eq!(game.settings_return, None);
+    }
+
+    #[test]
+    fn settings_opened_while_paused_return_to_the_pause_menu() {
+        let mut game = test_game(test_world(10, 10, (2, 2)));
+        assert_eq!(
+            game.pause_option_selected(PauseOption::Settings),
+            State::Menu(Menu::Settings(SettingsOption::Audio))
+        );
+        assert_eq!(
+            game.settings_return,
+            Some(State::Game(Gameplay::Paused(PauseOption::Settings)))
+        );
+    }
+
+    #[test]
+    fn climbing_back_up_restores_the_level_above() {
+        raws::spawn::load().unwrap();
+        let mut game = test_game(test_world(10, 10, (2, 2)));
+        game.generate_world_map(1);
+        let first_level = game.world.fetch::<Map>().tiles.clone();
+
+        game.change_level(2);
+        assert_eq!(game.world.fetch::<Map>().depth, 2);
+        assert!(player_tile(&game) == TileType::StairsUp);
+
+        game.change_level(1);
+        assert!(game.world.fetch::<Map>().tiles == first_level);
+        assert!(player_tile(&game) == TileType::StairsDown);
+    }
+
+    #[test]
+    fn every_player_turn_is_counted() {
+        let mut game = test_game(test_world(10, 10, (2, 2)));
+        for _ in 0..3 {
+            assert_eq!(game.player_turn(), State::Game(Gameplay::MonsterTurn));
+        }
+        assert_eq!(game.world.fetch::<RunSummary>().turns, 3);
+    }
+
+    #[test]
+    fn only_boss_levels_have_a_boss_and_no_stairs_down() {
+        raws::spawn::load().unwrap();
+        for depth in 4..=6 {
+            let mut game = test_game(test_world(10, 10, (2, 2)));
+            game.generate_world_map(depth);
+            let bosses = game.world.read_storage::<Boss>().count();
+            let stairs_down = game
+                .world
+                .fetch::<Map>()
+                .tiles
+                .iter()
+                .filter(|tile| **tile == TileType::StairsDown)
+                .count();
+            if map_builder::is_boss_depth(depth) {
+                assert_eq!((bosses, stairs_down), (1, 0));
+            } else {
+                assert_eq!(bosses, 0);
+                assert!(stairs_down > 0);
+            }
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use super::{
+    common::{self, apply_horizontal_tunnel, apply_room_to_map},
+    map::Map,
+    rect, MapBuilder,
+};
+use crate::{components::Position, spawning::spawn_boss};
+use rltk::RandomNumberGenerator;
+use specs::World;
+
+const START_ROOM_SIZE: i32 = 8;
+//Room the player arrives in, kept clear of the arena's walls
+const START_ROOM_X: i32 = 2;
+
+///Builds a level that is little more than one large hall, where the boss waits with its minions.
+///There are no stairs down, the way on only opens once the boss has been slain
+pub struct BossArenaBuilder {
+    map: Map,
+    starting_position: Position,
+    snapshot_history: Vec<Map>,
+    arena: rect::Rect,
+}
+
+impl BossArenaBuilder {
+    pub fn new(width: i32, height: i32, depth: i32) -> Self {
+        Self {
+            map: Map::new(width, height, depth),
+            starting_position: Position { x: 0, y: 0 },
+            snapshot_history: Vec::new(),
+            arena: rect::Rect::new(0, 0, 0, 0),
+        }
+    }
+}
+
+impl MapBuilder for BossArenaBuilder {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator) {
+        let (width, height) = (self.map.width, self.map.height);
+        let start_room = rect::Rect::new(
+            START_ROOM_X,
+            height / 2 - START_ROOM_SIZE / 2,
+            START_ROOM_SIZE,
+            START_ROOM_SIZE,
+        );
+        apply_room_to_map(&mut self.map, &start_room);
+        self.take_snapshot();
+
+        //The hall takes up the middle half of the level, with a little variation in its size
+        let arena_w = width / 2 + rng.range(-2, 3);
+        let arena_h = height / 2 + rng.range(-2, 3);
+        self.arena = rect::Rect::new(
+            (width - arena_w) / 2,
+            (height - arena_h) / 2,
+            arena_w,
+            arena_h,
+        );
+        apply_room_to_map(&mut self.map, &self.arena);
+        self.take_snapshot();
+
+        let (start_x, start_y) = start_room.center();
+        let (arena_x, _) = self.arena.center();
+        apply_horizontal_tunnel(&mut self.map, start_x, arena_x, start_y);
+        self.take_snapshot();
+
+        self.starting_position = Position {
+            x: start_x,
+            y: start_y,
+        };
+    }
+
+    fn spawn_entities(&mut self, ecs: &mut World) {
+        let depth = self.map.depth;
+        spawn_boss(ecs, self.arena.center(), depth);
+    }
+
+    fn get_map(&self) -> Map {
+        self.map.clone()
+    }
+
+    fn get_starting_position(&self) -> Position {
+        self.starting_position.clone()
+    }
+
+    fn get_snapshot_history(&self) -> Vec<Map> {
+        self.snapshot_history.clone()
+    }
+
+    fn take_snapshot(&mut self) {
+        common::take_snapshot(&mut self.snapshot_history, &self.map);
+    }
+}
@@ -1,5 +1,5 @@
 use super::{
-    common::{connect_rooms_via_corridors, EDGE_BUFFER},
+    common::{self, connect_rooms_via_corridors, EDGE_BUFFER},
     map::{Map, TileType},
     rect::Rect,
     MapBuilder,
@@ -13,6 +13,7 @@ const MIN_ROOM_SIZE: i32 = 8;
 pub struct BSPInteriorBuilder {
     map: Map,
     starting_position: Position,
+    snapshot_history: Vec<Map>,
     rects: Vec<Rect>,
     rooms: Vec<Rect>,
 }
@@ -22,6 +23,7 @@ impl BSPInteriorBuilder {
         Self {
             map: Map::new(width, height, new_depth),
             starting_position: Position { x: 0, y: 0 },
+            snapshot_history: Vec::new(),
             rects: Vec::new(),
             rooms: Vec::new(),
         }
@@ -61,10 +63,8 @@ impl BSPInteriorBuilder {
 }
 
 impl MapBuilder for BSPInteriorBuilder {
-    fn build_map(&mut self) {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator) {
         assert!(i32::checked_mul(self.map.width, self.map.height) != None);
-
-        let mut rng = RandomNumberGenerator::new();
         self.rects.clear();
 
         let first_room = Rect::new(
@@ -73,7 +73,7 @@ impl MapBuilder for BSPInteriorBuilder {
             self.map.width - EDGE_BUFFER * 2,
             self.map.height - EDGE_BUFFER * 2,
         );
-        self.add_sub_rects(first_room, &mut rng);
+        self.add_sub_rects(first_room, rng);
 
         for room in &self.rects.clone() {
             self.rooms.push(*room);
@@ -84,14 +84,17 @@ impl MapBuilder for BSPInteriorBuilder {
                     self.map.tiles[idx] = TileType::Floor;
                 }
             }
+            self.take_snapshot();
         }
 
-        connect_rooms_via_corridors(&mut self.map, &self.rooms, &mut rng);
+        connect_rooms_via_corridors(&mut self.map, &self.rooms, rng);
+        self.take_snapshot();
 
         //Get stairs in!
         let stairs = self.rooms[self.rooms.len() - 1].center();
         let stairs_idx = self.map.xy_idx(stairs.0, stairs.1);
         self.map.tiles[stairs_idx] = TileType::StairsDown;
+        self.take_snapshot();
 
         // Set player start
         let start = self.rooms[0].center();
@@ -114,4 +117,11 @@ impl MapBuilder for BSPInteriorBuilder {
     fn get_starting_position(&self) -> Position {
         self.starting_position.clone()
     }
+    fn get_snapshot_history(&self) -> Vec<Map> {
+        self.snapshot_history.clone()
+    }
+
+    fn take_snapshot(&mut self) {
+        common::take_snapshot(&mut self.snapshot_history, &self.map);
+    }
 }
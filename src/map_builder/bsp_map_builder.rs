@@ -1,5 +1,5 @@
 use super::{
-    common::{apply_room_to_map, connect_rooms_via_corridors, EDGE_BUFFER},
+    common::{self, apply_room_to_map, connect_rooms_via_corridors, place_doors, EDGE_BUFFER},
     map::{Map, TileType},
     rect::Rect,
     MapBuilder,
@@ -13,6 +13,7 @@ const MAX_ATTEMPTS: usize = 240;
 pub struct BSPMapBuilder {
     map: Map,
     starting_position: Position,
+    snapshot_history: Vec<Map>,
     rects: Vec<Rect>,
     rooms: Vec<Rect>,
 }
@@ -22,6 +23,7 @@ impl BSPMapBuilder {
         Self {
             map: Map::new(width, height, new_depth),
             starting_position: Position { x: 0, y: 0 },
+            snapshot_history: Vec::new(),
             rects: Vec::new(),
             rooms: Vec::new(),
         }
@@ -29,9 +31,8 @@ impl BSPMapBuilder {
 }
 
 impl MapBuilder for BSPMapBuilder {
-    fn build_map(&mut self) {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator) {
         assert!(i32::checked_mul(self.map.width, self.map.height) != None);
-        let mut rng = RandomNumberGenerator::new();
 
         self.rects.clear();
         self.rects.push(Rect::new(
@@ -42,26 +43,34 @@ impl MapBuilder for BSPMapBuilder {
         ));
         let first_room = self.rects[0];
         self.add_sub_rects(first_room);
-        for _ in 0..MAX_ATTEMPTS {
-            let rect = self.get_random_rect(&mut rng);
-            let candidate = Self::get_random_sub_rect(rect, &mut rng);
+        //Keep trying past MAX_ATTEMPTS until two rooms have been placed, as the map needs one to
+        //start in and another for the stairs
+        let mut attempts = 0;
+        while attempts < MAX_ATTEMPTS || self.rooms.len() < 2 {
+            attempts += 1;
+            let rect = self.get_random_rect(rng);
+            let candidate = Self::get_random_sub_rect(rect, rng);
 
             if self.is_possible(candidate) {
                 apply_room_to_map(&mut self.map, &candidate);
                 self.rooms.push(candidate);
                 self.add_sub_rects(rect);
+                self.take_snapshot();
             }
         }
 
         //Sort left to right
         self.rooms.sort_by(|a, b| a.x1.cmp(&b.x1));
 
-        connect_rooms_via_corridors(&mut self.map, &self.rooms, &mut rng);
+        connect_rooms_via_corridors(&mut self.map, &self.rooms, rng);
+        place_doors(&mut self.map, &self.rooms, rng);
+        self.take_snapshot();
 
         //Get stairs in!
         let stairs = self.rooms[self.rooms.len() - 1].center();
         let stairs_idx = self.map.xy_idx(stairs.0, stairs.1);
         self.map.tiles[stairs_idx] = TileType::StairsDown;
+        self.take_snapshot();
 
         // Set player start
         let start = self.rooms[0].center();
@@ -84,6 +93,13 @@ impl MapBuilder for BSPMapBuilder {
     fn get_starting_position(&self) -> Position {
         self.starting_position.clone()
     }
+    fn get_snapshot_history(&self) -> Vec<Map> {
+        self.snapshot_history.clone()
+    }
+
+    fn take_snapshot(&mut self) {
+        common::take_snapshot(&mut self.snapshot_history, &self.map);
+    }
 }
 
 impl BSPMapBuilder {
@@ -165,3 +181,45 @@ impl BSPMapBuilder {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ///Whether each tile can be walked to from the start, through doors but never diagonally
+    fn reachable_from(map: &Map, start: &Position) -> Vec<bool> {
+        let mut reachable = vec![false; map.tiles.len()];
+        let mut open = vec![(start.x, start.y)];
+        reachable[map.xy_idx(start.x, start.y)] = true;
+        while let Some((x, y)) = open.pop() {
+            for (nx, ny) in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+                let idx = map.xy_idx(nx, ny);
+                if !reachable[idx] && map.tiles[idx] != TileType::Wall {
+                    reachable[idx] = true;
+                    open.push((nx, ny));
+                }
+            }
+        }
+        reachable
+    }
+
+    #[test]
+    fn every_room_can_be_reached_from_the_start() {
+        for seed in 1..=20 {
+            let mut rng = RandomNumberGenerator::seeded(seed);
+            let mut builder = BSPMapBuilder::new(64, 64, 1);
+            builder.build_map(&mut rng);
+            let map = builder.get_map();
+            let start = builder.get_starting_position();
+
+            assert!(map.tiles[map.xy_idx(start.x, start.y)] == TileType::Floor);
+            let reachable = reachable_from(&map, &start);
+            for room in &builder.rooms {
+                let (x, y) = room.center();
+                assert!(reachable[map.xy_idx(x, y)], "seed {} cut off a room", seed);
+            }
+            let stairs = map.find_tile(TileType::StairsDown).unwrap();
+            assert!(reachable[stairs], "seed {} cut off the stairs", seed);
+        }
+    }
+}
@@ -1,19 +1,21 @@
 use super::{
-    common::{cull_and_set_exit, gen_voronoi_regions, EDGE_BUFFER},
+    common::{self, cull_and_set_exit, gen_voronoi_regions, EDGE_BUFFER},
     map::{Map, TileType},
     MapBuilder,
 };
 use crate::{components::Position, spawning::spawn_region};
 use rltk::RandomNumberGenerator;
 use specs::World;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 const MAX_ITERATIONS: usize = 15;
+const WALL_CHANCE: i32 = 45;
 
 pub struct CellularAutomataBuilder {
     map: Map,
     starting_position: Position,
-    noise_areas: HashMap<i32, Vec<(i32, i32)>>,
+    snapshot_history: Vec<Map>,
+    noise_areas: BTreeMap<i32, Vec<(i32, i32)>>,
 }
 
 impl CellularAutomataBuilder {
@@ -21,27 +23,32 @@ impl CellularAutomataBuilder {
         Self {
             map: Map::new(width, height, new_depth),
             starting_position: Position { x: 0, y: 0 },
-            noise_areas: HashMap::new(),
+            snapshot_history: Vec::new(),
+            noise_areas: BTreeMap::new(),
         }
     }
-}
-
-impl MapBuilder for CellularAutomataBuilder {
-    fn build_map(&mut self) {
-        assert!(i32::checked_mul(self.map.width, self.map.height) != None);
-        let mut rng = RandomNumberGenerator::new();
 
-        //Randomize map
+    ///Makes every tile away from the edges a wall or a floor at random
+    fn randomize(&mut self, rng: &mut RandomNumberGenerator) {
         for y in EDGE_BUFFER..self.map.height - EDGE_BUFFER {
             for x in EDGE_BUFFER..self.map.width - EDGE_BUFFER {
                 let idx = self.map.xy_idx(x, y);
-                if rng.roll_dice(1, 100) > 55 {
+                if rng.roll_dice(1, 100) > WALL_CHANCE {
                     self.map.tiles[idx] = TileType::Floor;
                 } else {
                     self.map.tiles[idx] = TileType::Wall;
                 }
             }
         }
+    }
+}
+
+impl MapBuilder for CellularAutomataBuilder {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator) {
+        assert!(i32::checked_mul(self.map.width, self.map.height) != None);
+
+        self.randomize(rng);
+        self.take_snapshot();
 
         let mut new_tiles = self.map.tiles.clone();
         for _ in 0..MAX_ITERATIONS {
@@ -71,14 +78,19 @@ impl MapBuilder for CellularAutomataBuilder {
                 }
             }
             self.map.tiles = new_tiles.clone();
+            self.take_snapshot();
         }
 
-        //Find start tile. Go left up until a floor tile is found. Go up after x = 0
-        let (center_x, center_y) = (self.map.width / 2, self.map.height / 2);
-        let mut start_idx = self.map.xy_idx(center_x, center_y);
-        while self.map.tiles[start_idx] != TileType::Floor {
-            start_idx -= 1;
-        }
+        //Find start tile, the floor tile nearest to the center of the map
+        let center = rltk::Point::new(self.map.width / 2, self.map.height / 2);
+        let width = self.map.width;
+        let start_idx = (0..self.map.tiles.len())
+            .filter(|&idx| self.map.tiles[idx] == TileType::Floor)
+            .min_by_key(|&idx| {
+                let (x, y) = (idx as i32 % width, idx as i32 / width);
+                (x - center.x).pow(2) + (y - center.y).pow(2)
+            })
+            .expect("Cellular automata produced a map without floors");
 
         //Creating start pos
         self.starting_position = Position {
@@ -87,9 +99,10 @@ impl MapBuilder for CellularAutomataBuilder {
         };
 
         cull_and_set_exit(&mut self.map, start_idx);
+        self.take_snapshot();
 
         //Build noise map for use in spawn entities
-        self.noise_areas = gen_voronoi_regions(&self.map, &mut rng);
+        self.noise_areas = gen_voronoi_regions(&self.map, rng);
     }
 
     fn spawn_entities(&mut self, ecs: &mut World) {
@@ -105,6 +118,55 @@ impl MapBuilder for CellularAutomataBuilder {
     fn get_starting_position(&self) -> Position {
         self.starting_position.clone()
     }
+    fn get_snapshot_history(&self) -> Vec<Map> {
+        self.snapshot_history.clone()
+    }
+
+    fn take_snapshot(&mut self) {
+        common::take_snapshot(&mut self.snapshot_history, &self.map);
+    }
 }
 
-impl CellularAutomataBuilder {}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TEST_SEED;
+
+    #[test]
+    fn close_to_the_wall_chance_of_tiles_start_as_walls() {
+        let mut rng = RandomNumberGenerator::seeded(TEST_SEED);
+        let mut builder = CellularAutomataBuilder::new(80, 60, 1);
+        builder.randomize(&mut rng);
+        let inner = (EDGE_BUFFER..60 - EDGE_BUFFER)
+            .flat_map(|y| (EDGE_BUFFER..80 - EDGE_BUFFER).map(move |x| (x, y)))
+            .map(|(x, y)| builder.map.tiles[builder.map.xy_idx(x, y)] == TileType::Wall)
+            .collect::<Vec<_>>();
+        let walls = inner.iter().filter(|is_wall| **is_wall).count();
+        let percent = walls * 100 / inner.len();
+        assert!(
+            (WALL_CHANCE - 3..=WALL_CHANCE + 3).contains(&(percent as i32)),
+            "{}",
+            percent
+        );
+    }
+
+    #[test]
+    fn start_is_the_floor_nearest_the_center() {
+        for seed in 1..=10 {
+            let mut rng = RandomNumberGenerator::seeded(seed);
+            let mut builder = CellularAutomataBuilder::new(64, 48, 1);
+            builder.build_map(&mut rng);
+            let map = builder.get_map();
+            let start = builder.get_starting_position();
+            assert!(map.tiles[map.xy_idx(start.x, start.y)] == TileType::Floor);
+
+            let distance = |x: i32, y: i32| (x - 32).pow(2) + (y - 24).pow(2);
+            let nearest = (0..map.height)
+                .flat_map(|y| (0..map.width).map(move |x| (x, y)))
+                .filter(|(x, y)| map.tiles[map.xy_idx(*x, *y)] == TileType::Floor)
+                .map(|(x, y)| distance(x, y))
+                .min();
+            assert_eq!(nearest, Some(distance(start.x, start.y)), "seed {seed}");
+        }
+    }
+}
@@ -1,12 +1,25 @@
 use super::{
-    map::{Map, TileType},
+    map::{Map, TileStatus, TileType},
     rect::Rect,
+    SHOW_MAPGEN,
 };
 use rltk::RandomNumberGenerator;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 pub const EDGE_BUFFER: i32 = 2;
-const MAX_STEPS: f32 = 200.0;
+const DOOR_CHANCE: i32 = 3;
+
+///Pushes a fully revealed copy of the map into the history if map generation is being shown
+pub fn take_snapshot(history: &mut Vec<Map>, map: &Map) {
+    if SHOW_MAPGEN {
+        let mut snapshot = map.clone();
+        for idx in 0..snapshot.tile_status.len() {
+            snapshot.set_tile_status(idx, TileStatus::Revealed);
+            snapshot.set_tile_status(idx, TileStatus::Visible);
+        }
+        history.push(snapshot);
+    }
+}
 
 ///Given a room, it fills the inner part of the with floors.
 pub fn apply_room_to_map(map: &mut Map, room: &Rect) {
@@ -18,6 +31,37 @@ pub fn apply_room_to_map(map: &mut Map, room: &Rect) {
     }
 }
 
+///Places closed doors in some of the gaps that corridors made in the walls surrounding the rooms.
+///Expects the rooms to have been applied with `apply_room_to_map`.
+pub fn place_doors(map: &mut Map, rooms: &[Rect], rng: &mut RandomNumberGenerator) {
+    for room in rooms {
+        let horizontal_walls =
+            (room.x1 + 1..room.x2).flat_map(|x| vec![(x, room.y1), (x, room.y2)]);
+        let vertical_walls = (room.y1 + 1..room.y2).flat_map(|y| vec![(room.x1, y), (room.x2, y)]);
+
+        for (x, y) in horizontal_walls.chain(vertical_walls) {
+            if is_doorway(map, x, y) && rng.roll_dice(1, DOOR_CHANCE) == 1 {
+                let idx = map.xy_idx(x, y);
+                map.tiles[idx] = TileType::DoorClosed;
+            }
+        }
+    }
+}
+
+///A doorway is a floor tile with walls on opposing sides, and floor on the other two
+fn is_doorway(map: &Map, x: i32, y: i32) -> bool {
+    if x < 1 || y < 1 || x > map.width - 2 || y > map.height - 2 {
+        return false;
+    }
+
+    let is_floor = |x: i32, y: i32| map.tiles[map.xy_idx(x, y)] == TileType::Floor;
+    let is_wall = |x: i32, y: i32| map.tiles[map.xy_idx(x, y)] == TileType::Wall;
+
+    is_floor(x, y)
+        && ((is_wall(x - 1, y) && is_wall(x + 1, y) && is_floor(x, y - 1) && is_floor(x, y + 1))
+            || (is_wall(x, y - 1) && is_wall(x, y + 1) && is_floor(x - 1, y) && is_floor(x + 1, y)))
+}
+
 pub fn apply_horizontal_tunnel(map: &mut Map, x1: i32, x2: i32, y: i32) {
     for x in std::cmp::min(x1, x2)..=std::cmp::max(x1, x2) {
         let idx = map.xy_idx(x, y);
@@ -35,8 +79,8 @@ pub fn apply_vertical_tunnel(map: &mut Map, y1: i32, y2: i32, x: i32) {
 pub fn gen_voronoi_regions(
     map: &Map,
     rng: &mut rltk::RandomNumberGenerator,
-) -> HashMap<i32, Vec<(i32, i32)>> {
-    let mut noise_areas: HashMap<i32, Vec<(i32, i32)>> = HashMap::new();
+) -> BTreeMap<i32, Vec<(i32, i32)>> {
+    let mut noise_areas: BTreeMap<i32, Vec<(i32, i32)>> = BTreeMap::new();
     let mut noise = rltk::FastNoise::seeded(rng.roll_dice(1, 65536) as u64);
     noise.set_noise_type(rltk::NoiseType::Cellular);
     noise.set_frequency(0.08);
@@ -58,13 +102,17 @@ pub fn gen_voronoi_regions(
     noise_areas
 }
 
+///Walls off every floor tile that can not be reached from the start, and places the stairs on
+///the reachable tile furthest away from it
 pub fn cull_and_set_exit(map: &mut Map, start_idx: usize) {
+    //Paths are only blocked by walls once the blocked statuses are known
+    map.populate_blocked();
     let dijkstra_map = rltk::DijkstraMap::new(
         map.width,
         map.height,
         &[start_idx],
         &*map,
-        MAX_STEPS, //Stop counting at max steps
+        map.tiles.len() as f32, //Long enough for any path, even through a maze
     );
     let mut exit_tile = (0, 0.0);
 
@@ -85,21 +133,29 @@ pub fn cull_and_set_exit(map: &mut Map, start_idx: usize) {
     }
     map.tiles[exit_tile.0] = TileType::StairsDown;
 }
+///Joins each room to the next with a corridor, so every room can be reached from the first one.
+///Corridors run between tiles inside the walls of the rooms, so they never end in a wall
 pub fn connect_rooms_via_corridors(map: &mut Map, rooms: &[Rect], rng: &mut RandomNumberGenerator) {
-    for i in 0..rooms.len() - 1 {
-        let room = rooms[i];
-        let next_room = rooms[i + 1];
-        let start_x = room.x1 + (rng.roll_dice(1, i32::abs(room.x1 - room.x2)) - 1);
-        let start_y = room.y1 + (rng.roll_dice(1, i32::abs(room.y1 - room.y2)) - 1);
-        let end_x = next_room.x1 + (rng.roll_dice(1, i32::abs(next_room.x1 - next_room.x2)) - 1);
-        let end_y = next_room.y1 + (rng.roll_dice(1, i32::abs(next_room.y1 - next_room.y2)) - 1);
+    for pair in rooms.windows(2) {
+        let (start_x, start_y) = random_interior_tile(&pair[0], rng);
+        let (end_x, end_y) = random_interior_tile(&pair[1], rng);
         draw_corridor(map, start_x, start_y, end_x, end_y);
     }
 }
 
+///A random tile inside the walls of the room, or its corner if it is too thin to have an inside
+fn random_interior_tile(room: &Rect, rng: &mut RandomNumberGenerator) -> (i32, i32) {
+    (
+        rng.range(room.x1 + 1, i32::max(room.x2, room.x1 + 2)),
+        rng.range(room.y1 + 1, i32::max(room.y2, room.y1 + 2)),
+    )
+}
+
 fn draw_corridor(map: &mut Map, x1: i32, y1: i32, x2: i32, y2: i32) {
     let mut x = x1;
     let mut y = y1;
+    let idx = map.xy_idx(x, y);
+    map.tiles[idx] = TileType::Floor;
 
     while x != x2 || y != y2 {
         if x < x2 {
@@ -1,14 +1,13 @@
 use super::{
-    common::{cull_and_set_exit, gen_voronoi_regions, EDGE_BUFFER},
+    common::{self, cull_and_set_exit, gen_voronoi_regions, EDGE_BUFFER},
     map::{Map, TileType},
     MapBuilder,
 };
 use crate::{spawning::spawn_region, Position};
 use rltk::RandomNumberGenerator;
 use specs::World;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
-#[allow(dead_code)]
 pub enum DrunkardSpawnMode {
     Random,
     Centered,
@@ -17,9 +16,11 @@ pub enum DrunkardSpawnMode {
 pub struct DrunkardsBuilder {
     map: Map,
     starting_position: Position,
-    noise_areas: HashMap<i32, Vec<(i32, i32)>>,
+    snapshot_history: Vec<Map>,
+    noise_areas: BTreeMap<i32, Vec<(i32, i32)>>,
     spawn_mode: DrunkardSpawnMode,
     lifetime: i32,
+    floor_percent: i32,
 }
 
 impl DrunkardsBuilder {
@@ -29,21 +30,23 @@ impl DrunkardsBuilder {
         new_depth: i32,
         spawn_mode: DrunkardSpawnMode,
         lifetime: i32,
+        floor_percent: i32,
     ) -> Self {
         Self {
             map: Map::new(width, height, new_depth),
             starting_position: Position { x: 0, y: 0 },
-            noise_areas: HashMap::new(),
+            snapshot_history: Vec::new(),
+            noise_areas: BTreeMap::new(),
             spawn_mode,
             lifetime,
+            floor_percent,
         }
     }
 }
 
 impl MapBuilder for DrunkardsBuilder {
-    fn build_map(&mut self) {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator) {
         assert!(i32::checked_mul(self.map.width, self.map.height) != None);
-        let mut rng = RandomNumberGenerator::new();
 
         //Always start in the center
         self.starting_position = Position {
@@ -57,9 +60,9 @@ impl MapBuilder for DrunkardsBuilder {
 
         self.map.tiles[start_idx] = TileType::Floor;
 
-        //Begin the drunken digging
+        //Begin the drunken digging, sending out new drunkards until enough of the map is dug out
         let total_tiles = self.map.width * self.map.height;
-        let min_floor_tiles = (total_tiles / 2) as usize;
+        let min_floor_tiles = (total_tiles * self.floor_percent / 100) as usize;
         let mut floor_tile_count = self
             .map
             .tiles
@@ -103,6 +106,7 @@ impl MapBuilder for DrunkardsBuilder {
                 .iter()
                 .filter(|&a| *a == TileType::Floor)
                 .count();
+            self.take_snapshot();
 
             match self.spawn_mode {
                 DrunkardSpawnMode::Random => {
@@ -117,7 +121,8 @@ impl MapBuilder for DrunkardsBuilder {
         }
 
         cull_and_set_exit(&mut self.map, start_idx);
-        self.noise_areas = gen_voronoi_regions(&self.map, &mut rng);
+        self.take_snapshot();
+        self.noise_areas = gen_voronoi_regions(&self.map, rng);
     }
 
     fn spawn_entities(&mut self, ecs: &mut World) {
@@ -133,4 +138,38 @@ impl MapBuilder for DrunkardsBuilder {
     fn get_starting_position(&self) -> Position {
         self.starting_position.clone()
     }
+    fn get_snapshot_history(&self) -> Vec<Map> {
+        self.snapshot_history.clone()
+    }
+
+    fn take_snapshot(&mut self) {
+        common::take_snapshot(&mut self.snapshot_history, &self.map);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TEST_SEED;
+
+    fn dug_out(floor_percent: i32) -> usize {
+        let mut rng = RandomNumberGenerator::seeded(TEST_SEED);
+        let mut builder =
+            DrunkardsBuilder::new(60, 40, 1, DrunkardSpawnMode::Centered, 400, floor_percent);
+        builder.build_map(&mut rng);
+        builder
+            .get_map()
+            .tiles
+            .iter()
+            .filter(|tile| **tile != TileType::Wall)
+            .count()
+    }
+
+    #[test]
+    fn centered_drunkards_dig_out_the_configured_share_of_the_map() {
+        let (low, high) = (dug_out(20), dug_out(45));
+        assert!(low >= 60 * 40 * 20 / 100, "{}", low);
+        assert!(high >= 60 * 40 * 45 / 100, "{}", high);
+        assert!(high > low);
+    }
 }
@@ -1,6 +1,11 @@
 use rltk::{Algorithm2D, BaseMap, Point};
 use serde::{Deserialize, Serialize};
 use specs::prelude::*;
+use std::collections::HashSet;
+use strum::AsRefStr;
+
+//Levels at or below this depth have no ambient light
+pub const DARKNESS_DEPTH: i32 = 3;
 
 //Tile Statuses
 pub enum TileStatus {
@@ -9,20 +14,38 @@ pub enum TileStatus {
     Blocked,
 }
 
-#[derive(PartialEq, Copy, Clone, Deserialize, Serialize)]
+#[derive(PartialEq, Copy, Clone, Deserialize, Serialize, AsRefStr)]
 pub enum TileType {
+    #[strum(serialize = "Closed Door")]
+    DoorClosed,
+    #[strum(serialize = "Open Door")]
+    DoorOpen,
     Floor,
+    #[strum(serialize = "Healing Pool")]
+    HealingPool,
+    Lava,
+    #[strum(serialize = "Shallow Water")]
+    ShallowWater,
+    #[strum(serialize = "Stairs Down")]
     StairsDown,
+    #[strum(serialize = "Stairs Up")]
+    StairsUp,
     Wall,
 }
 
 #[derive(Clone, Default, Deserialize, Serialize)]
 pub struct Map {
     pub tiles: Vec<TileType>,
+    ///Only the revealed status is saved, the others are derived and rebuilt by `rebuild_derived`
+    #[serde(serialize_with = "serialize_revealed")]
     pub tile_status: Vec<u8>,
     pub width: i32,
     pub height: i32,
     pub depth: i32,
+    #[serde(default)]
+    pub is_dark: bool,
+    #[serde(default)]
+    pub bloodstains: HashSet<usize>,
 
     #[serde(skip_serializing)]
     #[serde(skip_deserializing)]
@@ -38,6 +61,8 @@ impl Map {
             width,
             height,
             depth,
+            is_dark: depth >= DARKNESS_DEPTH,
+            bloodstains: HashSet::new(),
         }
     }
 
@@ -47,7 +72,7 @@ impl Map {
 
     pub fn populate_blocked(&mut self) {
         for idx in 0..self.tiles.len() {
-            if self.tiles[idx] == TileType::Wall {
+            if self.tiles[idx] == TileType::Wall || self.tiles[idx] == TileType::DoorClosed {
                 self.set_tile_status(idx, TileStatus::Blocked);
             } else {
                 self.remove_tile_status(idx, TileStatus::Blocked);
@@ -55,6 +80,30 @@ impl Map {
         }
     }
 
+    ///Drops what can be worked out from the tiles again, keeping only what the player has seen.
+    ///Used for levels the player has left, so cached levels take up less memory
+    pub fn strip_derived(&mut self) {
+        self.tile_content = Vec::new();
+        for status in &mut self.tile_status {
+            *status &= REVEALED_MASK;
+        }
+    }
+
+    ///Recomputes the blocked tiles and an empty content index for a map that was loaded or taken
+    ///out of the level cache, nothing is visible until the next field of view update
+    pub fn rebuild_derived(&mut self) {
+        self.tile_content = vec![Vec::new(); self.tiles.len()];
+        for status in &mut self.tile_status {
+            *status &= REVEALED_MASK;
+        }
+        self.populate_blocked();
+    }
+
+    ///Marks the tile as the place something died
+    pub fn stain(&mut self, idx: usize) {
+        self.bloodstains.insert(idx);
+    }
+
     pub fn clear_content_index(&mut self) {
         for content in &mut self.tile_content {
             content.clear();
@@ -74,15 +123,48 @@ impl Map {
         self.tile_status[idx] &= !(1 << status as u8);
     }
 
+    ///Index of the first tile of the given type, used to find the stairs of a level
+    pub fn find_tile(&self, tile_type: TileType) -> Option<usize> {
+        self.tiles.iter().position(|&tile| tile == tile_type)
+    }
+
     fn is_exit_valid(&self, x: i32, y: i32) -> bool {
         if x < 1 || x > self.width - 1 || y < 1 || y > self.height - 1 {
             return false;
         }
         let idx = self.xy_idx(x, y);
-        !self.is_tile_status_set(idx, TileStatus::Blocked)
+        //Lava can be walked into, but nothing should plan a route through it
+        !self.is_tile_status_set(idx, TileStatus::Blocked) && self.tiles[idx] != TileType::Lava
     }
 }
 
+const REVEALED_MASK: u8 = 1 << TileStatus::Revealed as u8;
+
+fn serialize_revealed<S: serde::Serializer>(
+    tile_status: &[u8],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(tile_status.iter().map(|status| status & REVEALED_MASK))
+}
+
+///Indices of the tiles caught in a blast, which does not pass through walls
+pub fn aoe_tiles(center: Point, radius: i32, map: &Map) -> Vec<usize> {
+    rltk::field_of_view(center, radius, map)
+        .into_iter()
+        .filter(|tile| map.in_bounds(*tile))
+        .map(|tile| map.xy_idx(tile.x, tile.y))
+        .collect()
+}
+
+///Whether a straight line between the two points is free of opaque tiles such as walls and closed
+///doors, the end points themselves are not checked
+pub fn has_line_of_sight(map: &Map, from: Point, to: Point) -> bool {
+    rltk::line2d(rltk::LineAlg::Bresenham, from, to)
+        .into_iter()
+        .filter(|point| *point != from && *point != to)
+        .all(|point| map.in_bounds(point) && !map.is_opaque(map.point2d_to_index(point)))
+}
+
 impl Algorithm2D for Map {
     fn dimensions(&self) -> Point {
         Point::new(self.width, self.height)
@@ -97,8 +179,14 @@ impl BaseMap for Map {
     fn is_opaque(&self, idx: usize) -> bool {
         #[allow(clippy::match_on_vec_items)]
         match self.tiles[idx] {
-            TileType::Wall => true,
-            TileType::StairsDown | TileType::Floor => false,
+            TileType::Wall | TileType::DoorClosed => true,
+            TileType::StairsDown
+            | TileType::StairsUp
+            | TileType::Floor
+            | TileType::DoorOpen
+            | TileType::HealingPool
+            | TileType::Lava
+            | TileType::ShallowWater => false,
         }
     }
 
@@ -136,3 +224,87 @@ impl BaseMap for Map {
         rltk::DistanceAlg::Pythagoras.distance2d(p1, p2)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::room_map;
+
+    #[test]
+    fn walls_and_closed_doors_block_the_line_of_sight() {
+        let mut map = room_map(10, 10, 1);
+        let (from, to) = (Point::new(2, 5), Point::new(7, 5));
+        assert!(has_line_of_sight(&map, from, to));
+
+        let idx = map.xy_idx(4, 5);
+        map.tiles[idx] = TileType::DoorClosed;
+        assert!(!has_line_of_sight(&map, from, to));
+        map.tiles[idx] = TileType::DoorOpen;
+        assert!(has_line_of_sight(&map, from, to));
+        map.tiles[idx] = TileType::Wall;
+        assert!(!has_line_of_sight(&map, from, to));
+
+        //Only the tiles between the ends are checked
+        assert!(has_line_of_sight(&map, from, Point::new(4, 5)));
+    }
+
+    #[test]
+    fn loaded_map_rebuilds_its_blocked_tiles_and_sees_nothing() {
+        let mut map = room_map(8, 6, 1);
+        let door = map.xy_idx(3, 2);
+        map.tiles[door] = TileType::DoorClosed;
+        map.populate_blocked();
+        for idx in 0..map.tiles.len() {
+            map.set_tile_status(idx, TileStatus::Visible);
+            if idx % 3 == 0 {
+                map.set_tile_status(idx, TileStatus::Revealed);
+            }
+        }
+
+        let data = serde_json::to_string(&map).unwrap();
+        let mut loaded: Map = serde_json::from_str(&data).unwrap();
+        assert!(loaded
+            .tile_status
+            .iter()
+            .all(|status| *status & !REVEALED_MASK == 0));
+        assert!(loaded.tile_content.is_empty());
+
+        loaded.rebuild_derived();
+        assert_eq!(loaded.tile_content.len(), map.tiles.len());
+        for idx in 0..map.tiles.len() {
+            assert_eq!(
+                loaded.is_tile_status_set(idx, TileStatus::Revealed),
+                map.is_tile_status_set(idx, TileStatus::Revealed)
+            );
+            assert_eq!(
+                loaded.is_tile_status_set(idx, TileStatus::Blocked),
+                map.is_tile_status_set(idx, TileStatus::Blocked)
+            );
+            assert!(!loaded.is_tile_status_set(idx, TileStatus::Visible));
+        }
+        assert!(loaded.is_tile_status_set(door, TileStatus::Blocked));
+    }
+
+    #[test]
+    fn blasts_reach_their_radius_but_not_through_walls() {
+        let mut map = room_map(12, 12, 1);
+        for y in 1..11 {
+            let idx = map.xy_idx(7, y);
+            map.tiles[idx] = TileType::Wall;
+        }
+        let center = Point::new(5, 5);
+        let blast = aoe_tiles(center, 3, &map);
+
+        assert!(blast.contains(&map.xy_idx(5, 5)));
+        assert!(blast.contains(&map.xy_idx(5, 2)));
+        assert!(blast.contains(&map.xy_idx(2, 5)));
+        assert!(!blast.contains(&map.xy_idx(5, 1)));
+        //The wall itself is hit, but nothing behind it
+        assert!(blast.contains(&map.xy_idx(7, 5)));
+        assert!(!blast.contains(&map.xy_idx(8, 5)));
+        for idx in blast {
+            let tile = Point::new(idx as i32 % map.width, idx as i32 / map.width);
+            assert!(rltk::DistanceAlg::Chebyshev.distance2d(center, tile) <= 3.0);
+        }
+    }
+}
@@ -1,17 +1,21 @@
 use super::{
-    common::{gen_voronoi_regions, EDGE_BUFFER},
+    common::{self, cull_and_set_exit, gen_voronoi_regions, EDGE_BUFFER},
     map::{Map, TileType},
     MapBuilder,
 };
 use crate::{components::Position, spawning};
 use rltk::RandomNumberGenerator;
 use specs::World;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+
+//Number of carved cells between each snapshot, as a snapshot per cell is far too slow to watch
+const SNAPSHOT_INTERVAL: usize = 10;
 
 pub struct MazeBuilder {
     map: Map,
     starting_position: Position,
-    noise_areas: HashMap<i32, Vec<(i32, i32)>>,
+    snapshot_history: Vec<Map>,
+    noise_areas: BTreeMap<i32, Vec<(i32, i32)>>,
 }
 
 impl MazeBuilder {
@@ -19,33 +23,32 @@ impl MazeBuilder {
         Self {
             map: Map::new(width, height, new_depth),
             starting_position: Position { x: 0, y: 0 },
-            noise_areas: HashMap::new(),
+            snapshot_history: Vec::new(),
+            noise_areas: BTreeMap::new(),
         }
     }
 }
 
 impl MapBuilder for MazeBuilder {
-    fn build_map(&mut self) {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator) {
         assert!(i32::checked_mul(self.map.width, self.map.height) != None);
 
         //generate maze copies the generated maze to the map of argument, "self" in this case
-        let mut rng = RandomNumberGenerator::new();
         Grid::new(
             self.map.width / 2 - EDGE_BUFFER,
             self.map.height / 2 - EDGE_BUFFER,
-            &mut rng,
+            rng,
         )
         .generate_maze(self);
+        //The maze starts in the top left corner, the stairs end up at its furthest dead end
         self.starting_position = Position {
             x: EDGE_BUFFER,
             y: EDGE_BUFFER,
         };
-
-        let exit_tile = self
-            .map
-            .xy_idx(self.map.width - EDGE_BUFFER, self.map.height - EDGE_BUFFER);
-        self.map.tiles[exit_tile] = TileType::StairsDown;
-        self.noise_areas = gen_voronoi_regions(&self.map, &mut rng);
+        let start_idx = self.map.xy_idx(EDGE_BUFFER, EDGE_BUFFER);
+        cull_and_set_exit(&mut self.map, start_idx);
+        self.take_snapshot();
+        self.noise_areas = gen_voronoi_regions(&self.map, rng);
     }
 
     fn spawn_entities(&mut self, ecs: &mut World) {
@@ -61,6 +64,13 @@ impl MapBuilder for MazeBuilder {
     fn get_starting_position(&self) -> Position {
         self.starting_position.clone()
     }
+    fn get_snapshot_history(&self) -> Vec<Map> {
+        self.snapshot_history.clone()
+    }
+
+    fn take_snapshot(&mut self) {
+        common::take_snapshot(&mut self.snapshot_history, &self.map);
+    }
 }
 enum CellStatus {
     LeftWall = 0,
@@ -91,6 +101,7 @@ impl<'a> Grid<'a> {
 
     fn generate_maze(&mut self, generator: &mut MazeBuilder) {
         let mut current = 0;
+        let mut steps = 0;
         loop {
             Self::set_cell_status(&mut self.cells[current], CellStatus::BeenVisited);
             if let Some(next) = self.find_next_cell(current) {
@@ -114,6 +125,12 @@ impl<'a> Grid<'a> {
                     max_idx as i32 / self.width,
                 );
                 current = next;
+
+                steps += 1;
+                if steps % SNAPSHOT_INTERVAL == 0 {
+                    self.copy_to_map(&mut generator.map);
+                    generator.take_snapshot();
+                }
             } else if self.backtrace.is_empty() {
                 break;
             } else {
@@ -232,3 +249,46 @@ impl<'a> Grid<'a> {
         *cell &= !(1 << status as u8);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TEST_SEED;
+
+    #[test]
+    fn maze_stairs_are_at_the_furthest_reachable_tile() {
+        for seed in TEST_SEED..TEST_SEED + 5 {
+            let mut rng = RandomNumberGenerator::seeded(seed);
+            let mut builder = MazeBuilder::new(40, 30, 1);
+            builder.build_map(&mut rng);
+            let mut map = builder.get_map();
+            let start = builder.get_starting_position();
+            let start_idx = map.xy_idx(start.x, start.y);
+            map.populate_blocked();
+            let dijkstra_map = rltk::DijkstraMap::new(
+                map.width,
+                map.height,
+                &[start_idx],
+                &map,
+                map.tiles.len() as f32,
+            );
+
+            let stairs: Vec<usize> = (0..map.tiles.len())
+                .filter(|idx| map.tiles[*idx] == TileType::StairsDown)
+                .collect();
+            assert_eq!(stairs.len(), 1, "seed {seed}");
+            let furthest = dijkstra_map
+                .map
+                .iter()
+                .filter(|distance| **distance < f32::MAX)
+                .fold(0.0, |furthest: f32, distance| furthest.max(*distance));
+            let stairs_distance = dijkstra_map.map[stairs[0]];
+            assert!(stairs_distance < f32::MAX, "seed {}", seed);
+            assert!(
+                (stairs_distance - furthest).abs() < f32::EPSILON,
+                "seed {}",
+                seed
+            );
+        }
+    }
+}
@@ -1,32 +1,75 @@
+mod boss_arena_builder;
 mod bsp_interior_builder;
 mod bsp_map_builder;
 mod cellular_automata_builder;
 mod common;
 mod drunkard_builder;
 mod maze_builder;
+mod prefab;
 mod simple_map_builder;
+mod terrain_builder;
+mod vault_builder;
 
 pub mod map;
 pub mod rect;
 
+use boss_arena_builder::BossArenaBuilder;
 use bsp_interior_builder::BSPInteriorBuilder;
 use bsp_map_builder::BSPMapBuilder;
 use cellular_automata_builder::CellularAutomataBuilder;
 use drunkard_builder::{DrunkardSpawnMode, DrunkardsBuilder};
 use map::Map;
 use maze_builder::MazeBuilder;
+use rltk::RandomNumberGenerator;
 use simple_map_builder::SimpleMapBuilder;
+use terrain_builder::TerrainBuilder;
+use vault_builder::VaultBuilder;
+
+///When set, builders record snapshots of the map as it is generated, which are played back
+///before the player gains control of a new level
+pub const SHOW_MAPGEN: bool = false;
+
+const BASE_MAP_SIZE: i32 = 64;
+const MAX_MAP_SIZE: i32 = 96;
+//How many tiles wider and taller each level is than the one above it
+const MAP_GROWTH_PER_DEPTH: i32 = 4;
+//Every this many levels, the level is a boss arena
+const BOSS_DEPTH_INTERVAL: i32 = 5;
+
+///Width and height of the levels at the given depth, deeper levels are larger, up to a limit
+pub fn map_size(depth: i32) -> (i32, i32) {
+    let size = i32::min(
+        BASE_MAP_SIZE + (depth - 1).max(0) * MAP_GROWTH_PER_DEPTH,
+        MAX_MAP_SIZE,
+    );
+    (size, size)
+}
+
+///Whether the level at the given depth is a boss arena rather than a regular level
+pub const fn is_boss_depth(depth: i32) -> bool {
+    depth > 0 && depth % BOSS_DEPTH_INTERVAL == 0
+}
 
 pub trait MapBuilder {
-    fn build_map(&mut self);
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator);
     fn spawn_entities(&mut self, ecs: &mut specs::World);
     fn get_map(&self) -> Map;
     fn get_starting_position(&self) -> super::ecs::Position;
+    fn get_snapshot_history(&self) -> Vec<Map>;
+    fn take_snapshot(&mut self);
 }
 
-pub fn random_builder(width: i32, height: i32, depth: i32) -> Box<dyn MapBuilder> {
-    let mut rng = rltk::RandomNumberGenerator::new();
-    match rng.roll_dice(1, 6) {
+pub fn random_builder(
+    width: i32,
+    height: i32,
+    depth: i32,
+    rng: &mut RandomNumberGenerator,
+) -> Box<dyn MapBuilder> {
+    if is_boss_depth(depth) {
+        return Box::new(BossArenaBuilder::new(width, height, depth));
+    }
+    //Any regular level may have pools of terrain spread over it and a vault carved into it
+    let builder: Box<dyn MapBuilder> = match rng.roll_dice(1, 7) {
         1 => Box::new(SimpleMapBuilder::new(width, height, depth)),
         2 => Box::new(BSPMapBuilder::new(width, height, depth)),
         3 => Box::new(BSPInteriorBuilder::new(width, height, depth)),
@@ -37,8 +80,49 @@ pub fn random_builder(width: i32, height: i32, depth: i32) -> Box<dyn MapBuilder
             depth,
             DrunkardSpawnMode::Random,
             200,
+            40,
         )),
-        6 => Box::new(MazeBuilder::new(width, height, depth)),
+        6 => Box::new(DrunkardsBuilder::new(
+            width,
+            height,
+            depth,
+            DrunkardSpawnMode::Centered,
+            400,
+            40,
+        )),
+        7 => Box::new(MazeBuilder::new(width, height, depth)),
         _ => unreachable!(),
+    };
+    Box::new(VaultBuilder::new(Box::new(TerrainBuilder::new(builder))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TEST_SEED;
+
+    #[test]
+    fn levels_grow_with_depth_up_to_the_limit() {
+        assert_eq!(map_size(1), (BASE_MAP_SIZE, BASE_MAP_SIZE));
+        assert_eq!(map_size(5), (80, 80));
+        assert_eq!(map_size(10), (MAX_MAP_SIZE, MAX_MAP_SIZE));
+    }
+
+    #[test]
+    fn deep_levels_are_built_at_their_full_size() {
+        let mut rng = RandomNumberGenerator::seeded(TEST_SEED);
+        for depth in [9, 10] {
+            let (width, height) = map_size(depth);
+            let mut builder = random_builder(width, height, depth, &mut rng);
+            builder.build_map(&mut rng);
+            let map = builder.get_map();
+            assert_eq!((map.width, map.height), (width, height));
+            assert_eq!(map.tiles.len(), (width * height) as usize);
+            assert_eq!(map.xy_idx(width - 1, height - 1), map.tiles.len() - 1);
+
+            let start = builder.get_starting_position();
+            assert!(start.x > 0 && start.x < width - 1);
+            assert!(start.y > 0 && start.y < height - 1);
+        }
     }
 }
@@ -0,0 +1,161 @@
+use super::map::TileType;
+
+///A hand made room drawn in ASCII: '#' is a wall, '+' a closed door and anything else is floor.
+///Spawn markers stand on floor, see `marker_spawn` for what each one places
+#[derive(Copy, Clone)]
+pub struct Prefab {
+    pub template: &'static str,
+}
+
+///The tiles of a prefab, row by row, and what spawns where, relative to its top left corner
+pub struct PrefabLayout {
+    pub width: i32,
+    pub height: i32,
+    pub tiles: Vec<TileType>,
+    pub spawns: Vec<((i32, i32), String)>,
+}
+
+impl PrefabLayout {
+    pub const fn xy_idx(&self, x: i32, y: i32) -> usize {
+        (y * self.width + x) as usize
+    }
+}
+
+impl Prefab {
+    ///Blank lines are ignored and short rows are padded with walls, so templates can be written
+    ///as indented raw strings
+    pub fn parse(&self) -> PrefabLayout {
+        let rows = self
+            .template
+            .lines()
+            .map(str::trim)
+            .filter(|row| !row.is_empty())
+            .collect::<Vec<_>>();
+        let width = rows
+            .iter()
+            .map(|row| row.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        let mut tiles = Vec::with_capacity(width * rows.len());
+        let mut spawns = Vec::new();
+        for (y, row) in rows.iter().enumerate() {
+            let mut chars = row.chars();
+            for x in 0..width {
+                let glyph = chars.next().unwrap_or('#');
+                tiles.push(match glyph {
+                    '#' => TileType::Wall,
+                    '+' => TileType::DoorClosed,
+                    _ => TileType::Floor,
+                });
+                if let Some(name) = marker_spawn(glyph) {
+                    spawns.push(((x as i32, y as i32), name.to_string()));
+                }
+            }
+        }
+
+        PrefabLayout {
+            width: width as i32,
+            height: rows.len() as i32,
+            tiles,
+            spawns,
+        }
+    }
+}
+
+///The spawn table entry a template marker stands for
+const fn marker_spawn(marker: char) -> Option<&'static str> {
+    match marker {
+        'g' => Some("Goblin"),
+        'o' => Some("Orc"),
+        'k' => Some("Kobold"),
+        'r' => Some("Giant Rat"),
+        '!' => Some("Health Potion"),
+        '?' => Some("Magic Missile Scroll"),
+        ')' => Some("Simple Dagger"),
+        '[' => Some("Simple Shield"),
+        _ => None,
+    }
+}
+
+pub const VAULTS: [Prefab; 3] = [
+    //Goblin den around a potion
+    Prefab {
+        template: "
+            #########
+            #.g...g.#
+            #...!...#
+            #.g...g.#
+            #########
+        ",
+    },
+    //Pillared treasury
+    Prefab {
+        template: "
+            ###########
+            #.........#
+            #.#.#.#.#.#
+            #..?.!.?..#
+            #.#.#.#.#.#
+            #.........#
+            ###########
+        ",
+    },
+    //Guarded armory
+    Prefab {
+        template: "
+            #######
+            #o...o#
+            #.[.).#
+            #o...o#
+            #######
+        ",
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ragged_template_is_padded_with_walls() {
+        let layout = Prefab {
+            template: "
+                ####
+                #g!+
+                ##
+            ",
+        }
+        .parse();
+        assert_eq!((layout.width, layout.height), (4, 3));
+        let tiles = layout
+            .tiles
+            .iter()
+            .map(|tile| match tile {
+                TileType::Wall => '#',
+                TileType::DoorClosed => '+',
+                TileType::Floor => '.',
+                _ => '?',
+            })
+            .collect::<String>();
+        assert_eq!(tiles, "#####..+####");
+        assert_eq!(
+            layout.spawns,
+            vec![
+                ((1, 1), "Goblin".to_string()),
+                ((2, 1), "Health Potion".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn vault_spawns_stand_on_floor() {
+        for vault in VAULTS {
+            let layout = vault.parse();
+            assert_eq!(layout.tiles.len(), (layout.width * layout.height) as usize);
+            for ((x, y), _) in &layout.spawns {
+                assert!(layout.tiles[layout.xy_idx(*x, *y)] == TileType::Floor);
+            }
+        }
+    }
+}
@@ -1,9 +1,12 @@
 use super::{
-    common::{apply_horizontal_tunnel, apply_room_to_map, apply_vertical_tunnel},
+    common::{
+        self, apply_horizontal_tunnel, apply_room_to_map, apply_vertical_tunnel, place_doors,
+    },
     map::{Map, TileType},
     rect, MapBuilder,
 };
 use crate::{components::Position, spawning::populate_room};
+use rltk::RandomNumberGenerator;
 use specs::World;
 
 const MAX_ROOMS: i32 = 30;
@@ -13,6 +16,7 @@ const MAX_SIZE: i32 = 10;
 pub struct SimpleMapBuilder {
     map: Map,
     starting_position: Position,
+    snapshot_history: Vec<Map>,
     rooms: Vec<rect::Rect>,
 }
 
@@ -21,16 +25,15 @@ impl SimpleMapBuilder {
         Self {
             map: Map::new(width, height, depth),
             starting_position: Position { x: 0, y: 0 },
+            snapshot_history: Vec::new(),
             rooms: Vec::new(),
         }
     }
 }
 
 impl MapBuilder for SimpleMapBuilder {
-    fn build_map(&mut self) {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator) {
         assert!(i32::checked_mul(self.map.width, self.map.height) != None);
-
-        let mut rng = rltk::RandomNumberGenerator::new();
         for _ in 0..MAX_ROOMS {
             let w = rng.range(MIN_SIZE, MAX_SIZE);
             let h = rng.range(MIN_SIZE, MAX_SIZE);
@@ -52,13 +55,18 @@ impl MapBuilder for SimpleMapBuilder {
                     }
                 }
                 self.rooms.push(new_room);
+                self.take_snapshot();
             }
         }
 
+        place_doors(&mut self.map, &self.rooms, rng);
+        self.take_snapshot();
+
         //Apply stairs to center of last room
         let stairs_pos = self.rooms.last().unwrap().center();
         let stairs_idx = self.map.xy_idx(stairs_pos.0, stairs_pos.1);
         self.map.tiles[stairs_idx] = TileType::StairsDown;
+        self.take_snapshot();
 
         let start_pos = self.rooms[0].center();
         self.starting_position = Position {
@@ -80,4 +88,11 @@ impl MapBuilder for SimpleMapBuilder {
     fn get_starting_position(&self) -> Position {
         self.starting_position.clone()
     }
+    fn get_snapshot_history(&self) -> Vec<Map> {
+        self.snapshot_history.clone()
+    }
+
+    fn take_snapshot(&mut self) {
+        common::take_snapshot(&mut self.snapshot_history, &self.map);
+    }
 }
@@ -0,0 +1,147 @@
+use super::{
+    common,
+    map::{Map, TileType},
+    MapBuilder,
+};
+use crate::components::Position;
+use rltk::RandomNumberGenerator;
+use specs::World;
+
+//Chance out of 100 for a level to get pools of terrain
+const TERRAIN_CHANCE: i32 = 40;
+const MAX_POOLS: i32 = 4;
+//Steps taken by the random walk that spreads each pool
+const POOL_STEPS: i32 = 8;
+
+///Builds a level with another builder, then sometimes spreads a few pools of lava, shallow water
+///or healing water over its floor, never letting lava cut off any part of the level
+pub struct TerrainBuilder {
+    inner: Box<dyn MapBuilder>,
+    map: Map,
+    snapshot_history: Vec<Map>,
+}
+
+impl TerrainBuilder {
+    pub fn new(inner: Box<dyn MapBuilder>) -> Self {
+        Self {
+            inner,
+            map: Map::default(),
+            snapshot_history: Vec::new(),
+        }
+    }
+
+    ///Floor tiles a random walk from the center wanders over, keeping clear of the start
+    fn pool_tiles(&self, center: usize, rng: &mut RandomNumberGenerator) -> Vec<usize> {
+        let map = &self.map;
+        let start = self.inner.get_starting_position();
+        let (mut x, mut y) = (center as i32 % map.width, center as i32 / map.width);
+        let mut tiles = Vec::new();
+        for _ in 0..POOL_STEPS {
+            let idx = map.xy_idx(x, y);
+            let near_start = (x - start.x).abs() <= 1 && (y - start.y).abs() <= 1;
+            if map.tiles[idx] == TileType::Floor && !near_start && !tiles.contains(&idx) {
+                tiles.push(idx);
+            }
+            match rng.roll_dice(1, 4) {
+                1 => x += 1,
+                2 => x -= 1,
+                3 => y += 1,
+                _ => y -= 1,
+            }
+            if x < 1 || y < 1 || x >= map.width - 1 || y >= map.height - 1 {
+                break;
+            }
+        }
+        tiles
+    }
+
+    ///Whether each tile can be walked to from the start without crossing lava
+    fn reachable_tiles(&self) -> Vec<bool> {
+        let map = &self.map;
+        let start = self.inner.get_starting_position();
+        let mut reachable = vec![false; map.tiles.len()];
+        let mut open = vec![(start.x, start.y)];
+        reachable[map.xy_idx(start.x, start.y)] = true;
+        while let Some((x, y)) = open.pop() {
+            for (dx, dy) in (-1..=1).flat_map(|dy| (-1..=1).map(move |dx| (dx, dy))) {
+                let (nx, ny) = (x + dx, y + dy);
+                if nx < 0 || ny < 0 || nx >= map.width || ny >= map.height {
+                    continue;
+                }
+                let idx = map.xy_idx(nx, ny);
+                if !reachable[idx] && !matches!(map.tiles[idx], TileType::Wall | TileType::Lava) {
+                    reachable[idx] = true;
+                    open.push((nx, ny));
+                }
+            }
+        }
+        reachable
+    }
+}
+
+impl MapBuilder for TerrainBuilder {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator) {
+        self.inner.build_map(rng);
+        self.map = self.inner.get_map();
+        self.snapshot_history = self.inner.get_snapshot_history();
+        if rng.roll_dice(1, 100) > TERRAIN_CHANCE {
+            return;
+        }
+
+        for _ in 0..rng.roll_dice(1, MAX_POOLS) {
+            let terrain = match rng.roll_dice(1, 6) {
+                1 | 2 => TileType::Lava,
+                3..=5 => TileType::ShallowWater,
+                _ => TileType::HealingPool,
+            };
+            let floors = (0..self.map.tiles.len())
+                .filter(|&idx| self.map.tiles[idx] == TileType::Floor)
+                .collect::<Vec<_>>();
+            let Some(&center) = rng.random_slice_entry(&floors) else {
+                return;
+            };
+
+            let pool = self.pool_tiles(center, rng);
+            let reachable_before = self.reachable_tiles();
+            for &idx in &pool {
+                self.map.tiles[idx] = terrain;
+            }
+            //Lava that walls off part of the level is turned back into floor
+            if terrain == TileType::Lava {
+                let reachable_after = self.reachable_tiles();
+                let cut_off = (0..self.map.tiles.len()).any(|idx| {
+                    reachable_before[idx]
+                        && !reachable_after[idx]
+                        && self.map.tiles[idx] != TileType::Lava
+                });
+                if cut_off {
+                    for &idx in &pool {
+                        self.map.tiles[idx] = TileType::Floor;
+                    }
+                    continue;
+                }
+            }
+            self.take_snapshot();
+        }
+    }
+
+    fn spawn_entities(&mut self, ecs: &mut World) {
+        self.inner.spawn_entities(ecs);
+    }
+
+    fn get_map(&self) -> Map {
+        self.map.clone()
+    }
+
+    fn get_starting_position(&self) -> Position {
+        self.inner.get_starting_position()
+    }
+
+    fn get_snapshot_history(&self) -> Vec<Map> {
+        self.snapshot_history.clone()
+    }
+
+    fn take_snapshot(&mut self) {
+        common::take_snapshot(&mut self.snapshot_history, &self.map);
+    }
+}
@@ -0,0 +1,163 @@
+use super::{
+    common::{self, EDGE_BUFFER},
+    map::{Map, TileType},
+    prefab::{PrefabLayout, VAULTS},
+    MapBuilder,
+};
+use crate::{components::Position, spawning::spawn_at_points};
+use rltk::RandomNumberGenerator;
+use specs::World;
+
+//Chance out of 100 for a level to get a vault
+const VAULT_CHANCE: i32 = 35;
+
+///Builds a level with another builder, then sometimes carves a vault into solid rock and digs a
+///corridor from it to the nearest floor
+pub struct VaultBuilder {
+    inner: Box<dyn MapBuilder>,
+    map: Map,
+    snapshot_history: Vec<Map>,
+    spawns: Vec<((i32, i32), String)>,
+}
+
+impl VaultBuilder {
+    pub fn new(inner: Box<dyn MapBuilder>) -> Self {
+        Self {
+            inner,
+            map: Map::default(),
+            snapshot_history: Vec::new(),
+            spawns: Vec::new(),
+        }
+    }
+
+    ///Top left corners where the vault fits with a margin of rock all around it
+    fn vault_sites(&self, vault: &PrefabLayout) -> Vec<(i32, i32)> {
+        let map = &self.map;
+        let is_rock = |x: i32, y: i32| map.tiles[map.xy_idx(x, y)] == TileType::Wall;
+        let fits = |x: i32, y: i32| {
+            (y - 1..=y + vault.height).all(|ty| (x - 1..=x + vault.width).all(|tx| is_rock(tx, ty)))
+        };
+        let max_x = map.width - EDGE_BUFFER - vault.width;
+        (EDGE_BUFFER..map.height - EDGE_BUFFER - vault.height)
+            .flat_map(|y| (EDGE_BUFFER..max_x).map(move |x| (x, y)))
+            .filter(|&(x, y)| fits(x, y))
+            .collect()
+    }
+
+    fn stamp_vault(&mut self, vault: &PrefabLayout, (x, y): (i32, i32)) {
+        for vy in 0..vault.height {
+            for vx in 0..vault.width {
+                let idx = self.map.xy_idx(x + vx, y + vy);
+                self.map.tiles[idx] = vault.tiles[vault.xy_idx(vx, vy)];
+            }
+        }
+        self.spawns.extend(
+            vault
+                .spawns
+                .iter()
+                .map(|((vx, vy), name)| ((x + vx, y + vy), name.clone())),
+        );
+    }
+
+    ///Whether each tile can be walked to from the start, counting doors as open
+    fn reachable_tiles(&self) -> Vec<bool> {
+        let map = &self.map;
+        let start = self.inner.get_starting_position();
+        let mut reachable = vec![false; map.tiles.len()];
+        let mut open = vec![(start.x, start.y)];
+        reachable[map.xy_idx(start.x, start.y)] = true;
+        while let Some((x, y)) = open.pop() {
+            for (dx, dy) in (-1..=1).flat_map(|dy| (-1..=1).map(move |dx| (dx, dy))) {
+                let (nx, ny) = (x + dx, y + dy);
+                if nx < 0 || ny < 0 || nx >= map.width || ny >= map.height {
+                    continue;
+                }
+                let idx = map.xy_idx(nx, ny);
+                if !reachable[idx] && map.tiles[idx] != TileType::Wall {
+                    reachable[idx] = true;
+                    open.push((nx, ny));
+                }
+            }
+        }
+        reachable
+    }
+
+    ///Digs through rock from the middle of the vault to the closest reachable floor outside of it,
+    ///leaving every tile that is not a wall as it was
+    fn connect_vault(&mut self, vault: &PrefabLayout, (x, y): (i32, i32)) {
+        let (start_x, start_y) = (x + vault.width / 2, y + vault.height / 2);
+        let in_vault =
+            |tx: i32, ty: i32| tx >= x && tx < x + vault.width && ty >= y && ty < y + vault.height;
+        let width = self.map.width;
+        let reachable = self.reachable_tiles();
+        let Some(target) = (0..self.map.tiles.len())
+            .filter(|&idx| reachable[idx] && self.map.tiles[idx] == TileType::Floor)
+            .map(|idx| (idx as i32 % width, idx as i32 / width))
+            .filter(|&(tx, ty)| !in_vault(tx, ty))
+            .min_by_key(|&(tx, ty)| (tx - start_x).abs() + (ty - start_y).abs())
+        else {
+            return;
+        };
+
+        let (mut cx, mut cy) = (start_x, start_y);
+        while (cx, cy) != target {
+            if cx == target.0 {
+                cy += (target.1 - cy).signum();
+            } else {
+                cx += (target.0 - cx).signum();
+            }
+            let idx = self.map.xy_idx(cx, cy);
+            if self.map.tiles[idx] == TileType::Wall {
+                self.map.tiles[idx] = TileType::Floor;
+            }
+        }
+    }
+}
+
+impl MapBuilder for VaultBuilder {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator) {
+        self.inner.build_map(rng);
+        self.map = self.inner.get_map();
+        self.snapshot_history = self.inner.get_snapshot_history();
+        if rng.roll_dice(1, 100) > VAULT_CHANCE {
+            return;
+        }
+
+        let vault = rng
+            .random_slice_entry(&VAULTS)
+            .expect("There are always vaults")
+            .parse();
+        let sites = self.vault_sites(&vault);
+        let Some(&site) = rng.random_slice_entry(&sites) else {
+            return;
+        };
+        self.stamp_vault(&vault, site);
+        self.connect_vault(&vault, site);
+        self.take_snapshot();
+    }
+
+    fn spawn_entities(&mut self, ecs: &mut World) {
+        self.inner.spawn_entities(ecs);
+        spawn_at_points(
+            ecs,
+            self.spawns.iter().map(|(point, name)| (point, name)),
+            self.map.depth,
+        );
+    }
+
+    fn get_map(&self) -> Map {
+        self.map.clone()
+    }
+
+    fn get_starting_position(&self) -> Position {
+        self.inner.get_starting_position()
+    }
+
+    fn get_snapshot_history(&self) -> Vec<Map> {
+        self.snapshot_history.clone()
+    }
+
+    fn take_snapshot(&mut self) {
+        common::take_snapshot(&mut self.snapshot_history, &self.map);
+    }
+}
@@ -0,0 +1,114 @@
+use crate::{
+    camera,
+    components::{Hidden, Position, Render, Size},
+    map_builder::map::{Map, TileStatus},
+};
+use rltk::Point;
+use specs::prelude::*;
+use std::{fs, io, path::PathBuf};
+
+const EXPORT_DIR: &str = "./exports";
+
+///Draws the level as text, one line per row: tiles the player has found are shown with the glyph
+///they have on screen, and whatever the player can currently see is drawn on top of them
+pub fn export_map_ascii(ecs: &World) -> String {
+    let map = ecs.fetch::<Map>();
+    let mut grid = (0..map.tiles.len())
+        .map(|idx| {
+            if map.is_tile_status_set(idx, TileStatus::Revealed) {
+                let (glyph, _) = camera::get_tile_glyph(idx, &map);
+                rltk::to_char(glyph as u8)
+            } else {
+                ' '
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let positions = ecs.read_storage::<Position>();
+    let renderables = ecs.read_storage::<Render>();
+    let hidden = ecs.read_storage::<Hidden>();
+    let sizes = ecs.read_storage::<Size>();
+    let mut entities = (&positions, &renderables, sizes.maybe(), !&hidden)
+        .join()
+        .collect::<Vec<_>>();
+    //Same order as on screen, so an item never hides the monster standing on it
+    entities.sort_by_key(|(_, render, ..)| std::cmp::Reverse(render.render_order));
+    for (pos, render, size, ()) in entities {
+        let size = size.copied().unwrap_or_default();
+        for tile in size.tiles(Point::new(pos.x, pos.y)) {
+            if !rltk::Algorithm2D::in_bounds(&*map, tile) {
+                continue;
+            }
+            let idx = map.xy_idx(tile.x, tile.y);
+            if map.is_tile_status_set(idx, TileStatus::Visible) {
+                grid[idx] = rltk::to_char(render.glyph as u8);
+            }
+        }
+    }
+
+    grid.chunks(map.width as usize)
+        .map(|row| row.iter().collect::<String>() + "\n")
+        .collect()
+}
+
+///Writes the level to a text file named after its depth, returning where it was written
+pub fn export(ecs: &World) -> io::Result<PathBuf> {
+    fs::create_dir_all(EXPORT_DIR)?;
+    let path = PathBuf::from(EXPORT_DIR).join(format!("map_{}.txt", ecs.fetch::<Map>().depth));
+    fs::write(&path, export_map_ascii(ecs))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ecs::pre_run_systems,
+        test_support::{spawn_monster, test_world},
+    };
+    use rltk::{ColorPair, RGB};
+
+    fn render(glyph: char, render_order: i32) -> Render {
+        Render {
+            glyph: rltk::to_cp437(glyph),
+            colors: ColorPair::new(RGB::named(rltk::WHITE), RGB::named(rltk::BLACK)),
+            render_order,
+        }
+    }
+
+    #[test]
+    fn map_is_drawn_as_the_player_knows_it() {
+        let mut world = test_world(6, 4, (1, 1));
+        let player_ent = *world.fetch::<Entity>();
+        world
+            .write_storage()
+            .insert(player_ent, render('@', 1))
+            .unwrap();
+        world
+            .create_entity()
+            .with(Position { x: 1, y: 1 })
+            .with(render('!', 2))
+            .build();
+        let goblin = spawn_monster(&mut world, (3, 2), 5, 1);
+        world
+            .write_storage()
+            .insert(goblin, render('g', 1))
+            .unwrap();
+        let lurker = spawn_monster(&mut world, (4, 1), 5, 1);
+        world
+            .write_storage()
+            .insert(lurker, render('s', 1))
+            .unwrap();
+        pre_run_systems::execute(&mut world);
+        {
+            let mut map = world.fetch_mut::<Map>();
+            //Remembered but out of sight, and never found at all
+            let remembered = map.xy_idx(4, 1);
+            map.remove_tile_status(remembered, TileStatus::Visible);
+            let unknown = map.xy_idx(5, 3);
+            map.remove_tile_status(unknown, TileStatus::Revealed);
+        }
+
+        assert_eq!(export_map_ascii(&world), "######\n#@...#\n#..g.#\n##### \n");
+    }
+}
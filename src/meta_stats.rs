@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::Path};
+
+const STATS_PATH: &str = "./stats.ron";
+
+///Totals kept across every run, stored apart from the save so they outlive each character
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MetaStats {
+    pub runs: u32,
+    pub deepest_level: i32,
+    pub monsters_killed: u32,
+    pub deaths: u32,
+}
+
+///Reads the stats of previous runs, starting from nothing if there are none or they are unreadable
+pub fn load_meta_stats() -> MetaStats {
+    load_from(Path::new(STATS_PATH))
+}
+
+fn load_from(path: &Path) -> MetaStats {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|data| ron::de::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_meta_stats(stats: &MetaStats) -> io::Result<()> {
+    save_to(stats, Path::new(STATS_PATH))
+}
+
+///Writes to a temporary file first, so a crash while saving can not corrupt the previous stats
+fn save_to(stats: &MetaStats, path: &Path) -> io::Result<()> {
+    let data = ron::ser::to_string_pretty(stats, ron::ser::PrettyConfig::new())
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    let temp_path = path.with_extension("ron.tmp");
+    fs::write(&temp_path, data)?;
+    fs::rename(temp_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_load_back_as_they_were_saved() {
+        let dir = std::env::temp_dir().join(format!("roguelike-stats-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("stats.ron");
+
+        assert_eq!(load_from(&path), MetaStats::default());
+        let stats = MetaStats {
+            runs: 4,
+            deepest_level: 7,
+            monsters_killed: 52,
+            deaths: 3,
+        };
+        save_to(&stats, &path).unwrap();
+        assert_eq!(load_from(&path), stats);
+        assert!(!path.with_extension("ron.tmp").exists());
+
+        fs::write(&path, "not stats").unwrap();
+        assert_eq!(load_from(&path), MetaStats::default());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
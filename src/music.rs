@@ -0,0 +1,192 @@
+use crate::{
+    audio,
+    components::{Boss, Faction, FieldOfView, Hidden, Monster, Position},
+    gui::settings::audio::MAX_VOLUME,
+    raws::config::Config,
+    state::State,
+};
+use rltk::Point;
+use specs::prelude::*;
+
+//Turns without a hostile monster in view before the music calms down again
+const CALM_TURNS: i32 = 5;
+//Time in ms it takes to fade a track out, and as long again to fade the next one in
+const CROSSFADE_TIME: f32 = 1500.0;
+
+///What is going on in the game, which decides the music that plays
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum MusicContext {
+    Menu,
+    Exploration,
+    Combat,
+    Boss,
+}
+
+impl MusicContext {
+    ///Name of the track played in this context, several contexts may share a track
+    pub const fn track(self) -> &'static str {
+        match self {
+            Self::Menu | Self::Exploration => "dungeon_sewer",
+            Self::Combat | Self::Boss => "scene_through_the_darkness",
+        }
+    }
+}
+
+///The context whose track is playing and how far it has faded, along with how long the player has
+///been out of combat
+pub struct MusicState {
+    playing: MusicContext,
+    //Loudness of the playing track, from silent at 0 to full at 1
+    fade: f32,
+    calm_turns: i32,
+}
+
+impl Default for MusicState {
+    fn default() -> Self {
+        Self {
+            playing: MusicContext::Menu,
+            fade: 1.0,
+            calm_turns: CALM_TURNS,
+        }
+    }
+}
+
+///Counts the turns since a hostile monster was last in view of the player, run once every turn
+pub fn note_turn(ecs: &World) {
+    let player_ent = *ecs.fetch::<Entity>();
+    let fields_of_view = ecs.read_storage::<FieldOfView>();
+    let Some(player_fov) = fields_of_view.get(player_ent) else {
+        return;
+    };
+    let factions = ecs.read_storage::<Faction>();
+    let hidden = ecs.read_storage::<Hidden>();
+    let monsters = ecs.read_storage::<Monster>();
+    let positions = ecs.read_storage::<Position>();
+    let monster_in_view =
+        (&monsters, &factions, &positions, !&hidden)
+            .join()
+            .any(|(_, faction, pos, ())| {
+                Faction::Player.is_hostile_to(*faction)
+                    && player_fov.visible_tiles.contains(&Point::new(pos.x, pos.y))
+            });
+
+    let mut music = ecs.fetch_mut::<MusicState>();
+    music.calm_turns = if monster_in_view {
+        0
+    } else {
+        i32::min(music.calm_turns + 1, CALM_TURNS)
+    };
+}
+
+///The context the music should be playing for. Bosses set the mood for their whole level, other
+///monsters only while in view and for a few turns after
+pub fn current_music_context(ecs: &World) -> MusicContext {
+    if matches!(*ecs.fetch::<State>(), State::Menu(_)) {
+        return MusicContext::Menu;
+    }
+    let boss_on_level = (&ecs.read_storage::<Boss>(), &ecs.read_storage::<Position>())
+        .join()
+        .next()
+        .is_some();
+    if boss_on_level {
+        MusicContext::Boss
+    } else if ecs.fetch::<MusicState>().calm_turns < CALM_TURNS {
+        MusicContext::Combat
+    } else {
+        MusicContext::Exploration
+    }
+}
+
+///Fades the playing track out when the context calls for another one, starting the new track once
+///the old one is silent and fading it in
+pub fn update_music(
+    configs: &Config,
+    ecs: &World,
+    handle: Option<&rodio::OutputStreamHandle>,
+    music_sink: &mut Option<rodio::Sink>,
+    frame_time_ms: f32,
+) {
+    let wanted = current_music_context(ecs);
+    let mut music = ecs.fetch_mut::<MusicState>();
+    let step = frame_time_ms / CROSSFADE_TIME;
+    if wanted.track() == music.playing.track() {
+        music.playing = wanted;
+        music.fade = f32::min(music.fade + step, 1.0);
+    } else {
+        music.fade = f32::max(music.fade - step, 0.0);
+        if music.fade <= 0.0 {
+            music.playing = wanted;
+            *music_sink = handle
+                .and_then(|handle| audio::configure_music(configs, handle, wanted.track()).ok());
+        }
+    }
+
+    if let Some(sink) = music_sink {
+        let master_volume: f32 = configs.audio.master_volume as f32 / MAX_VOLUME as f32;
+        let music_volume: f32 = configs.audio.music_volume as f32 / MAX_VOLUME as f32;
+        sink.set_volume(master_volume * music_volume * music.fade);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ecs::pre_run_systems,
+        state::{Gameplay, MainOption, Menu},
+        test_support::{spawn_monster, test_world},
+    };
+
+    #[test]
+    fn music_follows_the_monsters_in_view() {
+        let mut world = test_world(12, 12, (2, 5));
+        world.insert(State::Menu(Menu::Main(MainOption::NewGame)));
+        assert_eq!(current_music_context(&world), MusicContext::Menu);
+        world.insert(State::Game(Gameplay::PlayerTurn));
+        assert_eq!(current_music_context(&world), MusicContext::Exploration);
+
+        let monster = spawn_monster(&mut world, (6, 5), 5, 1);
+        pre_run_systems::execute(&mut world);
+        note_turn(&world);
+        assert_eq!(current_music_context(&world), MusicContext::Combat);
+
+        world.delete_entity(monster).unwrap();
+        for _ in 1..CALM_TURNS {
+            note_turn(&world);
+            assert_eq!(current_music_context(&world), MusicContext::Combat);
+        }
+        note_turn(&world);
+        assert_eq!(current_music_context(&world), MusicContext::Exploration);
+
+        let boss = spawn_monster(&mut world, (9, 9), 5, 1);
+        world.write_storage().insert(boss, Boss {}).unwrap();
+        assert_eq!(current_music_context(&world), MusicContext::Boss);
+    }
+
+    #[test]
+    fn new_track_fades_in_once_the_old_one_faded_out() {
+        let mut world = test_world(12, 12, (2, 5));
+        let configs = Config::default();
+        let mut sink = None;
+
+        //Exploration shares the track of the menu, so the music plays on at full loudness
+        update_music(&configs, &world, None, &mut sink, 100.0);
+        assert_eq!(
+            world.fetch::<MusicState>().playing,
+            MusicContext::Exploration
+        );
+
+        let boss = spawn_monster(&mut world, (9, 9), 5, 1);
+        world.write_storage().insert(boss, Boss {}).unwrap();
+        update_music(&configs, &world, None, &mut sink, CROSSFADE_TIME / 2.0);
+        {
+            let music = world.fetch::<MusicState>();
+            assert_eq!(music.playing, MusicContext::Exploration);
+            assert!((music.fade - 0.5).abs() < f32::EPSILON);
+        }
+        update_music(&configs, &world, None, &mut sink, CROSSFADE_TIME / 2.0);
+        assert_eq!(world.fetch::<MusicState>().playing, MusicContext::Boss);
+        update_music(&configs, &world, None, &mut sink, CROSSFADE_TIME);
+        assert!((world.fetch::<MusicState>().fade - 1.0).abs() < f32::EPSILON);
+    }
+}
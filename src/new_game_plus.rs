@@ -0,0 +1,136 @@
+use crate::{
+    components::{DefenseBonus, EquipmentSlot, Equipped, MeleeDamageBonus, Name, RangedWeapon},
+    spawning,
+};
+use serde::{Deserialize, Serialize};
+use specs::prelude::*;
+use std::{fs, io, path::Path};
+
+const CARRYOVER_DIR: &str = "./saves";
+const CARRYOVER_PATH: &str = "./saves/ngplus.ron";
+
+///The equipment worn at the end of a run in which a boss fell, which a new game plus starts with
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Carryover {
+    pub items: Vec<CarriedItem>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CarriedItem {
+    pub name: String,
+    pub slot: EquipmentSlot,
+}
+
+///Lists the items the player has equipped, by the name they are spawned under
+pub fn gather(ecs: &World) -> Carryover {
+    let player_ent = *ecs.fetch::<Entity>();
+    let equipped = ecs.read_storage::<Equipped>();
+    let names = ecs.read_storage::<Name>();
+    let items = (&equipped, &names)
+        .join()
+        .filter(|(equipped, _)| equipped.owner == player_ent)
+        .map(|(equipped, name)| CarriedItem {
+            name: name.name.clone(),
+            slot: equipped.slot,
+        })
+        .collect();
+    Carryover { items }
+}
+
+pub fn save_carryover(carryover: &Carryover) -> io::Result<()> {
+    fs::create_dir_all(CARRYOVER_DIR)?;
+    save_to(carryover, Path::new(CARRYOVER_PATH))
+}
+
+fn save_to(carryover: &Carryover, path: &Path) -> io::Result<()> {
+    let data = ron::ser::to_string_pretty(carryover, ron::ser::PrettyConfig::new())
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    fs::write(path, data)
+}
+
+///Reads and removes the carried equipment, so it is only handed out to a single new game
+pub fn take_carryover() -> Option<Carryover> {
+    take_from(Path::new(CARRYOVER_PATH))
+}
+
+fn take_from(path: &Path) -> Option<Carryover> {
+    let data = fs::read_to_string(path).ok()?;
+    fs::remove_file(path).ok()?;
+    ron::de::from_str(&data).ok()
+}
+
+///Equips the player with the carried items, each at half of its usual power rounded up
+pub fn grant(ecs: &mut World, carryover: &Carryover) {
+    let player_ent = *ecs.fetch::<Entity>();
+    for carried in &carryover.items {
+        if let Some(item) = spawning::spawn_equipped(ecs, &carried.name, player_ent, carried.slot) {
+            weaken(ecs, item);
+        }
+    }
+}
+
+fn weaken(ecs: &World, item: Entity) {
+    let halve = |value: i32| value - value / 2;
+    if let Some(weapon) = ecs.write_storage::<MeleeDamageBonus>().get_mut(item) {
+        weapon.bonus = halve(weapon.bonus);
+    }
+    if let Some(shield) = ecs.write_storage::<DefenseBonus>().get_mut(item) {
+        shield.bonus = halve(shield.bonus);
+    }
+    if let Some(weapon) = ecs.write_storage::<RangedWeapon>().get_mut(item) {
+        weapon.damage = halve(weapon.damage);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{raws, test_support::test_world};
+
+    fn melee_bonus(ecs: &World, item: Entity) -> i32 {
+        ecs.read_storage::<MeleeDamageBonus>()
+            .get(item)
+            .unwrap()
+            .bonus
+    }
+
+    #[test]
+    fn equipment_worn_at_the_end_carries_over_at_half_power() {
+        raws::spawn::load().unwrap();
+        let mut world = test_world(10, 10, (3, 3));
+        let player_ent = *world.fetch::<Entity>();
+        let axe = spawning::spawn_equipped(
+            &mut world,
+            "Battle Axe",
+            player_ent,
+            EquipmentSlot::PrimaryHand,
+        )
+        .unwrap();
+        //Worn by someone else, so it stays behind
+        let monster = world.create_entity().build();
+        spawning::spawn_equipped(&mut world, "Simple Shield", monster, EquipmentSlot::OffHand)
+            .unwrap();
+
+        let path =
+            std::env::temp_dir().join(format!("roguelike-ngplus-{}.ron", std::process::id()));
+        save_to(&gather(&world), &path).unwrap();
+        let carryover = take_from(&path).unwrap();
+        assert!(!path.exists());
+        assert!(take_from(&path).is_none());
+
+        let mut next_run = test_world(10, 10, (3, 3));
+        let next_player = *next_run.fetch::<Entity>();
+        grant(&mut next_run, &carryover);
+        let equipped = next_run.read_storage::<Equipped>();
+        let names = next_run.read_storage::<Name>();
+        let carried = (&next_run.entities(), &equipped, &names)
+            .join()
+            .map(|(item, equipped, name)| (item, equipped.owner, name.name.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(carried.len(), 1);
+        let (item, owner, name) = &carried[0];
+        assert_eq!((*owner, name.as_str()), (next_player, "Battle Axe"));
+        let full = melee_bonus(&world, axe);
+        assert_eq!(melee_bonus(&next_run, *item), full - full / 2);
+    }
+}
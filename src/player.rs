@@ -1,18 +1,42 @@
 use super::{
     components::{
-        CombatStats, FieldOfView, Item, Monster, Player, Position, WantsToMelee, WantsToPickupItem,
+        Ammo, CombatStats, Confusion, EntityMoved, Equipped, Faction, FieldOfView, HungerClock,
+        HungerState, Item, Player, Position, RangedWeapon, Stealth, WantsToMelee,
+        WantsToPickupItem,
     },
     BashingBytes, GameLog,
 };
 use crate::{
-    gui::inventory::InvMode,
+    gui::{
+        inventory::{group_inventory, shown_in_mode, InvMode},
+        targeting::Aim,
+        UiState,
+    },
+    log_export,
     map_builder::map::{Map, TileStatus, TileType},
-    state::Gameplay,
+    map_export,
+    state::{Gameplay, PauseOption},
 };
-use rltk::{Point, Rltk, VirtualKeyCode};
+use rltk::{Point, RandomNumberGenerator, Rltk, VirtualKeyCode, RGB};
 use specs::{Entity, Join, World, WorldExt};
 
+const WAITS_PER_HEAL: i32 = 4;
+
 pub fn respond_to_input(game: &mut BashingBytes, ctx: &mut Rltk) -> Gameplay {
+    //Auto explore and resting keep going on their own until any key is pressed
+    if game.auto_exploring || game.resting {
+        if ctx.key.is_some() {
+            game.auto_exploring = false;
+            game.resting = false;
+            return Gameplay::AwaitingInput;
+        }
+        return if game.resting {
+            rest(game)
+        } else {
+            auto_explore(game)
+        };
+    }
+
     let keys = &game.configs.keys;
     if let Some(key) = ctx.key {
         if key == keys.move_up {
@@ -33,6 +57,8 @@ pub fn respond_to_input(game: &mut BashingBytes, ctx: &mut Rltk) -> Gameplay {
             try_move(1, 1, &mut game.world);
         } else if key == keys.descend {
             return try_descend(&mut game.world);
+        } else if key == keys.ascend {
+            return try_ascend(&game.world);
         } else if key == keys.grab_item {
             try_pickup(&mut game.world);
         } else if key == keys.drop_item {
@@ -41,12 +67,54 @@ pub fn respond_to_input(game: &mut BashingBytes, ctx: &mut Rltk) -> Gameplay {
             return Gameplay::Inventory(InvMode::Remove);
         } else if key == keys.open_inventory {
             return Gameplay::Inventory(InvMode::Use);
+        } else if key == keys.quaff {
+            return open_filtered_inventory(&game.world, InvMode::Quaff);
+        } else if key == keys.read {
+            return open_filtered_inventory(&game.world, InvMode::Read);
         } else if key == keys.go_back {
-            return Gameplay::SaveGame;
-        } else if key == keys.wait_turn {
+            //Pausing does not take a turn
+            return Gameplay::Paused(PauseOption::Resume);
+        } else if key == keys.wait_turn || key == keys.wait_turn_alt {
             return skip_turn(&mut game.world);
-        } else if key == VirtualKeyCode::M {
-
+        } else if key == keys.examine {
+            //Looking around does not take a turn
+            return Gameplay::Examine;
+        } else if key == keys.fire {
+            return fire(&game.world);
+        } else if key == keys.throw {
+            return open_filtered_inventory(&game.world, InvMode::Throw);
+        } else if key == keys.export_log {
+            //Exporting the log does not take a turn
+            export_log(&game.world);
+            return Gameplay::AwaitingInput;
+        } else if key == keys.export_map {
+            //Exporting the map does not take a turn
+            export_map(&game.world);
+            return Gameplay::AwaitingInput;
+        } else if key == keys.auto_explore {
+            game.auto_exploring = true;
+            return auto_explore(game);
+        } else if key == keys.rest {
+            return start_resting(game);
+        } else if key == keys.sneak {
+            //Changing pace does not take a turn
+            toggle_sneaking(&game.world);
+            return Gameplay::AwaitingInput;
+        } else if key == keys.help {
+            //Reading the controls does not take a turn
+            return Gameplay::Help;
+        } else if key == keys.toggle_minimap {
+            //Toggling the minimap does not take a turn
+            let mut ui_state = game.world.fetch_mut::<UiState>();
+            ui_state.show_minimap = !ui_state.show_minimap;
+            return Gameplay::AwaitingInput;
+        } else if key == keys.toggle_debug {
+            //Toggling the debug overlay does not take a turn
+            let mut ui_state = game.world.fetch_mut::<UiState>();
+            ui_state.show_debug = !ui_state.show_debug;
+            return Gameplay::AwaitingInput;
+        } else if let Some((delta_x, delta_y)) = numpad_direction(key) {
+            try_move(delta_x, delta_y, &mut game.world);
         } else {
             return Gameplay::AwaitingInput;
         }
@@ -57,16 +125,136 @@ pub fn respond_to_input(game: &mut BashingBytes, ctx: &mut Rltk) -> Gameplay {
     Gameplay::PlayerTurn
 }
 
-fn try_move(delta_x: i32, delta_y: i32, ecs: &mut World) {
+///Opens the inventory listing only the items the mode applies to, unless none are carried
+fn open_filtered_inventory(ecs: &World, mode: InvMode) -> Gameplay {
+    let player_ent = *ecs.fetch::<Entity>();
+    let any_carried = group_inventory(ecs, player_ent)
+        .into_iter()
+        .any(|(_, _, item)| shown_in_mode(ecs, mode, item));
+    if any_carried {
+        return Gameplay::Inventory(mode);
+    }
+    let kind = match mode {
+        InvMode::Read => "scrolls",
+        InvMode::Throw => "items to throw",
+        _ => "potions",
+    };
+    ecs.fetch_mut::<GameLog>()
+        .push(&format!("You have no {kind}."));
+    Gameplay::AwaitingInput
+}
+
+fn export_map(ecs: &World) {
+    let result = map_export::export(ecs);
+    let mut logs = ecs.fetch_mut::<GameLog>();
+    match result {
+        Ok(path) => logs.push(&format!("The map was written to {}.", path.display())),
+        Err(err) => logs.push_colored(
+            &format!("Unable to write the map: {err}"),
+            RGB::named(rltk::RED),
+        ),
+    }
+}
+
+///Switches the player between walking and sneaking, which moves slower but quieter
+fn toggle_sneaking(ecs: &World) {
+    let player_ent = ecs.fetch::<Entity>();
+    let mut stealths = ecs.write_storage::<Stealth>();
+    let Some(stealth) = stealths.get_mut(*player_ent) else {
+        return;
+    };
+    stealth.sneaking = !stealth.sneaking;
+    let message = if stealth.sneaking {
+        "You begin sneaking."
+    } else {
+        "You stop sneaking."
+    };
+    ecs.fetch_mut::<GameLog>().push(&message);
+}
+
+///Writes the recent events and the player's surroundings to a text file
+fn export_log(ecs: &World) {
+    let result = log_export::export(ecs);
+    let mut logs = ecs.fetch_mut::<GameLog>();
+    match result {
+        Ok(()) => logs.push(&"The recent events were written to log.txt."),
+        Err(err) => logs.push_colored(
+            &format!("Unable to write log.txt: {err}"),
+            RGB::named(rltk::RED),
+        ),
+    }
+}
+
+///Aims the equipped ranged weapon, which needs a target before the turn is taken and an arrow in
+///the quiver
+fn fire(ecs: &World) -> Gameplay {
+    let player_ent = *ecs.fetch::<Entity>();
+    let ranged_weapons = ecs.read_storage::<RangedWeapon>();
+    let equipped_items = ecs.read_storage::<Equipped>();
+    let entities = ecs.entities();
+
+    (&entities, &ranged_weapons, &equipped_items)
+        .join()
+        .find(|(_, _, equipped)| equipped.owner == player_ent)
+        .map_or_else(
+            || {
+                ecs.fetch_mut::<GameLog>()
+                    .push(&"You have no ranged weapon equipped.");
+                Gameplay::AwaitingInput
+            },
+            |(weapon, ranged_weapon, _)| {
+                let quivers = ecs.read_storage::<Ammo>();
+                if quivers
+                    .get(player_ent)
+                    .is_some_and(|quiver| quiver.count <= 0)
+                {
+                    ecs.fetch_mut::<GameLog>().push(&"You are out of arrows.");
+                    return Gameplay::AwaitingInput;
+                }
+                Gameplay::ShowTargeting(ranged_weapon.range, weapon, Aim::Fire)
+            },
+        )
+}
+
+///Numpad directions are always available, regardless of the configured movement keys
+pub const fn numpad_direction(key: VirtualKeyCode) -> Option<(i32, i32)> {
+    match key {
+        VirtualKeyCode::Numpad1 => Some((-1, 1)),
+        VirtualKeyCode::Numpad2 => Some((0, 1)),
+        VirtualKeyCode::Numpad3 => Some((1, 1)),
+        VirtualKeyCode::Numpad4 => Some((-1, 0)),
+        VirtualKeyCode::Numpad6 => Some((1, 0)),
+        VirtualKeyCode::Numpad7 => Some((-1, -1)),
+        VirtualKeyCode::Numpad8 => Some((0, -1)),
+        VirtualKeyCode::Numpad9 => Some((1, -1)),
+        _ => None,
+    }
+}
+
+fn try_move(mut delta_x: i32, mut delta_y: i32, ecs: &mut World) {
+    //Confused players stagger in a random direction, the confusion wears off in `ConfusionSystem`
+    if ecs
+        .read_storage::<Confusion>()
+        .contains(*ecs.fetch::<Entity>())
+    {
+        let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+        delta_x = rng.range(-1, 2);
+        delta_y = rng.range(-1, 2);
+    }
+
     let mut positions = ecs.write_storage::<Position>();
     let mut fields_of_view = ecs.write_storage::<FieldOfView>();
     let mut players = ecs.write_storage::<Player>();
     let mut attacks = ecs.write_storage::<WantsToMelee>();
+    let mut moved = ecs.write_storage::<EntityMoved>();
     let entities = ecs.entities();
 
     let combat_stats = ecs.read_storage::<CombatStats>();
-    let map = &ecs.fetch::<Map>();
+    let factions = ecs.read_storage::<Faction>();
+    let mut map = ecs.fetch_mut::<Map>();
 
+    let mut door_opened = false;
+    let mut swapped_ally = None;
     //Allows the player to attack if position is occupied
     for (entity, _, pos, fov) in
         (&entities, &mut players, &mut positions, &mut fields_of_view).join()
@@ -80,9 +268,16 @@ fn try_move(delta_x: i32, delta_y: i32, ecs: &mut World) {
             return;
         }
 
-        //Attack if possible
+        //Attack if possible, creatures on the player's side trade places with them instead
         let destination_idx = map.xy_idx(pos.x + delta_x, pos.y + delta_y);
         for potential_target in &map.tile_content[destination_idx] {
+            if factions
+                .get(*potential_target)
+                .is_some_and(|faction| Faction::Player.is_friendly_to(*faction))
+            {
+                swapped_ally = Some((*potential_target, Point::new(pos.x, pos.y)));
+                break;
+            }
             if combat_stats.get(*potential_target).is_some() {
                 attacks
                     .insert(
@@ -96,14 +291,43 @@ fn try_move(delta_x: i32, delta_y: i32, ecs: &mut World) {
             }
         }
 
+        //Bumping into a closed door opens it instead of moving
+        if map.tiles[destination_idx] == TileType::DoorClosed {
+            map.tiles[destination_idx] = TileType::DoorOpen;
+            map.remove_tile_status(destination_idx, TileStatus::Blocked);
+            door_opened = true;
+            break;
+        }
+
         //If not blocked, moves the player there
-        if !map.is_tile_status_set(destination_idx, TileStatus::Blocked) {
+        if swapped_ally.is_some() || !map.is_tile_status_set(destination_idx, TileStatus::Blocked) {
             pos.x = std::cmp::min(map.width - 1, std::cmp::max(0, pos.x + delta_x));
             pos.y = std::cmp::min(map.height - 1, std::cmp::max(0, pos.y + delta_y));
             let mut player_pos = ecs.write_resource::<Point>();
             player_pos.x = pos.x;
             player_pos.y = pos.y;
             fov.is_dirty = true;
+            moved
+                .insert(entity, EntityMoved {})
+                .expect("Unable to insert movement marker");
+        }
+    }
+
+    if let Some((ally, old_pos)) = swapped_ally {
+        if let (Some(pos), Some(fov)) = (positions.get_mut(ally), fields_of_view.get_mut(ally)) {
+            let (ally_idx, old_idx) = (map.xy_idx(pos.x, pos.y), map.xy_idx(old_pos.x, old_pos.y));
+            map.remove_tile_status(ally_idx, TileStatus::Blocked);
+            map.set_tile_status(old_idx, TileStatus::Blocked);
+            pos.x = old_pos.x;
+            pos.y = old_pos.y;
+            fov.is_dirty = true;
+        }
+    }
+
+    //An opened door changes what everyone nearby can see
+    if door_opened {
+        for fov in (&mut fields_of_view).join() {
+            fov.is_dirty = true;
         }
     }
 }
@@ -153,31 +377,452 @@ fn try_descend(ecs: &mut World) -> Gameplay {
     }
 }
 
-fn skip_turn(ecs: &mut World) -> Gameplay {
+fn try_ascend(ecs: &World) -> Gameplay {
+    let player_pos = ecs.fetch::<Point>();
+    let map = ecs.fetch::<Map>();
+    let player_idx = map.xy_idx(player_pos.x, player_pos.y);
+    if map.tiles[player_idx] == TileType::StairsUp {
+        Gameplay::PreviousLevel
+    } else {
+        let mut logs = ecs.fetch_mut::<GameLog>();
+        logs.push(&"There is no way up from here.");
+        Gameplay::AwaitingInput
+    }
+}
+
+///Checks if the players fov contains any creature hostile to them, neutral ones are no threat
+fn is_monster_in_view(ecs: &World) -> bool {
     let fields_of_view = ecs.read_storage::<FieldOfView>();
-    let player_ent = ecs.fetch::<Entity>();
-    let player_vs = fields_of_view.get(*player_ent).unwrap();
-    let mobs = ecs.read_storage::<Monster>();
+    let player_vs = fields_of_view.get(*ecs.fetch::<Entity>()).unwrap();
+    let factions = ecs.read_storage::<Faction>();
     let map = ecs.fetch::<Map>();
 
-    //Checks if the point contains a mob given the map
-    let contains_mob = |tile: Point| {
+    player_vs.visible_tiles.iter().any(|tile| {
         let idx = map.xy_idx(tile.x, tile.y);
-        map.tile_content[idx]
-            .iter()
-            .any(|ent| mobs.get(*ent).is_some())
-    };
+        map.tile_content[idx].iter().any(|ent| {
+            factions
+                .get(*ent)
+                .is_some_and(|faction| faction.is_hostile_to(Faction::Player))
+        })
+    })
+}
 
-    //If the players fov does not contain mobs they may heal a point by waiting
-    if !player_vs
-        .visible_tiles
-        .iter()
-        .any(|&tile| contains_mob(tile))
-    {
-        let mut all_stats = ecs.write_storage::<CombatStats>();
-        let player_stats = all_stats.get_mut(*player_ent).unwrap();
-        player_stats.hp = i32::min(player_stats.hp + 1, player_stats.max_hp);
+fn skip_turn(ecs: &mut World) -> Gameplay {
+    let player_ent = ecs.fetch::<Entity>();
+
+    //If the players fov does not contain mobs they heal a point every few turns spent waiting
+    let mut players = ecs.write_storage::<Player>();
+    let player = players.get_mut(*player_ent).unwrap();
+    if is_monster_in_view(ecs) {
+        player.quiet_waits = 0;
+        ecs.fetch_mut::<GameLog>().push(&"You wait warily.");
+    } else {
+        player.quiet_waits += 1;
+        if player.quiet_waits >= WAITS_PER_HEAL {
+            player.quiet_waits = 0;
+            let mut all_stats = ecs.write_storage::<CombatStats>();
+            let player_stats = all_stats.get_mut(*player_ent).unwrap();
+            player_stats.hp = i32::min(player_stats.hp + 1, player_stats.max_hp);
+        }
     }
 
     Gameplay::PlayerTurn
 }
+
+///Begins waiting turn after turn, unless there is a reason resting would stop right away
+fn start_resting(game: &mut BashingBytes) -> Gameplay {
+    if is_monster_in_view(&game.world) {
+        game.world
+            .fetch_mut::<GameLog>()
+            .push(&"You can not rest with monsters nearby.");
+        return Gameplay::AwaitingInput;
+    }
+    if is_fully_healed(&game.world) {
+        game.world
+            .fetch_mut::<GameLog>()
+            .push(&"You are already fully healed.");
+        return Gameplay::AwaitingInput;
+    }
+
+    game.world.fetch_mut::<GameLog>().push(&"You rest.");
+    game.resting = true;
+    rest(game)
+}
+
+///Waits a turn, stopping once the player is fully healed or a monster comes into view. Starving
+///players stop as well, since waiting would only hurt them further
+fn rest(game: &mut BashingBytes) -> Gameplay {
+    let starving = game
+        .world
+        .read_storage::<HungerClock>()
+        .get(*game.world.fetch::<Entity>())
+        .is_some_and(|clock| clock.state == HungerState::Starving);
+    let reason_to_stop = if is_monster_in_view(&game.world) || starving {
+        Some("You are disturbed!")
+    } else if is_fully_healed(&game.world) {
+        Some("You feel rested.")
+    } else {
+        None
+    };
+
+    if let Some(message) = reason_to_stop {
+        game.resting = false;
+        game.world.fetch_mut::<GameLog>().push(&message);
+        return Gameplay::AwaitingInput;
+    }
+    skip_turn(&mut game.world)
+}
+
+fn is_fully_healed(ecs: &World) -> bool {
+    ecs.read_storage::<CombatStats>()
+        .get(*ecs.fetch::<Entity>())
+        .is_none_or(|stats| stats.hp >= stats.max_hp)
+}
+
+///Takes a step towards the nearest revealed tile that borders unrevealed ones, stopping when a
+///monster comes into view or there is nothing left to explore
+fn auto_explore(game: &mut BashingBytes) -> Gameplay {
+    if is_monster_in_view(&game.world) {
+        game.auto_exploring = false;
+        game.world
+            .fetch_mut::<GameLog>()
+            .push(&"You spot a monster and stop exploring.");
+        return Gameplay::AwaitingInput;
+    }
+
+    let step = {
+        let map = game.world.fetch::<Map>();
+        let player_pos = game.world.fetch::<Point>();
+        next_explore_step(&map, *player_pos)
+    };
+
+    if let Some((delta_x, delta_y)) = step {
+        try_move(delta_x, delta_y, &mut game.world);
+        Gameplay::PlayerTurn
+    } else {
+        game.auto_exploring = false;
+        game.world
+            .fetch_mut::<GameLog>()
+            .push(&"Nothing left to explore.");
+        Gameplay::AwaitingInput
+    }
+}
+
+///Finds the direction of the first step towards the closest frontier tile, if any can be reached
+fn next_explore_step(map: &Map, player_pos: Point) -> Option<(i32, i32)> {
+    const MAX_STEPS: f32 = 1000.0;
+
+    let is_revealed = |x: i32, y: i32| {
+        x < 0
+            || y < 0
+            || x >= map.width
+            || y >= map.height
+            || map.is_tile_status_set(map.xy_idx(x, y), TileStatus::Revealed)
+    };
+
+    let frontier = (0..map.tiles.len())
+        .filter(|&idx| {
+            let (x, y) = (idx as i32 % map.width, idx as i32 / map.width);
+            !matches!(map.tiles[idx], TileType::Wall | TileType::Lava)
+                && is_revealed(x, y)
+                && (-1..=1).any(|dy| (-1..=1).any(|dx| !is_revealed(x + dx, y + dy)))
+        })
+        .collect::<Vec<_>>();
+
+    if frontier.is_empty() {
+        return None;
+    }
+
+    let dijkstra_map = rltk::DijkstraMap::new(map.width, map.height, &frontier, map, MAX_STEPS);
+
+    //Neighbors are checked by hand rather than through the maps exits so closed doors are bumped
+    let mut best: Option<((i32, i32), f32)> = None;
+    for delta_y in -1..=1 {
+        for delta_x in -1..=1 {
+            let (x, y) = (player_pos.x + delta_x, player_pos.y + delta_y);
+            if (delta_x, delta_y) == (0, 0) || x < 1 || y < 1 || x >= map.width || y >= map.height {
+                continue;
+            }
+            let idx = map.xy_idx(x, y);
+            let distance = dijkstra_map.map[idx];
+            if !matches!(map.tiles[idx], TileType::Wall | TileType::Lava)
+                && distance < MAX_STEPS
+                && best.is_none_or(|(_, best_distance)| distance < best_distance)
+            {
+                best = Some(((delta_x, delta_y), distance));
+            }
+        }
+    }
+
+    best.map(|(delta, _)| delta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        components::{EquipmentSlot, Size},
+        ecs::pre_run_systems,
+        test_support::{room_map, spawn_monster, test_game, test_world},
+    };
+    use specs::Builder;
+
+    fn wounded_player_world() -> World {
+        let world = test_world(12, 12, (5, 5));
+        let player_ent = *world.fetch::<Entity>();
+        world
+            .write_storage::<CombatStats>()
+            .get_mut(player_ent)
+            .unwrap()
+            .hp = 1;
+        world
+    }
+
+    fn player_hp(world: &World) -> i32 {
+        world
+            .read_storage::<CombatStats>()
+            .get(*world.fetch::<Entity>())
+            .unwrap()
+            .hp
+    }
+
+    #[test]
+    fn waiting_alone_heals_every_few_waits() {
+        let mut world = wounded_player_world();
+        pre_run_systems::execute(&mut world);
+        for _ in 0..WAITS_PER_HEAL * 2 {
+            assert_eq!(skip_turn(&mut world), Gameplay::PlayerTurn);
+        }
+        assert_eq!(player_hp(&world), 3);
+    }
+
+    #[test]
+    fn waiting_with_a_monster_in_view_does_not_heal() {
+        let mut world = wounded_player_world();
+        spawn_monster(&mut world, (7, 5), 5, 1);
+        pre_run_systems::execute(&mut world);
+        for _ in 0..WAITS_PER_HEAL * 2 {
+            skip_turn(&mut world);
+        }
+        assert_eq!(player_hp(&world), 1);
+    }
+
+    #[test]
+    fn resting_waits_until_fully_healed() {
+        let mut world = wounded_player_world();
+        pre_run_systems::execute(&mut world);
+        let mut game = test_game(world);
+
+        let mut state = start_resting(&mut game);
+        let mut turns = 0;
+        while game.resting {
+            assert_eq!(state, Gameplay::PlayerTurn);
+            state = rest(&mut game);
+            turns += 1;
+            assert!(turns < 1000, "resting never stopped");
+        }
+        assert_eq!(state, Gameplay::AwaitingInput);
+        assert!(is_fully_healed(&game.world));
+    }
+
+    #[test]
+    fn resting_is_refused_with_a_monster_in_view() {
+        let mut world = wounded_player_world();
+        spawn_monster(&mut world, (7, 5), 5, 1);
+        pre_run_systems::execute(&mut world);
+        let mut game = test_game(world);
+
+        assert_eq!(start_resting(&mut game), Gameplay::AwaitingInput);
+        assert!(!game.resting);
+        assert_eq!(player_hp(&game.world), 1);
+    }
+
+    #[test]
+    fn bumping_into_any_tile_of_a_large_monster_attacks_it() {
+        let mut world = test_world(12, 12, (5, 5));
+        let troll = spawn_monster(&mut world, (6, 4), 20, 1);
+        world
+            .write_storage()
+            .insert(troll, Size { w: 2, h: 2 })
+            .unwrap();
+        pre_run_systems::execute(&mut world);
+        {
+            let map = world.fetch::<Map>();
+            let blocked = (3..=8)
+                .flat_map(|y| (5..=9).map(move |x| (x, y)))
+                .filter(|(x, y)| map.is_tile_status_set(map.xy_idx(*x, *y), TileStatus::Blocked))
+                .collect::<Vec<_>>();
+            assert_eq!(blocked, vec![(6, 4), (7, 4), (6, 5), (7, 5)]);
+        }
+
+        let player_ent = *world.fetch::<Entity>();
+        for (delta_x, delta_y) in [(1, -1), (1, 0)] {
+            try_move(delta_x, delta_y, &mut world);
+            let target = world
+                .write_storage::<WantsToMelee>()
+                .remove(player_ent)
+                .map(|attack| attack.target);
+            assert_eq!(target, Some(troll));
+            let pos = world
+                .read_storage::<Position>()
+                .get(player_ent)
+                .map(|pos| (pos.x, pos.y));
+            assert_eq!(pos, Some((5, 5)));
+        }
+    }
+
+    #[test]
+    fn aiming_needs_a_ranged_weapon_and_an_arrow() {
+        let mut world = test_world(10, 10, (5, 5));
+        let player_ent = *world.fetch::<Entity>();
+        world
+            .write_storage()
+            .insert(player_ent, Ammo { count: 1 })
+            .unwrap();
+        assert_eq!(fire(&world), Gameplay::AwaitingInput);
+
+        let bow = world
+            .create_entity()
+            .with(RangedWeapon {
+                range: 6,
+                damage: 3,
+                crit_bonus: 0,
+            })
+            .with(Equipped {
+                owner: player_ent,
+                slot: EquipmentSlot::Ranged,
+            })
+            .build();
+        assert_eq!(fire(&world), Gameplay::ShowTargeting(6, bow, Aim::Fire));
+
+        world
+            .write_storage::<Ammo>()
+            .get_mut(player_ent)
+            .unwrap()
+            .count = 0;
+        assert_eq!(fire(&world), Gameplay::AwaitingInput);
+        let logs = world.fetch::<GameLog>();
+        let (message, _) = logs.recent(1).next().unwrap();
+        assert_eq!(message, "You are out of arrows.");
+    }
+
+    #[test]
+    fn numpad_moves_in_all_eight_directions() {
+        let keys = [
+            VirtualKeyCode::Numpad7,
+            VirtualKeyCode::Numpad8,
+            VirtualKeyCode::Numpad9,
+            VirtualKeyCode::Numpad4,
+            VirtualKeyCode::Numpad6,
+            VirtualKeyCode::Numpad1,
+            VirtualKeyCode::Numpad2,
+            VirtualKeyCode::Numpad3,
+        ];
+        let reached = keys
+            .iter()
+            .map(|key| {
+                let mut world = test_world(12, 12, (5, 5));
+                let (delta_x, delta_y) = numpad_direction(*key).unwrap();
+                try_move(delta_x, delta_y, &mut world);
+                let positions = world.read_storage::<Position>();
+                let pos = positions.get(*world.fetch::<Entity>()).unwrap();
+                (pos.x, pos.y)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            reached,
+            vec![
+                (4, 4),
+                (5, 4),
+                (6, 4),
+                (4, 5),
+                (6, 5),
+                (4, 6),
+                (5, 6),
+                (6, 6)
+            ]
+        );
+        assert_eq!(numpad_direction(VirtualKeyCode::Numpad5), None);
+        assert_eq!(numpad_direction(VirtualKeyCode::Key7), None);
+    }
+
+    #[test]
+    fn up_left_defaults_to_the_vi_key() {
+        let shipped = crate::raws::config::load().ok().unwrap();
+        for keys in [shipped.keys, crate::raws::config::Config::default().keys] {
+            assert_eq!(keys.move_up_left, VirtualKeyCode::Y);
+        }
+    }
+
+    #[test]
+    fn bumping_a_closed_door_opens_it_without_moving() {
+        let mut world = test_world(12, 12, (5, 5));
+        {
+            let mut map = world.fetch_mut::<Map>();
+            let door = map.xy_idx(6, 5);
+            map.tiles[door] = TileType::DoorClosed;
+            map.populate_blocked();
+        }
+        let player_ent = *world.fetch::<Entity>();
+        let player_x = |world: &World| {
+            let positions = world.read_storage::<Position>();
+            positions.get(player_ent).map(|pos| pos.x)
+        };
+
+        pre_run_systems::execute(&mut world);
+        try_move(1, 0, &mut world);
+        assert_eq!(player_x(&world), Some(5));
+        {
+            let map = world.fetch::<Map>();
+            let door = map.xy_idx(6, 5);
+            assert!(map.tiles[door] == TileType::DoorOpen);
+            assert!(!map.is_tile_status_set(door, TileStatus::Blocked));
+        }
+        assert!(
+            world
+                .read_storage::<FieldOfView>()
+                .get(player_ent)
+                .unwrap()
+                .is_dirty
+        );
+
+        try_move(1, 0, &mut world);
+        assert_eq!(player_x(&world), Some(6));
+    }
+
+    #[test]
+    fn auto_explore_heads_for_unrevealed_tiles_and_stops_when_done() {
+        let mut map = room_map(12, 12, 1);
+        for y in 0..12 {
+            for x in 0..8 {
+                let idx = map.xy_idx(x, y);
+                map.set_tile_status(idx, TileStatus::Revealed);
+            }
+        }
+        assert_eq!(
+            next_explore_step(&map, Point::new(3, 5)).map(|(dx, _)| dx),
+            Some(1)
+        );
+
+        for idx in 0..map.tiles.len() {
+            map.set_tile_status(idx, TileStatus::Revealed);
+        }
+        assert_eq!(next_explore_step(&map, Point::new(3, 5)), None);
+    }
+
+    #[test]
+    fn auto_explore_stops_for_a_monster_in_view() {
+        let mut world = test_world(12, 12, (3, 5));
+        spawn_monster(&mut world, (6, 5), 5, 1);
+        pre_run_systems::execute(&mut world);
+        let mut game = test_game(world);
+        game.auto_exploring = true;
+
+        assert_eq!(auto_explore(&mut game), Gameplay::AwaitingInput);
+        assert!(!game.auto_exploring);
+        let player_ent = *game.world.fetch::<Entity>();
+        let positions = game.world.read_storage::<Position>();
+        let pos = positions.get(player_ent).unwrap();
+        assert_eq!((pos.x, pos.y), (3, 5));
+    }
+}
@@ -1,5 +1,5 @@
-use crate::gui::settings::visual;
-use rltk::VirtualKeyCode;
+use crate::{gui::settings::visual, save_load_util::SaveFormat};
+use rltk::{VirtualKeyCode, RGBA};
 use serde::Deserialize;
 use serde::Serialize;
 
@@ -364,6 +364,8 @@ pub struct KeyBinds {
     pub move_down_right: VirtualKeyCode,
     #[serde(with = "VirtualKeyCodeDef")]
     pub descend: VirtualKeyCode,
+    #[serde(with = "VirtualKeyCodeDef")]
+    pub ascend: VirtualKeyCode,
 
     //Item Related keys
     #[serde(with = "VirtualKeyCodeDef")]
@@ -381,7 +383,37 @@ pub struct KeyBinds {
     #[serde(with = "VirtualKeyCodeDef")]
     pub wait_turn: VirtualKeyCode,
     #[serde(with = "VirtualKeyCodeDef")]
+    pub auto_explore: VirtualKeyCode,
+    #[serde(with = "VirtualKeyCodeDef")]
+    pub fire: VirtualKeyCode,
+    #[serde(with = "VirtualKeyCodeDef")]
+    pub examine: VirtualKeyCode,
+    #[serde(with = "VirtualKeyCodeDef")]
     pub select: VirtualKeyCode,
+    #[serde(with = "VirtualKeyCodeDef")]
+    pub export_log: VirtualKeyCode,
+    #[serde(with = "VirtualKeyCodeDef")]
+    pub export_map: VirtualKeyCode,
+    #[serde(with = "VirtualKeyCodeDef")]
+    pub quaff: VirtualKeyCode,
+    #[serde(with = "VirtualKeyCodeDef")]
+    pub read: VirtualKeyCode,
+    #[serde(with = "VirtualKeyCodeDef")]
+    pub rest: VirtualKeyCode,
+    #[serde(with = "VirtualKeyCodeDef")]
+    pub sneak: VirtualKeyCode,
+    #[serde(with = "VirtualKeyCodeDef")]
+    pub throw: VirtualKeyCode,
+    #[serde(with = "VirtualKeyCodeDef")]
+    pub toggle_minimap: VirtualKeyCode,
+    #[serde(with = "VirtualKeyCodeDef")]
+    pub help: VirtualKeyCode,
+    #[serde(with = "VirtualKeyCodeDef")]
+    pub toggle_debug: VirtualKeyCode,
+    #[serde(with = "VirtualKeyCodeDef")]
+    pub wait_turn_alt: VirtualKeyCode,
+    #[serde(with = "VirtualKeyCodeDef")]
+    pub cycle_target: VirtualKeyCode,
 }
 impl Default for KeyBinds {
     fn default() -> Self {
@@ -391,11 +423,12 @@ impl Default for KeyBinds {
             move_down: VirtualKeyCode::J,
             move_left: VirtualKeyCode::H,
             move_right: VirtualKeyCode::L,
-            move_up_left: VirtualKeyCode::Z,
+            move_up_left: VirtualKeyCode::Y,
             move_up_right: VirtualKeyCode::U,
             move_down_left: VirtualKeyCode::B,
             move_down_right: VirtualKeyCode::N,
             descend: VirtualKeyCode::Period,
+            ascend: VirtualKeyCode::Comma,
 
             //Item related
             grab_item: VirtualKeyCode::G,
@@ -406,18 +439,71 @@ impl Default for KeyBinds {
             //Other
             go_back: VirtualKeyCode::Escape,
             wait_turn: VirtualKeyCode::Space,
+            auto_explore: VirtualKeyCode::X,
+            fire: VirtualKeyCode::F,
+            examine: VirtualKeyCode::V,
             select: VirtualKeyCode::Return,
+            export_log: VirtualKeyCode::P,
+            export_map: VirtualKeyCode::M,
+            quaff: VirtualKeyCode::Q,
+            read: VirtualKeyCode::S,
+            rest: VirtualKeyCode::Z,
+            sneak: VirtualKeyCode::C,
+            throw: VirtualKeyCode::T,
+            toggle_minimap: VirtualKeyCode::Tab,
+            help: VirtualKeyCode::F1,
+            toggle_debug: VirtualKeyCode::F3,
+            wait_turn_alt: VirtualKeyCode::Numpad5,
+            cycle_target: VirtualKeyCode::Tab,
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Default, Clone)]
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct VisualConfigs {
     pub full_screen: bool,
     pub screen_shake: bool,
     pub dynamic_color: bool,
     pub active_font: visual::Font,
     pub color_mapping: ColorMapping,
+    #[serde(default)]
+    pub color_theme: visual::ColorTheme,
+    ///Whether tiles are drawn with background colors, such as bloodstains
+    #[serde(default = "background_enabled_default")]
+    pub background_enabled: bool,
+    #[serde(default)]
+    pub colorblind_mode: visual::ColorblindMode,
+    ///Draws the map zoomed in, showing fewer but larger tiles
+    #[serde(default)]
+    pub large_tiles: bool,
+}
+
+impl VisualConfigs {
+    ///Adjusts a foreground color to the chosen theme and colorblind mode
+    pub fn recolor(&self, color: RGBA) -> RGBA {
+        visual::remap_color(self.color_theme.recolor(color), self.colorblind_mode)
+    }
+}
+
+impl Default for VisualConfigs {
+    fn default() -> Self {
+        Self {
+            full_screen: false,
+            screen_shake: false,
+            dynamic_color: false,
+            active_font: visual::Font::default(),
+            color_mapping: ColorMapping::default(),
+            color_theme: visual::ColorTheme::default(),
+            background_enabled: true,
+            colorblind_mode: visual::ColorblindMode::default(),
+            large_tiles: false,
+        }
+    }
+}
+
+const fn background_enabled_default() -> bool {
+    true
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -452,6 +538,24 @@ pub struct AudioConfigs {
     pub sfx_volume: usize,
 }
 
+///How often the run is saved in the background, in player turns, zero turns autosaving off. And
+///whether saves are written as text or binary
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SaveConfigs {
+    pub autosave_turns: u32,
+    #[serde(default)]
+    pub format: SaveFormat,
+}
+
+impl Default for SaveConfigs {
+    fn default() -> Self {
+        Self {
+            autosave_turns: 50,
+            format: SaveFormat::default(),
+        }
+    }
+}
+
 impl Default for AudioConfigs {
     fn default() -> Self {
         Self {
@@ -1,5 +1,6 @@
 mod config_structs;
-use config_structs::{AudioConfigs, KeyBinds, VisualConfigs};
+pub use config_structs::VisualConfigs;
+use config_structs::{AudioConfigs, KeyBinds, SaveConfigs};
 
 use serde::{Deserialize, Serialize};
 
@@ -8,6 +9,8 @@ pub struct Config {
     pub keys: KeyBinds,
     pub visual: VisualConfigs,
     pub audio: AudioConfigs,
+    #[serde(default)]
+    pub save: SaveConfigs,
 }
 
 impl Config {
@@ -16,6 +19,7 @@ impl Config {
     }
 }
 
+#[allow(clippy::result_large_err)]
 pub fn load() -> Result<Config, Config> {
     let config = include_bytes!("../../../prefabs/config.ron");
 
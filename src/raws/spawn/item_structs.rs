@@ -4,10 +4,19 @@ use std::collections::HashMap;
 #[derive(Deserialize, Debug)]
 pub struct Item {
     pub name: String,
+    #[serde(default)]
+    pub weight: i32,
     pub render: RawRender,
     pub consumable: Option<RawConsumable>,
     pub weapon: Option<RawWeapon>,
+    pub ranged_weapon: Option<RawRangedWeapon>,
     pub shield: Option<RawShield>,
+    pub light: Option<RawLight>,
+    pub unidentified: Option<String>,
+    #[serde(default)]
+    pub cursed: bool,
+    pub charges: Option<i32>,
+    pub ammo: Option<i32>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -27,7 +36,29 @@ pub struct RawShield {
     pub defense_bonus: i32,
 }
 
+#[derive(Deserialize, Debug)]
+pub struct RawLight {
+    pub radius: i32,
+    pub color: (u8, u8, u8),
+}
+
 #[derive(Deserialize, Debug)]
 pub struct RawWeapon {
     pub damage_bonus: i32,
+    pub attacks_per_turn: Option<i32>,
+    pub crit_bonus: Option<i32>,
+    pub poison: Option<RawPoison>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RawPoison {
+    pub damage_per_turn: i32,
+    pub turns: i32,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RawRangedWeapon {
+    pub range: i32,
+    pub damage: i32,
+    pub crit_bonus: Option<i32>,
 }
@@ -1,4 +1,5 @@
-use super::item_structs::RawRender;
+use super::item_structs::{RawPoison, RawRender};
+use crate::components::{Faction, Size};
 use serde::Deserialize;
 
 #[derive(Deserialize, Debug)]
@@ -6,8 +7,18 @@ pub struct Mob {
     pub name: String,
     pub blocks_tile: bool,
     pub vision_range: i32,
+    pub aggro_range: Option<i32>,
     pub render: RawRender,
     pub stats: RawStats,
+    pub ranged: Option<RawRanged>,
+    pub poison: Option<RawPoison>,
+    pub speed: Option<i32>,
+    pub drop_chance: Option<i32>,
+    pub flee_threshold: Option<f32>,
+    pub faction: Option<Faction>,
+    pub size: Option<Size>,
+    #[serde(default)]
+    pub flammable: bool,
 }
 
 #[derive(Deserialize, Debug)]
@@ -15,4 +26,11 @@ pub struct RawStats {
     pub max_hp: i32,
     pub defense: i32,
     pub power: i32,
+    pub crit_chance: Option<i32>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RawRanged {
+    pub range: i32,
+    pub damage: i32,
 }
@@ -2,8 +2,9 @@ mod item_structs;
 mod mob_structs;
 mod spawn_master;
 mod spawn_table_structs;
+mod trap_structs;
 
-use std::sync::Mutex;
+use std::{error::Error, path::Path, sync::Mutex};
 
 pub use spawn_master::{SpawnMaster, SpawnType};
 
@@ -15,13 +16,42 @@ macro_rules! raw_spawns_path {
     () => ("../../../prefabs/spawns.ron")
 }
 
+//Spawn raws next to the game, which take precedence over the ones built into it
+const SPAWNS_PATH: &str = "./prefabs/spawns.ron";
+
 lazy_static::lazy_static! {
     pub static ref SPAWN_RAWS: Mutex<SpawnMaster> = Mutex::new(SpawnMaster::empty());
 }
 
 rltk::embedded_resource!(RAW_SPAWNS, raw_spawns_path!());
 
-pub fn load() {
+///Loads the spawn raws from the file next to the game, so monsters, items and the spawn table can
+///be changed without rebuilding it. Falls back to the raws built into the game when there is no such
+///file, or when it can not be read. The raws are loaded either way, the error is only returned so
+///the player can be told their file was not used
+pub fn load() -> Result<(), String> {
+    let (spawn_master, result) = match load_spawn_table(SPAWNS_PATH) {
+        Ok(spawn_master) => (spawn_master, Ok(())),
+        Err(_) if !Path::new(SPAWNS_PATH).exists() => (load_embedded(), Ok(())),
+        Err(err) => (
+            load_embedded(),
+            Err(format!("Unable to load {SPAWNS_PATH}: {err}")),
+        ),
+    };
+    *SPAWN_RAWS.lock().unwrap() = spawn_master;
+    result
+}
+
+///Reads the monsters, items, traps and spawn table from a RON file
+pub fn load_spawn_table(path: impl AsRef<Path>) -> Result<SpawnMaster, Box<dyn Error>> {
+    let data = std::fs::read(path)?;
+    let decoder: spawn_master::RawData = ron::de::from_bytes(&data)?;
+    let mut spawn_master = SpawnMaster::empty();
+    spawn_master.load(decoder);
+    Ok(spawn_master)
+}
+
+fn load_embedded() -> SpawnMaster {
     rltk::link_resource!(RAW_SPAWNS, raw_spawns_path!());
     let spawn_raw = rltk::embedding::EMBED
         .lock()
@@ -29,5 +59,72 @@ pub fn load() {
         .unwrap();
     let decoder: spawn_master::RawData =
         ron::de::from_bytes(spawn_raw).expect("Unable to parse RON");
-    SPAWN_RAWS.lock().unwrap().load(decoder);
+    let mut spawn_master = SpawnMaster::empty();
+    spawn_master.load(decoder);
+    spawn_master
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{components::*, test_support::test_world};
+    use specs::prelude::*;
+
+    const SMALL_TABLE: &str = r#"#![enable(implicit_some)]
+(
+    spawn_table: [
+        (name: "Test Rat", weight: 1, min_depth: 1, max_depth: 100, scales_to_depth: false,),
+    ],
+    mobs: [
+        (
+            name: "Test Rat",
+            blocks_tile: true,
+            flammable: true,
+            vision_range: 5,
+            render: (glyph: 114, color: (120, 80, 40), order: 1,),
+            stats: (max_hp: 4, defense: 1, power: 2,),
+        ),
+    ],
+    items: [],
+)"#;
+
+    #[test]
+    fn small_table_spawns_a_mob_with_its_components() {
+        let path =
+            std::env::temp_dir().join(format!("roguelike-spawns-{}.ron", std::process::id()));
+        std::fs::write(&path, SMALL_TABLE).unwrap();
+        let spawn_master = load_spawn_table(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(spawn_master.monster_names(), vec!["Test Rat".to_string()]);
+
+        let mut world = test_world(10, 10, (1, 1));
+        let rat = spawn_master
+            .spawn_named_entity(
+                world.create_entity(),
+                "Test Rat",
+                SpawnType::AtPosition(4, 5),
+                1,
+            )
+            .unwrap();
+
+        let positions = world.read_storage::<Position>();
+        let pos = positions.get(rat).unwrap();
+        assert_eq!((pos.x, pos.y), (4, 5));
+        let stats = world.read_storage::<CombatStats>();
+        let stats = stats.get(rat).unwrap();
+        assert_eq!((stats.max_hp, stats.defense, stats.power), (4, 1, 2));
+        assert_eq!(
+            world.read_storage::<Name>().get(rat).unwrap().name,
+            "Test Rat"
+        );
+        assert!(world.read_storage::<Monster>().contains(rat));
+        assert!(world.read_storage::<BlocksTile>().contains(rat));
+        assert!(world.read_storage::<Flammable>().contains(rat));
+        assert!(!world.read_storage::<Item>().contains(rat));
+    }
+
+    #[test]
+    fn unreadable_table_is_an_error() {
+        assert!(load_spawn_table("./prefabs/missing_spawns.ron").is_err());
+    }
 }
@@ -1,6 +1,13 @@
 use super::item_structs::RawRender;
-use crate::{components::*, constants::colors, spawning::RandomTable};
-use rltk::ColorPair;
+use crate::{
+    components::*,
+    constants::colors,
+    difficulty::Difficulty,
+    ecs::{ACTION_COST, BASE_CRIT_CHANCE},
+    identification::Identification,
+    spawning::{difficulty_multiplier, RandomTable},
+};
+use rltk::{ColorPair, RandomNumberGenerator, RGB};
 use serde::Deserialize;
 use specs::{
     saveload::{MarkedBuilder, SimpleMarker},
@@ -11,12 +18,19 @@ use std::collections::HashMap;
 #[derive(Copy, Clone)]
 pub enum SpawnType {
     AtPosition(i32, i32),
+    ///Worn by the given creature in the slot, rather than lying on the map
+    Equipped {
+        by: Entity,
+        slot: EquipmentSlot,
+    },
 }
 
 #[derive(Deserialize, Debug)]
 pub struct RawData {
     pub mobs: Vec<super::mob_structs::Mob>,
     pub items: Vec<super::item_structs::Item>,
+    #[serde(default)]
+    pub traps: Vec<super::trap_structs::Trap>,
     pub spawn_table: Vec<super::spawn_table_structs::Entry>,
 }
 
@@ -25,6 +39,7 @@ impl RawData {
         Self {
             mobs: Vec::new(),
             items: Vec::new(),
+            traps: Vec::new(),
             spawn_table: Vec::new(),
         }
     }
@@ -34,6 +49,7 @@ pub struct SpawnMaster {
     raw_data: RawData,
     mob_index: HashMap<String, usize>,
     item_index: HashMap<String, usize>,
+    trap_index: HashMap<String, usize>,
 }
 
 impl SpawnMaster {
@@ -42,6 +58,7 @@ impl SpawnMaster {
             raw_data: RawData::new(),
             mob_index: HashMap::new(),
             item_index: HashMap::new(),
+            trap_index: HashMap::new(),
         }
     }
 
@@ -55,25 +72,72 @@ impl SpawnMaster {
         for (i, item) in self.raw_data.items.iter().enumerate() {
             self.item_index.insert(item.name.clone(), i);
         }
+
+        for (i, trap) in self.raw_data.traps.iter().enumerate() {
+            self.trap_index.insert(trap.name.clone(), i);
+        }
     }
 
     pub fn spawn_table(&self, depth: i32) -> RandomTable {
-        let possibilities = self
-            .raw_data
+        self.weighted_table(depth, |_| true)
+    }
+
+    ///The items that can spawn at this depth, which monsters may drop
+    pub fn loot_table(&self, depth: i32) -> RandomTable {
+        self.weighted_table(depth, |name| self.item_index.contains_key(name))
+    }
+
+    ///Entries that scale to depth get more common the deeper the player goes
+    fn weighted_table(&self, depth: i32, include: impl Fn(&str) -> bool) -> RandomTable {
+        self.raw_data
             .spawn_table
             .iter()
             .filter(|entry| entry.min_depth <= depth && entry.max_depth > depth)
-            .collect::<Vec<_>>();
-        let mut table = RandomTable::new();
-        for entry in possibilities {
-            let weight = if entry.scales_to_depth {
-                entry.weight + depth
-            } else {
-                entry.weight
-            };
-            table.insert(&entry.name.clone(), weight);
-        }
-        table
+            .filter(|entry| include(&entry.name))
+            .fold(RandomTable::new(), |table, entry| {
+                let weight = if entry.scales_to_depth {
+                    entry.weight + depth
+                } else {
+                    entry.weight
+                };
+                table.add(&entry.name, weight)
+            })
+    }
+
+    ///Names of the monsters that can be met in the dungeon, leaving out those that are only summoned
+    pub fn monster_names(&self) -> Vec<String> {
+        self.raw_data
+            .mobs
+            .iter()
+            .filter(|mob| {
+                self.raw_data
+                    .spawn_table
+                    .iter()
+                    .any(|entry| entry.name == mob.name)
+            })
+            .map(|mob| mob.name.clone())
+            .collect()
+    }
+
+    pub fn trap_names(&self) -> Vec<String> {
+        self.raw_data
+            .traps
+            .iter()
+            .map(|trap| trap.name.clone())
+            .collect()
+    }
+
+    pub fn is_mob(&self, name: &str) -> bool {
+        self.mob_index.contains_key(name)
+    }
+
+    ///The smallest and largest pack the named entity spawns in, if it spawns in packs
+    pub fn pack_size(&self, name: &str) -> Option<(i32, i32)> {
+        self.raw_data
+            .spawn_table
+            .iter()
+            .find(|entry| entry.name == name)
+            .and_then(|entry| entry.pack_size)
     }
 
     pub fn spawn_named_entity(
@@ -81,11 +145,14 @@ impl SpawnMaster {
         new_entity: EntityBuilder<'_>,
         key: &str,
         pos: SpawnType,
+        depth: i32,
     ) -> Option<Entity> {
         if self.item_index.contains_key(key) {
             Some(self.spawn_named_item(new_entity, self.item_index[key], pos))
         } else if self.mob_index.contains_key(key) {
-            Some(self.spawn_named_mob(new_entity, self.mob_index[key], pos))
+            Some(self.spawn_named_mob(new_entity, self.mob_index[key], pos, depth))
+        } else if self.trap_index.contains_key(key) {
+            Some(self.spawn_named_trap(new_entity, self.trap_index[key], pos, depth))
         } else {
             None
         }
@@ -105,41 +172,80 @@ impl SpawnMaster {
             .with(Name {
                 name: item_template.name.clone(),
             })
+            .with(Weight {
+                pounds: item_template.weight,
+            })
             .marked::<SimpleMarker<SerializeMe>>();
         new_entity = Self::assign_render(new_entity, &item_template.render);
         new_entity = Self::assign_position(new_entity, &pos);
 
+        //Items of an unidentified kind hide behind an appearance until one of them is used
+        if let Some(kind) = &item_template.unidentified {
+            //The kind also tells whether the item is quaffed or read
+            match kind.as_str() {
+                "potion" => new_entity = new_entity.with(Potion {}),
+                "scroll" => new_entity = new_entity.with(Scroll {}),
+                _ => {}
+            }
+            let world = new_entity.world;
+            let mut identification = world.fetch_mut::<Identification>();
+            if !identification.is_identified(&item_template.name) {
+                let mut rng = world.fetch_mut::<RandomNumberGenerator>();
+                new_entity = new_entity.with(Unidentified {
+                    appearance: identification.appearance_of(&item_template.name, kind, &mut rng),
+                });
+            }
+        }
+
+        if item_template.cursed {
+            new_entity = new_entity.with(Cursed {});
+        }
+
         //Assign optional components
         if let Some(consumable) = &item_template.consumable {
-            new_entity = new_entity.with(Consumable {});
-            //Assign effect components
-            for effect in &consumable.effects {
-                new_entity = match effect.0.as_str() {
-                    "provides_healing" => new_entity.with(ProvidesHealing {
-                        heal_amount: effect.1.parse().unwrap(),
-                    }),
-                    "range" => new_entity.with(Range {
-                        range: effect.1.parse().unwrap(),
-                    }),
-                    "damage" => new_entity.with(InflictsDamage {
-                        damage: effect.1.parse().unwrap(),
-                    }),
-                    "area_of_effect" => new_entity.with(AreaOfEffect {
-                        radius: effect.1.parse().unwrap(),
-                    }),
-                    name => panic!("Consumable effect \"{}\" not implemented", name),
-                }
-            }
+            //Items with charges keep their effects for as many uses instead of being used up
+            new_entity = match item_template.charges {
+                Some(charges) => new_entity.with(Charges {
+                    current: charges,
+                    max: charges,
+                }),
+                None => new_entity.with(Consumable {}),
+            };
+            new_entity = Self::assign_effects(new_entity, &consumable.effects);
         }
 
         if let Some(weapon) = &item_template.weapon {
             new_entity = new_entity
                 .with(MeleeDamageBonus {
                     bonus: weapon.damage_bonus,
+                    attacks_per_turn: weapon.attacks_per_turn.unwrap_or(1),
+                    crit_bonus: weapon.crit_bonus.unwrap_or(0),
                 })
                 .with(Equipment {
                     slot: EquipmentSlot::PrimaryHand,
                 });
+            if let Some(poison) = &weapon.poison {
+                new_entity = new_entity.with(InflictsPoison {
+                    damage_per_turn: poison.damage_per_turn,
+                    turns: poison.turns,
+                });
+            }
+        }
+
+        if let Some(count) = item_template.ammo {
+            new_entity = new_entity.with(Ammo { count });
+        }
+
+        if let Some(ranged_weapon) = &item_template.ranged_weapon {
+            new_entity = new_entity
+                .with(RangedWeapon {
+                    range: ranged_weapon.range,
+                    damage: ranged_weapon.damage,
+                    crit_bonus: ranged_weapon.crit_bonus.unwrap_or(0),
+                })
+                .with(Equipment {
+                    slot: EquipmentSlot::Ranged,
+                });
         }
 
         if let Some(shield) = &item_template.shield {
@@ -152,6 +258,17 @@ impl SpawnMaster {
                 })
         }
 
+        if let Some(light) = &item_template.light {
+            new_entity = new_entity
+                .with(LightSource {
+                    radius: light.radius,
+                    color: RGB::named(light.color),
+                })
+                .with(Equipment {
+                    slot: EquipmentSlot::OffHand,
+                });
+        }
+
         new_entity.build()
     }
 
@@ -160,25 +277,42 @@ impl SpawnMaster {
         mut new_entity: EntityBuilder<'_>,
         index: usize,
         pos: SpawnType,
+        depth: i32,
     ) -> Entity {
         let mob_template = &self.raw_data.mobs[index];
+        let multiplier = difficulty_multiplier(depth)
+            * new_entity.world.fetch::<Difficulty>().monster_multiplier();
+        let scale = |stat: i32| (stat as f32 * multiplier).round() as i32;
 
         //Assign required components
         new_entity = new_entity
             .with(Monster {})
+            .with(mob_template.faction.unwrap_or(Faction::Monster))
             .with(Name {
                 name: mob_template.name.clone(),
             })
             .with(CombatStats {
-                max_hp: mob_template.stats.max_hp,
-                hp: mob_template.stats.max_hp,
+                max_hp: scale(mob_template.stats.max_hp),
+                hp: scale(mob_template.stats.max_hp),
                 defense: mob_template.stats.defense,
-                power: mob_template.stats.power,
+                power: scale(mob_template.stats.power),
+                crit_chance: mob_template.stats.crit_chance.unwrap_or(BASE_CRIT_CHANCE),
             })
             .with(FieldOfView {
                 visible_tiles: vec![],
                 range: mob_template.vision_range,
                 is_dirty: true,
+                lights: Vec::new(),
+            })
+            .with(Initiative {
+                current: ACTION_COST,
+            })
+            .with(Aggro {
+                range: mob_template
+                    .aggro_range
+                    .unwrap_or(mob_template.vision_range),
+                last_known_position: None,
+                pursuit_turns: 0,
             })
             .marked::<SimpleMarker<SerializeMe>>();
         new_entity = Self::assign_render(new_entity, &mob_template.render);
@@ -187,13 +321,127 @@ impl SpawnMaster {
             new_entity = new_entity.with(BlocksTile {})
         }
 
+        if mob_template.flammable {
+            new_entity = new_entity.with(Flammable {});
+        }
+
+        if let Some(size) = mob_template.size {
+            new_entity = new_entity.with(size);
+        }
+
+        if let Some(speed) = mob_template.speed {
+            new_entity = new_entity.with(Speed { speed });
+        }
+
+        if let Some(chance) = mob_template.drop_chance {
+            new_entity = new_entity.with(DropsLoot { chance });
+        }
+
+        if let Some(fraction) = mob_template.flee_threshold {
+            new_entity = new_entity.with(FleeThreshold { fraction });
+        }
+
+        if let Some(poison) = &mob_template.poison {
+            new_entity = new_entity.with(InflictsPoison {
+                damage_per_turn: scale(poison.damage_per_turn),
+                turns: poison.turns,
+            });
+        }
+
+        if let Some(ranged) = &mob_template.ranged {
+            new_entity = new_entity
+                .with(Range {
+                    range: ranged.range,
+                })
+                .with(InflictsDamage {
+                    damage: scale(ranged.damage),
+                });
+        }
+
         new_entity.build()
     }
 
+    fn assign_effects<'a>(
+        mut new_entity: EntityBuilder<'a>,
+        effects: &HashMap<String, String>,
+    ) -> EntityBuilder<'a> {
+        for effect in effects {
+            new_entity = match effect.0.as_str() {
+                "provides_healing" => new_entity.with(ProvidesHealing {
+                    heal_amount: effect.1.parse().unwrap(),
+                }),
+                "range" => new_entity.with(Range {
+                    range: effect.1.parse().unwrap(),
+                }),
+                "damage" => new_entity.with(InflictsDamage {
+                    damage: effect.1.parse().unwrap(),
+                }),
+                "area_of_effect" => new_entity.with(AreaOfEffect {
+                    radius: effect.1.parse().unwrap(),
+                }),
+                "food" => new_entity.with(ProvidesNutrition {}),
+                "magic_mapping" => new_entity.with(ProvidesMapReveal {}),
+                "teleport" => new_entity.with(ProvidesTeleport {}),
+                "remove_curse" => new_entity.with(ProvidesRemoveCurse {}),
+                "summon" => new_entity.with(SummonsAlly {
+                    name: effect.1.clone(),
+                }),
+                "confusion" => new_entity.with(Confusion {
+                    turns: effect.1.parse().unwrap(),
+                }),
+                "cure" => new_entity.with(ProvidesCure {}),
+                "burning" => new_entity.with(InflictsBurning {
+                    turns: effect.1.parse().unwrap(),
+                }),
+                name => panic!("Consumable effect \"{}\" not implemented", name),
+            }
+        }
+        new_entity
+    }
+
     fn assign_position<'a>(new_entity: EntityBuilder<'a>, pos: &SpawnType) -> EntityBuilder<'a> {
         match pos {
             SpawnType::AtPosition(x, y) => new_entity.with(Position { x: *x, y: *y }),
+            SpawnType::Equipped { by, slot } => new_entity.with(Equipped {
+                owner: *by,
+                slot: *slot,
+            }),
+        }
+    }
+
+    fn spawn_named_trap(
+        &self,
+        mut new_entity: EntityBuilder<'_>,
+        index: usize,
+        pos: SpawnType,
+        depth: i32,
+    ) -> Entity {
+        let trap_template = &self.raw_data.traps[index];
+
+        //Traps stay hidden until spotted or stepped on
+        new_entity = new_entity
+            .with(Name {
+                name: trap_template.name.clone(),
+            })
+            .with(Hidden {})
+            .with(EntryTrigger {})
+            .marked::<SimpleMarker<SerializeMe>>();
+        new_entity = Self::assign_render(new_entity, &trap_template.render);
+        new_entity = Self::assign_position(new_entity, &pos);
+
+        if let Some(damage) = trap_template.damage {
+            new_entity = new_entity.with(InflictsDamage {
+                damage: (damage as f32 * difficulty_multiplier(depth)).round() as i32,
+            });
+        }
+        if let Some(turns) = trap_template.confusion {
+            new_entity = new_entity.with(Confusion { turns });
+        }
+        if trap_template.single_activation {
+            new_entity = new_entity.with(SingleActivation {});
         }
+
+        new_entity.build()
     }
 
     fn assign_render<'a>(new_entity: EntityBuilder<'a>, render: &RawRender) -> EntityBuilder<'a> {
@@ -205,3 +453,16 @@ impl SpawnMaster {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::raws::spawn::load_embedded;
+
+    #[test]
+    fn monsters_are_drawn_above_items_and_items_above_traps() {
+        let raws = load_embedded().raw_data;
+        assert!(raws.mobs.iter().all(|mob| mob.render.order == 1));
+        assert!(raws.items.iter().all(|item| item.render.order == 2));
+        assert!(raws.traps.iter().all(|trap| trap.render.order == 3));
+    }
+}
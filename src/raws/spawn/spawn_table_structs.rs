@@ -7,4 +7,5 @@ pub struct Entry {
     pub min_depth: i32,
     pub max_depth: i32,
     pub scales_to_depth: bool,
+    pub pack_size: Option<(i32, i32)>,
 }
@@ -0,0 +1,12 @@
+use super::item_structs::RawRender;
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct Trap {
+    pub name: String,
+    pub render: RawRender,
+    pub damage: Option<i32>,
+    pub confusion: Option<i32>,
+    #[serde(default)]
+    pub single_activation: bool,
+}
@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+///What the player has achieved during the current run, shown once it ends
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct RunSummary {
+    pub turns: u32,
+    pub kills: u32,
+    pub deepest_level: i32,
+    pub items_collected: u32,
+    pub gold_collected: i32,
+    pub bosses_slain: u32,
+}
+
+impl Default for RunSummary {
+    //Every run starts on the first level
+    fn default() -> Self {
+        Self {
+            turns: 0,
+            kills: 0,
+            deepest_level: 1,
+            items_collected: 0,
+            gold_collected: 0,
+            bosses_slain: 0,
+        }
+    }
+}
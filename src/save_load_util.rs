@@ -1,26 +1,136 @@
-use crate::{components::*, map_builder::map::Map};
+use crate::{
+    components::*, difficulty::Difficulty, dungeon_master::DungeonMaster, game_seed::GameSeed,
+    identification::Identification, map_builder::map::Map, run_summary::RunSummary,
+    specs_helpers::register_all_components,
+};
+use serde::{de::IgnoredAny, ser::SerializeSeq, Deserialize, Deserializer, Serialize, Serializer};
 use specs::{
     error::NoError,
     prelude::*,
     saveload::{
-        DeserializeComponents, MarkedBuilder, SerializeComponents, SimpleMarker,
+        DeserializeComponents, EntityData, MarkedBuilder, SerializeComponents, SimpleMarker,
         SimpleMarkerAllocator,
     },
 };
-use std::{fs, path::Path};
+use std::{
+    fmt, fs,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+const SAVE_DIR: &str = "./saves";
+const SAVE_FILE: &str = "savegame.ron";
+const BINARY_SAVE_FILE: &str = "savegame.bin";
+//Written every few turns so a crash loses little progress, kept apart from the save made on quitting
+const AUTOSAVE_FILE: &str = "autosave.ron";
+const BINARY_AUTOSAVE_FILE: &str = "autosave.bin";
+const ALL_SAVES: [(&str, SaveFormat); 4] = [
+    (SAVE_FILE, SaveFormat::Text),
+    (BINARY_SAVE_FILE, SaveFormat::Binary),
+    (AUTOSAVE_FILE, SaveFormat::Text),
+    (BINARY_AUTOSAVE_FILE, SaveFormat::Binary),
+];
+
+//Increase whenever the saved components change in a way older saves can not be read
+const SAVE_VERSION: u32 = 39;
+
+///Written at the start of every save, ahead of the serialized components
+#[derive(Serialize, Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+///How saves are written: text can be read and edited by hand, binary is smaller and quicker to write
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum SaveFormat {
+    #[default]
+    Text,
+    Binary,
+}
+
+impl SaveFormat {
+    const fn save_file(self) -> &'static str {
+        match self {
+            Self::Text => SAVE_FILE,
+            Self::Binary => BINARY_SAVE_FILE,
+        }
+    }
+
+    const fn autosave_file(self) -> &'static str {
+        match self {
+            Self::Text => AUTOSAVE_FILE,
+            Self::Binary => BINARY_AUTOSAVE_FILE,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SaveLoadError {
+    Io(std::io::Error),
+    Serde(ron::Error),
+    Binary(bincode::Error),
+    MissingHelper,
+    Incompatible { found: Option<u32> },
+}
+
+impl From<std::io::Error> for SaveLoadError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<ron::Error> for SaveLoadError {
+    fn from(err: ron::Error) -> Self {
+        Self::Serde(err)
+    }
+}
 
-const SAVE_PATH: &str = "./saves/savegame.ron";
+impl From<bincode::Error> for SaveLoadError {
+    fn from(err: bincode::Error) -> Self {
+        Self::Binary(err)
+    }
+}
 
+impl fmt::Display for SaveLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "Save unreadable: {}", err.kind()),
+            Self::Serde(err) => write!(f, "Save corrupted: {err}"),
+            Self::Binary(err) => write!(f, "Save corrupted: {err}"),
+            Self::MissingHelper => write!(f, "Save is missing the map"),
+            Self::Incompatible {
+                found: Some(version),
+            } => {
+                write!(f, "Save incompatible (version {version})")
+            }
+            Self::Incompatible { found: None } => write!(f, "Save incompatible"),
+        }
+    }
+}
+
+//Each component is written as a list holding every marked entity. The length is given up front,
+//unlike in `SerializeComponents::serialize`, as binary formats can not write lists without one
 macro_rules! serialize_individually {
     ($ecs:expr, $ser:expr, $data:expr, $( $type:ty),* $(,)?) => {
+        let marked = (&$data.0, &$data.1).join().count();
+        let ids = |entity| $data.1.get(entity).cloned();
         $(
-        SerializeComponents::<NoError, SimpleMarker<SerializeMe>>::serialize(
-            &( $ecs.read_storage::<$type>(), ),
-            &$data.0,
-            &$data.1,
-            &mut $ser,
-        )
-        .unwrap();
+        {
+            let storage = ( $ecs.read_storage::<$type>(), );
+            let mut seq = (&mut $ser).serialize_seq(Some(marked))?;
+            for (entity, marker) in (&$data.0, &$data.1).join() {
+                seq.serialize_element(&EntityData {
+                    marker: *marker,
+                    components: SerializeComponents::<NoError, SimpleMarker<SerializeMe>>::serialize_entity(
+                        &storage,
+                        entity,
+                        ids,
+                    )
+                    .unwrap_or_else(|err| match err {}),
+                })?;
+            }
+            seq.end()?;
+        }
         )*
     };
 }
@@ -34,66 +144,265 @@ macro_rules! deserialize_individually {
             &mut $data.1, // marker
             &mut $data.2, // allocator
             &mut $de,
-        )
-        .unwrap();
+        )?;
         )*
     };
 }
 
-pub fn save_game(ecs: &mut World) {
-    let map_copy = ecs.get_mut::<Map>().unwrap().clone();
+pub fn save_game(ecs: &mut World, format: SaveFormat) -> Result<(), SaveLoadError> {
+    save_to(ecs, &Path::new(SAVE_DIR).join(format.save_file()), format)
+}
+
+///Saves the run in progress without leaving it
+pub fn autosave(ecs: &mut World, format: SaveFormat) -> Result<(), SaveLoadError> {
+    save_to(
+        ecs,
+        &Path::new(SAVE_DIR).join(format.autosave_file()),
+        format,
+    )
+}
+
+///Whether the given number of turns into a run calls for an autosave, every `interval` turns
+pub const fn is_autosave_due(turns: u32, interval: u32) -> bool {
+    interval > 0 && turns > 0 && turns.is_multiple_of(interval)
+}
+
+fn save_to(ecs: &mut World, path: &Path, format: SaveFormat) -> Result<(), SaveLoadError> {
+    let map_copy = (*ecs.fetch::<Map>()).clone();
+    let identification_copy = (*ecs.fetch::<Identification>()).clone();
+    let difficulty = *ecs.fetch::<Difficulty>();
+    let seed = *ecs.fetch::<GameSeed>();
+    let dungeon = (*ecs.fetch::<DungeonMaster>()).clone();
+    let summary = *ecs.fetch::<RunSummary>();
     let save_helper = ecs
         .create_entity()
-        .with(SerializationHelper { map: map_copy })
+        .with(SerializationHelper {
+            map: map_copy,
+            identification: identification_copy,
+            difficulty,
+            seed,
+            dungeon,
+            summary,
+        })
         .marked::<SimpleMarker<SerializeMe>>()
         .build();
-    {
-        let data = (
-            ecs.entities(),
-            ecs.read_storage::<SimpleMarker<SerializeMe>>(),
-        );
-        let writer = std::fs::File::create(SAVE_PATH).unwrap();
 
-        let mut serializer = ron::Serializer::new(writer, None, false).unwrap();
-        serialize_individually!(
-            ecs,
-            serializer,
-            data,
-            AreaOfEffect,
-            BlocksTile,
-            CombatStats,
-            Consumable,
-            DefenseBonus,
-            Equipment,
-            Equipped,
-            InBackpack,
-            InflictsDamage,
-            Item,
-            MeleeDamageBonus,
-            Monster,
-            Name,
-            ParticleLifetime,
-            Player,
-            Position,
-            ProvidesHealing,
-            Range,
-            Render,
-            SerializationHelper,
-            SufferDamage,
-            FieldOfView,
-            WantsToDropItem,
-            WantsToMelee,
-            WantsToPickupItem,
-            WantsToRemoveItem,
-            WantsToUseItem,
-        );
-    }
+    //The helper is removed again whether or not writing the save succeeded
+    let result = write_save(ecs, path, format);
 
     ecs.delete_entity(save_helper)
         .expect("Unable to delete save helper");
+
+    result
+}
+
+fn write_save(ecs: &World, path: &Path, format: SaveFormat) -> Result<(), SaveLoadError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let writer = fs::File::create(path)?;
+    match format {
+        SaveFormat::Text => write_components(ecs, &mut ron::Serializer::new(writer, None, false)?),
+        SaveFormat::Binary => {
+            //Binary saves are written a few bytes at a time, so they are buffered
+            let mut writer = BufWriter::new(writer);
+            write_components(
+                ecs,
+                &mut bincode::Serializer::new(&mut writer, bincode::options()),
+            )?;
+            writer.flush()?;
+            Ok(())
+        }
+    }
+}
+
+///Writes the header and the marked components, in the same order whatever the format
+fn write_components<S, E>(ecs: &World, serializer: &mut S) -> Result<(), SaveLoadError>
+where
+    for<'s> &'s mut S: Serializer<Error = E>,
+    SaveLoadError: From<E>,
+{
+    let data = (
+        ecs.entities(),
+        ecs.read_storage::<SimpleMarker<SerializeMe>>(),
+    );
+    SaveHeader {
+        version: SAVE_VERSION,
+    }
+    .serialize(&mut *serializer)?;
+    serialize_individually!(
+        ecs,
+        *serializer,
+        data,
+        AreaOfEffect,
+        BlocksTile,
+        CombatStats,
+        Confusion,
+        Consumable,
+        DefenseBonus,
+        DropsLoot,
+        EntityMoved,
+        EntryTrigger,
+        Equipment,
+        Equipped,
+        Experience,
+        Hidden,
+        HungerClock,
+        InBackpack,
+        InflictsDamage,
+        Initiative,
+        Item,
+        LightSource,
+        MeleeDamageBonus,
+        Monster,
+        Name,
+        Boss,
+        Size,
+        Stealth,
+        Aggro,
+        PathCache,
+        Charges,
+        Cursed,
+        ProvidesRemoveCurse,
+        Elite,
+        Sighted,
+        Faction,
+        Summoned,
+        SummonsAlly,
+        WantsToSummon,
+        FleeThreshold,
+        Potion,
+        Scroll,
+        Gold,
+        Wallet,
+        Ammo,
+        Weight,
+        OnFire,
+        InflictsBurning,
+        Flammable,
+        Poison,
+        InflictsPoison,
+        ProvidesCure,
+        OtherLevelPosition,
+        ParticleLifetime,
+        Player,
+        Position,
+        ProvidesHealing,
+        ProvidesMapReveal,
+        ProvidesNutrition,
+        ProvidesTeleport,
+        Range,
+        RangedWeapon,
+        Render,
+        SerializationHelper,
+        SingleActivation,
+        Speed,
+        FieldOfView,
+        Unidentified,
+        WantsToDropItem,
+        WantsToMelee,
+        WantsToPickupItem,
+        WantsToRemoveItem,
+        WantsToShoot,
+        WantsToTeleport,
+        WantsToThrow,
+        WantsToUseItem,
+    );
+
+    Ok(())
+}
+
+pub fn load_game(ecs: &mut World) -> Result<(), SaveLoadError> {
+    load_from(ecs, Path::new(SAVE_DIR))
+}
+
+///Loads the newest save kept in the given directory
+fn load_from(ecs: &mut World, dir: &Path) -> Result<(), SaveLoadError> {
+    let (path, format) =
+        newest_save(dir).unwrap_or_else(|| (dir.join(SAVE_FILE), SaveFormat::Text));
+    let data = fs::read(path)?;
+    if is_unversioned(&data, format) {
+        return Err(SaveLoadError::Incompatible { found: None });
+    }
+    //Read into a world of its own first, so a save that fails part way leaves the game running as
+    //it was
+    let mut scratch = World::new();
+    register_all_components(&mut scratch);
+    scratch.insert(SimpleMarkerAllocator::<SerializeMe>::new());
+    read_save(&mut scratch, &data, format)?;
+    if scratch.read_storage::<SerializationHelper>().is_empty() {
+        return Err(SaveLoadError::MissingHelper);
+    }
+    read_save(ecs, &data, format)?;
+
+    let mut delete_me = None;
+    {
+        let entities = ecs.entities();
+        let helper = ecs.read_storage::<SerializationHelper>();
+        let player = ecs.read_storage::<Player>();
+        let position = ecs.read_storage::<Position>();
+        for (e, h) in (&entities, &helper).join() {
+            let mut world_map = ecs.write_resource::<Map>();
+            *world_map = h.map.clone();
+            world_map.rebuild_derived();
+            *ecs.write_resource::<Identification>() = h.identification.clone();
+            *ecs.write_resource::<Difficulty>() = h.difficulty;
+            *ecs.write_resource::<GameSeed>() = h.seed;
+            *ecs.write_resource::<DungeonMaster>() = h.dungeon.clone();
+            *ecs.write_resource::<RunSummary>() = h.summary;
+            delete_me = Some(e);
+        }
+        for (e, _, pos) in (&entities, &player, &position).join() {
+            let mut player_pos = ecs.write_resource::<rltk::Point>();
+            let mut player_ent = ecs.write_resource::<Entity>();
+            *player_pos = rltk::Point::new(pos.x, pos.y);
+            *player_ent = e;
+            //Visible tiles are not saved, so the view is recomputed by the pre run systems
+            if let Some(fov) = ecs.write_storage::<FieldOfView>().get_mut(e) {
+                fov.is_dirty = true;
+            }
+        }
+    }
+
+    ecs.delete_entity(delete_me.ok_or(SaveLoadError::MissingHelper)?)
+        .expect("Unable to delete helper");
+
+    Ok(())
+}
+
+///Saves from before versioning start with a list of components where the header now is. Binary
+///saves were only added after versioning, so they always have one
+fn is_unversioned(data: &[u8], format: SaveFormat) -> bool {
+    format == SaveFormat::Text
+        && ron::Deserializer::from_bytes(data)
+            .is_ok_and(|mut de| Vec::<IgnoredAny>::deserialize(&mut de).is_ok())
+}
+
+fn read_save(ecs: &mut World, data: &[u8], format: SaveFormat) -> Result<(), SaveLoadError> {
+    match format {
+        SaveFormat::Text => read_components(ecs, &mut ron::Deserializer::from_bytes(data)?),
+        SaveFormat::Binary => read_components(
+            ecs,
+            &mut bincode::Deserializer::from_slice(data, bincode::options()),
+        ),
+    }
 }
 
-pub fn load_game(ecs: &mut World) {
+///Checks the header, then replaces every entity with the saved ones
+//Most of the length is the list of saved components
+#[allow(clippy::too_many_lines)]
+fn read_components<'de, D, E>(ecs: &mut World, deserializer: &mut D) -> Result<(), SaveLoadError>
+where
+    for<'d> &'d mut D: Deserializer<'de, Error = E>,
+    SaveLoadError: From<E>,
+{
+    let header = SaveHeader::deserialize(&mut *deserializer)?;
+    if header.version != SAVE_VERSION {
+        return Err(SaveLoadError::Incompatible {
+            found: Some(header.version),
+        });
+    }
+
     {
         let mut to_delete = Vec::new();
         for e in ecs.entities().join() {
@@ -104,9 +413,6 @@ pub fn load_game(ecs: &mut World) {
         }
     }
 
-    let data = fs::read_to_string(SAVE_PATH).unwrap();
-    let mut de = ron::Deserializer::from_str(&data).unwrap();
-
     {
         let mut d = (
             &mut ecs.entities(),
@@ -115,69 +421,309 @@ pub fn load_game(ecs: &mut World) {
         );
         deserialize_individually!(
             ecs,
-            de,
+            *deserializer,
             d,
             AreaOfEffect,
             BlocksTile,
             CombatStats,
+            Confusion,
             Consumable,
             DefenseBonus,
+            DropsLoot,
+            EntityMoved,
+            EntryTrigger,
             Equipment,
             Equipped,
+            Experience,
+            Hidden,
+            HungerClock,
             InBackpack,
             InflictsDamage,
+            Initiative,
             Item,
+            LightSource,
             MeleeDamageBonus,
             Monster,
             Name,
+            Boss,
+            Size,
+            Stealth,
+            Aggro,
+            PathCache,
+            Charges,
+            Cursed,
+            ProvidesRemoveCurse,
+            Elite,
+            Sighted,
+            Faction,
+            Summoned,
+            SummonsAlly,
+            WantsToSummon,
+            FleeThreshold,
+            Potion,
+            Scroll,
+            Gold,
+            Wallet,
+            Ammo,
+            Weight,
+            OnFire,
+            InflictsBurning,
+            Flammable,
+            Poison,
+            InflictsPoison,
+            ProvidesCure,
+            OtherLevelPosition,
             ParticleLifetime,
             Player,
             Position,
             ProvidesHealing,
+            ProvidesMapReveal,
+            ProvidesNutrition,
+            ProvidesTeleport,
             Range,
+            RangedWeapon,
             Render,
             SerializationHelper,
-            SufferDamage,
+            SingleActivation,
+            Speed,
             FieldOfView,
+            Unidentified,
             WantsToDropItem,
             WantsToMelee,
             WantsToPickupItem,
             WantsToRemoveItem,
+            WantsToShoot,
+            WantsToTeleport,
+            WantsToThrow,
             WantsToUseItem,
         );
     }
 
-    let mut delete_me = None;
-    {
-        let entities = ecs.entities();
-        let helper = ecs.read_storage::<SerializationHelper>();
-        let player = ecs.read_storage::<Player>();
-        let position = ecs.read_storage::<Position>();
-        for (e, h) in (&entities, &helper).join() {
-            let mut world_map = ecs.write_resource::<Map>();
-            *world_map = h.map.clone();
-            world_map.tile_content =
-                vec![Vec::new(); (world_map.width * world_map.height) as usize];
-            delete_me = Some(e);
-        }
-        for (e, _, pos) in (&entities, &player, &position).join() {
-            let mut player_pos = ecs.write_resource::<rltk::Point>();
-            let mut player_ent = ecs.write_resource::<Entity>();
-            *player_pos = rltk::Point::new(pos.x, pos.y);
-            *player_ent = e;
-        }
-    }
+    Ok(())
+}
 
-    ecs.delete_entity(delete_me.unwrap())
-        .expect("Unable to delete helper");
+///The most recently written of the manual saves and autosaves in the directory, which is the one
+///continued
+fn newest_save(dir: &Path) -> Option<(PathBuf, SaveFormat)> {
+    ALL_SAVES
+        .iter()
+        .filter_map(|&(file, format)| {
+            let path = dir.join(file);
+            let modified = fs::metadata(&path).ok()?.modified().ok()?;
+            Some((modified, path, format))
+        })
+        .max_by_key(|(modified, ..)| *modified)
+        .map(|(_, path, format)| (path, format))
 }
 
 pub fn does_save_exist() -> bool {
-    Path::new(SAVE_PATH).exists()
+    save_exists_in(Path::new(SAVE_DIR))
 }
 
-pub fn delete_save() {
-    if does_save_exist() {
-        std::fs::remove_file(SAVE_PATH).expect("Unable to delete file");
+fn save_exists_in(dir: &Path) -> bool {
+    newest_save(dir).is_some()
+}
+
+///Removes the manual saves and the autosaves, so a run can only be continued once
+pub fn delete_save() -> Result<(), SaveLoadError> {
+    for (file, _) in &ALL_SAVES {
+        let path = Path::new(SAVE_DIR).join(file);
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{spawn_monster, test_world};
+    use specs::saveload::MarkerAllocator;
+
+    ///A directory of its own for every test, so tests running at once can not share saves
+    fn temp_save_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("roguelike-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    ///Name, position and health of every named entity, sorted so worlds can be compared
+    fn snapshot(ecs: &World) -> Vec<(String, i32, i32, i32)> {
+        let names = ecs.read_storage::<Name>();
+        let positions = ecs.read_storage::<Position>();
+        let stats = ecs.read_storage::<CombatStats>();
+        let mut entities = (&names, &positions, &stats)
+            .join()
+            .map(|(name, pos, stats)| (name.name.clone(), pos.x, pos.y, stats.hp))
+            .collect::<Vec<_>>();
+        entities.sort();
+        entities
+    }
+
+    fn saved_world() -> World {
+        let mut world = test_world(20, 20, (3, 4));
+        let monster = spawn_monster(&mut world, (7, 8), 9, 2);
+        world
+            .write_resource::<SimpleMarkerAllocator<SerializeMe>>()
+            .mark(
+                monster,
+                &mut world.write_storage::<SimpleMarker<SerializeMe>>(),
+            );
+        world
+            .write_storage::<CombatStats>()
+            .get_mut(monster)
+            .unwrap()
+            .hp = 4;
+        world.write_resource::<Map>().depth = 3;
+        world
+    }
+
+    fn round_trip(format: SaveFormat, name: &str) {
+        let dir = temp_save_dir(name);
+        let mut world = saved_world();
+        assert!(!save_exists_in(&dir));
+        //The directory does not exist yet, saving creates it
+        save_to(&mut world, &dir.join(format.save_file()), format).unwrap();
+        assert!(save_exists_in(&dir));
+        assert_eq!(newest_save(&dir).map(|(_, format)| format), Some(format));
+
+        let mut loaded = test_world(10, 10, (1, 1));
+        load_from(&mut loaded, &dir).unwrap();
+        loaded.maintain();
+
+        assert_eq!(snapshot(&loaded), snapshot(&world));
+        assert_eq!(loaded.fetch::<Map>().depth, 3);
+        assert_eq!(loaded.fetch::<Map>().width, 20);
+        assert_eq!(*loaded.fetch::<rltk::Point>(), rltk::Point::new(3, 4));
+        let player = *loaded.fetch::<Entity>();
+        assert!(loaded.read_storage::<Player>().contains(player));
+        assert!(loaded.read_storage::<SerializationHelper>().is_empty());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn text_save_loads_the_same_entities() {
+        round_trip(SaveFormat::Text, "text");
+    }
+
+    #[test]
+    fn binary_save_loads_the_same_entities() {
+        round_trip(SaveFormat::Binary, "binary");
+    }
+
+    #[test]
+    fn autosaves_are_due_every_interval() {
+        let due = (0..120)
+            .filter(|turns| is_autosave_due(*turns, 50))
+            .collect::<Vec<_>>();
+        assert_eq!(due, vec![50, 100]);
+        assert!(!(0..120).any(|turns| is_autosave_due(turns, 0)));
+    }
+
+    #[test]
+    fn latest_of_the_save_and_the_autosave_is_continued() {
+        let dir = temp_save_dir("autosave");
+        let mut world = saved_world();
+        let format = SaveFormat::Text;
+        save_to(&mut world, &dir.join(format.save_file()), format).unwrap();
+        //Far enough apart for the files to be told apart by when they were written
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        save_to(&mut world, &dir.join(format.autosave_file()), format).unwrap();
+
+        let newest = newest_save(&dir).map(|(path, _)| path);
+        assert_eq!(newest, Some(dir.join(AUTOSAVE_FILE)));
+        let mut loaded = test_world(10, 10, (1, 1));
+        load_from(&mut loaded, &dir).unwrap();
+        loaded.maintain();
+        assert_eq!(snapshot(&loaded), snapshot(&world));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn corrupted_save_fails_without_touching_the_world() {
+        for (format, name) in [
+            (SaveFormat::Text, "corrupt-text"),
+            (SaveFormat::Binary, "corrupt-bin"),
+        ] {
+            let dir = temp_save_dir(name);
+            let path = dir.join(format.save_file());
+            save_to(&mut saved_world(), &path, format).unwrap();
+            //Cut off part way through the components, past the header
+            let data = fs::read(&path).unwrap();
+            fs::write(&path, &data[..data.len() / 2]).unwrap();
+
+            let mut running = test_world(10, 10, (1, 1));
+            spawn_monster(&mut running, (5, 5), 6, 1);
+            running.maintain();
+            let before = snapshot(&running);
+            let entities = running.entities().join().count();
+
+            let err = load_from(&mut running, &dir).unwrap_err();
+            assert!(
+                matches!(err, SaveLoadError::Serde(_) | SaveLoadError::Binary(_)),
+                "{:?}",
+                err
+            );
+            running.maintain();
+            assert_eq!(snapshot(&running), before);
+            assert_eq!(running.entities().join().count(), entities);
+            assert_eq!(running.fetch::<Map>().width, 10);
+            fs::remove_dir_all(dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn only_saves_without_a_header_are_from_before_versioning() {
+        let dir = temp_save_dir("unversioned");
+        fs::create_dir_all(&dir).unwrap();
+        let mut world = test_world(10, 10, (1, 1));
+        fs::write(dir.join(SAVE_FILE), "[(marker: (1), components: ())]").unwrap();
+        let err = load_from(&mut world, &dir).unwrap_err();
+        assert!(
+            matches!(err, SaveLoadError::Incompatible { found: None }),
+            "{:?}",
+            err
+        );
+
+        fs::write(dir.join(SAVE_FILE), "(versoin: )").unwrap();
+        let err = load_from(&mut world, &dir).unwrap_err();
+        assert!(matches!(err, SaveLoadError::Serde(_)), "{:?}", err);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    fn encoded_header(version: u32, format: SaveFormat) -> Vec<u8> {
+        let header = SaveHeader { version };
+        match format {
+            SaveFormat::Text => ron::ser::to_string(&header).unwrap().into_bytes(),
+            SaveFormat::Binary => bincode::Options::serialize(bincode::options(), &header).unwrap(),
+        }
+    }
+
+    #[test]
+    fn saves_from_an_older_version_are_rejected() {
+        for (format, name) in [
+            (SaveFormat::Text, "old-text"),
+            (SaveFormat::Binary, "old-bin"),
+        ] {
+            let dir = temp_save_dir(name);
+            let path = dir.join(format.save_file());
+            save_to(&mut saved_world(), &path, format).unwrap();
+            let data = fs::read(&path).unwrap();
+            let current = encoded_header(SAVE_VERSION, format);
+            assert!(data.starts_with(&current));
+            let mut older = encoded_header(SAVE_VERSION - 1, format);
+            older.extend_from_slice(&data[current.len()..]);
+            fs::write(&path, older).unwrap();
+
+            let mut world = test_world(10, 10, (1, 1));
+            let err = load_from(&mut world, &dir).unwrap_err();
+            assert!(
+                matches!(err, SaveLoadError::Incompatible { found: Some(v) } if v == SAVE_VERSION - 1),
+                "{:?}",
+                err
+            );
+            fs::remove_dir_all(dir).unwrap();
+        }
     }
 }
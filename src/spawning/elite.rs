@@ -0,0 +1,91 @@
+use crate::{
+    constants::colors,
+    ecs::components::{CombatStats, Elite, Name, Render},
+};
+use rltk::{RandomNumberGenerator, RGB};
+use specs::prelude::*;
+
+//Chance out of 100 for a spawned monster to be an elite
+const ELITE_CHANCE: i32 = 5;
+const ELITE_HP_MULTIPLIER: f32 = 1.5;
+const ELITE_POWER_BONUS: i32 = 2;
+const ELITE_DEFENSE_BONUS: i32 = 1;
+
+const NAME_STARTS: [&str; 8] = ["Gr", "Sk", "Mor", "Th", "Vr", "Dr", "Kal", "Zug"];
+const NAME_VOWELS: [&str; 5] = ["a", "o", "u", "e", "i"];
+const NAME_ENDS: [&str; 8] = ["k", "g", "th", "r", "sh", "x", "z", "nd"];
+const EPITHETS: [&str; 10] = [
+    "Fierce", "Cruel", "Bloody", "Cunning", "Grim", "Vile", "Mad", "Hungry", "Old", "Unbroken",
+];
+
+///Makes up a name for a unique variant of a monster, such as "Grok the Fierce Goblin"
+pub fn generate_unique_name(rng: &mut RandomNumberGenerator, base: &str) -> String {
+    let pick = |rng: &mut RandomNumberGenerator, parts: &[&'static str]| {
+        *rng.random_slice_entry(parts)
+            .expect("Name parts are never empty")
+    };
+    let given = [
+        pick(rng, &NAME_STARTS),
+        pick(rng, &NAME_VOWELS),
+        pick(rng, &NAME_ENDS),
+    ]
+    .concat();
+    format!("{} the {} {}", given, pick(rng, &EPITHETS), base)
+}
+
+///Rolls whether a freshly spawned monster is an elite, renaming and strengthening it if it is
+pub fn roll_elite(ecs: &World, monster: Entity) {
+    let mut names = ecs.write_storage::<Name>();
+    let Some(name) = names.get_mut(monster) else {
+        return;
+    };
+    let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+    if rng.roll_dice(1, 100) > ELITE_CHANCE {
+        return;
+    }
+
+    let kind = std::mem::take(&mut name.name);
+    name.name = generate_unique_name(&mut rng, &kind);
+    ecs.write_storage::<Elite>()
+        .insert(monster, Elite { kind })
+        .expect("Unable to insert elite");
+    if let Some(stats) = ecs.write_storage::<CombatStats>().get_mut(monster) {
+        stats.max_hp = (stats.max_hp as f32 * ELITE_HP_MULTIPLIER).round() as i32;
+        stats.hp = stats.max_hp;
+        stats.power += ELITE_POWER_BONUS;
+        stats.defense += ELITE_DEFENSE_BONUS;
+    }
+    if let Some(render) = ecs.write_storage::<Render>().get_mut(monster) {
+        render.colors.fg = RGB::from(colors::ELITE).into();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TEST_SEED;
+
+    #[test]
+    fn same_seed_gives_the_same_names() {
+        let names = |seed| {
+            let mut rng = RandomNumberGenerator::seeded(seed);
+            (0..5)
+                .map(|_| generate_unique_name(&mut rng, "Goblin"))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(TEST_SEED), names(TEST_SEED));
+    }
+
+    #[test]
+    fn name_is_made_of_a_given_name_an_epithet_and_the_kind() {
+        let mut rng = RandomNumberGenerator::seeded(TEST_SEED);
+        for _ in 0..20 {
+            let name = generate_unique_name(&mut rng, "Giant Spider");
+            let (given, rest) = name.split_once(" the ").unwrap();
+            let epithet = rest.strip_suffix(" Giant Spider").unwrap();
+            assert!(NAME_STARTS.iter().any(|start| given.starts_with(start)));
+            assert!(NAME_ENDS.iter().any(|end| given.ends_with(end)));
+            assert!(EPITHETS.contains(&epithet));
+        }
+    }
+}
@@ -1,7 +1,16 @@
+mod elite;
 mod random_table;
 mod spawner;
 
 pub use random_table::RandomTable;
+pub use spawner::difficulty_multiplier;
 pub use spawner::populate_room;
+pub use spawner::roll_drops;
+pub use spawner::spawn_ally;
+pub use spawner::spawn_at_points;
+pub use spawner::spawn_boss;
+pub use spawner::spawn_equipped;
+pub use spawner::spawn_gold;
 pub use spawner::spawn_player;
 pub use spawner::spawn_region;
+pub use spawner::spawn_traps;
@@ -1,5 +1,6 @@
 use rltk::RandomNumberGenerator;
 
+///Picks names at random, each entry being as likely to come up as its share of the total weight
 pub struct RandomTable {
     entries: Vec<RandomEntry>,
     total_weight: i32,
@@ -13,6 +14,14 @@ impl RandomTable {
         }
     }
 
+    ///Adds an entry and hands the table back, so tables can be built in a single expression
+    #[must_use]
+    pub fn add<S: ToString + std::fmt::Display>(mut self, name: &S, weight: i32) -> Self {
+        self.insert(name, weight);
+        self
+    }
+
+    ///Entries that have no weight can never be rolled, so they are left out
     pub fn insert<S: ToString + std::fmt::Display>(&mut self, name: &S, weight: i32) {
         if weight > 0 {
             self.total_weight += weight;
@@ -25,14 +34,12 @@ impl RandomTable {
             return None;
         }
         let mut roll = rng.roll_dice(1, self.total_weight) - 1;
-        let mut index = 0;
 
-        while roll > 0 {
-            if roll < self.entries[index].weight {
-                return Some(self.entries[index].name.clone());
+        for entry in &self.entries {
+            if roll < entry.weight {
+                return Some(entry.name.clone());
             }
-            roll -= self.entries[index].weight;
-            index += 1;
+            roll -= entry.weight;
         }
 
         None
@@ -52,3 +59,35 @@ impl RandomEntry {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TEST_SEED;
+
+    #[test]
+    fn entries_come_up_as_often_as_their_weight() {
+        let table = RandomTable::new()
+            .add(&"Common", 8)
+            .add(&"Rare", 2)
+            .add(&"Never", 0);
+        let mut rng = RandomNumberGenerator::seeded(TEST_SEED);
+        let rolls = (0..10_000)
+            .map(|_| table.roll(&mut rng).unwrap())
+            .collect::<Vec<_>>();
+        let common = rolls.iter().filter(|name| *name == "Common").count();
+        let rare = rolls.iter().filter(|name| *name == "Rare").count();
+        assert_eq!(common + rare, rolls.len());
+        assert!((7_700..8_300).contains(&common));
+    }
+
+    #[test]
+    fn single_entry_always_comes_up_and_an_empty_table_never() {
+        let mut rng = RandomNumberGenerator::seeded(TEST_SEED);
+        let single = RandomTable::new().add(&"Only", 1);
+        for _ in 0..10 {
+            assert_eq!(single.roll(&mut rng).as_deref(), Some("Only"));
+        }
+        assert_eq!(RandomTable::new().roll(&mut rng), None);
+    }
+}
@@ -1,21 +1,56 @@
-use super::random_table::RandomTable;
+use super::{elite, random_table::RandomTable};
 use crate::{
     constants::colors,
-    ecs::components::{CombatStats, FieldOfView, Name, Player, Position, Render, SerializeMe},
+    difficulty::Difficulty,
+    ecs::{
+        components::{
+            Ammo, Boss, CombatStats, DropsLoot, EquipmentSlot, Experience, Faction, FieldOfView,
+            Flammable, Gold, HungerClock, HungerState, LightSource, Monster, Name, Player,
+            Position, Render, SerializeMe, Stealth, Summoned, Wallet,
+        },
+        BASE_CRIT_CHANCE, HUNGER_STATE_DURATION,
+    },
     map_builder::{
         map::{Map, TileType},
         rect::Rect,
     },
     raws::spawn::{SpawnType, SPAWN_RAWS},
 };
-use rltk::{ColorPair, RGB};
+use rltk::{ColorPair, RandomNumberGenerator, RGB};
 use specs::{
     prelude::*,
     saveload::{MarkedBuilder, SimpleMarker},
 };
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 const MAX_MONSTERS: i32 = 4;
+//The most monsters a room holds on the first levels, allowed one more every few levels
+const BASE_MONSTERS_PER_ROOM: i32 = 3;
+const LEVELS_PER_EXTRA_MONSTER: i32 = 3;
+pub const MAX_MONSTERS_PER_ROOM: i32 = 6;
+const PLAYER_LIGHT_RADIUS: i32 = 3;
+const PLAYER_STEALTH: i32 = 1;
+const DIFFICULTY_PER_DEPTH: f32 = 0.1;
+const TRAP_MIN_START_DISTANCE: f32 = 10.0;
+const MAX_GOLD_PILES: i32 = 4;
+const GOLD_PER_DEPTH: i32 = 5;
+const BOSS_NAME: &str = "Cave Troll";
+const BOSS_MINIONS: [&str; 2] = ["Orc", "Goblin Archer"];
+//Where minions stand around the boss, relative to the top left of its footprint
+const MINION_OFFSETS: [(i32, i32); 4] = [(-3, -3), (4, -3), (-3, 4), (4, 4)];
+
+///Scale applied to monster stats, growing linearly with every level below the first
+pub fn difficulty_multiplier(depth: i32) -> f32 {
+    DIFFICULTY_PER_DEPTH.mul_add((i32::max(depth, 1) - 1) as f32, 1.0)
+}
+
+///The most monsters a single room or region may hold at this depth
+pub fn max_monsters_per_room(depth: i32) -> i32 {
+    i32::min(
+        MAX_MONSTERS_PER_ROOM,
+        BASE_MONSTERS_PER_ROOM + i32::max(depth - 1, 0) / LEVELS_PER_EXTRA_MONSTER,
+    )
+}
 
 pub fn populate_room(ecs: &mut World, room: &Rect) {
     let mut possible_spawns = Vec::new();
@@ -35,38 +70,115 @@ pub fn populate_room(ecs: &mut World, room: &Rect) {
 
 pub fn spawn_region(ecs: &mut World, area: &[(i32, i32)], map_depth: i32) {
     let spawn_table = create_room_table(map_depth);
-    let mut rng = rltk::RandomNumberGenerator::new();
-    let mut spawn_points = HashMap::new();
+    let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+    //Ordered so that spawning, and with it every roll made while spawning, follows the seed
+    let mut spawn_points = BTreeMap::new();
+    let mut packs = Vec::new();
     let mut areas = Vec::from(area);
+    let monster_cap = max_monsters_per_room(map_depth);
+    let mut monsters = 0;
 
     let num_spawns = i32::min(
         areas.len() as i32,
-        rng.roll_dice(1, MAX_MONSTERS + 3) + map_depth - 1 - 3,
+        rng.roll_dice(1, MAX_MONSTERS + 3) + map_depth - 1 - 3
+            + ecs.fetch::<Difficulty>().extra_spawns(),
     );
 
     for _ in 0..num_spawns {
+        if areas.is_empty() {
+            break;
+        }
         let array_index = if areas.len() == 1 {
             0_usize
         } else {
             (rng.roll_dice(1, areas.len() as i32) - 1) as usize
         };
-        let map_point = areas[array_index];
+        let map_point = areas.remove(array_index);
         if let Some(spawn) = spawn_table.roll(&mut rng) {
+            let (is_mob, pack_size) = {
+                let raws = SPAWN_RAWS.lock().unwrap();
+                (raws.is_mob(&spawn), raws.pack_size(&spawn))
+            };
+            //Monsters past the cap are skipped rather than crowding the region
+            if is_mob && monsters >= monster_cap {
+                continue;
+            }
+            //The tiles of the region closest to the first member are kept free for the others
+            if let Some((min_size, max_size)) = pack_size {
+                let mut count = rng.range(min_size, max_size + 1);
+                if is_mob {
+                    count = i32::min(count, monster_cap - monsters);
+                }
+                let mut members = vec![map_point];
+                members.extend(
+                    take_nearest(map_point, count - 1, &mut areas)
+                        .into_iter()
+                        .rev(),
+                );
+                monsters += members.len() as i32 * i32::from(is_mob);
+                packs.push((members, spawn));
+                continue;
+            }
             spawn_points.insert(map_point, spawn);
+            monsters += i32::from(is_mob);
         }
-        areas.remove(array_index);
     }
 
     std::mem::drop(rng);
-    for spawn in &spawn_points {
-        spawn_named_entity(ecs, &spawn);
+    spawn_at_points(ecs, &spawn_points, map_depth);
+    for (members, kind) in packs {
+        spawn_pack(ecs, &members, &kind, map_depth);
+    }
+}
+
+///Spawns a pack of the named kind, one member on each of the given tiles, returning the members
+///that were spawned. The tiles are those the region kept free for the pack
+pub fn spawn_pack(
+    ecs: &mut World,
+    tiles: &[(i32, i32)],
+    kind: &str,
+    map_depth: i32,
+) -> Vec<Entity> {
+    let kind = kind.to_string();
+    tiles
+        .iter()
+        .filter_map(|point| spawn_with_elites(ecs, *point, &kind, map_depth))
+        .collect()
+}
+
+///Spawns each named entity at its point, rolling for elites among the monsters
+pub fn spawn_at_points<'a>(
+    ecs: &mut World,
+    spawns: impl IntoIterator<Item = (&'a (i32, i32), &'a String)>,
+    map_depth: i32,
+) {
+    for (point, name) in spawns {
+        spawn_with_elites(ecs, *point, name, map_depth);
+    }
+}
+
+///Spawns the named entity at the point, rolling whether it is an elite if it is a monster
+fn spawn_with_elites(
+    ecs: &mut World,
+    point: (i32, i32),
+    name: &String,
+    map_depth: i32,
+) -> Option<Entity> {
+    let ent = spawn_named_entity(ecs, &(&point, name), map_depth)?;
+    if ecs.read_storage::<Monster>().contains(ent) {
+        elite::roll_elite(ecs, ent);
     }
+    Some(ent)
 }
 
 pub fn spawn_player(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let max_hp = ecs.fetch::<Difficulty>().player_hp();
     ecs.create_entity()
         .with(Position { x, y })
-        .with(Player {})
+        .with(Player::default())
+        .with(Faction::Player)
+        .with(Flammable {})
+        .with(Ammo::default())
         .with(Render {
             glyph: rltk::to_cp437('@'),
             colors: ColorPair::new(RGB::named(rltk::YELLOW), RGB::from(colors::BACKGROUND)),
@@ -76,33 +188,399 @@ pub fn spawn_player(ecs: &mut World, x: i32, y: i32) -> Entity {
             visible_tiles: Vec::new(),
             range: 8,
             is_dirty: true,
+            lights: Vec::new(),
         })
         .with(Name {
             name: "Player".to_string(),
         })
         .with(CombatStats {
-            max_hp: 30,
-            hp: 30,
+            max_hp,
+            hp: max_hp,
             defense: 2,
             power: 5,
+            crit_chance: BASE_CRIT_CHANCE,
+        })
+        .with(LightSource {
+            radius: PLAYER_LIGHT_RADIUS,
+            color: RGB::named(rltk::LIGHT_YELLOW),
+        })
+        .with(HungerClock {
+            state: HungerState::WellFed,
+            duration: HUNGER_STATE_DURATION,
+        })
+        .with(Experience {
+            current: 0,
+            level: 1,
+        })
+        .with(Wallet::default())
+        .with(Stealth {
+            skill: PLAYER_STEALTH,
+            sneaking: false,
         })
         .marked::<SimpleMarker<SerializeMe>>()
         .build()
 }
 
-fn create_room_table(map_depth: i32) -> RandomTable {
-    SPAWN_RAWS.lock().unwrap().spawn_table(map_depth)
+///Removes and returns up to `count` points of the area, closest to the center first
+fn take_nearest(center: (i32, i32), count: i32, area: &mut Vec<(i32, i32)>) -> Vec<(i32, i32)> {
+    let distance = |(x, y): (i32, i32)| (x - center.0).pow(2) + (y - center.1).pow(2);
+    area.sort_by_key(|&point| std::cmp::Reverse(distance(point)));
+    let taken = usize::min(count.max(0) as usize, area.len());
+    area.split_off(area.len() - taken)
 }
 
-fn spawn_named_entity(ecs: &mut World, ((x, y), name): &(&(i32, i32), &String)) {
-    if SPAWN_RAWS
-        .lock()
-        .unwrap()
-        .spawn_named_entity(ecs.create_entity(), name, SpawnType::AtPosition(*x, *y))
-        .is_some()
+///Hides a number of traps, growing with depth, on floor tiles away from the starting position
+pub fn spawn_traps(ecs: &mut World, start: (i32, i32), map_depth: i32) {
+    let trap_names = SPAWN_RAWS.lock().unwrap().trap_names();
+    if trap_names.is_empty() {
+        return;
+    }
+
+    let mut candidates = {
+        let map = ecs.fetch::<Map>();
+        (0..map.tiles.len())
+            .filter(|&idx| map.tiles[idx] == TileType::Floor)
+            .map(|idx| (idx as i32 % map.width, idx as i32 / map.width))
+            .filter(|&(x, y)| {
+                rltk::DistanceAlg::Pythagoras
+                    .distance2d(rltk::Point::new(x, y), rltk::Point::new(start.0, start.1))
+                    > TRAP_MIN_START_DISTANCE
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let mut traps = Vec::new();
     {
+        let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+        let num_traps = rng.roll_dice(1, 3) + map_depth - 1;
+        for _ in 0..num_traps {
+            if candidates.is_empty() {
+                break;
+            }
+            let point = candidates.remove((rng.roll_dice(1, candidates.len() as i32) - 1) as usize);
+            let name = rng.random_slice_entry(&trap_names).unwrap().clone();
+            traps.push((point, name));
+        }
+    }
+    for (point, name) in &traps {
+        spawn_named_entity(ecs, &(point, name), map_depth);
+    }
+}
+
+///Scatters a few piles of gold over the floor of the level, holding more the deeper it is
+pub fn spawn_gold(ecs: &mut World, start: (i32, i32), map_depth: i32) {
+    let mut candidates = {
+        let map = ecs.fetch::<Map>();
+        (0..map.tiles.len())
+            .filter(|&idx| map.tiles[idx] == TileType::Floor)
+            .map(|idx| (idx as i32 % map.width, idx as i32 / map.width))
+            .filter(|&point| point != start)
+            .collect::<Vec<_>>()
+    };
+
+    let mut piles = Vec::new();
+    {
+        let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+        for _ in 0..rng.roll_dice(1, MAX_GOLD_PILES) {
+            if candidates.is_empty() {
+                break;
+            }
+            let point = candidates.remove((rng.roll_dice(1, candidates.len() as i32) - 1) as usize);
+            let amount = rng.roll_dice(2, GOLD_PER_DEPTH * map_depth);
+            piles.push((point, amount));
+        }
+    }
+    for ((x, y), amount) in piles {
+        ecs.create_entity()
+            .with(Position { x, y })
+            .with(Render {
+                glyph: rltk::to_cp437('$'),
+                colors: ColorPair::new(RGB::named(rltk::GOLD), RGB::from(colors::BACKGROUND)),
+                render_order: 2,
+            })
+            .with(Name {
+                name: format!("{amount} gold"),
+            })
+            .with(Gold { amount })
+            .marked::<SimpleMarker<SerializeMe>>()
+            .build();
+    }
+}
+
+///Rolls whether a dying monster leaves an item on its tile, drawn from the items of this depth
+pub fn roll_drops(ecs: &mut World, victim: Entity) {
+    let drop = {
+        let loot = ecs.read_storage::<DropsLoot>();
+        let positions = ecs.read_storage::<Position>();
+        match (loot.get(victim), positions.get(victim)) {
+            (Some(loot), Some(pos)) => Some((loot.chance, (pos.x, pos.y))),
+            _ => None,
+        }
+    };
+    let Some((chance, point)) = drop else {
         return;
+    };
+
+    let map_depth = ecs.fetch::<Map>().depth;
+    let dropped = {
+        let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+        if rng.roll_dice(1, 100) > chance {
+            return;
+        }
+        let loot_table = SPAWN_RAWS.lock().unwrap().loot_table(map_depth);
+        loot_table.roll(&mut rng)
+    };
+    if let Some(name) = dropped {
+        spawn_named_entity(ecs, &(&point, &name), map_depth);
+    }
+}
+
+///Spawns the boss of a boss level, surrounded by more minions the deeper the level is
+pub fn spawn_boss(ecs: &mut World, (x, y): (i32, i32), depth: i32) -> Option<Entity> {
+    let boss = spawn_named_entity(ecs, &(&(x, y), &BOSS_NAME.to_string()), depth)?;
+    ecs.write_storage::<Boss>()
+        .insert(boss, Boss {})
+        .expect("Unable to insert boss");
+
+    let minion_count = usize::min(1 + depth as usize / 5, MINION_OFFSETS.len());
+    for (i, (dx, dy)) in MINION_OFFSETS.iter().take(minion_count).enumerate() {
+        let point = (x + dx, y + dy);
+        let is_floor = {
+            let map = ecs.fetch::<Map>();
+            map.tiles[map.xy_idx(point.0, point.1)] == TileType::Floor
+        };
+        if is_floor {
+            let name = BOSS_MINIONS[i % BOSS_MINIONS.len()].to_string();
+            spawn_named_entity(ecs, &(&point, &name), depth);
+        }
+    }
+    Some(boss)
+}
+
+fn create_room_table(map_depth: i32) -> RandomTable {
+    SPAWN_RAWS.lock().unwrap().spawn_table(map_depth)
+}
+
+///Spawns the named creature on the player's side, where it stays for the given number of turns
+pub fn spawn_ally(
+    ecs: &mut World,
+    name: &str,
+    (x, y): (i32, i32),
+    depth: i32,
+    turns: i32,
+) -> Option<Entity> {
+    let ally = SPAWN_RAWS.lock().unwrap().spawn_named_entity(
+        ecs.create_entity(),
+        name,
+        SpawnType::AtPosition(x, y),
+        depth,
+    )?;
+    ecs.write_storage::<Monster>().remove(ally);
+    ecs.write_storage::<DropsLoot>().remove(ally);
+    ecs.write_storage::<Faction>()
+        .insert(ally, Faction::Ally)
+        .expect("Unable to insert faction");
+    ecs.write_storage::<Summoned>()
+        .insert(
+            ally,
+            Summoned {
+                turns_remaining: turns,
+            },
+        )
+        .expect("Unable to insert summoned");
+    Some(ally)
+}
+
+///Spawns the named item already worn by the owner in the given slot
+pub fn spawn_equipped(
+    ecs: &mut World,
+    name: &str,
+    owner: Entity,
+    slot: EquipmentSlot,
+) -> Option<Entity> {
+    let depth = ecs.fetch::<Map>().depth;
+    SPAWN_RAWS.lock().unwrap().spawn_named_entity(
+        ecs.create_entity(),
+        name,
+        SpawnType::Equipped { by: owner, slot },
+        depth,
+    )
+}
+
+fn spawn_named_entity(
+    ecs: &mut World,
+    ((x, y), name): &(&(i32, i32), &String),
+    depth: i32,
+) -> Option<Entity> {
+    let spawned = SPAWN_RAWS.lock().unwrap().spawn_named_entity(
+        ecs.create_entity(),
+        name,
+        SpawnType::AtPosition(*x, *y),
+        depth,
+    );
+    if spawned.is_none() {
+        println!("There exists no entity with the name \"{}\" to spawn", name);
+    }
+    spawned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        raws,
+        test_support::{spawn_monster, test_world},
+    };
+    use std::collections::{HashMap, HashSet};
+
+    #[test]
+    fn multiplier_grows_a_tenth_every_level() {
+        assert!((difficulty_multiplier(1) - 1.0).abs() < f32::EPSILON);
+        assert!((difficulty_multiplier(5) - 1.4).abs() < f32::EPSILON);
+        assert!((difficulty_multiplier(10) - 1.9).abs() < f32::EPSILON);
+        //Depths below the first are treated as the first
+        assert!((difficulty_multiplier(0) - 1.0).abs() < f32::EPSILON);
     }
 
-    println!("There exists no entity with the name \"{}\" to spawn", name);
+    #[test]
+    fn pack_spawns_one_member_on_each_given_tile() {
+        raws::spawn::load().unwrap();
+        let mut world = test_world(12, 12, (9, 9));
+        let tiles = [(5, 5), (5, 4), (4, 5), (6, 6)];
+
+        let pack = spawn_pack(&mut world, &tiles, "Kobold", 1);
+        let positions = world.read_storage::<Position>();
+        let names = world.read_storage::<Name>();
+        let spawned = pack
+            .iter()
+            .map(|member| {
+                assert!(names.get(*member).unwrap().name.contains("Kobold"));
+                positions.get(*member).map(|pos| (pos.x, pos.y)).unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(spawned, tiles);
+    }
+
+    #[test]
+    fn packs_stay_inside_their_region() {
+        raws::spawn::load().unwrap();
+        //The rest of the map is open floor a pack could otherwise spill onto
+        let mut world = test_world(20, 12, (18, 10));
+        let player_ent = *world.fetch::<Entity>();
+        let region = (1..=4)
+            .flat_map(|y| (1..=4).map(move |x| (x, y)))
+            .collect::<Vec<_>>();
+        let mut packs_seen = 0;
+        for _ in 0..40 {
+            spawn_region(&mut world, &region, 1);
+            world.maintain();
+            {
+                let positions = world.read_storage::<Position>();
+                let names = world.read_storage::<Name>();
+                let mut tiles = HashSet::new();
+                let mut kinds = HashMap::new();
+                for (ent, pos, name) in (&world.entities(), &positions, &names).join() {
+                    if ent == player_ent {
+                        continue;
+                    }
+                    assert!(
+                        region.contains(&(pos.x, pos.y)),
+                        "{} left the region",
+                        name.name
+                    );
+                    assert!(tiles.insert((pos.x, pos.y)), "two spawns share a tile");
+                    *kinds.entry(name.name.clone()).or_insert(0) += 1;
+                }
+                packs_seen += kinds.values().filter(|count| **count > 1).count();
+            }
+            let spawned = (&world.entities())
+                .join()
+                .filter(|ent| *ent != player_ent)
+                .collect::<Vec<_>>();
+            world.delete_entities(&spawned).unwrap();
+            world.maintain();
+        }
+        assert!(packs_seen > 0);
+    }
+
+    #[test]
+    fn regions_never_hold_more_monsters_than_the_cap() {
+        raws::spawn::load().unwrap();
+        let mut world = test_world(12, 12, (11, 11));
+        let player_ent = *world.fetch::<Entity>();
+        let area = (1..=10)
+            .flat_map(|y| (1..=10).map(move |x| (x, y)))
+            .collect::<Vec<_>>();
+        for depth in 1..=15 {
+            let cap = max_monsters_per_room(depth) as usize;
+            let mut most = 0;
+            for _ in 0..40 {
+                spawn_region(&mut world, &area, depth);
+                world.maintain();
+                let monsters = world.read_storage::<Monster>().count();
+                assert!(monsters <= cap, "{} monsters at depth {}", monsters, depth);
+                most = usize::max(most, monsters);
+
+                let spawned = (&world.entities())
+                    .join()
+                    .filter(|ent| *ent != player_ent)
+                    .collect::<Vec<_>>();
+                world.delete_entities(&spawned).unwrap();
+                world.maintain();
+            }
+            //Deep down the rolls easily run into the cap, so it must have held at least once
+            if depth == 15 {
+                assert_eq!(most, cap);
+            }
+        }
+        assert_eq!(max_monsters_per_room(1), 3);
+        assert_eq!(max_monsters_per_room(15), MAX_MONSTERS_PER_ROOM);
+    }
+
+    ///Everything but monsters lying on the given tile
+    fn items_at(world: &World, (x, y): (i32, i32)) -> usize {
+        let monsters = world.read_storage::<Monster>();
+        let positions = world.read_storage::<Position>();
+        (&positions, !&monsters)
+            .join()
+            .filter(|(pos, ())| (pos.x, pos.y) == (x, y))
+            .count()
+    }
+
+    #[test]
+    fn monsters_drop_loot_as_often_as_their_chance() {
+        raws::spawn::load().unwrap();
+        let mut world = test_world(10, 10, (1, 1));
+        for (chance, tile) in [(100, (3, 3)), (0, (6, 6))] {
+            let monster = spawn_monster(&mut world, tile, 5, 1);
+            world
+                .write_storage()
+                .insert(monster, DropsLoot { chance })
+                .unwrap();
+            roll_drops(&mut world, monster);
+        }
+        world.maintain();
+        assert_eq!(items_at(&world, (3, 3)), 1);
+        assert_eq!(items_at(&world, (6, 6)), 0);
+    }
+
+    #[test]
+    fn difficulty_sets_the_player_hp_and_scales_monsters() {
+        raws::spawn::load().unwrap();
+        let mut kobold_hp = Vec::new();
+        for difficulty in [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard] {
+            let mut world = test_world(12, 12, (9, 9));
+            world.insert(difficulty);
+            let player_ent = spawn_player(&mut world, 2, 2);
+            let kobold = spawn_pack(&mut world, &[(5, 5)], "Kobold", 1)[0];
+
+            let all_stats = world.read_storage::<CombatStats>();
+            let player_stats = all_stats.get(player_ent).unwrap();
+            assert_eq!(player_stats.max_hp, difficulty.player_hp());
+            assert_eq!(player_stats.hp, difficulty.player_hp());
+            kobold_hp.push(all_stats.get(kobold).unwrap().max_hp);
+        }
+        assert!(kobold_hp[0] < kobold_hp[1], "{:?}", kobold_hp);
+        assert!(kobold_hp[1] < kobold_hp[2], "{:?}", kobold_hp);
+    }
 }
@@ -1,9 +1,21 @@
 use super::{
-    ecs::{components::*, ParticleBuilder},
+    audio::SfxQueue,
+    bestiary::load_bestiary,
+    camera::MapRenderCache,
+    difficulty::Difficulty,
+    dungeon_master::DungeonMaster,
+    ecs::{components::*, DeadEntities, ParticleBuilder},
     game_log::GameLog,
+    game_seed::GameSeed,
+    gui::UiState,
+    identification::Identification,
+    meta_stats::load_meta_stats,
+    music::MusicState,
     rex_assets::RexAssets,
+    run_summary::RunSummary,
     state::{MainOption, Menu, State},
 };
+use rltk::RandomNumberGenerator;
 use specs::{
     prelude::*,
     saveload::{SimpleMarker, SimpleMarkerAllocator},
@@ -33,30 +45,77 @@ pub fn register_all_components(world: &mut specs::World) {
         AreaOfEffect,
         BlocksTile,
         CombatStats,
+        Confusion,
         Consumable,
         DefenseBonus,
+        DropsLoot,
+        EntityMoved,
+        EntryTrigger,
         Equipment,
         Equipped,
+        Experience,
+        Hidden,
+        HungerClock,
         InBackpack,
         InflictsDamage,
+        Initiative,
         Item,
+        LightSource,
         MeleeDamageBonus,
         Monster,
         Name,
+        Boss,
+        Size,
+        Stealth,
+        Aggro,
+        PathCache,
+        Charges,
+        Cursed,
+        ProvidesRemoveCurse,
+        Elite,
+        Sighted,
+        Faction,
+        Summoned,
+        SummonsAlly,
+        WantsToSummon,
+        FleeThreshold,
+        Potion,
+        Scroll,
+        Gold,
+        Wallet,
+        Ammo,
+        Weight,
+        OnFire,
+        InflictsBurning,
+        Flammable,
+        Poison,
+        InflictsPoison,
+        ProvidesCure,
+        OtherLevelPosition,
         ParticleLifetime,
         Player,
         Position,
         ProvidesHealing,
+        ProvidesMapReveal,
+        ProvidesNutrition,
+        ProvidesTeleport,
         Range,
+        RangedWeapon,
         Render,
         SerializationHelper,
+        SingleActivation,
+        Speed,
         SimpleMarker<SerializeMe>,
         SufferDamage,
         FieldOfView,
+        Unidentified,
         WantsToDropItem,
         WantsToMelee,
         WantsToPickupItem,
         WantsToRemoveItem,
+        WantsToShoot,
+        WantsToTeleport,
+        WantsToThrow,
         WantsToUseItem,
     );
 }
@@ -70,7 +129,20 @@ pub fn insert_all_resources(world: &mut specs::World) {
         SimpleMarkerAllocator::<SerializeMe>::new(),
         RexAssets::load(),
         ParticleBuilder::new(),
+        DeadEntities::default(),
+        SfxQueue::default(),
+        MusicState::default(),
         GameLog::new(),
+        UiState::default(),
+        MapRenderCache::default(),
+        Identification::new(),
+        Difficulty::default(),
+        GameSeed::random(),
+        DungeonMaster::default(),
+        RandomNumberGenerator::new(),
+        load_meta_stats(),
+        load_bestiary(),
+        RunSummary::default(),
     );
 
     //Unable to include this statement in the above batch due to the borrow checker
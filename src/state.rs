@@ -12,23 +12,31 @@ pub enum State {
 pub enum Gameplay {
     AwaitingInput,
     GameOver,
+    MapGeneration,
     MonsterTurn,
     NextLevel,
+    PreviousLevel,
     PlayerTurn,
     PreRun,
     SaveGame,
     Inventory(gui::inventory::InvMode),
-    ShowTargeting(i32, specs::Entity),
+    ShowTargeting(i32, specs::Entity, gui::targeting::Aim),
+    Examine,
+    Help,
+    Paused(PauseOption),
 }
 
 #[derive(PartialEq, Copy, Clone, Debug)]
 pub enum Menu {
     Main(MainOption),
+    Difficulty(crate::difficulty::Difficulty),
+    SeedEntry(u64),
     Settings(SettingsOption),
     Audio(AudioOption),
     Visual(VisualOption),
     Keybinding(KeyBindingOption),
     KeySelect(KeyBindingOption),
+    Bestiary,
 }
 
 //Menu Options
@@ -38,12 +46,25 @@ pub enum Menu {
 pub enum MainOption {
     #[strum(serialize = "Start Anew")]
     NewGame,
+    #[strum(serialize = "Start Seeded")]
+    SeededGame,
     #[strum(serialize = "Continue")]
     LoadGame,
     Settings,
+    Bestiary,
     Quit,
 }
 
+#[derive(PartialEq, Eq, Copy, Clone, Debug, EnumIter, AsRefStr, EnumCycle)]
+pub enum PauseOption {
+    Resume,
+    #[strum(serialize = "Save & Quit")]
+    SaveAndQuit,
+    Settings,
+    #[strum(serialize = "Quit to Main Menu")]
+    QuitToMenu,
+}
+
 #[derive(PartialEq, Copy, Clone, Debug, EnumIter, AsRefStr, EnumCycle)]
 pub enum SettingsOption {
     Audio,
@@ -77,6 +98,13 @@ pub enum VisualOption {
     ActiveFont,
     #[strum(serialize = "Color Mapping")]
     ColorMapping,
+    #[strum(serialize = "Color Theme")]
+    ColorTheme,
+    Backgrounds,
+    #[strum(serialize = "Colorblind Mode")]
+    Colorblind,
+    #[strum(serialize = "Large Tiles")]
+    LargeTiles,
     #[skip]
     Back,
 }
@@ -96,6 +124,7 @@ pub enum KeyBindingOption {
     #[strum(serialize = "Down & Left")]
     DownLeft,
     Descend,
+    Ascend,
     Inventory,
     #[strum(serialize = "Grab Item")]
     GrabItem,
@@ -107,7 +136,32 @@ pub enum KeyBindingOption {
     GoBack,
     #[strum(serialize = "Wait a Turn")]
     WaitTurn,
+    #[strum(serialize = "Auto Explore")]
+    AutoExplore,
+    Fire,
+    Examine,
     Select,
+    #[strum(serialize = "Export Log")]
+    ExportLog,
+    #[strum(serialize = "Export Map")]
+    ExportMap,
+    #[strum(serialize = "Quaff Potion")]
+    Quaff,
+    #[strum(serialize = "Read Scroll")]
+    Read,
+    Rest,
+    Sneak,
+    #[strum(serialize = "Throw Item")]
+    Throw,
+    #[strum(serialize = "Toggle Minimap")]
+    ToggleMinimap,
+    Help,
+    #[strum(serialize = "Debug Overlay")]
+    ToggleDebug,
+    #[strum(serialize = "Wait a Turn (Alt)")]
+    WaitTurnAlt,
+    #[strum(serialize = "Cycle Targets")]
+    CycleTarget,
     #[skip]
     Back,
 }
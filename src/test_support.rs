@@ -0,0 +1,116 @@
+use crate::{
+    audio::SfxQueue,
+    bestiary::Bestiary,
+    camera::MapRenderCache,
+    difficulty::Difficulty,
+    dungeon_master::DungeonMaster,
+    ecs::{components::*, DeadEntities, ParticleBuilder},
+    game_log::GameLog,
+    game_seed::GameSeed,
+    gui::UiState,
+    identification::Identification,
+    map_builder::map::{Map, TileType},
+    meta_stats::MetaStats,
+    music::MusicState,
+    raws::config::Config,
+    run_summary::RunSummary,
+    spawning,
+    specs_helpers::register_all_components,
+    state::{Gameplay, State},
+    BashingBytes,
+};
+use rltk::{Point, RandomNumberGenerator};
+use specs::{prelude::*, saveload::SimpleMarkerAllocator};
+
+//Seed of the random number generator every test world starts with
+pub const TEST_SEED: u64 = 42;
+
+///A world holding every component and resource the game uses, with a walled room of floor as its
+///map and the player standing at the given position. Nothing is read from or written to the files
+///of the player, and the random number generator is seeded so tests can be repeated
+pub fn test_world(width: i32, height: i32, player: (i32, i32)) -> World {
+    let mut world = World::new();
+    register_all_components(&mut world);
+    world.insert(State::Game(Gameplay::PlayerTurn));
+    world.insert(SimpleMarkerAllocator::<SerializeMe>::new());
+    world.insert(ParticleBuilder::new());
+    world.insert(DeadEntities::default());
+    world.insert(SfxQueue::default());
+    world.insert(MusicState::default());
+    world.insert(GameLog::new());
+    world.insert(UiState::default());
+    world.insert(MapRenderCache::default());
+    world.insert(Identification::new());
+    world.insert(Difficulty::default());
+    world.insert(GameSeed::random());
+    world.insert(DungeonMaster::default());
+    world.insert(RandomNumberGenerator::seeded(TEST_SEED));
+    world.insert(MetaStats::default());
+    world.insert(Bestiary::default());
+    world.insert(RunSummary::default());
+    world.insert(room_map(width, height, 1));
+    world.insert(Point::new(player.0, player.1));
+
+    let player_ent = spawning::spawn_player(&mut world, player.0, player.1);
+    world.insert(player_ent);
+    world
+}
+
+///A map of the given size that is floor everywhere but its outer walls
+pub fn room_map(width: i32, height: i32, depth: i32) -> Map {
+    let mut map = Map::new(width, height, depth);
+    for y in 1..height - 1 {
+        for x in 1..width - 1 {
+            let idx = map.xy_idx(x, y);
+            map.tiles[idx] = TileType::Floor;
+        }
+    }
+    map.populate_blocked();
+    map
+}
+
+///A plain monster with the given stats, which takes part in every system a spawned one would
+pub fn spawn_monster(world: &mut World, (x, y): (i32, i32), hp: i32, power: i32) -> Entity {
+    world
+        .create_entity()
+        .with(Position { x, y })
+        .with(Monster {})
+        .with(Faction::Monster)
+        .with(BlocksTile {})
+        .with(FieldOfView {
+            visible_tiles: Vec::new(),
+            range: 8,
+            is_dirty: true,
+            lights: Vec::new(),
+        })
+        .with(Name {
+            name: "Test Monster".to_string(),
+        })
+        .with(CombatStats {
+            max_hp: hp,
+            hp,
+            defense: 0,
+            power,
+            crit_chance: 0,
+        })
+        .build()
+}
+
+///A game around the given world with the default configs and no audio output
+pub fn test_game(world: World) -> BashingBytes {
+    BashingBytes {
+        world,
+        configs: Config::default(),
+        music_sink: None,
+        music_handle: None,
+        sfx_sink: None,
+        mapgen_history: Vec::new(),
+        mapgen_index: 0,
+        mapgen_timer: 0.0,
+        menu_notice: None,
+        settings_return: None,
+        auto_exploring: false,
+        resting: false,
+        new_game_plus: false,
+    }
+}